@@ -0,0 +1,112 @@
+//! The decode-to-display path, short of the decoder: finding the videos,
+//! turning decoded frames into images, and handing them to the window.
+//!
+//! Run with `cargo bench -p summit_hip_core`. Criterion compares each run
+//! with the last one on the same machine; CI keeps the history.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::fs;
+use summit_hip_core::config::OutputConfig;
+use summit_hip_core::file_scanner::scan_video_files;
+use summit_hip_core::playback::{rgba_image, Adjustment, FrameSender};
+use tempfile::TempDir;
+use tokio::sync::watch;
+
+/// Frame sizes the kiosks play.
+const FRAME_SIZES: [(&str, [usize; 2]); 2] = [("720p", [1280, 720]), ("1080p", [1920, 1080])];
+
+/// A video folder of `videos` hips, with a sidecar file beside every tenth
+/// and a subfolder, as operators leave them.
+fn video_dir(videos: usize) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    for i in 0..videos {
+        let hip = format!("{:03}", i % 1000);
+        fs::write(dir.path().join(format!("{} lot {}.mp4", hip, i)), b"").unwrap();
+        if i % 10 == 0 {
+            fs::write(dir.path().join(format!("{} lot {}.txt", hip, i)), b"").unwrap();
+        }
+    }
+    fs::create_dir(dir.path().join("old")).unwrap();
+    dir
+}
+
+fn scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan_video_files");
+    for videos in [100, 1_000, 10_000] {
+        let dir = video_dir(videos);
+        group.throughput(Throughput::Elements(videos as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(videos), &dir, |b, dir| {
+            b.iter(|| scan_video_files(dir.path()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn convert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rgba_image");
+    for (name, [width, height]) in FRAME_SIZES {
+        group.throughput(Throughput::Bytes((width * height * 4) as u64));
+        let packed = vec![128; width * height * 4];
+        group.bench_function(BenchmarkId::new("packed", name), |b| {
+            b.iter(|| rgba_image([width, height], width * 4, &packed))
+        });
+        // Rows padded past the pixels, as FFmpeg aligns them
+        let stride = width * 4 + 64;
+        let padded = vec![128; stride * height];
+        group.bench_function(BenchmarkId::new("padded", name), |b| {
+            b.iter(|| rgba_image([width, height], stride, &padded))
+        });
+    }
+    group.finish();
+}
+
+/// A frame from the decoder thread to a texture: send, take it on the UI
+/// side and upload it, as the window does each new frame.
+fn deliver(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deliver_frame");
+    for (name, size) in FRAME_SIZES {
+        let (tx, mut rx) = watch::channel(None);
+        let frames = FrameSender::new(tx);
+        let ctx = egui::Context::default();
+        frames.repaint(&ctx);
+        let image = egui::ColorImage::new(size, egui::Color32::GRAY);
+        group.throughput(Throughput::Elements(1));
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                frames.send(image.clone()).unwrap();
+                let image = rx.borrow_and_update().clone().unwrap();
+                ctx.load_texture("video_frame", image, Default::default())
+            })
+        });
+    }
+    group.finish();
+}
+
+/// `[output]` adjustments, with and without the saturation pass.
+fn adjust(c: &mut Criterion) {
+    let mut group = c.benchmark_group("adjust_frame");
+    let levels = Adjustment::new(&OutputConfig {
+        brightness: 0.1,
+        ..Default::default()
+    })
+    .unwrap();
+    let saturation = Adjustment::new(&OutputConfig {
+        saturation: 1.2,
+        ..Default::default()
+    })
+    .unwrap();
+    for (name, size) in FRAME_SIZES {
+        group.throughput(Throughput::Bytes((size[0] * size[1] * 4) as u64));
+        let mut image = egui::ColorImage::new(size, egui::Color32::GRAY);
+        group.bench_function(BenchmarkId::new("levels", name), |b| {
+            b.iter(|| levels.apply(&mut image))
+        });
+        group.bench_function(BenchmarkId::new("saturation", name), |b| {
+            b.iter(|| saturation.apply(&mut image))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, scan, convert, deliver, adjust);
+criterion_main!(benches);
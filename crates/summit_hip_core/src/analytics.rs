@@ -0,0 +1,468 @@
+//! Play analytics for the end-of-sale report, enabled with
+//! `[analytics] enabled = true`. Sale companies use it to show consignors how
+//! often their hip's video was shown.
+//!
+//! Every hip requested and every video played is appended to
+//! `plays-<date>.csv` in the analytics folder. A play records how long it was
+//! watched and why it played:
+//!
+//! - `manual`: someone asked for it, on the keypad, the arrow keys or a
+//!   remote control.
+//! - `auto`: a later video of a hip someone asked for.
+//! - `attract`: the player moved on to the next hip by itself.
+//!
+//! The day's report, `report-<date>.csv` and `report-<date>.html`, is
+//! rewritten after every play so it is complete when the sale ends, and
+//! `--report` writes it for any day on demand. The same records give the
+//! [`SaleStats`] shown in the bar, which carry on from them after a restart.
+
+use crate::config::Config;
+use crate::csv;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const HEADER: [&str; 6] = ["time", "event", "hip", "file", "source", "seconds"];
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Why a video played.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Source {
+    Manual,
+    Auto,
+    #[default]
+    Attract,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Source::Manual => "manual",
+            Source::Auto => "auto",
+            Source::Attract => "attract",
+        })
+    }
+}
+
+/// Where the records and reports go.
+pub fn dir(config: &Config) -> PathBuf {
+    match &config.analytics.directory {
+        Some(dir) => PathBuf::from(dir),
+        None => Path::new(&config.logging.file)
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join("analytics"),
+    }
+}
+
+fn records_path(dir: &Path, date: NaiveDate) -> PathBuf {
+    dir.join(format!("plays-{}.csv", date.format("%Y-%m-%d")))
+}
+
+struct Play {
+    hip: String,
+    file: String,
+    source: Source,
+    started: DateTime<Local>,
+}
+
+/// How the sale is going: the lots shown so far today and since when.
+#[derive(Debug, PartialEq)]
+pub struct SaleStats {
+    /// Hips someone asked for or stepped to.
+    pub lots_played: usize,
+    /// Hips in the playlist not played yet.
+    pub remaining: usize,
+    /// Since the first lot played, once one has.
+    pub elapsed: Option<Duration>,
+}
+
+/// Appends the player's requests and plays to the day's records.
+pub struct Recorder {
+    dir: PathBuf,
+    playing: Option<Play>,
+    /// Hips played today, for [`Recorder::stats`].
+    lots: BTreeSet<String>,
+    /// When the first of them was.
+    sale_started: Option<DateTime<Local>>,
+}
+
+impl Recorder {
+    /// Record in `dir`, counting the lots already in today's records.
+    pub fn new(dir: PathBuf) -> Self {
+        let records = fs::read_to_string(records_path(&dir, Local::now().date_naive()));
+        let (lots, sale_started) = lots_played(&records.unwrap_or_default());
+        Self {
+            dir,
+            playing: None,
+            lots,
+            sale_started,
+        }
+    }
+
+    /// A hip was asked for; `file` is the video found for it, if any.
+    pub fn request(&mut self, hip: &str, file: Option<&str>) {
+        let now = Local::now();
+        if file.is_some() {
+            self.lot_played(hip, now);
+        }
+        self.append(now, ["request", hip, file.unwrap_or(""), "manual", ""]);
+    }
+
+    /// A video started. The play is recorded once it ends.
+    pub fn play_started(&mut self, hip: &str, file: &str, source: Source) {
+        let now = Local::now();
+        if source == Source::Manual {
+            self.lot_played(hip, now);
+        }
+        self.playing = Some(Play {
+            hip: hip.to_string(),
+            file: file.to_string(),
+            source,
+            started: now,
+        });
+    }
+
+    fn lot_played(&mut self, hip: &str, at: DateTime<Local>) {
+        self.lots.insert(hip.to_string());
+        self.sale_started.get_or_insert(at);
+    }
+
+    /// The sale so far, out of the playlist's `hips`.
+    pub fn stats(&self, hips: &[String], now: DateTime<Local>) -> SaleStats {
+        SaleStats {
+            lots_played: self.lots.len(),
+            remaining: hips.iter().filter(|hip| !self.lots.contains(*hip)).count(),
+            elapsed: self
+                .sale_started
+                .map(|started| (now - started).to_std().unwrap_or_default()),
+        }
+    }
+
+    /// The video playing since [`Recorder::play_started`] ended after
+    /// `watched` of it was shown.
+    pub fn play_ended(&mut self, watched: Duration) {
+        let Some(play) = self.playing.take() else {
+            return;
+        };
+        let source = play.source.to_string();
+        let seconds = format!("{:.1}", watched.as_secs_f64());
+        self.append(
+            play.started,
+            ["play", &play.hip, &play.file, &source, &seconds],
+        );
+        if let Err(e) = write_report(&self.dir, play.started.date_naive()) {
+            log::warn!("Failed to update the play report: {}", e);
+        }
+    }
+
+    fn append(&self, time: DateTime<Local>, fields: [&str; 5]) {
+        let path = records_path(&self.dir, time.date_naive());
+        let time = time.format(TIME_FORMAT).to_string();
+        let mut line = vec![time.as_str()];
+        line.extend(fields);
+        let mut line = csv::join_line(&line);
+        line.push('\n');
+        if let Err(e) = append_line(&path, &line) {
+            log::warn!("Failed to record play analytics: {}", e);
+        }
+    }
+}
+
+/// The hips asked for or stepped to in `records`, and when the first was.
+fn lots_played(records: &str) -> (BTreeSet<String>, Option<DateTime<Local>>) {
+    let mut lots = BTreeSet::new();
+    let mut started = None;
+    for line in records.lines().skip(1) {
+        let fields = csv::split_line(line);
+        let [time, event, hip, file, source, _] = fields.as_slice() else {
+            continue;
+        };
+        let played = match event.as_str() {
+            "request" => !file.is_empty(),
+            "play" => source == "manual",
+            _ => false,
+        };
+        if !played {
+            continue;
+        }
+        lots.insert(hip.clone());
+        let time = NaiveDateTime::parse_from_str(time, TIME_FORMAT)
+            .ok()
+            .and_then(|time| time.and_local_timezone(Local).earliest());
+        if let Some(time) = time {
+            started = Some(started.map_or(time, |started: DateTime<Local>| started.min(time)));
+        }
+    }
+    (lots, started)
+}
+
+fn append_line(path: &Path, line: &str) -> Result<(), String> {
+    let describe = |e: std::io::Error| format!("{}: {}", path.display(), e);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(describe)?;
+    }
+    let new = !path.exists();
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(describe)?;
+    if new {
+        writeln!(file, "{}", csv::join_line(&HEADER)).map_err(describe)?;
+    }
+    file.write_all(line.as_bytes()).map_err(describe)
+}
+
+/// One hip's line in the report.
+#[derive(Debug, Default, PartialEq)]
+struct HipSummary {
+    requests: usize,
+    plays: usize,
+    manual: usize,
+    auto: usize,
+    attract: usize,
+    seconds: f64,
+    first_shown: Option<String>,
+    last_shown: Option<String>,
+}
+
+fn summarize(records: &str) -> BTreeMap<String, HipSummary> {
+    let mut hips: BTreeMap<String, HipSummary> = BTreeMap::new();
+    for line in records.lines().skip(1).filter(|l| !l.trim().is_empty()) {
+        let fields = csv::split_line(line);
+        let [time, event, hip, _file, source, seconds] = fields.as_slice() else {
+            continue;
+        };
+        let summary = hips.entry(hip.clone()).or_default();
+        match event.as_str() {
+            "request" => summary.requests += 1,
+            "play" => {
+                summary.plays += 1;
+                match source.as_str() {
+                    "manual" => summary.manual += 1,
+                    "auto" => summary.auto += 1,
+                    _ => summary.attract += 1,
+                }
+                summary.seconds += seconds.parse::<f64>().unwrap_or(0.0);
+                summary.first_shown.get_or_insert_with(|| time.clone());
+                summary.last_shown = Some(time.clone());
+            }
+            _ => {}
+        }
+    }
+    hips
+}
+
+const REPORT_COLUMNS: [&str; 9] = [
+    "hip",
+    "requests",
+    "plays",
+    "manual",
+    "auto",
+    "attract",
+    "minutes watched",
+    "first shown",
+    "last shown",
+];
+
+fn report_rows(hips: &BTreeMap<String, HipSummary>) -> Vec<[String; 9]> {
+    hips.iter()
+        .map(|(hip, s)| {
+            [
+                hip.clone(),
+                s.requests.to_string(),
+                s.plays.to_string(),
+                s.manual.to_string(),
+                s.auto.to_string(),
+                s.attract.to_string(),
+                format!("{:.1}", s.seconds / 60.0),
+                s.first_shown.clone().unwrap_or_default(),
+                s.last_shown.clone().unwrap_or_default(),
+            ]
+        })
+        .collect()
+}
+
+fn report_csv(hips: &BTreeMap<String, HipSummary>) -> String {
+    let mut out = csv::join_line(&REPORT_COLUMNS);
+    out.push('\n');
+    for row in report_rows(hips) {
+        out.push_str(&csv::join_line(&row));
+        out.push('\n');
+    }
+    out
+}
+
+fn report_html(date: NaiveDate, hips: &BTreeMap<String, HipSummary>) -> String {
+    let title = format!("Videos shown on {}", date.format("%A %-d %B %Y"));
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>\nbody {{ font-family: sans-serif; }}\n\
+         table {{ border-collapse: collapse; }}\n\
+         th, td {{ border: 1px solid #999; padding: 4px 8px; text-align: right; }}\n\
+         </style>\n</head>\n<body>\n<h1>{title}</h1>\n<table>\n<tr>",
+    );
+    for column in REPORT_COLUMNS {
+        let _ = write!(out, "<th>{}</th>", escape_html(column));
+    }
+    out.push_str("</tr>\n");
+    for row in report_rows(hips) {
+        out.push_str("<tr>");
+        for cell in row {
+            let _ = write!(out, "<td>{}</td>", escape_html(&cell));
+        }
+        out.push_str("</tr>\n");
+    }
+    let plays: usize = hips.values().map(|s| s.plays).sum();
+    let minutes: f64 = hips.values().map(|s| s.seconds).sum::<f64>() / 60.0;
+    let _ = write!(
+        out,
+        "</table>\n<p>{} plays of {} hips, {:.1} minutes in all.</p>\n</body>\n</html>\n",
+        plays,
+        hips.values().filter(|s| s.plays > 0).count(),
+        minutes
+    );
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Write the CSV and HTML reports for `date` from its records in `dir`.
+/// Returns the paths written.
+pub fn write_report(dir: &Path, date: NaiveDate) -> Result<[PathBuf; 2], String> {
+    let records_path = records_path(dir, date);
+    let records = fs::read_to_string(&records_path)
+        .map_err(|e| format!("{}: {}", records_path.display(), e))?;
+    let hips = summarize(&records);
+    let stem = format!("report-{}", date.format("%Y-%m-%d"));
+    let csv_path = dir.join(format!("{}.csv", stem));
+    let html_path = dir.join(format!("{}.html", stem));
+    for (path, contents) in [
+        (&csv_path, report_csv(&hips)),
+        (&html_path, report_html(date, &hips)),
+    ] {
+        fs::write(path, contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok([csv_path, html_path])
+}
+
+/// A `--report` date: `today`, `yesterday` or `YYYY-MM-DD`.
+pub fn parse_date(text: &str) -> Result<NaiveDate, String> {
+    let today = Local::now().date_naive();
+    match text.trim() {
+        "today" => Ok(today),
+        "yesterday" => today
+            .pred_opt()
+            .ok_or_else(|| "no day before today".to_string()),
+        date => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("'{}' is not a date (YYYY-MM-DD, today or yesterday)", date)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_and_report() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut recorder = Recorder::new(temp_dir.path().to_path_buf());
+        recorder.play_started("001", "001.mp4", Source::Attract);
+        recorder.request("042", Some("042, the colt.mp4"));
+        recorder.play_ended(Duration::from_secs(30));
+        recorder.play_started("042", "042, the colt.mp4", Source::Manual);
+        recorder.play_ended(Duration::from_secs(90));
+        recorder.play_started("042", "042 b.mp4", Source::Auto);
+        recorder.play_ended(Duration::from_millis(1500));
+        recorder.request("099", None);
+        // Nothing is playing, so there is nothing to record
+        recorder.play_ended(Duration::from_secs(5));
+
+        let today = Local::now().date_naive();
+        let records = fs::read_to_string(records_path(temp_dir.path(), today)).unwrap();
+        let lines: Vec<Vec<String>> = records.lines().map(csv::split_line).collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], HEADER);
+        assert_eq!(
+            lines[1][1..],
+            ["request", "042", "042, the colt.mp4", "manual", ""]
+        );
+        assert_eq!(lines[2][1..], ["play", "001", "001.mp4", "attract", "30.0"]);
+        assert_eq!(lines[4][1..], ["play", "042", "042 b.mp4", "auto", "1.5"]);
+
+        let hips = summarize(&records);
+        assert_eq!(hips["042"].requests, 1);
+        assert_eq!(hips["042"].plays, 2);
+        assert_eq!((hips["042"].manual, hips["042"].auto), (1, 1));
+        assert_eq!(hips["042"].seconds, 91.5);
+        assert_eq!(hips["001"].attract, 1);
+        assert_eq!(hips["099"].plays, 0);
+
+        let date = today.format("%Y-%m-%d");
+        let report = fs::read_to_string(temp_dir.path().join(format!("report-{}.csv", date)));
+        assert!(report.unwrap().contains("\n042,1,2,1,1,0,1.5,"));
+        let html = fs::read_to_string(temp_dir.path().join(format!("report-{}.html", date)));
+        assert!(html.unwrap().contains("3 plays of 2 hips"));
+    }
+
+    #[test]
+    fn test_sale_stats() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let hips = ["001", "002", "042", "099"].map(String::from);
+        let mut recorder = Recorder::new(temp_dir.path().to_path_buf());
+        let now = Local::now();
+        assert_eq!(
+            recorder.stats(&hips, now),
+            SaleStats {
+                lots_played: 0,
+                remaining: 4,
+                elapsed: None
+            }
+        );
+
+        // Hips moved on to by themselves aren't lots played
+        recorder.play_started("001", "001.mp4", Source::Attract);
+        recorder.play_ended(Duration::from_secs(30));
+        recorder.request("099", None);
+        recorder.request("042", Some("042.mp4"));
+        recorder.play_started("042", "042.mp4", Source::Manual);
+        recorder.play_ended(Duration::from_secs(60));
+        recorder.play_started("002", "002.mp4", Source::Manual);
+        let stats = recorder.stats(&hips, now + chrono::Duration::minutes(5));
+        assert_eq!((stats.lots_played, stats.remaining), (2, 2));
+        assert!(stats.elapsed.unwrap() >= Duration::from_secs(299));
+
+        // A restart carries on from the day's records
+        let recorder = Recorder::new(temp_dir.path().to_path_buf());
+        let stats = recorder.stats(&hips, now + chrono::Duration::minutes(5));
+        assert_eq!((stats.lots_played, stats.remaining), (1, 3));
+        assert!(stats.elapsed.unwrap() >= Duration::from_secs(299));
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(
+            parse_date("2024-05-01").unwrap(),
+            NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+        );
+        assert_eq!(parse_date("today").unwrap(), Local::now().date_naive());
+        assert!(parse_date("May 1").is_err());
+    }
+
+    #[test]
+    fn test_report_without_records() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        assert!(write_report(temp_dir.path(), date).is_err());
+    }
+}
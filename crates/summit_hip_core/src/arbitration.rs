@@ -0,0 +1,107 @@
+//! Which input gets to switch the hip when several are connected. With
+//! `[arbitration] operator_lock` on, the source that switched to the hip on
+//! screen holds it: a switch from a source lower in `priority` doesn't cut it
+//! off. A hip asked for that way is queued and plays once the holder's hip
+//! has finished; next, previous and undo from such a source are dropped.
+
+use crate::config::ArbitrationConfig;
+use crate::control::{Command, Source};
+use log::info;
+
+#[derive(Debug, Default)]
+pub struct Arbiter {
+    /// The source that switched to the hip on screen.
+    holder: Option<Source>,
+    /// The latest hip asked for by a source below the holder.
+    queued: Option<(Source, String)>,
+}
+
+impl Arbiter {
+    /// Whether `command` from `source` should be applied now. Commands
+    /// from no particular source, and commands that don't switch the hip,
+    /// always are.
+    pub fn admit(
+        &mut self,
+        config: &ArbitrationConfig,
+        source: Option<Source>,
+        command: &Command,
+    ) -> bool {
+        let switches = matches!(
+            command,
+            Command::PlayHip(_) | Command::Next | Command::Previous | Command::Undo
+        );
+        let Some(source) = source.filter(|_| config.operator_lock && switches) else {
+            return true;
+        };
+        if let Some(holder) = self.holder {
+            if config.rank(source) > config.rank(holder) {
+                match command {
+                    Command::PlayHip(hip) => {
+                        info!("Queued hip {} from {} behind {}", hip, source, holder);
+                        self.queued = Some((source, hip.clone()));
+                    }
+                    _ => info!("Ignored {:?} from {} behind {}", command, source, holder),
+                }
+                return false;
+            }
+        }
+        self.holder = Some(source);
+        true
+    }
+
+    /// The holder's hip has finished: let go of it, and return the hip
+    /// queued behind it, which then holds the screen.
+    pub fn release(&mut self) -> Option<String> {
+        let queued = self.queued.take();
+        self.holder = queued.as_ref().map(|(source, _)| *source);
+        queued.map(|(_, hip)| hip)
+    }
+
+    pub fn queued_hip(&self) -> Option<&str> {
+        self.queued.as_ref().map(|(_, hip)| hip.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(hip: &str) -> Command {
+        Command::PlayHip(hip.to_string())
+    }
+
+    #[test]
+    fn test_operator_lock() {
+        let mut config = ArbitrationConfig {
+            operator_lock: true,
+            ..ArbitrationConfig::default()
+        };
+        let mut arbiter = Arbiter::default();
+        assert!(arbiter.admit(&config, Some(Source::Auction), &play("041")));
+        assert!(arbiter.admit(&config, Some(Source::Keyboard), &play("042")));
+
+        // The auction software can't take the operator's hip away
+        assert!(!arbiter.admit(&config, Some(Source::Auction), &play("043")));
+        assert!(!arbiter.admit(&config, Some(Source::Http), &Command::Next));
+        assert!(arbiter.admit(&config, Some(Source::Http), &Command::Pause));
+        assert!(arbiter.admit(&config, None, &play("044")));
+        assert!(!arbiter.admit(&config, Some(Source::Auction), &play("045")));
+        assert_eq!(arbiter.queued_hip(), Some("045"));
+
+        assert_eq!(arbiter.release().as_deref(), Some("045"));
+        assert_eq!(arbiter.queued_hip(), None);
+        // Now the auction holds it, which the others outrank
+        assert!(arbiter.admit(&config, Some(Source::Sync), &play("046")));
+        assert!(!arbiter.admit(&config, Some(Source::Auction), &play("047")));
+        assert!(arbiter.admit(&config, Some(Source::Keyboard), &play("048")));
+        assert_eq!(arbiter.release().as_deref(), Some("047"));
+        assert_eq!(arbiter.release(), None);
+
+        // Sources left out of the order rank below all the others
+        config.priority = vec![Source::Auction];
+        assert!(arbiter.admit(&config, Some(Source::Auction), &play("049")));
+        assert!(!arbiter.admit(&config, Some(Source::Keyboard), &play("050")));
+        config.operator_lock = false;
+        assert!(arbiter.admit(&config, Some(Source::Keyboard), &play("050")));
+    }
+}
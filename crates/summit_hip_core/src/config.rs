@@ -0,0 +1,2005 @@
+//! The player's settings, from `config.toml` plus `SHN_*` environment
+//! overrides. [`load`] finds the file in the usual places and falls back to
+//! the defaults when there is none or it doesn't parse.
+
+use crate::control::Source;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+mod types;
+
+pub use types::{HexColor, Ratio01, Seconds};
+
+/// File name used for the configuration in every search location.
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Directory name used under the per-user configuration root.
+const APP_DIR_NAME: &str = "SummitHipNumbers";
+
+/// Everything in `config.toml`, one field per section.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Config {
+    pub video: VideoConfig,
+    pub splash: SplashConfig,
+    pub logging: LoggingConfig,
+    pub ui: UiConfig,
+    pub demo: DemoConfig,
+    #[serde(default)]
+    pub lock: LockConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub tcp: TcpConfig,
+    #[serde(default)]
+    pub serial: SerialConfig,
+    #[serde(default)]
+    pub mqtt: MqttConfig,
+    #[serde(default)]
+    pub osc: OscConfig,
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+    #[serde(default)]
+    pub auction: AuctionConfig,
+    #[serde(default)]
+    pub sync: SyncConfig,
+    #[serde(default)]
+    pub mdns: MdnsConfig,
+    #[serde(default)]
+    pub heartbeat: HeartbeatConfig,
+    #[serde(default)]
+    pub analytics: AnalyticsConfig,
+    #[serde(default)]
+    pub resources: ResourcesConfig,
+    #[serde(default)]
+    pub audio: AudioConfig,
+    #[serde(default)]
+    pub music: MusicConfig,
+    #[serde(default)]
+    pub session: SessionConfig,
+    #[serde(default)]
+    pub entry_overlay: EntryOverlayConfig,
+    #[serde(default)]
+    pub clock: ClockConfig,
+    #[serde(default)]
+    pub bar: BarConfig,
+    #[serde(default)]
+    pub display: DisplayConfig,
+    #[serde(default)]
+    pub output: OutputConfig,
+    #[serde(default)]
+    pub media_source: MediaSourceConfig,
+    #[serde(default)]
+    pub media_cache: MediaCacheConfig,
+    #[serde(default)]
+    pub arbitration: ArbitrationConfig,
+    /// Per-hip overrides keyed by 3-digit hip number, e.g. `[hips."042"]`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hips: BTreeMap<String, HipOverride>,
+    /// Sale rings sharing the machine, each with its own videos.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rings: Vec<RingConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VideoConfig {
+    pub directory: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SplashConfig {
+    pub enabled: bool,
+    pub duration_seconds: Seconds,
+    pub text: String,
+    pub background_color: HexColor,
+    pub text_color: HexColor,
+    pub interval: usize, // Show splash every N videos (0 = only at startup)
+    pub rotation_mode: Option<String>, // "cycle", "random", or "static"
+    pub static_splash_path: Option<String>, // For "static" mode
+    pub directory: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LoggingConfig {
+    pub file: String,
+    pub max_lines: usize,
+    /// Collector to forward records to: `udp://host:514`, `tcp://host:601`
+    /// or an `http(s)://` URL.
+    #[serde(default)]
+    pub remote: Option<String>,
+    /// Lowest level forwarded to `remote`, `info` if unset.
+    #[serde(default)]
+    pub remote_level: Option<String>,
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Lowest level logged, `debug` if unset.
+    #[serde(default)]
+    pub level: Option<String>,
+    /// Levels for individual targets, e.g. `eframe = "warn"`. A target also
+    /// covers its submodules.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub levels: BTreeMap<String, String>,
+}
+
+/// Parse a log level from the config: `off`, `error`, `warn`, `info`,
+/// `debug` or `trace`.
+pub fn parse_level(level: &str) -> Result<log::LevelFilter, String> {
+    level.trim().parse().map_err(|_| {
+        format!(
+            "'{}' is not a log level (off, error, warn, info, debug or trace)",
+            level
+        )
+    })
+}
+
+/// How lines in the log file and on stdout are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+/// What the player does when the config file doesn't match its signature.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockMode {
+    #[default]
+    Off,
+    Warn,
+    Refuse,
+}
+
+/// The configuration GUI's PIN. What the config file is checked against
+/// comes from the build or a file beside the player, never from the file
+/// itself.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LockConfig {
+    /// Salted PIN hash; when set the configuration GUI opens read-only.
+    pub pin_hash: Option<String>,
+}
+
+/// Remote control API, only served by builds with the `http-api` feature.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    pub enabled: bool,
+    /// Address and port to listen on; use `0.0.0.0:<port>` to accept
+    /// connections from other machines.
+    pub bind: String,
+    /// When set, requests must send `Authorization: Bearer <token>`.
+    pub token: Option<String>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "127.0.0.1:8080".to_string(),
+            token: None,
+        }
+    }
+}
+
+/// Line-based TCP control for AV control processors.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TcpConfig {
+    pub enabled: bool,
+    pub bind: String,
+}
+
+impl Default for TcpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "0.0.0.0:5250".to_string(),
+        }
+    }
+}
+
+/// RS-232 control using the same commands as `[tcp]`, only served by builds
+/// with the `serial` feature.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SerialConfig {
+    pub enabled: bool,
+    /// Device name, e.g. `/dev/ttyUSB0` or `COM3`.
+    pub port: String,
+    pub baud: u32,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: if cfg!(windows) {
+                "COM1"
+            } else {
+                "/dev/ttyUSB0"
+            }
+            .to_string(),
+            baud: 9600,
+        }
+    }
+}
+
+/// MQTT status and control, only used by builds with the `mqtt` feature.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    /// Must be unique per kiosk on the broker.
+    pub client_id: String,
+    /// Topics are `<topic_prefix>/status`, `<topic_prefix>/command`, etc.
+    pub topic_prefix: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// 0 turns the heartbeat off.
+    pub heartbeat_seconds: u64,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            client_id: "summit_hip_numbers".to_string(),
+            topic_prefix: "summit_hip_numbers".to_string(),
+            username: None,
+            password: None,
+            heartbeat_seconds: 30,
+        }
+    }
+}
+
+/// OSC over UDP for lighting and video switcher cues.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OscConfig {
+    pub enabled: bool,
+    pub bind: String,
+    /// Where to send `/summit/nowplaying`, e.g. `192.168.1.20:53000`.
+    pub send_to: Option<String>,
+}
+
+impl Default for OscConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "0.0.0.0:8000".to_string(),
+            send_to: None,
+        }
+    }
+}
+
+/// HTTP callbacks on playback events, only used by builds with the
+/// `webhooks` feature.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub enabled: bool,
+    /// URL templates; see CONFIGURATION.md for the placeholders.
+    pub urls: Vec<String>,
+    /// "POST" sends the event as JSON, "GET" only calls the URL.
+    pub method: String,
+    /// Event names: video_started, video_ended, error, hip_entered, splash_shown.
+    pub events: Vec<String>,
+    pub timeout_seconds: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            urls: Vec::new(),
+            method: "POST".to_string(),
+            events: vec![
+                "video_started".to_string(),
+                "video_ended".to_string(),
+                "error".to_string(),
+            ],
+            timeout_seconds: 5,
+        }
+    }
+}
+
+/// Polling the auction management software for the lot in the ring, only
+/// used by builds with the `auction` feature.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AuctionConfig {
+    pub enabled: bool,
+    /// An http(s) URL or the path of a shared CSV file.
+    pub source: String,
+    pub poll_seconds: u64,
+    /// Play the lot's video as soon as it enters the ring.
+    pub auto_switch: bool,
+    /// Where the lot is in a JSON response, e.g. `/ring/current_lot`.
+    pub json_pointer: String,
+    /// CSV column holding the lot number.
+    pub column: String,
+}
+
+impl Default for AuctionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            source: String::new(),
+            poll_seconds: 5,
+            auto_switch: false,
+            json_pointer: "/lot".to_string(),
+            column: "lot".to_string(),
+        }
+    }
+}
+
+/// Role of this kiosk when several show the same lot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncMode {
+    #[default]
+    Off,
+    Leader,
+    Follower,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    pub mode: SyncMode,
+    /// Followers only follow a leader with the same group, e.g. one per ring.
+    pub group: String,
+    /// Where the leader sends its state; a broadcast or follower address.
+    pub address: String,
+    /// Where followers listen.
+    pub bind: String,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            mode: SyncMode::Off,
+            group: "default".to_string(),
+            address: "255.255.255.255:5260".to_string(),
+            bind: "0.0.0.0:5260".to_string(),
+        }
+    }
+}
+
+/// Zeroconf advertising, only used by builds with the `mdns` feature.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MdnsConfig {
+    pub enabled: bool,
+    /// Name shown to other kiosks; defaults to the computer's host name.
+    pub name: Option<String>,
+}
+
+/// Signs of life for an external watchdog.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HeartbeatConfig {
+    pub enabled: bool,
+    /// Seconds between heartbeats; `/healthz` fails after three missed ones.
+    pub interval_seconds: u64,
+    /// File rewritten with the current time on every heartbeat.
+    pub file: Option<String>,
+    /// Ping the systemd watchdog when run as a `Type=notify` service.
+    pub systemd: bool,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_seconds: 5,
+            file: None,
+            systemd: true,
+        }
+    }
+}
+
+/// Records of what was requested and shown, for the end-of-sale report.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AnalyticsConfig {
+    pub enabled: bool,
+    /// Folder for the play records and reports; `analytics` next to the log
+    /// file if unset.
+    pub directory: Option<String>,
+    /// Show the lots played, the hips left and the time since the first lot
+    /// in the bar's `stats` widget.
+    pub show_in_bar: bool,
+}
+
+/// Ceilings on the memory the player holds on to, in megabytes; 0 for none.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ResourcesConfig {
+    /// Video frame, splash image and thumbnail textures. Thumbnails shown
+    /// longest ago are dropped first; a splash image that won't fit is shown
+    /// scaled down.
+    pub texture_mb: u64,
+    /// Decoded audio waiting to be played. Decoding waits at the ceiling.
+    pub audio_buffer_mb: u64,
+}
+
+impl Default for ResourcesConfig {
+    fn default() -> Self {
+        Self {
+            texture_mb: 256,
+            audio_buffer_mb: 16,
+        }
+    }
+}
+
+/// The output devices the sound plays on.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    /// Output device by name; the system default if unset.
+    pub device: Option<String>,
+    /// Volume multiplier on this output: 1.0 unchanged, 0.0 muted.
+    pub gain: f32,
+    /// A second device, such as the broadcast mixer, that gets the videos'
+    /// sound too. The background music stays on the first.
+    pub broadcast: Option<AudioOutputConfig>,
+    /// Warn the operator when a video with an audio track plays this long
+    /// without making a sound; 0 never warns.
+    pub silence_warning_seconds: Seconds,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            device: None,
+            gain: 1.0,
+            broadcast: None,
+            silence_warning_seconds: Seconds::clamped(10.0),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AudioOutputConfig {
+    pub device: String,
+    /// Volume multiplier on this output, independent of the first.
+    #[serde(default = "unity_gain")]
+    pub gain: f32,
+}
+
+fn unity_gain() -> f32 {
+    1.0
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// Background music under the splash screen and between videos.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MusicConfig {
+    pub enabled: bool,
+    /// An audio file, or a folder whose audio files play in name order.
+    /// Either way it loops. Relative paths are resolved like the video folder.
+    pub path: String,
+    pub volume: Ratio01,
+    /// Volume while a video with sound plays: 0.0 silences the music, the
+    /// same as `volume` keeps it going underneath.
+    pub ducked_volume: Ratio01,
+}
+
+impl Default for MusicConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "music".to_string(),
+            volume: Ratio01::clamped(0.5),
+            ducked_volume: Ratio01::clamped(0.0),
+        }
+    }
+}
+
+/// What the player does on startup when it didn't exit cleanly last time,
+/// after a power cut or a crash.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResumeMode {
+    /// Offer to resume, and resume once the offer times out.
+    #[default]
+    Ask,
+    Always,
+    /// Start from the first hip.
+    Never,
+}
+
+/// Picking up where the player left off.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub resume: ResumeMode,
+    /// How long the resume offer waits for an answer.
+    pub prompt_seconds: Seconds,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            resume: ResumeMode::Ask,
+            prompt_seconds: Seconds::clamped(15.0),
+        }
+    }
+}
+
+/// The hip number being typed, in large type over the video, so the
+/// auctioneer can check it from across the ring.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EntryOverlayConfig {
+    pub enabled: bool,
+    pub font_size: f32,
+    /// Where its center is, as a fraction of the video's width from the left.
+    pub x: Ratio01,
+    /// Where its center is, as a fraction of the video's height from the top.
+    pub y: Ratio01,
+    /// How long it stays after the last key, or after Enter.
+    pub hold_seconds: Seconds,
+    /// How long it then takes to fade away.
+    pub fade_seconds: Seconds,
+}
+
+impl Default for EntryOverlayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            font_size: 160.0,
+            x: Ratio01::clamped(0.5),
+            y: Ratio01::clamped(0.5),
+            hold_seconds: Seconds::clamped(2.0),
+            fade_seconds: Seconds::clamped(0.5),
+        }
+    }
+}
+
+/// When the lot countdown starts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CountdownStart {
+    /// As each hip's video starts.
+    #[default]
+    Video,
+    /// Only when F4 is pressed.
+    Hotkey,
+}
+
+/// The time of day and the lot countdown in the bar, for pacing bidding.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ClockConfig {
+    /// Show the time of day in the bar's `clock` widget.
+    pub show_clock: bool,
+    /// How the time is written, in `strftime` format.
+    pub format: String,
+    /// How long each lot's countdown runs; no countdown if unset.
+    pub countdown_seconds: Option<Seconds>,
+    pub countdown_start: CountdownStart,
+    /// The countdown turns red with this much left.
+    pub warning_seconds: Seconds,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            show_clock: false,
+            format: "%H:%M".to_string(),
+            countdown_seconds: None,
+            countdown_start: CountdownStart::Video,
+            warning_seconds: Seconds::clamped(10.0),
+        }
+    }
+}
+
+/// What can go in a slot of the bar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BarWidget {
+    /// The hip number field and its label.
+    Input,
+    /// The video playing, and the lot in the ring or the hip queued.
+    NowPlaying,
+    /// The time of day and lot countdown, as set in `[clock]`.
+    Clock,
+    /// `ticker_text` scrolling by.
+    Ticker,
+    /// The company logo, or `[ui] company_label` without one.
+    Logo,
+    /// The sale so far, with `[analytics] show_in_bar`.
+    Stats,
+    /// The audio level and the hip's trim.
+    Meter,
+}
+
+/// Which widgets the bar shows, left to right in each of its three slots.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BarConfig {
+    pub left: Vec<BarWidget>,
+    /// Stacked when there are several.
+    pub center: Vec<BarWidget>,
+    pub right: Vec<BarWidget>,
+    pub ticker_text: String,
+    /// How fast the ticker scrolls, in points a second.
+    pub ticker_speed: f32,
+    /// How wide the ticker is, in points.
+    pub ticker_width: f32,
+}
+
+impl BarConfig {
+    /// Whether `widget` is in any slot.
+    pub fn shows(&self, widget: BarWidget) -> bool {
+        [&self.left, &self.center, &self.right]
+            .iter()
+            .any(|slot| slot.contains(&widget))
+    }
+}
+
+impl Default for BarConfig {
+    fn default() -> Self {
+        Self {
+            left: vec![BarWidget::Input, BarWidget::Stats],
+            center: vec![BarWidget::NowPlaying],
+            right: vec![BarWidget::Meter, BarWidget::Logo, BarWidget::Clock],
+            ticker_text: String::new(),
+            ticker_speed: 60.0,
+            ticker_width: 400.0,
+        }
+    }
+}
+
+/// The windowing system to open the windows on, on Linux.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayBackend {
+    /// Wayland when there's a Wayland session, else X11.
+    #[default]
+    Auto,
+    X11,
+    Wayland,
+}
+
+/// What draws the windows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Renderer {
+    /// OpenGL.
+    #[default]
+    Glow,
+    /// wgpu, on the graphics API of `[display] graphics_api`.
+    Wgpu,
+}
+
+/// The graphics API wgpu draws with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsApi {
+    /// The best the machine has.
+    #[default]
+    Auto,
+    Vulkan,
+    /// Direct3D 12, on Windows.
+    Dx12,
+    /// On macOS.
+    Metal,
+    /// OpenGL, or OpenGL ES.
+    Gl,
+}
+
+/// How the windows are opened and drawn, for getting around flaky graphics
+/// drivers on kiosk hardware. Read at startup.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    pub backend: DisplayBackend,
+    pub renderer: Renderer,
+    /// Only with `renderer = "wgpu"`.
+    pub graphics_api: GraphicsApi,
+}
+
+/// Adjustments to the video on its way to the screen, for projectors and LED
+/// walls that the venue can't calibrate. The defaults leave it as it is.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    /// Added to every channel, from -1 (all black) to 1 (all white).
+    pub brightness: f32,
+    /// How far colors spread from mid-grey, from 0 (flat grey) to 2.
+    pub contrast: f32,
+    /// From 0 (black and white) to 2.
+    pub saturation: f32,
+    /// Above 1 lifts the midtones and below 1 darkens them, from 0.2 to 5.
+    pub gamma: f32,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            gamma: 1.0,
+        }
+    }
+}
+
+/// Checks that the video directory can still be read, for one on an SMB or
+/// NFS share that can drop out during a sale.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MediaSourceConfig {
+    /// Seconds between checks; 0 never checks.
+    pub check_seconds: Seconds,
+    /// A check taking longer than this counts as offline, as with a share
+    /// whose server has gone away.
+    pub timeout_seconds: Seconds,
+    /// Run through the shell when the directory goes offline, and after
+    /// every failed check until it's back, e.g. `mount /mnt/sale`.
+    pub reconnect_command: Option<String>,
+}
+
+impl Default for MediaSourceConfig {
+    fn default() -> Self {
+        Self {
+            check_seconds: Seconds::clamped(0.0),
+            timeout_seconds: Seconds::clamped(3.0),
+            reconnect_command: None,
+        }
+    }
+}
+
+/// Copies of the videos on the local disk, played in their place, for a
+/// video directory on a USB stick or another slow or removable drive.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MediaCacheConfig {
+    pub enabled: bool,
+    /// Folder for the copies; `media_cache` next to the log file if unset.
+    pub directory: Option<String>,
+    /// Most the copies may take up, in megabytes; the copies played longest
+    /// ago are removed to make room.
+    pub max_size_mb: u64,
+    /// Copy every video when the directory is scanned, rather than each on
+    /// its first play.
+    pub copy_all: bool,
+}
+
+impl Default for MediaCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: None,
+            max_size_mb: 20_000,
+            copy_all: false,
+        }
+    }
+}
+
+/// Which input may switch the hip when several are connected; see
+/// [`crate::arbitration`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ArbitrationConfig {
+    /// Don't let a lower-priority source cut off the hip on screen.
+    pub operator_lock: bool,
+    /// Sources from highest to lowest; any left out rank below them all.
+    pub priority: Vec<Source>,
+}
+
+impl ArbitrationConfig {
+    /// Where `source` is in the priority order, 0 first.
+    pub fn rank(&self, source: Source) -> usize {
+        self.priority
+            .iter()
+            .position(|s| *s == source)
+            .unwrap_or(self.priority.len())
+    }
+}
+
+impl Default for ArbitrationConfig {
+    fn default() -> Self {
+        Self {
+            operator_lock: false,
+            priority: vec![
+                Source::Keyboard,
+                Source::Http,
+                Source::Tcp,
+                Source::Serial,
+                Source::Mqtt,
+                Source::Osc,
+                Source::Sync,
+                Source::Auction,
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DemoConfig {
+    pub timeout_seconds: u64,
+    pub max_videos: usize,
+    pub hip_number_limit: u32,
+}
+
+/// Settings that replace the defaults for one hip. Unset fields keep the
+/// normal behavior.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HipOverride {
+    /// Audio volume multiplier: 1.0 unchanged, 0.5 half, 0.0 muted.
+    pub gain: Option<f32>,
+    /// Stay on the final frame instead of advancing to the next hip.
+    pub hold_last_frame: Option<bool>,
+    /// Never show a splash screen before this hip.
+    pub skip_splash: Option<bool>,
+    /// Text shown after "now playing" instead of the file name.
+    pub label: Option<String>,
+    /// A live `rtsp://` or `http(s)://` source played for this hip, e.g. a
+    /// camera on the walking ring. It needs no video file.
+    pub stream: Option<String>,
+    /// A reminder for the operator when the hip is called, e.g. "no audio
+    /// per consignor". Shown on the dashboard, never on screen.
+    pub note: Option<String>,
+    /// Where in its videos to start, skipping the rest of the front.
+    pub start_seconds: Option<Seconds>,
+    /// Where in its videos to end, as if they ended there.
+    pub end_seconds: Option<Seconds>,
+}
+
+impl HipOverride {
+    /// Overlay `other` on top of `self`; fields set in `other` win.
+    pub fn merge(&mut self, other: &HipOverride) {
+        if other.gain.is_some() {
+            self.gain = other.gain;
+        }
+        if other.hold_last_frame.is_some() {
+            self.hold_last_frame = other.hold_last_frame;
+        }
+        if other.skip_splash.is_some() {
+            self.skip_splash = other.skip_splash;
+        }
+        if other.label.is_some() {
+            self.label.clone_from(&other.label);
+        }
+        if other.stream.is_some() {
+            self.stream.clone_from(&other.stream);
+        }
+        if other.note.is_some() {
+            self.note.clone_from(&other.note);
+        }
+        if other.start_seconds.is_some() {
+            self.start_seconds = other.start_seconds;
+        }
+        if other.end_seconds.is_some() {
+            self.end_seconds = other.end_seconds;
+        }
+    }
+}
+
+/// One sale ring run off this machine, `[[rings]]`: its own video folder,
+/// and the branding and window position it sets over `[ui]`. The player
+/// starts on the first and F6 or a `ring` command switches between them.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RingConfig {
+    /// What the operator and remote commands call it, e.g. "Ring B".
+    pub name: String,
+    pub video_directory: String,
+    #[serde(default)]
+    pub company_label: Option<String>,
+    #[serde(default)]
+    pub background_color: Option<HexColor>,
+    /// Where the window goes for this ring, e.g. on the second monitor;
+    /// `[ui] window_x` and `window_y` if unset.
+    #[serde(default)]
+    pub window_x: Option<f32>,
+    #[serde(default)]
+    pub window_y: Option<f32>,
+}
+
+impl Config {
+    /// This config as ring `index` of `[[rings]]` has it; unchanged without
+    /// such a ring.
+    pub fn for_ring(&self, index: usize) -> Config {
+        let mut config = self.clone();
+        let Some(ring) = self.rings.get(index) else {
+            return config;
+        };
+        config.video.directory.clone_from(&ring.video_directory);
+        if let Some(label) = &ring.company_label {
+            config.ui.company_label.clone_from(label);
+        }
+        if let Some(color) = ring.background_color {
+            config.ui.background_color = color;
+        }
+        if ring.window_x.is_some() {
+            config.ui.window_x = ring.window_x;
+        }
+        if ring.window_y.is_some() {
+            config.ui.window_y = ring.window_y;
+        }
+        config
+    }
+
+    /// The index of the ring called `name`, ignoring case.
+    pub fn ring_index(&self, name: &str) -> Option<usize> {
+        self.rings
+            .iter()
+            .position(|ring| ring.name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UiConfig {
+    pub input_label: String,
+    pub now_playing_label: String,
+    pub company_label: String,
+    pub input_text_color: HexColor,
+    pub input_stroke_color: HexColor,
+    pub label_color: HexColor,
+    pub background_color: HexColor,
+    pub kiosk_mode: bool,
+    /// How the window is shown; `fullscreen` with `kiosk_mode`, `windowed`
+    /// without, if unset.
+    #[serde(default)]
+    pub kiosk_style: Option<KioskStyle>,
+    pub enable_arrow_nav: bool,
+    pub window_width: f32,
+    pub window_height: f32,
+    /// Where the window's top left corner goes, in points. A borderless
+    /// window goes in the top left corner of the screen if unset.
+    #[serde(default)]
+    pub window_x: Option<f32>,
+    #[serde(default)]
+    pub window_y: Option<f32>,
+    /// Keep the window above every other window.
+    #[serde(default)]
+    pub always_on_top: bool,
+    /// Take the focus, and fullscreen, back from any program that takes it.
+    #[serde(default)]
+    pub keep_focus: bool,
+    /// When the mouse cursor is hidden, outside a normal window.
+    #[serde(default)]
+    pub hide_cursor: HideCursor,
+    /// How long the mouse is left alone before the cursor hides with
+    /// `hide_cursor = "idle"`, [`DEFAULT_CURSOR_IDLE`] if unset.
+    #[serde(default)]
+    pub cursor_idle_seconds: Option<Seconds>,
+    /// Let mouse clicks on the video do nothing, outside a normal window.
+    #[serde(default = "enabled")]
+    pub ignore_video_clicks: bool,
+    pub video_height_ratio: Ratio01,
+    pub bar_height_ratio: Ratio01,
+    pub splash_font_size: f32,
+    pub placeholder_font_size: f32,
+    pub demo_watermark_font_size: f32,
+    pub input_field_width: f32,
+    pub input_max_length: usize,
+    pub demo_watermark_x_offset: f32,
+    pub demo_watermark_y_offset: f32,
+    pub demo_watermark_width: f32,
+    pub demo_watermark_height: f32,
+    pub ui_spacing: f32,
+    pub stroke_width: f32,
+    pub invalid_input_timeout: Seconds,
+    pub no_video_popup_timeout: Seconds,
+    /// Longest the player window goes without repainting while nothing on
+    /// screen changes, [`DEFAULT_IDLE_REPAINT`] if unset; 0 repaints
+    /// continuously. Remote commands and background timers are handled on
+    /// these idle repaints.
+    #[serde(default)]
+    pub idle_repaint_seconds: Option<Seconds>,
+    /// How soon the hip on screen can be entered again without restarting
+    /// it, [`DEFAULT_REPEAT_ENTRY`] if unset; 0 always restarts it.
+    #[serde(default)]
+    pub repeat_entry_seconds: Option<Seconds>,
+}
+
+/// How the player's window is shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KioskStyle {
+    /// Exclusive fullscreen on the screen it opens on.
+    Fullscreen,
+    /// A window without a title bar or borders at a fixed position and size,
+    /// for LED wall processors and capture cards that don't take to
+    /// fullscreen.
+    Borderless,
+    /// An ordinary window.
+    Windowed,
+}
+
+/// When the mouse cursor is hidden over the player.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HideCursor {
+    Never,
+    /// Once the mouse has been left alone for `cursor_idle_seconds`.
+    #[default]
+    Idle,
+    Always,
+}
+
+/// `[ui] cursor_idle_seconds` when unset.
+pub const DEFAULT_CURSOR_IDLE: Duration = Duration::from_secs(3);
+
+/// `[ui] idle_repaint_seconds` when unset.
+pub const DEFAULT_IDLE_REPAINT: Duration = Duration::from_millis(250);
+
+/// `[ui] repeat_entry_seconds` when unset.
+pub const DEFAULT_REPEAT_ENTRY: Duration = Duration::from_secs(2);
+
+impl UiConfig {
+    pub fn kiosk_style(&self) -> KioskStyle {
+        self.kiosk_style.unwrap_or(if self.kiosk_mode {
+            KioskStyle::Fullscreen
+        } else {
+            KioskStyle::Windowed
+        })
+    }
+
+    pub fn cursor_idle(&self) -> Duration {
+        self.cursor_idle_seconds
+            .map_or(DEFAULT_CURSOR_IDLE, |seconds| {
+                Duration::try_from_secs_f64(seconds.get()).unwrap_or(Duration::MAX)
+            })
+    }
+
+    pub fn idle_repaint(&self) -> Duration {
+        self.idle_repaint_seconds
+            .map_or(DEFAULT_IDLE_REPAINT, |seconds| {
+                Duration::try_from_secs_f64(seconds.get()).unwrap_or(Duration::MAX)
+            })
+    }
+
+    pub fn repeat_entry(&self) -> Duration {
+        self.repeat_entry_seconds
+            .map_or(DEFAULT_REPEAT_ENTRY, |seconds| {
+                Duration::try_from_secs_f64(seconds.get()).unwrap_or(Duration::MAX)
+            })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            video: VideoConfig {
+                directory: "./videos".to_string(),
+            },
+            splash: SplashConfig {
+                enabled: true,
+                duration_seconds: Seconds::clamped(3.0),
+                text: "Summit Professional Services".to_string(),
+                background_color: HexColor::BLACK,
+                text_color: HexColor::WHITE,
+                interval: 0,
+                rotation_mode: None,
+                static_splash_path: None,
+                directory: "./splash".to_string(),
+            },
+            logging: LoggingConfig {
+                file: "summit_hip_numbers.log".to_string(),
+                max_lines: 10000,
+                remote: None,
+                remote_level: None,
+                format: LogFormat::Text,
+                level: None,
+                levels: BTreeMap::new(),
+            },
+            ui: UiConfig {
+                input_label: "3-digit hip number:".to_string(),
+                now_playing_label: "now playing".to_string(),
+                company_label: "SUMMIT PROFESSIONAL Solutions".to_string(),
+                input_text_color: HexColor::WHITE,
+                input_stroke_color: HexColor::WHITE,
+                label_color: HexColor::WHITE,
+                background_color: HexColor::BLACK,
+                kiosk_mode: true,
+                kiosk_style: None,
+                enable_arrow_nav: true,
+                window_width: 1920.0,
+                window_height: 1080.0,
+                window_x: None,
+                window_y: None,
+                always_on_top: false,
+                keep_focus: false,
+                hide_cursor: HideCursor::Idle,
+                cursor_idle_seconds: None,
+                ignore_video_clicks: true,
+                video_height_ratio: Ratio01::clamped(0.92),
+                bar_height_ratio: Ratio01::clamped(0.08),
+                splash_font_size: 48.0,
+                placeholder_font_size: 48.0,
+                demo_watermark_font_size: 24.0,
+                input_field_width: 45.0,
+                input_max_length: 3,
+                demo_watermark_x_offset: 200.0,
+                demo_watermark_y_offset: 10.0,
+                demo_watermark_width: 180.0,
+                demo_watermark_height: 30.0,
+                ui_spacing: 10.0,
+                stroke_width: 1.0,
+                invalid_input_timeout: Seconds::clamped(0.5),
+                no_video_popup_timeout: Seconds::clamped(3.0),
+                idle_repaint_seconds: None,
+                repeat_entry_seconds: None,
+            },
+            demo: DemoConfig {
+                timeout_seconds: 300,
+                max_videos: 5,
+                hip_number_limit: 5,
+            },
+            lock: LockConfig::default(),
+            http: HttpConfig::default(),
+            tcp: TcpConfig::default(),
+            serial: SerialConfig::default(),
+            mqtt: MqttConfig::default(),
+            osc: OscConfig::default(),
+            webhooks: WebhookConfig::default(),
+            auction: AuctionConfig::default(),
+            sync: SyncConfig::default(),
+            mdns: MdnsConfig::default(),
+            heartbeat: HeartbeatConfig::default(),
+            analytics: AnalyticsConfig::default(),
+            resources: ResourcesConfig::default(),
+            audio: AudioConfig::default(),
+            music: MusicConfig::default(),
+            session: SessionConfig::default(),
+            entry_overlay: EntryOverlayConfig::default(),
+            clock: ClockConfig::default(),
+            bar: BarConfig::default(),
+            display: DisplayConfig::default(),
+            output: OutputConfig::default(),
+            media_source: MediaSourceConfig::default(),
+            media_cache: MediaCacheConfig::default(),
+            arbitration: ArbitrationConfig::default(),
+            hips: BTreeMap::new(),
+            rings: Vec::new(),
+        }
+    }
+}
+
+/// Directory containing the running executable. In a macOS `.app` bundle
+/// that is `Contents/Resources`, where the config and assets are packaged.
+/// If the executable can't be found, the working directory stands in.
+pub fn exe_dir() -> PathBuf {
+    let dir = match std::env::current_exe() {
+        Ok(exe) => exe.parent().map(Path::to_path_buf).unwrap_or_default(),
+        Err(e) => {
+            warn!(
+                "Can't find the running executable, using the working directory: {}",
+                e
+            );
+            PathBuf::from(".")
+        }
+    };
+    app_bundle_resources(&dir).unwrap_or(dir)
+}
+
+/// Whether the player runs from a cargo `target` directory, in development.
+fn in_cargo_target(exe_dir: &Path) -> bool {
+    exe_dir.to_string_lossy().contains("target")
+}
+
+/// `Contents/Resources` for an executable in an `.app`'s `Contents/MacOS`.
+fn app_bundle_resources(exe_dir: &Path) -> Option<PathBuf> {
+    let contents = exe_dir.parent()?;
+    let in_bundle = exe_dir.file_name()? == "MacOS"
+        && contents.file_name()? == "Contents"
+        && contents.parent()?.extension()? == "app";
+    in_bundle.then(|| contents.join("Resources"))
+}
+
+/// Resolve a video/splash directory from the config. Absolute paths are used
+/// as-is. Relative paths are taken from the repo `assets/` folder when running
+/// from a cargo `target` directory, otherwise from the executable directory.
+pub fn resolve_asset_dir(dir: &str) -> PathBuf {
+    let dir_path = Path::new(dir);
+    let exe_dir = exe_dir();
+    if dir_path.is_absolute() {
+        // Absolute path (e.g., from tests)
+        dir_path.to_path_buf()
+    } else if in_cargo_target(&exe_dir) {
+        // Development: use repo assets
+        // Check if the config path already contains "assets"
+        let cwd = std::env::current_dir().unwrap_or_default();
+        if dir.contains("assets") {
+            cwd.join(dir)
+        } else {
+            cwd.join("assets").join(dir)
+        }
+    } else {
+        // Production: use exe dir
+        exe_dir.join(dir)
+    }
+}
+
+/// Logo shown in the bottom bar: `assets/logo/logo.svg` during development,
+/// otherwise `logo/logo.svg` next to the executable.
+pub fn logo_path() -> PathBuf {
+    let exe_dir = exe_dir();
+    if in_cargo_target(&exe_dir) {
+        // Development: use repo assets
+        std::env::current_dir()
+            .unwrap_or_default()
+            .join("assets")
+            .join("logo")
+            .join("logo.svg")
+    } else {
+        // Production: use exe dir
+        exe_dir.join("logo").join("logo.svg")
+    }
+}
+
+/// Per-user configuration path: `$XDG_CONFIG_HOME` (or `~/.config`) on Linux,
+/// `~/Library/Application Support` on macOS and `%APPDATA%` on Windows.
+pub fn user_config_path() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let base = std::env::var_os("APPDATA").map(PathBuf::from);
+
+    #[cfg(target_os = "macos")]
+    let base = std::env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join("Library")
+            .join("Application Support")
+    });
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+    base.map(|dir| dir.join(APP_DIR_NAME).join(CONFIG_FILE_NAME))
+}
+
+/// Locations checked for a config file, in priority order: an explicit
+/// `--config-path`, the per-user config path, then next to the executable.
+pub fn search_paths(explicit: Option<&Path>) -> Vec<PathBuf> {
+    if let Some(path) = explicit {
+        return vec![path.to_path_buf()];
+    }
+    let mut paths = Vec::new();
+    if let Some(user) = user_config_path() {
+        paths.push(user);
+    }
+    paths.push(exe_dir().join(CONFIG_FILE_NAME));
+    paths
+}
+
+/// First existing config file in the search order.
+pub fn locate(explicit: Option<&Path>) -> Option<PathBuf> {
+    search_paths(explicit).into_iter().find(|p| p.is_file())
+}
+
+/// Where configuration changes should be written. An explicit path always wins;
+/// otherwise the currently active file is reused if it can be written (e.g. a
+/// portable USB install), falling back to the per-user location for read-only
+/// installs such as Program Files or /usr/bin.
+pub fn save_path(explicit: Option<&Path>) -> PathBuf {
+    if let Some(path) = explicit {
+        return path.to_path_buf();
+    }
+    let exe_config = exe_dir().join(CONFIG_FILE_NAME);
+    if let Some(active) = locate(None) {
+        if is_writable(&active) {
+            return active;
+        }
+    } else if is_writable(&exe_config) {
+        return exe_config;
+    }
+    user_config_path().unwrap_or(exe_config)
+}
+
+fn is_writable(path: &Path) -> bool {
+    if path.exists() {
+        return fs::OpenOptions::new().append(true).open(path).is_ok();
+    }
+    let Some(dir) = path.parent() else {
+        return false;
+    };
+    let probe = dir.join(".summit_write_test");
+    let writable = fs::write(&probe, b"").is_ok();
+    let _ = fs::remove_file(&probe);
+    writable
+}
+
+/// Read the config file from the search order without environment overrides.
+/// Returns `None` if no file exists or it fails to parse.
+pub fn load_file(explicit: Option<&Path>) -> Option<Config> {
+    let Some(path) = locate(explicit) else {
+        warn!(
+            "Config file not found (searched {:?}), using defaults",
+            search_paths(explicit)
+        );
+        return None;
+    };
+    info!("Loading config from {}", path.display());
+    match fs::read_to_string(&path) {
+        Ok(config_str) => match toml::from_str(&config_str) {
+            Ok(config) => {
+                info!("Config loaded successfully");
+                Some(config)
+            }
+            Err(e) => {
+                error!("Failed to parse config {}: {}", path.display(), e);
+                None
+            }
+        },
+        Err(e) => {
+            error!("Failed to read config {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Load the effective config: file (or defaults) plus environment overrides.
+pub fn load(explicit: Option<&Path>) -> Config {
+    let mut config = load_file(explicit).unwrap_or_default();
+    apply_env_overrides(&mut config);
+    config
+}
+
+/// Write the config to `path`, creating parent directories as needed.
+pub fn save(config: &Config, path: &Path) -> Result<(), String> {
+    let toml_str = toml::to_string(config).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, toml_str).map_err(|e| e.to_string())
+}
+
+/// Optional file in the video directory with per-hip overrides in the same
+/// format as the `[hips]` table, so they travel with the videos for a sale.
+pub const HIPS_SIDECAR_FILE_NAME: &str = "hips.toml";
+
+/// Per-hip overrides from the config merged with the video directory's
+/// sidecar file, whose values win.
+pub fn hip_overrides(config: &Config, video_dir: &Path) -> BTreeMap<String, HipOverride> {
+    let mut hips = config.hips.clone();
+    let path = video_dir.join(HIPS_SIDECAR_FILE_NAME);
+    if !path.is_file() {
+        return hips;
+    }
+    let sidecar = fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|s| {
+            toml::from_str::<BTreeMap<String, HipOverride>>(&s).map_err(|e| e.to_string())
+        });
+    match sidecar {
+        Ok(sidecar) => {
+            info!(
+                "Loaded {} hip overrides from {}",
+                sidecar.len(),
+                path.display()
+            );
+            for (hip, overrides) in sidecar {
+                hips.entry(hip).or_default().merge(&overrides);
+            }
+        }
+        Err(e) => error!("Failed to load {}: {}", path.display(), e),
+    }
+    hips
+}
+
+/// Set `[<hip>] gain` in the video directory's sidecar file, creating it if
+/// needed and keeping everything else set in it. Comments in the file are
+/// not kept.
+pub fn save_hip_gain(video_dir: &Path, hip: &str, gain: f32) -> Result<(), String> {
+    // Through a string so the file gets 0.891, not 0.8910000324249268
+    let gain: f64 = gain.to_string().parse().unwrap_or(1.0);
+    save_hip_value(video_dir, hip, "gain", Some(toml::Value::Float(gain)))
+}
+
+/// Set or, with `None`, remove `[<hip>] note` in the video directory's
+/// sidecar file, like [`save_hip_gain`].
+pub fn save_hip_note(video_dir: &Path, hip: &str, note: Option<&str>) -> Result<(), String> {
+    let note = note.map(|note| toml::Value::String(note.to_string()));
+    save_hip_value(video_dir, hip, "note", note)
+}
+
+fn save_hip_value(
+    video_dir: &Path,
+    hip: &str,
+    key: &str,
+    value: Option<toml::Value>,
+) -> Result<(), String> {
+    let path = video_dir.join(HIPS_SIDECAR_FILE_NAME);
+    let mut hips = match fs::read_to_string(&path) {
+        Ok(text) => text
+            .parse::<toml::Table>()
+            .map_err(|e| format!("{}: {}", path.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    let entry = hips
+        .entry(hip)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    let toml::Value::Table(entry) = entry else {
+        return Err(format!("{}: [\"{}\"] is not a table", path.display(), hip));
+    };
+    match value {
+        Some(value) => entry.insert(key.to_string(), value),
+        None => entry.remove(key),
+    };
+    if entry.is_empty() {
+        hips.remove(hip);
+    }
+    let text = toml::to_string(&hips).map_err(|e| e.to_string())?;
+    fs::write(&path, text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Commented default config written by `--init`. Paths in it are relative to
+/// the executable, matching the folders in [`INIT_DIRS`].
+pub const TEMPLATE: &str = include_str!("../../../config.dist.toml");
+
+/// Asset folders created next to the config by `--init`.
+pub const INIT_DIRS: [&str; 3] = ["videos", "splash", "logo"];
+
+/// Write the commented config template and the asset folder skeleton into
+/// `dir`. An existing config.toml is only replaced when `force` is set.
+/// Returns the path of the written config.
+pub fn init(dir: &Path, force: bool) -> Result<PathBuf, String> {
+    let path = dir.join(CONFIG_FILE_NAME);
+    if path.exists() && !force {
+        return Err(format!(
+            "{} already exists (use --force to overwrite)",
+            path.display()
+        ));
+    }
+    for name in INIT_DIRS {
+        let sub = dir.join(name);
+        fs::create_dir_all(&sub).map_err(|e| format!("{}: {}", sub.display(), e))?;
+    }
+    fs::write(&path, TEMPLATE).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Prefix for environment variables that override config.toml values.
+/// The remainder of the name is `<SECTION>_<FIELD>`, e.g. `SHN_VIDEO_DIRECTORY`
+/// or `SHN_UI_KIOSK_MODE`.
+pub const ENV_PREFIX: &str = "SHN_";
+
+/// `SHN_` variables for the build and `cargo xtask` rather than for
+/// config.toml, passed over without a warning.
+const BUILD_ENV_VARS: [&str; 10] = [
+    "SHN_CODESIGN_IDENTITY",
+    "SHN_CONFIG_PUBLIC_KEY",
+    "SHN_FFMPEG_CACHE",
+    "SHN_GPG_KEY",
+    "SHN_LICENSE_PUBLIC_KEY",
+    "SHN_MINISIGN_KEY",
+    "SHN_MINISIGN_PUBLIC_KEY",
+    "SHN_PURCHASE_INFO",
+    "SHN_SIGNTOOL",
+    "SHN_TRIAL_SECRET",
+];
+
+/// Apply `SHN_*` environment variable overrides on top of a loaded config.
+pub fn apply_env_overrides(config: &mut Config) {
+    apply_env_overrides_from(config, std::env::vars());
+}
+
+fn apply_env_overrides_from<I>(config: &mut Config, vars: I)
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut value = match toml::Value::try_from(&*config) {
+        Ok(value) => value,
+        Err(e) => {
+            error!("Failed to prepare config for environment overrides: {}", e);
+            return;
+        }
+    };
+
+    // Longest first, so `SHN_MEDIA_SOURCE_...` finds `media_source` and
+    // not a `media` section
+    let mut sections: Vec<String> = value
+        .as_table()
+        .into_iter()
+        .flatten()
+        .filter(|(_, v)| v.is_table())
+        .map(|(name, _)| name.clone())
+        .collect();
+    if !sections.iter().any(|name| name == "hips") {
+        // Left out of the config while there are no hip overrides
+        sections.push("hips".to_string());
+    }
+    sections.sort_by_key(|name| std::cmp::Reverse(name.len()));
+
+    let mut applied = Vec::new();
+    for (key, raw) in vars {
+        let Some(name) = key.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if BUILD_ENV_VARS.contains(&key.as_str()) {
+            continue;
+        }
+        let name = name.to_ascii_lowercase();
+        let split = sections.iter().find_map(|section| {
+            let field = name.strip_prefix(section.as_str())?.strip_prefix('_')?;
+            (!field.is_empty()).then_some((section.as_str(), field))
+        });
+        let Some((section, field)) = split else {
+            warn!(
+                "Ignoring {}: expected {}<SECTION>_<FIELD> with a known section",
+                key, ENV_PREFIX
+            );
+            continue;
+        };
+        if section == "lock" {
+            warn!("Ignoring {}: lock settings can't be overridden", key);
+            continue;
+        }
+        // `[hips."042"] gain` is `SHN_HIPS_042_GAIN`
+        let path = match (section, field.split_once('_')) {
+            ("hips", Some((hip, field))) if !field.is_empty() => vec![section, hip, field],
+            ("hips", _) => {
+                warn!(
+                    "Ignoring {}: expected {}HIPS_<HIP>_<FIELD>",
+                    key, ENV_PREFIX
+                );
+                continue;
+            }
+            _ => vec![section, field],
+        };
+        let (field, parent) = path.split_last().expect("a section and a field");
+        let before = value.clone();
+        let Some(table) = table_at(&mut value, parent) else {
+            continue;
+        };
+
+        // Parse according to the type of the value being replaced; fields that are
+        // currently unset take it as a TOML value, or as a string when it isn't
+        // one or the field wants a string.
+        let candidates: Vec<toml::Value> = match table.get(*field) {
+            Some(toml::Value::Boolean(_)) => raw
+                .parse()
+                .ok()
+                .map(toml::Value::Boolean)
+                .into_iter()
+                .collect(),
+            Some(toml::Value::Integer(_)) => raw
+                .parse()
+                .ok()
+                .map(toml::Value::Integer)
+                .into_iter()
+                .collect(),
+            Some(toml::Value::Float(_)) => raw
+                .parse()
+                .ok()
+                .map(toml::Value::Float)
+                .into_iter()
+                .collect(),
+            Some(toml::Value::String(_)) => vec![toml::Value::String(raw.clone())],
+            _ => parse_value(&raw)
+                .into_iter()
+                .chain([toml::Value::String(raw.clone())])
+                .collect(),
+        };
+        if candidates.is_empty() {
+            warn!("Ignoring {}: '{}' is not a valid value", key, raw);
+            value = before;
+            continue;
+        }
+
+        // Validated fields (colors, ratios, durations) only reject bad values
+        // when the whole config is deserialized, so check each override on its
+        // own and roll back the ones that don't fit.
+        let mut error = None;
+        for candidate in candidates {
+            if let Some(table) = table_at(&mut value, parent) {
+                table.insert(field.to_string(), candidate);
+            }
+            match value.clone().try_into::<Config>() {
+                Ok(_) => {
+                    error = None;
+                    break;
+                }
+                Err(e) => {
+                    error = Some(e.message().to_string());
+                    value = before.clone();
+                }
+            }
+        }
+        if let Some(e) = error {
+            warn!("Ignoring {}: {}", key, e);
+            continue;
+        }
+        applied.push((
+            key,
+            path.iter().map(|key| key.to_string()).collect::<Vec<_>>(),
+        ));
+    }
+
+    if applied.is_empty() {
+        return;
+    }
+
+    match value.try_into::<Config>() {
+        Ok(overridden) => {
+            // Fields serde doesn't know about are silently dropped, so check which
+            // overrides actually landed in the resulting config.
+            let check = toml::Value::try_from(&overridden).ok();
+            for (key, path) in &applied {
+                let landed = check
+                    .as_ref()
+                    .and_then(|check| path.iter().try_fold(check, |value, key| value.get(key)))
+                    .is_some();
+                if landed {
+                    info!("Config override from environment: {}", key);
+                } else {
+                    warn!(
+                        "Ignoring {}: unknown config field '{}'",
+                        key,
+                        path.join(".")
+                    );
+                }
+            }
+            *config = overridden;
+        }
+        Err(e) => error!("Failed to apply environment overrides: {}", e),
+    }
+}
+
+/// The table at `path` in `value`, made empty where it isn't there yet.
+fn table_at<'a>(value: &'a mut toml::Value, path: &[&str]) -> Option<&'a mut toml::Table> {
+    path.iter()
+        .try_fold(value, |value, key| {
+            Some(
+                value
+                    .as_table_mut()?
+                    .entry(key.to_string())
+                    .or_insert_with(|| toml::Value::Table(toml::Table::new())),
+            )
+        })?
+        .as_table_mut()
+}
+
+/// `raw` as it would be read from config.toml, e.g. `30` or `true`.
+fn parse_value(raw: &str) -> Option<toml::Value> {
+    let mut table: toml::Table = toml::from_str(&format!("value = {}", raw)).ok()?;
+    table.remove("value")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn env(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_env_overrides_typed_values() {
+        let mut config = Config::default();
+        apply_env_overrides_from(
+            &mut config,
+            env(&[
+                ("SHN_VIDEO_DIRECTORY", "/srv/videos"),
+                ("SHN_UI_KIOSK_MODE", "false"),
+                ("SHN_UI_WINDOW_WIDTH", "1280"),
+                ("SHN_LOGGING_MAX_LINES", "500"),
+                ("SHN_SPLASH_ROTATION_MODE", "random"),
+                ("PATH", "/usr/bin"),
+            ]),
+        );
+        assert_eq!(config.video.directory, "/srv/videos");
+        assert!(!config.ui.kiosk_mode);
+        assert_eq!(config.ui.window_width, 1280.0);
+        assert_eq!(config.logging.max_lines, 500);
+        assert_eq!(config.splash.rotation_mode.as_deref(), Some("random"));
+    }
+
+    #[test]
+    fn test_env_overrides_multi_word_sections() {
+        let mut config = Config::default();
+        apply_env_overrides_from(
+            &mut config,
+            env(&[
+                ("SHN_ENTRY_OVERLAY_ENABLED", "true"),
+                ("SHN_ENTRY_OVERLAY_FONT_SIZE", "90"),
+                ("SHN_MEDIA_SOURCE_CHECK_SECONDS", "5"),
+                ("SHN_MEDIA_CACHE_COPY_ALL", "true"),
+            ]),
+        );
+        assert!(config.entry_overlay.enabled);
+        assert_eq!(config.entry_overlay.font_size, 90.0);
+        assert_eq!(config.media_source.check_seconds.get(), 5.0);
+        assert!(config.media_cache.copy_all);
+    }
+
+    #[test]
+    fn test_env_overrides_ignore_invalid() {
+        let mut config = Config::default();
+        apply_env_overrides_from(
+            &mut config,
+            env(&[
+                ("SHN_UI_KIOSK_MODE", "maybe"),
+                ("SHN_UI_NOT_A_FIELD", "1"),
+                ("SHN_BOGUS_DIRECTORY", "/tmp"),
+                ("SHN_VIDEO", "/tmp"),
+            ]),
+        );
+        assert!(config.ui.kiosk_mode);
+        assert_eq!(config.video.directory, "./videos");
+    }
+
+    #[test]
+    fn test_env_overrides_set_unset_fields() {
+        let mut config = Config::default();
+        apply_env_overrides_from(
+            &mut config,
+            env(&[
+                ("SHN_UI_WINDOW_X", "40"),
+                ("SHN_UI_CURSOR_IDLE_SECONDS", "2.5"),
+                ("SHN_MEDIA_SOURCE_RECONNECT_COMMAND", "true"),
+                ("SHN_HIPS_042_GAIN", "0.5"),
+                ("SHN_HIPS_042_SKIP_SPLASH", "true"),
+                ("SHN_HIPS_043_GAIN", "loud"),
+                ("SHN_LICENSE_PUBLIC_KEY", "3b6a27bc"),
+            ]),
+        );
+        assert_eq!(config.ui.window_x, Some(40.0));
+        assert_eq!(config.ui.cursor_idle_seconds, Some(Seconds::clamped(2.5)));
+        assert_eq!(
+            config.media_source.reconnect_command.as_deref(),
+            Some("true")
+        );
+        let hip = &config.hips["042"];
+        assert_eq!(hip.gain, Some(0.5));
+        assert_eq!(hip.skip_splash, Some(true));
+        assert!(!config.hips.contains_key("043"));
+    }
+
+    #[test]
+    fn test_env_overrides_cannot_unlock() {
+        let mut config = Config::default();
+        config.lock.pin_hash = Some("pbkdf2-sha256$1$00$00".to_string());
+        apply_env_overrides_from(&mut config, env(&[("SHN_LOCK_PIN_HASH", "")]));
+        assert!(config.lock.pin_hash.is_some());
+    }
+
+    #[test]
+    fn test_env_overrides_validate_typed_fields() {
+        let mut config = Config::default();
+        apply_env_overrides_from(
+            &mut config,
+            env(&[
+                ("SHN_UI_LABEL_COLOR", "red"),
+                ("SHN_UI_VIDEO_HEIGHT_RATIO", "1.5"),
+                ("SHN_UI_BACKGROUND_COLOR", "#102030"),
+                ("SHN_SPLASH_DURATION_SECONDS", "5"),
+            ]),
+        );
+        assert_eq!(config.ui.label_color, HexColor::WHITE);
+        assert_eq!(config.ui.video_height_ratio, Ratio01::clamped(0.92));
+        assert_eq!(config.ui.background_color, HexColor::rgb(0x10, 0x20, 0x30));
+        assert_eq!(config.splash.duration_seconds.get(), 5.0);
+    }
+
+    #[test]
+    fn test_app_bundle_resources() {
+        assert_eq!(
+            app_bundle_resources(Path::new(
+                "/Applications/Summit Hip Numbers.app/Contents/MacOS"
+            )),
+            Some(PathBuf::from(
+                "/Applications/Summit Hip Numbers.app/Contents/Resources"
+            ))
+        );
+        assert_eq!(app_bundle_resources(Path::new("/opt/kiosk/MacOS")), None);
+        assert_eq!(
+            app_bundle_resources(Path::new("E:\\SummitHipNumbers")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_explicit_path_is_only_candidate() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("custom.toml");
+        assert_eq!(search_paths(Some(&path)), vec![path.clone()]);
+        assert_eq!(locate(Some(&path)), None);
+        assert_eq!(save_path(Some(&path)), path);
+    }
+
+    #[test]
+    fn test_save_and_load_explicit_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested").join("config.toml");
+        let mut config = Config::default();
+        config.video.directory = "/media/sale".to_string();
+        save(&config, &path).unwrap();
+
+        let loaded = load_file(Some(&path)).unwrap();
+        assert_eq!(loaded.video.directory, "/media/sale");
+    }
+
+    #[test]
+    fn test_load_file_invalid_toml() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "not = [valid").unwrap();
+        assert!(load_file(Some(&path)).is_none());
+    }
+
+    #[test]
+    fn test_template_parses() {
+        let config: Config = toml::from_str(TEMPLATE).unwrap();
+        assert_eq!(config.video.directory, "./videos");
+        assert_eq!(config.splash.directory, "./splash");
+    }
+
+    #[test]
+    fn test_init_creates_skeleton() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("kiosk");
+        let path = init(&dir, false).unwrap();
+        assert_eq!(path, dir.join(CONFIG_FILE_NAME));
+        assert_eq!(fs::read_to_string(&path).unwrap(), TEMPLATE);
+        for name in INIT_DIRS {
+            assert!(dir.join(name).is_dir());
+        }
+    }
+
+    #[test]
+    fn test_init_refuses_overwrite() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(CONFIG_FILE_NAME);
+        fs::write(&path, "custom").unwrap();
+        assert!(init(temp_dir.path(), false).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "custom");
+
+        init(temp_dir.path(), true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), TEMPLATE);
+    }
+
+    #[test]
+    fn test_hips_table() {
+        let mut toml_str = toml::to_string(&Config::default()).unwrap();
+        assert!(!toml_str.contains("[hips"));
+        toml_str.push_str("\n[hips.\"042\"]\ngain = 0.25\nlabel = \"Quiet lot\"\n");
+
+        let config: Config = toml::from_str(&toml_str).unwrap();
+        let hip = &config.hips["042"];
+        assert_eq!(hip.gain, Some(0.25));
+        assert_eq!(hip.label.as_deref(), Some("Quiet lot"));
+        assert_eq!(hip.hold_last_frame, None);
+
+        let bad = toml_str.replace("gain = 0.25", "volume = 0.25");
+        assert!(toml::from_str::<Config>(&bad).is_err());
+    }
+
+    #[test]
+    fn test_rings() {
+        let mut toml_str = toml::to_string(&Config::default()).unwrap();
+        assert!(!toml_str.contains("[[rings]]"));
+        toml_str.push_str(
+            "\n[[rings]]\nname = \"Ring A\"\nvideo_directory = \"./ring_a\"\n\
+             \n[[rings]]\nname = \"Ring B\"\nvideo_directory = \"./ring_b\"\n\
+             company_label = \"RING B\"\nbackground_color = \"#102030\"\nwindow_x = 1920.0\n",
+        );
+        let config: Config = toml::from_str(&toml_str).unwrap();
+        assert_eq!(config.ring_index("ring b"), Some(1));
+        assert_eq!(config.ring_index("Ring C"), None);
+
+        let a = config.for_ring(0);
+        assert_eq!(a.video.directory, "./ring_a");
+        assert_eq!(a.ui.company_label, config.ui.company_label);
+        let b = config.for_ring(1);
+        assert_eq!(b.video.directory, "./ring_b");
+        assert_eq!(b.ui.company_label, "RING B");
+        assert_eq!(
+            b.ui.background_color,
+            "#102030".parse::<HexColor>().unwrap()
+        );
+        assert_eq!((b.ui.window_x, b.ui.window_y), (Some(1920.0), None));
+        assert_eq!(config.for_ring(2).video.directory, "./videos");
+
+        let bad = toml_str.replace("window_x", "monitor");
+        assert!(toml::from_str::<Config>(&bad).is_err());
+    }
+
+    #[test]
+    fn test_bar_slots() {
+        assert!(Config::default().bar.shows(BarWidget::Input));
+        let toml_str = "left = [\"logo\"]\ncenter = [\"now_playing\", \"ticker\"]\nright = []\n";
+        let bar: BarConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(bar.center, [BarWidget::NowPlaying, BarWidget::Ticker]);
+        assert!(bar.shows(BarWidget::Ticker));
+        assert!(!bar.shows(BarWidget::Input));
+        assert_eq!(bar.ticker_speed, 60.0);
+
+        let bad = toml_str.replace("\"logo\"", "\"weather\"");
+        assert!(toml::from_str::<BarConfig>(&bad).is_err());
+    }
+
+    #[test]
+    fn test_hip_overrides_merge_sidecar() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.hips.insert(
+            "001".to_string(),
+            HipOverride {
+                gain: Some(0.5),
+                label: Some("From config".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(hip_overrides(&config, temp_dir.path()), config.hips);
+
+        fs::write(
+            temp_dir.path().join(HIPS_SIDECAR_FILE_NAME),
+            "[\"001\"]\nlabel = \"From sidecar\"\n\n[\"002\"]\nskip_splash = true\n",
+        )
+        .unwrap();
+        let hips = hip_overrides(&config, temp_dir.path());
+        assert_eq!(hips["001"].gain, Some(0.5));
+        assert_eq!(hips["001"].label.as_deref(), Some("From sidecar"));
+        assert_eq!(hips["002"].skip_splash, Some(true));
+    }
+
+    #[test]
+    fn test_save_hip_gain() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(HIPS_SIDECAR_FILE_NAME);
+        save_hip_gain(temp_dir.path(), "001", 0.891).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[001]\ngain = 0.891\n");
+
+        fs::write(&path, "[\"002\"]\nlabel = \"Quiet lot\"\n").unwrap();
+        save_hip_gain(temp_dir.path(), "002", 0.5).unwrap();
+        let hips = hip_overrides(&Config::default(), temp_dir.path());
+        assert_eq!(hips["002"].gain, Some(0.5));
+        assert_eq!(hips["002"].label.as_deref(), Some("Quiet lot"));
+    }
+
+    #[test]
+    fn test_save_hip_note() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(HIPS_SIDECAR_FILE_NAME);
+        save_hip_gain(temp_dir.path(), "001", 0.5).unwrap();
+        save_hip_note(temp_dir.path(), "001", Some("Play only the first 30 s")).unwrap();
+        save_hip_note(temp_dir.path(), "002", Some("No audio")).unwrap();
+        let hips = hip_overrides(&Config::default(), temp_dir.path());
+        assert_eq!(hips["001"].gain, Some(0.5));
+        assert_eq!(
+            hips["001"].note.as_deref(),
+            Some("Play only the first 30 s")
+        );
+
+        // Clearing the only setting of a hip drops its table
+        save_hip_note(temp_dir.path(), "002", None).unwrap();
+        save_hip_note(temp_dir.path(), "001", None).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[001]\ngain = 0.5\n");
+    }
+
+    #[test]
+    fn test_is_writable() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(is_writable(&temp_dir.path().join("config.toml")));
+        assert!(!is_writable(Path::new("/nonexistent/dir/config.toml")));
+    }
+}
@@ -0,0 +1,264 @@
+//! Validated value types used in [`Config`](super::Config). Each one
+//! deserializes through `TryFrom`, so a bad value in config.toml fails to parse
+//! with a message naming the value instead of silently falling back.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// An RGB color written as `#RRGGBB` (the `#` is optional when reading).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HexColor([u8; 3]);
+
+impl HexColor {
+    pub const BLACK: HexColor = HexColor::rgb(0, 0, 0);
+    pub const WHITE: HexColor = HexColor::rgb(255, 255, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        HexColor([r, g, b])
+    }
+
+    pub fn color32(self) -> egui::Color32 {
+        let [r, g, b] = self.0;
+        egui::Color32::from_rgb(r, g, b)
+    }
+}
+
+impl FromStr for HexColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.trim();
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("invalid color '{}': expected #RRGGBB", s));
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+        Ok(HexColor([channel(0), channel(2), channel(4)]))
+    }
+}
+
+impl TryFrom<String> for HexColor {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<HexColor> for String {
+    fn from(color: HexColor) -> Self {
+        color.to_string()
+    }
+}
+
+impl fmt::Display for HexColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b] = self.0;
+        write!(f, "#{:02X}{:02X}{:02X}", r, g, b)
+    }
+}
+
+/// A fraction from 0.0 to 1.0 inclusive, e.g. a share of the window height.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[serde(try_from = "f32", into = "f32")]
+pub struct Ratio01(f32);
+
+impl Ratio01 {
+    pub fn new(value: f32) -> Result<Self, String> {
+        if (0.0..=1.0).contains(&value) {
+            Ok(Ratio01(value))
+        } else {
+            Err(format!(
+                "invalid ratio {}: expected a value from 0.0 to 1.0",
+                value
+            ))
+        }
+    }
+
+    /// Clamp into range; NaN becomes 0.0.
+    pub fn clamped(value: f32) -> Self {
+        Ratio01(if value > 0.0 { value.min(1.0) } else { 0.0 })
+    }
+
+    pub fn get(self) -> f32 {
+        self.0
+    }
+}
+
+impl FromStr for Ratio01 {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: f32 = s
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid ratio '{}': expected a number", s))?;
+        Ratio01::new(value)
+    }
+}
+
+impl TryFrom<f32> for Ratio01 {
+    type Error = String;
+
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        Ratio01::new(value)
+    }
+}
+
+impl From<Ratio01> for f32 {
+    fn from(ratio: Ratio01) -> Self {
+        ratio.0
+    }
+}
+
+impl fmt::Display for Ratio01 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A non-negative, finite duration in (fractional) seconds.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[serde(try_from = "f64", into = "f64")]
+pub struct Seconds(f64);
+
+impl Seconds {
+    pub fn new(value: f64) -> Result<Self, String> {
+        if value.is_finite() && value >= 0.0 {
+            Ok(Seconds(value))
+        } else {
+            Err(format!(
+                "invalid duration {}: expected a non-negative number of seconds",
+                value
+            ))
+        }
+    }
+
+    /// Clamp into range; NaN and negative values become 0.0.
+    pub fn clamped(value: f64) -> Self {
+        Seconds(if value > 0.0 {
+            value.min(f64::MAX)
+        } else {
+            0.0
+        })
+    }
+
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+
+impl FromStr for Seconds {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: f64 = s
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid duration '{}': expected a number", s))?;
+        Seconds::new(value)
+    }
+}
+
+impl TryFrom<f64> for Seconds {
+    type Error = String;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        Seconds::new(value)
+    }
+}
+
+impl From<Seconds> for f64 {
+    fn from(seconds: Seconds) -> Self {
+        seconds.0
+    }
+}
+
+impl fmt::Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_hex_color_parse() {
+        let red = egui::Color32::from_rgb(255, 0, 0);
+        assert_eq!("#FF0000".parse::<HexColor>().unwrap().color32(), red);
+        assert_eq!("#ff0000".parse::<HexColor>().unwrap().color32(), red);
+        assert_eq!("FF0000".parse::<HexColor>().unwrap().color32(), red);
+        assert_eq!(HexColor::rgb(255, 0, 0).to_string(), "#FF0000");
+    }
+
+    #[test]
+    fn test_hex_color_rejects_invalid() {
+        assert!("invalid".parse::<HexColor>().is_err());
+        assert!("#FFF".parse::<HexColor>().is_err());
+        assert!("#GG0000".parse::<HexColor>().is_err());
+        assert!("#FF00000".parse::<HexColor>().is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_hex_color_parse_never_panics(s in any::<String>()) {
+            if let Ok(color) = s.parse::<HexColor>() {
+                let hex = s.trim();
+                let hex = hex.strip_prefix('#').unwrap_or(hex);
+                prop_assert!(color.to_string()[1..].eq_ignore_ascii_case(hex));
+            }
+        }
+
+        #[test]
+        fn prop_hex_color_round_trips(r: u8, g: u8, b: u8) {
+            let color = HexColor::rgb(r, g, b);
+            let text = color.to_string();
+            prop_assert_eq!(text.parse::<HexColor>(), Ok(color));
+            prop_assert_eq!(text.to_lowercase().parse::<HexColor>(), Ok(color));
+            prop_assert_eq!(text[1..].parse::<HexColor>(), Ok(color));
+        }
+    }
+
+    #[test]
+    fn test_ratio_range() {
+        assert_eq!(Ratio01::new(0.92).unwrap().get(), 0.92);
+        assert!(Ratio01::new(1.5).is_err());
+        assert!(Ratio01::new(-0.1).is_err());
+        assert!(Ratio01::new(f32::NAN).is_err());
+        assert_eq!(Ratio01::clamped(1.5).get(), 1.0);
+        assert_eq!(Ratio01::clamped(f32::NAN).get(), 0.0);
+    }
+
+    #[test]
+    fn test_seconds_range() {
+        assert_eq!("2.5".parse::<Seconds>().unwrap().get(), 2.5);
+        assert!("-1".parse::<Seconds>().is_err());
+        assert!("soon".parse::<Seconds>().is_err());
+        assert_eq!(Seconds::clamped(-3.0).get(), 0.0);
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Sample {
+        color: HexColor,
+        ratio: Ratio01,
+    }
+
+    #[test]
+    fn test_invalid_values_rejected_at_parse_time() {
+        let err = toml::from_str::<Sample>("color = \"red\"\nratio = 0.5")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid color 'red'"), "{}", err);
+
+        let err = toml::from_str::<Sample>("color = \"#000000\"\nratio = 1.2")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid ratio 1.2"), "{}", err);
+    }
+}
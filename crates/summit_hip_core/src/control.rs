@@ -0,0 +1,346 @@
+//! Remote control of the player. Integrations such as the HTTP API hold a
+//! [`Remote`] to send [`Command`]s, read the latest [`Status`] and subscribe
+//! to [`Event`]s; the player owns the [`Endpoint`], applies commands on the UI
+//! thread each frame and publishes its status and events back.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    PlayHip(String),
+    Pause,
+    Resume,
+    Next,
+    Previous,
+    /// Go back to the hip playing before the last switch.
+    Undo,
+    Blackout(bool),
+    Rescan,
+    /// The auction software says this lot is in the ring.
+    RingLot(String),
+    /// Set the operator note of a hip, or clear it with `None`.
+    SetNote {
+        hip: String,
+        note: Option<String>,
+    },
+    /// Show a test pattern instead of the player, or go back with `None`.
+    TestPattern(Option<TestPattern>),
+    /// Switch to the ring of `[[rings]]` with this name.
+    SwitchRing(String),
+}
+
+/// A picture for lining up a projector, shown with a tone for checking the
+/// audio.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestPattern {
+    /// SMPTE color bars.
+    Bars,
+    /// A white grid on black, with a circle and the center marked.
+    Grid,
+}
+
+impl fmt::Display for TestPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TestPattern::Bars => "bars",
+            TestPattern::Grid => "grid",
+        })
+    }
+}
+
+/// Where a command came from, for [`arbitration`](crate::arbitration).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// The kiosk's own keyboard, including barcode scanners that type like one.
+    Keyboard,
+    Http,
+    Tcp,
+    Serial,
+    Mqtt,
+    Osc,
+    Sync,
+    Auction,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Keyboard => "keyboard",
+            Source::Http => "http",
+            Source::Tcp => "tcp",
+            Source::Serial => "serial",
+            Source::Mqtt => "mqtt",
+            Source::Osc => "osc",
+            Source::Sync => "sync",
+            Source::Auction => "auction",
+        })
+    }
+}
+
+/// Snapshot of what the player is doing, as reported to integrations.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Status {
+    pub hip: Option<String>,
+    pub file: Option<String>,
+    pub paused: bool,
+    pub blackout: bool,
+    pub splash: bool,
+    /// Hip numbers that have videos, in order.
+    pub hips: Vec<String>,
+    /// Lot in the ring according to the auction software, if followed.
+    pub ring_lot: Option<String>,
+    /// Custom labels from `[hips.<hip>] label`, for button text.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// The operator note of the hip playing, from `[hips.<hip>] note`.
+    pub note: Option<String>,
+    /// A hip asked for while the operator's hip plays, to play after it.
+    pub queued_hip: Option<String>,
+    /// Whether the video playing has an audio track, once known.
+    pub audio_track: Option<bool>,
+    pub audio_level: AudioLevel,
+    /// The test pattern on screen instead of the player.
+    pub test_pattern: Option<TestPattern>,
+    /// The video directory can't be read, under `[media_source]`.
+    pub media_offline: bool,
+    /// The ring of `[[rings]]` being shown, and the rings there are.
+    pub ring: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rings: Vec<String>,
+}
+
+/// Peak audio levels, 0.0 to 1.0, falling back over a second or so. Sound
+/// in `program` but not in `output` means the output gain is muting it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct AudioLevel {
+    /// The videos' sound, at the hip's gain.
+    pub program: f32,
+    /// Everything on the first output device, after its gain.
+    pub output: f32,
+}
+
+/// Something that happened in the player, pushed to subscribers as it happens.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    HipEntered {
+        hip: String,
+    },
+    VideoStarted {
+        hip: String,
+        file: String,
+    },
+    VideoEnded {
+        hip: String,
+        file: String,
+    },
+    SplashShown,
+    Error {
+        message: String,
+    },
+    /// Another program took the window's `focus` or `fullscreen`, under
+    /// `[ui] keep_focus`.
+    FocusStolen {
+        lost: String,
+    },
+    /// The file of the video playing was replaced on disk; the operator is
+    /// asked whether to swap it in.
+    VideoReplaced {
+        hip: String,
+        file: String,
+    },
+    /// The video directory can't be read, under `[media_source]`.
+    MediaOffline,
+    /// The video directory can be read again, and has been rescanned.
+    MediaOnline,
+}
+
+/// Why a hip number sent by an integration can't be played.
+#[derive(Debug, PartialEq)]
+pub enum HipError {
+    Malformed,
+    NoVideo(String),
+}
+
+impl fmt::Display for HipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HipError::Malformed => write!(f, "hip must be 3 digits"),
+            HipError::NoVideo(hip) => write!(f, "no video for hip {}", hip),
+        }
+    }
+}
+
+type Subscribers = Arc<Mutex<Vec<Sender<Event>>>>;
+
+/// A command and where it came from, if the sender said.
+type Tagged = (Option<Source>, Command);
+
+/// Player side of the control channel.
+pub struct Endpoint {
+    sender: Sender<Tagged>,
+    commands: Receiver<Tagged>,
+    status: Arc<Mutex<Status>>,
+    subscribers: Subscribers,
+}
+
+impl Default for Endpoint {
+    fn default() -> Self {
+        let (sender, commands) = mpsc::channel();
+        Self {
+            sender,
+            commands,
+            status: Arc::default(),
+            subscribers: Arc::default(),
+        }
+    }
+}
+
+impl Endpoint {
+    /// A handle for an integration to control this player.
+    pub fn remote(&self) -> Remote {
+        Remote {
+            source: None,
+            sender: self.sender.clone(),
+            status: self.status.clone(),
+            subscribers: self.subscribers.clone(),
+        }
+    }
+
+    pub fn try_recv(&self) -> Option<Command> {
+        self.try_recv_tagged().map(|(_, command)| command)
+    }
+
+    /// The next command, with where it came from.
+    pub fn try_recv_tagged(&self) -> Option<(Option<Source>, Command)> {
+        self.commands.try_recv().ok()
+    }
+
+    pub fn publish(&self, status: Status) {
+        *self.status.lock().unwrap() = status;
+    }
+
+    /// Send `event` to every subscriber, dropping the ones that went away.
+    pub fn emit(&self, event: Event) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
+
+/// Integration side of the control channel.
+#[derive(Clone)]
+pub struct Remote {
+    source: Option<Source>,
+    sender: Sender<Tagged>,
+    status: Arc<Mutex<Status>>,
+    subscribers: Subscribers,
+}
+
+impl Remote {
+    /// This handle, sending its commands as coming from `source`.
+    pub fn tagged(self, source: Source) -> Self {
+        Self {
+            source: Some(source),
+            ..self
+        }
+    }
+
+    pub fn send(&self, command: Command) -> Result<(), String> {
+        self.sender
+            .send((self.source, command))
+            .map_err(|_| "player is not running".to_string())
+    }
+
+    pub fn status(&self) -> Status {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Check `hip` against the player's videos, so integrations can report a
+    /// bad number to the caller instead of only on screen.
+    pub fn check_hip(&self, hip: &str) -> Result<(), HipError> {
+        if hip.len() != 3 || !hip.chars().all(|c| c.is_ascii_digit()) {
+            return Err(HipError::Malformed);
+        }
+        if !self.status().hips.iter().any(|h| h == hip) {
+            return Err(HipError::NoVideo(hip.to_string()));
+        }
+        Ok(())
+    }
+
+    /// The name of the ring called `name`, ignoring case, as the player
+    /// has it.
+    pub fn find_ring(&self, name: &str) -> Option<String> {
+        self.status()
+            .rings
+            .into_iter()
+            .find(|ring| ring.eq_ignore_ascii_case(name))
+    }
+
+    /// Receive every event emitted from now on.
+    pub fn subscribe(&self) -> Receiver<Event> {
+        let (sender, events) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(sender);
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_round_trip() {
+        let endpoint = Endpoint::default();
+        let remote = endpoint.remote();
+        remote.send(Command::PlayHip("042".to_string())).unwrap();
+        remote.send(Command::Pause).unwrap();
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::PlayHip("042".to_string()))
+        );
+        assert_eq!(endpoint.try_recv(), Some(Command::Pause));
+        assert_eq!(endpoint.try_recv(), None);
+
+        remote
+            .clone()
+            .tagged(Source::Osc)
+            .send(Command::Next)
+            .unwrap();
+        assert_eq!(
+            endpoint.try_recv_tagged(),
+            Some((Some(Source::Osc), Command::Next))
+        );
+
+        endpoint.publish(Status {
+            hip: Some("042".to_string()),
+            ..Status::default()
+        });
+        assert_eq!(remote.status().hip.as_deref(), Some("042"));
+    }
+
+    #[test]
+    fn test_events_reach_live_subscribers() {
+        let endpoint = Endpoint::default();
+        let remote = endpoint.remote();
+        let events = remote.subscribe();
+        drop(remote.subscribe());
+
+        endpoint.emit(Event::SplashShown);
+        assert_eq!(events.try_recv(), Ok(Event::SplashShown));
+        assert_eq!(endpoint.subscribers.lock().unwrap().len(), 1);
+
+        let json = serde_json::to_string(&Event::HipEntered {
+            hip: "042".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"event":"hip_entered","hip":"042"}"#);
+    }
+}
@@ -0,0 +1,50 @@
+//! Just enough CSV for the auction feed and the play analytics: quoted
+//! fields with `""` escapes, one record per line.
+
+/// The fields of one line, trimmed and unquoted.
+pub fn split_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields.iter().map(|f| f.trim().to_string()).collect()
+}
+
+/// Fields joined into one line, quoted where needed.
+pub fn join_line<S: AsRef<str>>(fields: &[S]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    fields.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let fields = ["042", "Smith, J", "Jones \"JJ\"", ""];
+        let line = join_line(&fields);
+        assert_eq!(line, "042,\"Smith, J\",\"Jones \"\"JJ\"\"\",");
+        assert_eq!(split_line(&line), fields);
+    }
+}
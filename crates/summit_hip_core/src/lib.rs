@@ -0,0 +1,33 @@
+//! What a Summit hip number player does, without a window or a decoder: the
+//! config, finding the videos and splash images, which video plays for a hip
+//! number and what plays next, and the remote control channel.
+//!
+//! The kiosk binary, `summit_hip_numbers`, is an egui frontend over this
+//! crate. It supplies the video decoder as a [`playback::PlayerBackend`] and
+//! draws the frames the decoder sends; everything else it does with the types
+//! here:
+//!
+//! - [`config`]: `config.toml`, loaded with defaults for anything missing.
+//! - [`file_scanner`]: the videos in the video folder, with the hip number
+//!   each one is for, and the splash images.
+//! - [`playback::Controller`]: the playlist. Switches on a hip number or the
+//!   arrows, advances at the end of a video and reports what happened as
+//!   [`control::Event`]s.
+//! - [`splash`]: when the splash screen shows, and which image.
+//! - [`control`]: commands from integrations in, status and events out.
+//! - [`arbitration`]: which of them may switch the hip, and when.
+//! - [`analytics`]: the plays log and end-of-sale report.
+//! - [`review`]: the pre-sale content review and its report.
+//!
+//! Tests of a frontend can drive a controller without decoding anything by
+//! enabling the `mock` feature and using `playback::mock::MockPlayer`.
+
+pub mod analytics;
+pub mod arbitration;
+pub mod config;
+pub mod control;
+pub mod csv;
+pub mod file_scanner;
+pub mod playback;
+pub mod review;
+pub mod splash;
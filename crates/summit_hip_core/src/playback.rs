@@ -0,0 +1,1290 @@
+//! Which video plays: the playlist of hips, switching on a hip number or
+//! the arrows, auto-advance at the end of a video, and the player that
+//! plays it. The player is a [`PlayerBackend`] supplied by the frontend, the
+//! FFmpeg decoder in the kiosk and `mock::MockPlayer` in tests, so the
+//! playlist logic runs without decoding.
+//!
+//! What happens is queued as [`control::Event`]s for the caller to send on.
+
+use crate::analytics;
+use crate::config::{HipOverride, OutputConfig};
+use crate::control;
+use crate::file_scanner::{self, VideoFile};
+use log::{error, info, warn};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Where a player sends the frames it decodes. Each frame sent also wakes
+/// the window showing them, once one is attached with [`Self::repaint`], so
+/// the window needn't repaint continuously to catch new frames.
+#[derive(Clone)]
+pub struct FrameSender {
+    frames: watch::Sender<Option<egui::ColorImage>>,
+    /// Shared by every clone, so players opened earlier wake it too.
+    repaint: Arc<OnceLock<egui::Context>>,
+    /// Applied to each frame sent; shared the same way.
+    adjustment: Arc<RwLock<Option<Adjustment>>>,
+}
+
+impl FrameSender {
+    pub fn new(frames: watch::Sender<Option<egui::ColorImage>>) -> Self {
+        Self {
+            frames,
+            repaint: Arc::default(),
+            adjustment: Arc::default(),
+        }
+    }
+
+    /// Adjust each frame sent from now on as `[output]` says.
+    pub fn adjust(&self, output: &OutputConfig) {
+        let adjustment = Adjustment::new(output);
+        if adjustment.is_some() {
+            info!("Adjusting the output: {:?}", output);
+        }
+        *self.adjustment.write().unwrap() = adjustment;
+    }
+
+    /// Repaint `ctx` on each frame sent from now on, by this sender or any
+    /// of its clones. Only the first window attached is woken.
+    pub fn repaint(&self, ctx: &egui::Context) {
+        if self.repaint.set(ctx.clone()).is_err() {
+            warn!("Frames already wake a window");
+        }
+    }
+
+    /// Send a frame to show. Fails once nothing receives frames any more.
+    pub fn send(
+        &self,
+        mut image: egui::ColorImage,
+    ) -> Result<(), watch::error::SendError<Option<egui::ColorImage>>> {
+        if let Some(adjustment) = &*self.adjustment.read().unwrap() {
+            adjustment.apply(&mut image);
+        }
+        self.frames.send(Some(image))?;
+        if let Some(ctx) = self.repaint.get() {
+            ctx.request_repaint_of(egui::ViewportId::ROOT);
+        }
+        Ok(())
+    }
+}
+
+/// A decoded RGBA frame as an image to show. Rows are `stride` bytes apart
+/// in `data`, which decoders pad past `4 * width` for alignment.
+pub fn rgba_image([width, height]: [usize; 2], stride: usize, data: &[u8]) -> egui::ColorImage {
+    let row = width * 4;
+    if stride == row {
+        return egui::ColorImage::from_rgba_unmultiplied([width, height], &data[..row * height]);
+    }
+    let mut pixels = Vec::with_capacity(row * height);
+    for y in 0..height {
+        pixels.extend_from_slice(&data[y * stride..y * stride + row]);
+    }
+    egui::ColorImage::from_rgba_unmultiplied([width, height], &pixels)
+}
+
+/// `[output]` brightness, contrast, gamma and saturation, made ready to apply
+/// to frames.
+#[derive(Debug, PartialEq)]
+pub struct Adjustment {
+    /// Each channel value as brightness, contrast and gamma leave it.
+    levels: [u8; 256],
+    saturation: f32,
+}
+
+impl Adjustment {
+    /// `None` when `output` leaves the frames as they are.
+    pub fn new(output: &OutputConfig) -> Option<Self> {
+        if *output == OutputConfig::default() {
+            return None;
+        }
+        let brightness = output.brightness.clamp(-1.0, 1.0);
+        let contrast = output.contrast.clamp(0.0, 2.0);
+        let gamma = output.gamma.clamp(0.2, 5.0);
+        let mut levels = [0; 256];
+        for (value, level) in levels.iter_mut().enumerate() {
+            let x = (value as f32 / 255.0).powf(1.0 / gamma);
+            let x = (x - 0.5) * contrast + 0.5 + brightness;
+            *level = (x.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        Some(Self {
+            levels,
+            saturation: output.saturation.clamp(0.0, 2.0),
+        })
+    }
+
+    /// Adjust `image`, an opaque video frame, in place.
+    pub fn apply(&self, image: &mut egui::ColorImage) {
+        for pixel in &mut image.pixels {
+            let [r, g, b, a] = pixel.to_array();
+            let mut rgb = [r, g, b].map(|c| self.levels[c as usize]);
+            if self.saturation != 1.0 {
+                // Rec. 709 luma
+                let [r, g, b] = rgb.map(f32::from);
+                let luma = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+                rgb = [r, g, b]
+                    .map(|c| (luma + (c - luma) * self.saturation).clamp(0.0, 255.0) as u8);
+            }
+            *pixel = egui::Color32::from_rgba_premultiplied(rgb[0], rgb[1], rgb[2], a);
+        }
+    }
+}
+
+/// Quietest and loudest a hip can be trimmed to, in dB.
+pub const TRIM_RANGE_DB: (f32, f32) = (-40.0, 12.0);
+
+/// `gain` turned up or down by `db` decibels, within [`TRIM_RANGE_DB`].
+/// Rounded to tenths of a dB, so steps up and back down return to where
+/// they started.
+pub fn trimmed(gain: f32, db: f32) -> f32 {
+    let (min, max) = TRIM_RANGE_DB;
+    let current = if gain > 0.0 { 20.0 * gain.log10() } else { min };
+    let db = (((current + db) * 10.0).round() / 10.0).clamp(min, max);
+    (10f32.powf(db / 20.0) * 10_000.0).round() / 10_000.0
+}
+
+/// What [`Controller`] needs of a video player.
+pub trait PlayerBackend: Sized {
+    fn open(uri: &str, frames: FrameSender) -> Result<Self, String>;
+    fn play(&mut self) -> Result<(), String>;
+    fn stop(&self) -> Result<(), String>;
+    /// Start `at` into the video instead of at its beginning.
+    fn set_start(&mut self, at: Duration);
+    /// End `at` into the video, as if it ended there.
+    fn set_end(&mut self, at: Duration);
+    fn set_gain(&self, gain: f32);
+    fn set_paused(&self, paused: bool);
+    fn position(&self) -> Duration;
+    fn is_eos(&self) -> bool;
+    /// Still opening the video, before its first frame.
+    fn is_loading(&self) -> bool;
+    fn error(&self) -> Option<String>;
+    /// Whether the video has an audio track, once the player knows.
+    fn has_audio(&self) -> Option<bool> {
+        None
+    }
+    /// Why the video file at `path` can't be played, and what to do about
+    /// it, found without playing it. The default finds nothing.
+    fn probe(_path: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// What the player is doing, for the window to show.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayerState {
+    /// No video playing, such as on the splash screen or holding a last frame.
+    Idle,
+    /// Opening a video, before its first frame.
+    Loading,
+    Playing,
+    /// The video couldn't be opened or failed while playing.
+    Error(String),
+}
+
+/// The outcome of asking for a hip.
+#[derive(Debug, PartialEq)]
+pub enum Request {
+    /// Its first video is up next.
+    Switched(usize),
+    /// Well formed, but there is no video for it.
+    Unavailable,
+    /// Its videos can't be played, for the reason given.
+    Unplayable(String),
+    /// Not a 3-digit hip number.
+    Malformed,
+}
+
+/// How many switches [`Controller::undo_switch`] can take back.
+const HISTORY_LEN: usize = 10;
+
+/// The playlist and the player playing from it.
+pub struct Controller<B: PlayerBackend> {
+    /// Sorted by hip number.
+    pub video_files: Vec<VideoFile>,
+    hip_to_index: HashMap<String, Vec<usize>>,
+    /// Why videos can't be played, by index, from [`PlayerBackend::probe`].
+    problems: HashMap<usize, String>,
+    pub hip_overrides: BTreeMap<String, HipOverride>,
+    /// Copies on the local disk to play in place of the video files, by the
+    /// files' paths, for a slow or removable video folder.
+    pub local_copies: HashMap<String, PathBuf>,
+    pub current_index: usize,
+    /// The video to load once no splash is in the way.
+    pub load_video_index: Option<usize>,
+    pub current_file_name: String,
+    player: Option<B>,
+    /// Why the last video couldn't be started.
+    failed: Option<String>,
+    pub paused: bool,
+    /// Where to start the next video loaded, after a restart or an undo.
+    pub resume_at: Option<Duration>,
+    /// Why the next video loaded plays.
+    pub play_source: analytics::Source,
+    /// Videos switched away from while playing, and where, latest last.
+    history: Vec<(usize, Duration)>,
+    pub videos_played: usize,
+    /// Records plays for the end-of-sale report, when enabled.
+    pub analytics: Option<analytics::Recorder>,
+    /// Stop on every video's last frame or error instead of moving on, for
+    /// the [`review`](crate::review).
+    pub hold_at_end: bool,
+    frames: FrameSender,
+    events: Vec<control::Event>,
+}
+
+impl<B: PlayerBackend> Controller<B> {
+    /// An empty playlist whose players send their frames to `frames`.
+    pub fn new(frames: FrameSender) -> Self {
+        Self {
+            video_files: Vec::new(),
+            hip_to_index: HashMap::new(),
+            problems: HashMap::new(),
+            hip_overrides: BTreeMap::new(),
+            local_copies: HashMap::new(),
+            current_index: 0,
+            load_video_index: None,
+            current_file_name: "No file loaded".to_string(),
+            player: None,
+            failed: None,
+            paused: false,
+            resume_at: None,
+            play_source: analytics::Source::default(),
+            history: Vec::new(),
+            videos_played: 0,
+            analytics: None,
+            hold_at_end: false,
+            frames,
+            events: Vec::new(),
+        }
+    }
+
+    /// Replace the playlist, sorted by hip number, index it by hip and probe
+    /// its files for videos that can't be played.
+    pub fn set_videos(&mut self, videos: Vec<VideoFile>, overrides: BTreeMap<String, HipOverride>) {
+        self.video_files = videos;
+        self.hip_overrides = overrides;
+        self.hip_to_index.clear();
+        self.problems.clear();
+        self.history.clear();
+        for (index, video) in self.video_files.iter().enumerate() {
+            self.hip_to_index
+                .entry(video.hip_number.clone())
+                .or_default()
+                .push(index);
+            if file_scanner::is_stream_url(&video.path) {
+                continue;
+            }
+            if let Err(problem) = B::probe(&video.path) {
+                warn!("Hip {}: {} ({})", video.hip_number, problem, video.name);
+                self.problems.insert(index, problem);
+            }
+        }
+        if !self.video_files.is_empty() {
+            self.current_index = 0;
+        }
+    }
+
+    /// The video selected, playing or not.
+    pub fn current(&self) -> Option<&VideoFile> {
+        self.video_files.get(self.current_index)
+    }
+
+    /// Where the players send their frames.
+    pub fn frames(&self) -> &FrameSender {
+        &self.frames
+    }
+
+    /// The player of the video on screen, if one is playing.
+    pub fn player(&self) -> Option<&B> {
+        self.player.as_ref()
+    }
+
+    /// Events since the last call, oldest first.
+    pub fn take_events(&mut self) -> Vec<control::Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Every hip with a video, sorted.
+    pub fn hips(&self) -> Vec<String> {
+        let mut hips: Vec<String> = self.hip_to_index.keys().cloned().collect();
+        hips.sort();
+        hips
+    }
+
+    /// Why the video at `index` can't be played, if it can't.
+    pub fn problem(&self, index: usize) -> Option<&str> {
+        self.problems.get(&index).map(String::as_str)
+    }
+
+    /// The videos that can't be played and why, in playlist order.
+    pub fn problems(&self) -> Vec<(&VideoFile, &str)> {
+        let mut indices: Vec<_> = self.problems.keys().copied().collect();
+        indices.sort_unstable();
+        indices
+            .into_iter()
+            .map(|index| (&self.video_files[index], self.problems[&index].as_str()))
+            .collect()
+    }
+
+    /// Select the video at `path` after a rescan, if it is still there.
+    pub fn select_path(&mut self, path: &str) {
+        if let Some(index) = self.video_files.iter().position(|v| v.path == path) {
+            self.current_index = index;
+        }
+    }
+
+    /// Queue the first video of `hip`. With `hip_limit`, as in the demo,
+    /// higher hips are unavailable.
+    pub fn request(&mut self, hip: &str, hip_limit: Option<u32>) -> Request {
+        if hip.len() != 3 || !hip.chars().all(|c| c.is_ascii_digit()) {
+            return Request::Malformed;
+        }
+        if hip_limit.is_some_and(|limit| hip.parse::<u32>().unwrap_or(0) > limit) {
+            warn!("Demo mode: Hip number {} not available", hip);
+            self.record_request(hip, None);
+            return Request::Unavailable;
+        }
+
+        let Some(indices) = self.hip_to_index.get(hip) else {
+            self.record_request(hip, None);
+            return Request::Unavailable;
+        };
+        // Load the first video for this hip number that can be played
+        let Some(&index) = indices.iter().find(|&&i| !self.problems.contains_key(&i)) else {
+            let problem = format!("Hip {}: {}", hip, self.problems[&indices[0]]);
+            warn!("{}", problem);
+            self.record_request(hip, None);
+            return Request::Unplayable(problem);
+        };
+        info!(
+            "Switching to video index {} for hip {} (1 of {} videos)",
+            index,
+            hip,
+            indices.len()
+        );
+        self.remember_interrupted();
+        self.current_index = index;
+        self.load_video_index = Some(index);
+        self.play_source = analytics::Source::Manual;
+        self.videos_played += 1;
+        self.events.push(control::Event::HipEntered {
+            hip: hip.to_string(),
+        });
+        self.record_request(hip, Some(index));
+        Request::Switched(index)
+    }
+
+    fn record_request(&mut self, hip: &str, index: Option<usize>) {
+        let file = index
+            .and_then(|index| self.video_files.get(index))
+            .map(|video| video.name.as_str());
+        if let Some(recorder) = &mut self.analytics {
+            recorder.request(hip, file);
+        }
+    }
+
+    /// Record the play of the video on screen, if any, as ended.
+    fn end_play(&mut self) {
+        if let Some(recorder) = &mut self.analytics {
+            let watched = self
+                .player
+                .as_ref()
+                .map_or(Duration::ZERO, |player| player.position());
+            recorder.play_ended(watched);
+        }
+    }
+
+    /// Queue the next video of the current hip, else the next in the list.
+    pub fn next_video(&mut self) {
+        if self.video_files.is_empty() {
+            return;
+        }
+        // First check if current video has more videos for the same hip number
+        if let Some(current_video) = self.video_files.get(self.current_index) {
+            let current_hip = &current_video.hip_number;
+            if let Some(indices) = self.hip_to_index.get(current_hip) {
+                // Find current position in the hip's video list
+                if let Some(pos) = indices.iter().position(|&idx| idx == self.current_index) {
+                    // If there are more videos for this hip, play the next one
+                    let next = indices[pos + 1..]
+                        .iter()
+                        .position(|i| !self.problems.contains_key(i));
+                    if let Some(skipped) = next {
+                        let pos = pos + skipped;
+                        let next_index = indices[pos + 1];
+                        self.current_index = next_index;
+                        self.load_video_index = Some(next_index);
+                        if self.play_source == analytics::Source::Manual {
+                            self.play_source = analytics::Source::Auto;
+                        }
+                        info!(
+                            "Playing next video for hip {} ({} of {})",
+                            current_hip,
+                            pos + 2,
+                            indices.len()
+                        );
+                        return;
+                    }
+                }
+            }
+        }
+
+        // Otherwise, move to the next video in the list, past any that can't
+        // be played
+        let count = self.video_files.len();
+        let next_index = (1..=count)
+            .map(|step| (self.current_index + step) % count)
+            .find(|i| !self.problems.contains_key(i))
+            .unwrap_or((self.current_index + 1) % count);
+        self.current_index = next_index;
+        self.load_video_index = Some(next_index);
+        self.play_source = analytics::Source::Attract;
+        self.videos_played += 1;
+        info!(
+            "Auto-advancing to next video, total played: {}",
+            self.videos_played
+        );
+    }
+
+    pub fn navigate_forward(&mut self) {
+        if self.current_index < self.video_files.len().saturating_sub(1) {
+            self.remember_interrupted();
+            self.navigate_to(self.current_index + 1);
+            info!(
+                "Navigated forward to index {}: {}",
+                self.current_index, self.current_file_name
+            );
+        }
+    }
+
+    pub fn navigate_backward(&mut self) {
+        if self.current_index > 0 {
+            self.remember_interrupted();
+            self.navigate_to(self.current_index - 1);
+            info!(
+                "Navigated backward to index {}: {}",
+                self.current_index, self.current_file_name
+            );
+        }
+    }
+
+    /// Keep the video playing and its position for [`Self::undo_switch`],
+    /// before switching away from it.
+    fn remember_interrupted(&mut self) {
+        let Some(player) = &self.player else {
+            return;
+        };
+        if self.history.len() == HISTORY_LEN {
+            self.history.remove(0);
+        }
+        self.history.push((self.current_index, player.position()));
+    }
+
+    /// Go back to the video playing before the last switch, where it was
+    /// interrupted. Returns its hip, or `None` with nothing to go back to.
+    pub fn undo_switch(&mut self) -> Option<String> {
+        let (index, position) = self.history.pop()?;
+        let hip = self.video_files.get(index)?.hip_number.clone();
+        info!(
+            "Undoing the last switch: back to hip {} at {:.1}s",
+            hip,
+            position.as_secs_f64()
+        );
+        self.navigate_to(index);
+        self.resume_at = Some(position);
+        Some(hip)
+    }
+
+    fn navigate_to(&mut self, index: usize) {
+        self.current_index = index;
+        self.load_video_index = Some(index);
+        self.play_source = analytics::Source::Manual;
+        self.current_file_name = self.display_name(index);
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if let Some(player) = &self.player {
+            player.set_paused(paused);
+        }
+    }
+
+    /// Stop the player for good, dropping any video queued.
+    pub fn stop(&mut self) {
+        if let Some(player) = self.player.take() {
+            if let Err(e) = player.stop() {
+                warn!("Failed to stop video player: {}", e);
+            }
+        }
+        self.load_video_index = None;
+    }
+
+    /// Start the video at `index` in place of the one playing.
+    pub fn load_video(&mut self, index: usize) {
+        self.end_play();
+        // Stopping only signals the old player's threads, which close the
+        // video and audio device on their own, so nothing here waits on them
+        if let Some(player) = self.player.take() {
+            if let Err(e) = player.stop() {
+                warn!("Failed to stop video player: {}", e);
+            }
+        }
+
+        self.paused = false;
+        self.failed = None;
+        let Some(video_file) = self.video_files.get(index) else {
+            error!("Invalid video index {}", index);
+            return;
+        };
+        self.current_index = index;
+        self.current_file_name = self.display_name(index);
+        info!("Loading video: {}", Path::new(&video_file.path).display());
+
+        let uri = if file_scanner::is_stream_url(&video_file.path) {
+            video_file.path.clone()
+        } else {
+            let path = match self.local_copies.get(&video_file.path) {
+                Some(copy) => {
+                    info!("Playing the local copy {}", copy.display());
+                    copy.as_path()
+                }
+                None => Path::new(&video_file.path),
+            };
+            match dunce::canonicalize(path) {
+                Ok(path) => format!("file://{}", path.display()),
+                Err(e) => {
+                    error!("Failed to canonicalize path {}: {}", video_file.path, e);
+                    self.current_file_name = format!("Error: {}", e);
+                    self.failed = Some(format!("Can't find {}: {}", video_file.name, e));
+                    return;
+                }
+            }
+        };
+
+        let mut player = match B::open(&uri, self.frames.clone()) {
+            Ok(player) => player,
+            Err(e) => {
+                error!("Failed to create player: {}", e);
+                self.current_file_name = format!("Error: {}", e);
+                let message = format!("Failed to open {}: {}", video_file.name, e);
+                self.failed = Some(message.clone());
+                self.events.push(control::Event::Error { message });
+                return;
+            }
+        };
+        let (start, end) = self.segment(index);
+        if end.is_none()
+            && self
+                .hip_override(index)
+                .is_some_and(|h| h.end_seconds.is_some())
+        {
+            warn!(
+                "Ignoring end_seconds of hip {}: it isn't after start_seconds",
+                video_file.hip_number
+            );
+        }
+        // Resuming a hip picks up where it was, inside its segment
+        if let Some(at) = self.resume_at.take().or(start) {
+            player.set_start(at);
+        }
+        if let Some(at) = end {
+            player.set_end(at);
+        }
+        if let Some(gain) = self.hip_override(index).and_then(|h| h.gain) {
+            info!(
+                "Applying audio gain {} for hip {}",
+                gain, video_file.hip_number
+            );
+            player.set_gain(gain);
+        }
+        if let Err(e) = player.play() {
+            error!("Failed to play video: {}", e);
+            self.current_file_name = format!("Error: {}", e);
+            let message = format!("Failed to play {}: {}", video_file.name, e);
+            self.failed = Some(message.clone());
+            self.events.push(control::Event::Error { message });
+            return;
+        }
+        if let Some(recorder) = &mut self.analytics {
+            recorder.play_started(&video_file.hip_number, &video_file.name, self.play_source);
+        }
+        info!("Video player started for {}", uri);
+        self.events.push(control::Event::VideoStarted {
+            hip: video_file.hip_number.clone(),
+            file: video_file.name.clone(),
+        });
+        self.player = Some(player);
+    }
+
+    /// Watch the video playing: move on after an error or at its end, or
+    /// hold its last frame where the hip asks for that.
+    pub fn update(&mut self) {
+        let Some(player) = &self.player else {
+            return;
+        };
+        if let Some(error) = player.error() {
+            error!("Playback error detected: {}", error);
+            self.events.push(control::Event::Error {
+                message: error.clone(),
+            });
+            if self.hold_at_end {
+                self.end_play();
+                self.player = None;
+                self.failed = Some(error);
+            } else {
+                self.next_video();
+            }
+            return;
+        }
+
+        if player.is_eos() {
+            if let Some(video) = self.video_files.get(self.current_index) {
+                self.events.push(control::Event::VideoEnded {
+                    hip: video.hip_number.clone(),
+                    file: video.name.clone(),
+                });
+            }
+            if self.holds_last_frame() {
+                info!(
+                    "EOS detected, holding last frame of {}",
+                    self.current_file_name
+                );
+                // The texture keeps the final frame until another hip is entered
+                self.end_play();
+                self.player = None;
+            } else {
+                info!("EOS detected, loading next video");
+                self.next_video();
+            }
+        }
+    }
+
+    pub fn hip_override(&self, index: usize) -> Option<&HipOverride> {
+        let video = self.video_files.get(index)?;
+        self.hip_overrides.get(&video.hip_number)
+    }
+
+    /// The in and out points of the video at `index`, from its hip's
+    /// `start_seconds` and `end_seconds`. An out point that isn't past the
+    /// in point is left out.
+    pub fn segment(&self, index: usize) -> (Option<Duration>, Option<Duration>) {
+        let Some(hip) = self.hip_override(index) else {
+            return (None, None);
+        };
+        let start = hip.start_seconds.map(|s| Duration::from_secs_f64(s.get()));
+        let end = hip.end_seconds.map(|s| Duration::from_secs_f64(s.get()));
+        match (start, end) {
+            (Some(start), Some(end)) if end <= start => (Some(start), None),
+            segment => segment,
+        }
+    }
+
+    /// Turn the hip playing up or down by `db` decibels, live, and keep the
+    /// new gain in its overrides. Returns the hip and the gain, or `None`
+    /// with nothing playing.
+    pub fn trim_gain(&mut self, db: f32) -> Option<(String, f32)> {
+        let player = self.player.as_ref()?;
+        let hip = self.video_files.get(self.current_index)?.hip_number.clone();
+        let overrides = self.hip_overrides.entry(hip.clone()).or_default();
+        let gain = trimmed(overrides.gain.unwrap_or(1.0), db);
+        overrides.gain = Some(gain);
+        info!("Trimmed hip {} to gain {}", hip, gain);
+        player.set_gain(gain);
+        Some((hip, gain))
+    }
+
+    /// What the player is doing.
+    pub fn state(&self) -> PlayerState {
+        let Some(player) = &self.player else {
+            return match &self.failed {
+                Some(e) => PlayerState::Error(e.clone()),
+                None => PlayerState::Idle,
+            };
+        };
+        if let Some(e) = player.error() {
+            PlayerState::Error(e)
+        } else if player.is_loading() {
+            PlayerState::Loading
+        } else {
+            PlayerState::Playing
+        }
+    }
+
+    /// How far into the video playing the player is.
+    pub fn position(&self) -> Option<Duration> {
+        Some(self.player.as_ref()?.position())
+    }
+
+    /// Whether the video playing has an audio track; `None` with nothing
+    /// playing or before the player knows.
+    pub fn has_audio(&self) -> Option<bool> {
+        self.player.as_ref()?.has_audio()
+    }
+
+    /// Text shown after the "now playing" label: the hip's custom label if set,
+    /// otherwise the file name.
+    pub fn display_name(&self, index: usize) -> String {
+        let Some(video) = self.video_files.get(index) else {
+            return String::new();
+        };
+        self.hip_override(index)
+            .and_then(|h| h.label.clone())
+            .unwrap_or_else(|| video.name.clone())
+    }
+
+    /// Whether playback should stop on the current video's last frame. Hips
+    /// with several videos still play through; the hold applies after the last.
+    pub fn holds_last_frame(&self) -> bool {
+        if self.hold_at_end {
+            return true;
+        }
+        let holds = self
+            .hip_override(self.current_index)
+            .and_then(|h| h.hold_last_frame)
+            .unwrap_or(false);
+        let last_for_hip = self
+            .video_files
+            .get(self.current_index)
+            .and_then(|video| self.hip_to_index.get(&video.hip_number))
+            .and_then(|indices| indices.last())
+            .map_or(true, |&last| last == self.current_index);
+        holds && last_for_hip
+    }
+}
+
+/// A player that plays nothing, for tests. Its state is shared with the
+/// test through [`MockState`], so it can be changed while the controller
+/// owns the player.
+#[cfg(any(test, feature = "mock"))]
+pub mod mock {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Default)]
+    pub struct MockState {
+        /// URIs opened, in order.
+        pub opened: Vec<String>,
+        pub playing: bool,
+        pub paused: bool,
+        pub start: Option<Duration>,
+        pub end: Option<Duration>,
+        pub gain: Option<f32>,
+        pub position: Duration,
+        pub eos: bool,
+        /// Opening, until a test clears it.
+        pub loading: bool,
+        pub error: Option<String>,
+        /// Fail to open URIs containing this.
+        pub fail_open: Option<String>,
+        /// Probe paths containing this as unplayable.
+        pub unplayable: Option<String>,
+        pub has_audio: Option<bool>,
+    }
+
+    thread_local! {
+        static STATE: Arc<Mutex<MockState>> = Arc::default();
+    }
+
+    /// The state of the mock players created on this thread.
+    pub fn state() -> Arc<Mutex<MockState>> {
+        STATE.with(Arc::clone)
+    }
+
+    pub struct MockPlayer {
+        state: Arc<Mutex<MockState>>,
+    }
+
+    impl PlayerBackend for MockPlayer {
+        fn open(uri: &str, _frames: FrameSender) -> Result<Self, String> {
+            let state = state();
+            let mut s = state.lock().unwrap();
+            if s.fail_open.as_ref().is_some_and(|fail| uri.contains(fail)) {
+                return Err(format!("can't open {}", uri));
+            }
+            s.opened.push(uri.to_string());
+            s.eos = false;
+            s.error = None;
+            s.start = None;
+            s.end = None;
+            s.gain = None;
+            drop(s);
+            Ok(Self { state })
+        }
+
+        fn play(&mut self) -> Result<(), String> {
+            self.state.lock().unwrap().playing = true;
+            Ok(())
+        }
+
+        fn stop(&self) -> Result<(), String> {
+            self.state.lock().unwrap().playing = false;
+            Ok(())
+        }
+
+        fn set_start(&mut self, at: Duration) {
+            self.state.lock().unwrap().start = Some(at);
+        }
+
+        fn set_end(&mut self, at: Duration) {
+            self.state.lock().unwrap().end = Some(at);
+        }
+
+        fn set_gain(&self, gain: f32) {
+            self.state.lock().unwrap().gain = Some(gain);
+        }
+
+        fn set_paused(&self, paused: bool) {
+            self.state.lock().unwrap().paused = paused;
+        }
+
+        fn position(&self) -> Duration {
+            self.state.lock().unwrap().position
+        }
+
+        fn is_eos(&self) -> bool {
+            self.state.lock().unwrap().eos
+        }
+
+        fn is_loading(&self) -> bool {
+            self.state.lock().unwrap().loading
+        }
+
+        fn probe(path: &str) -> Result<(), String> {
+            let state = state();
+            let s = state.lock().unwrap();
+            match &s.unplayable {
+                Some(bad) if path.contains(bad) => Err("unsupported codec (prores)".to_string()),
+                _ => Ok(()),
+            }
+        }
+
+        fn error(&self) -> Option<String> {
+            self.state.lock().unwrap().error.clone()
+        }
+
+        fn has_audio(&self) -> Option<bool> {
+            self.state.lock().unwrap().has_audio
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::{self, MockPlayer};
+    use super::*;
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::TempDir;
+
+    /// A controller over empty video files named `names`, in `dir`.
+    fn controller(dir: &TempDir, names: &[&str]) -> Controller<MockPlayer> {
+        for name in names {
+            fs::File::create(dir.path().join(name)).unwrap();
+        }
+        let videos = file_scanner::scan_video_files(dir.path()).unwrap();
+        let mut controller = Controller::new(FrameSender::new(watch::channel(None).0));
+        controller.set_videos(videos, BTreeMap::new());
+        controller
+    }
+
+    #[test]
+    fn test_request() {
+        let dir = TempDir::new().unwrap();
+        let mut controller = controller(&dir, &["001.mp4", "002.mp4"]);
+
+        assert_eq!(controller.request("12", None), Request::Malformed);
+        assert_eq!(controller.request("abc", None), Request::Malformed);
+        assert_eq!(controller.request("999", None), Request::Unavailable);
+        assert_eq!(controller.request("002", Some(1)), Request::Unavailable);
+        assert_eq!(controller.videos_played, 0);
+
+        assert_eq!(controller.request("002", None), Request::Switched(1));
+        assert_eq!(controller.load_video_index, Some(1));
+        assert_eq!(controller.videos_played, 1);
+        assert_eq!(
+            controller.take_events(),
+            vec![control::Event::HipEntered {
+                hip: "002".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_load_and_advance_at_end() {
+        let dir = TempDir::new().unwrap();
+        let mut controller = controller(&dir, &["001 a.mp4", "001 b.mp4", "002.mp4"]);
+        let state = mock::state();
+
+        controller.request("001", None);
+        controller.load_video(0);
+        assert!(state.lock().unwrap().playing);
+        // Videos of a hip play in the order they were scanned
+        let first = controller.video_files[0].name.clone();
+        assert!(state.lock().unwrap().opened[0].ends_with(&first));
+        assert_eq!(controller.current_file_name, first);
+
+        // Pausing reaches the player; loading another video unpauses
+        controller.set_paused(true);
+        assert!(state.lock().unwrap().paused);
+
+        state.lock().unwrap().eos = true;
+        controller.update();
+        assert_eq!(controller.load_video_index, Some(1));
+        assert_eq!(controller.play_source, analytics::Source::Auto);
+        controller.load_video(1);
+        assert!(!controller.paused);
+
+        // A playback error moves on as well
+        state.lock().unwrap().error = Some("decoder gone".to_string());
+        controller.update();
+        assert_eq!(controller.load_video_index, Some(2));
+        assert_eq!(controller.play_source, analytics::Source::Attract);
+
+        let events = controller.take_events();
+        assert!(matches!(&events[1], control::Event::VideoStarted { file, .. } if *file == first));
+        assert!(matches!(&events[2], control::Event::VideoEnded { .. }));
+        assert!(
+            matches!(&events[4], control::Event::Error { message } if message == "decoder gone")
+        );
+
+        // A local copy plays in place of the file
+        let copy = dir.path().join("copy of 002.mp4");
+        fs::File::create(&copy).unwrap();
+        let path = controller.video_files[2].path.clone();
+        controller.local_copies.insert(path, copy);
+        controller.load_video(2);
+        assert!(state.lock().unwrap().opened[2].ends_with("copy of 002.mp4"));
+    }
+
+    #[test]
+    fn test_hold_last_frame() {
+        let dir = TempDir::new().unwrap();
+        let mut controller = controller(&dir, &["001.mp4", "002.mp4"]);
+        let overrides = BTreeMap::from([(
+            "001".to_string(),
+            HipOverride {
+                hold_last_frame: Some(true),
+                gain: Some(0.5),
+                ..Default::default()
+            },
+        )]);
+        let videos = controller.video_files.clone();
+        controller.set_videos(videos, overrides);
+        let state = mock::state();
+
+        controller.load_video(0);
+        assert_eq!(state.lock().unwrap().gain, Some(0.5));
+        state.lock().unwrap().eos = true;
+        controller.update();
+        assert!(controller.player().is_none());
+        assert_eq!(controller.load_video_index, None);
+
+        // In a review every video holds, even one that fails
+        controller.hold_at_end = true;
+        controller.load_video(1);
+        state.lock().unwrap().position = Duration::from_secs(2);
+        assert_eq!(controller.position(), Some(Duration::from_secs(2)));
+        state.lock().unwrap().error = Some("decoder gone".to_string());
+        controller.update();
+        assert!(controller.player().is_none());
+        assert_eq!(controller.load_video_index, None);
+        assert_eq!(
+            controller.state(),
+            PlayerState::Error("decoder gone".to_string())
+        );
+        assert_eq!(controller.position(), None);
+    }
+
+    #[test]
+    fn test_failed_open_and_resume() {
+        let dir = TempDir::new().unwrap();
+        let mut controller = controller(&dir, &["001.mp4", "002 broken.mp4"]);
+        let state = mock::state();
+        state.lock().unwrap().fail_open = Some("broken".to_string());
+
+        controller.load_video(1);
+        assert!(controller.player().is_none());
+        assert!(controller.current_file_name.starts_with("Error: "));
+        assert!(
+            matches!(controller.state(), PlayerState::Error(e) if e.contains("002 broken.mp4"))
+        );
+        assert!(matches!(
+            &controller.take_events()[..],
+            [control::Event::Error { .. }]
+        ));
+
+        controller.resume_at = Some(Duration::from_secs(3));
+        controller.load_video(0);
+        assert_eq!(state.lock().unwrap().start, Some(Duration::from_secs(3)));
+        assert_eq!(controller.resume_at, None);
+
+        controller.stop();
+        assert!(controller.player().is_none());
+        assert!(!state.lock().unwrap().playing);
+    }
+
+    #[test]
+    fn test_in_and_out_points() {
+        let dir = TempDir::new().unwrap();
+        let mut controller = controller(&dir, &["001.mp4", "002.mp4", "003.mp4"]);
+        let seconds = |s| Some(crate::config::Seconds::new(s).unwrap());
+        let overrides = BTreeMap::from([
+            (
+                "001".to_string(),
+                HipOverride {
+                    start_seconds: seconds(12.5),
+                    end_seconds: seconds(40.0),
+                    ..Default::default()
+                },
+            ),
+            (
+                "002".to_string(),
+                HipOverride {
+                    start_seconds: seconds(30.0),
+                    end_seconds: seconds(30.0),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let videos = controller.video_files.clone();
+        controller.set_videos(videos, overrides);
+        let state = mock::state();
+
+        controller.load_video(0);
+        let s = state.lock().unwrap();
+        assert_eq!(s.start, Some(Duration::from_secs_f64(12.5)));
+        assert_eq!(s.end, Some(Duration::from_secs(40)));
+        drop(s);
+
+        // Resuming starts where the hip left off, still ending at its out point
+        controller.resume_at = Some(Duration::from_secs(20));
+        controller.load_video(0);
+        assert_eq!(state.lock().unwrap().start, Some(Duration::from_secs(20)));
+        assert_eq!(state.lock().unwrap().end, Some(Duration::from_secs(40)));
+
+        // An out point that isn't past the in point is ignored
+        assert_eq!(controller.segment(1), (Some(Duration::from_secs(30)), None));
+        controller.load_video(1);
+        assert_eq!(state.lock().unwrap().start, Some(Duration::from_secs(30)));
+        assert_eq!(state.lock().unwrap().end, None);
+
+        controller.load_video(2);
+        assert_eq!(state.lock().unwrap().start, None);
+        assert_eq!(state.lock().unwrap().end, None);
+    }
+
+    #[test]
+    fn test_player_state() {
+        let dir = TempDir::new().unwrap();
+        let mut controller = controller(&dir, &["001.mp4", "002.mp4"]);
+        let state = mock::state();
+        assert_eq!(controller.state(), PlayerState::Idle);
+
+        state.lock().unwrap().loading = true;
+        controller.load_video(0);
+        assert_eq!(controller.state(), PlayerState::Loading);
+        state.lock().unwrap().loading = false;
+        assert_eq!(controller.state(), PlayerState::Playing);
+
+        state.lock().unwrap().error = Some("decoder gone".to_string());
+        assert_eq!(
+            controller.state(),
+            PlayerState::Error("decoder gone".to_string())
+        );
+
+        controller.load_video(1);
+        assert_eq!(controller.state(), PlayerState::Playing);
+        controller.stop();
+        assert_eq!(controller.state(), PlayerState::Idle);
+    }
+
+    #[test]
+    fn test_trim_gain() {
+        assert_eq!(trimmed(1.0, -6.0), 0.5012);
+        assert_eq!(trimmed(trimmed(1.0, -1.0), 1.0), 1.0);
+        assert_eq!(trimmed(0.0, 1.0), 0.0112);
+        assert_eq!(trimmed(2.0, 20.0), 3.9811);
+
+        let dir = TempDir::new().unwrap();
+        let mut controller = controller(&dir, &["001.mp4", "002.mp4"]);
+        assert_eq!(controller.trim_gain(-1.0), None);
+        controller.load_video(1);
+        assert_eq!(controller.trim_gain(-20.0), Some(("002".to_string(), 0.1)));
+        assert_eq!(mock::state().lock().unwrap().gain, Some(0.1));
+        assert_eq!(controller.hip_override(1).unwrap().gain, Some(0.1));
+    }
+
+    #[test]
+    fn test_unplayable_videos() {
+        let dir = TempDir::new().unwrap();
+        mock::state().lock().unwrap().unplayable = Some("prores".to_string());
+        let mut controller = controller(
+            &dir,
+            &["001.mp4", "002 prores.mp4", "003 a prores.mp4", "003 b.mp4"],
+        );
+        let problems: Vec<_> = controller
+            .problems()
+            .into_iter()
+            .map(|(video, problem)| (video.name.as_str(), problem))
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                ("002 prores.mp4", "unsupported codec (prores)"),
+                ("003 a prores.mp4", "unsupported codec (prores)")
+            ]
+        );
+
+        assert_eq!(
+            controller.request("002", None),
+            Request::Unplayable("Hip 002: unsupported codec (prores)".to_string())
+        );
+        assert_eq!(controller.load_video_index, None);
+        // A hip plays its first video that can be played
+        assert_eq!(controller.request("003", None), Request::Switched(3));
+
+        // Advancing skips them too
+        controller.current_index = 0;
+        controller.next_video();
+        assert_eq!(controller.load_video_index, Some(3));
+        assert_eq!(controller.problem(1), Some("unsupported codec (prores)"));
+        assert_eq!(controller.problem(3), None);
+    }
+
+    #[test]
+    fn test_navigate() {
+        let dir = TempDir::new().unwrap();
+        let mut controller = controller(&dir, &["001.mp4", "002.mp4"]);
+
+        controller.navigate_backward();
+        assert_eq!(controller.load_video_index, None);
+        controller.navigate_forward();
+        assert_eq!(controller.load_video_index, Some(1));
+        assert_eq!(controller.current_file_name, "002.mp4");
+        controller.navigate_forward();
+        assert_eq!(controller.current_index, 1);
+        controller.navigate_backward();
+        assert_eq!(controller.current_index, 0);
+        assert_eq!(controller.play_source, analytics::Source::Manual);
+    }
+
+    #[test]
+    fn test_undo_switch() {
+        let dir = TempDir::new().unwrap();
+        let mut controller = controller(&dir, &["001.mp4", "002.mp4", "003.mp4"]);
+        let state = mock::state();
+        assert_eq!(controller.undo_switch(), None);
+
+        // Only switching away from a video playing counts
+        controller.request("002", None);
+        controller.load_video(1);
+        state.lock().unwrap().position = Duration::from_secs(40);
+        controller.request("003", None);
+        controller.load_video(2);
+        state.lock().unwrap().position = Duration::from_secs(5);
+        controller.navigate_backward();
+
+        assert_eq!(controller.undo_switch(), Some("003".to_string()));
+        assert_eq!(controller.load_video_index, Some(2));
+        assert_eq!(controller.resume_at, Some(Duration::from_secs(5)));
+        controller.load_video(2);
+        assert_eq!(state.lock().unwrap().start, Some(Duration::from_secs(5)));
+        assert_eq!(controller.undo_switch(), Some("002".to_string()));
+        assert_eq!(controller.current_file_name, "002.mp4");
+        assert_eq!(controller.resume_at, Some(Duration::from_secs(40)));
+        assert_eq!(controller.undo_switch(), None);
+    }
+
+    #[test]
+    fn test_rgba_image_padded_rows() {
+        // 2x2 red/green over blue/white, each row padded to 12 bytes
+        let data = [
+            255, 0, 0, 255, 0, 255, 0, 255, 9, 9, 9, 9, //
+            0, 0, 255, 255, 255, 255, 255, 255, 9, 9, 9, 9,
+        ];
+        let image = rgba_image([2, 2], 12, &data);
+        assert_eq!(
+            image.pixels,
+            [
+                egui::Color32::RED,
+                egui::Color32::GREEN,
+                egui::Color32::BLUE,
+                egui::Color32::WHITE
+            ]
+        );
+        assert_eq!(rgba_image([2, 2], 8, &data[..16]).size, [2, 2]);
+    }
+
+    #[test]
+    fn test_output_adjustment() {
+        assert_eq!(Adjustment::new(&OutputConfig::default()), None);
+        let grey = egui::Color32::from_gray(128);
+        let orange = egui::Color32::from_rgb(255, 128, 0);
+        let adjusted = |output: OutputConfig| {
+            let mut image = egui::ColorImage {
+                size: [2, 1],
+                pixels: vec![grey, orange],
+            };
+            Adjustment::new(&output).unwrap().apply(&mut image);
+            image.pixels
+        };
+
+        let brighter = adjusted(OutputConfig {
+            brightness: 0.2,
+            ..Default::default()
+        });
+        assert_eq!(
+            brighter,
+            [
+                egui::Color32::from_gray(179),
+                egui::Color32::from_rgb(255, 179, 51)
+            ]
+        );
+        let flat = adjusted(OutputConfig {
+            contrast: 0.0,
+            ..Default::default()
+        });
+        assert_eq!(flat, [egui::Color32::from_gray(128); 2]);
+        let grey_scale = adjusted(OutputConfig {
+            saturation: 0.0,
+            ..Default::default()
+        });
+        assert_eq!(grey_scale[0], grey);
+        let [r, g, b, _] = grey_scale[1].to_array();
+        assert!(r == g && g == b);
+        // Gamma lifts the midtones but leaves black and white be
+        let lifted = adjusted(OutputConfig {
+            gamma: 2.0,
+            ..Default::default()
+        });
+        assert_eq!(lifted[0], egui::Color32::from_gray(181));
+        assert_eq!(lifted[1].to_array()[..3], [255, 181, 0]);
+    }
+
+    #[test]
+    fn test_frames_wake_window() {
+        let (sender, receiver) = watch::channel(None);
+        let frames = FrameSender::new(sender);
+        let player_frames = frames.clone();
+        let ctx = egui::Context::default();
+        let wakes = Arc::new(AtomicUsize::new(0));
+        let counter = wakes.clone();
+        ctx.set_request_repaint_callback(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let frame = || egui::ColorImage::new([1, 1], egui::Color32::BLACK);
+        player_frames.send(frame()).unwrap();
+        assert_eq!(wakes.load(Ordering::SeqCst), 0);
+        frames.repaint(&ctx);
+        player_frames.send(frame()).unwrap();
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(wakes.load(Ordering::SeqCst), 1);
+
+        drop(receiver);
+        assert!(player_frames.send(frame()).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+//! The pre-sale content review, started with `--review`: the player steps
+//! through every hip, plays the first seconds of its first video and staff
+//! mark each one OK or needing a fix. The results go to `review-<date>.csv`
+//! in the analytics folder, rewritten after every mark so the report is
+//! complete whenever the review stops.
+
+use crate::csv;
+use crate::file_scanner::VideoFile;
+use chrono::NaiveDate;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How much of each hip is played.
+pub const CLIP: Duration = Duration::from_secs(5);
+
+const HEADER: [&str; 3] = ["hip", "file", "result"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Ok,
+    NeedsFix,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Verdict::Ok => "ok",
+            Verdict::NeedsFix => "needs fix",
+        })
+    }
+}
+
+/// One hip to review.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item {
+    pub hip: String,
+    pub file: String,
+    /// Where its first video is in the playlist.
+    pub index: usize,
+    pub verdict: Option<Verdict>,
+}
+
+/// Where the review is, and what has been marked so far.
+#[derive(Debug)]
+pub struct Review {
+    pub items: Vec<Item>,
+    /// The item on screen; `items.len()` once past the last.
+    pub position: usize,
+    /// The day the review started, which names its report.
+    pub date: NaiveDate,
+    /// Where the report was last written.
+    pub report: Option<PathBuf>,
+}
+
+impl Review {
+    /// Review the first video of every hip in `videos`, which are sorted by
+    /// hip, starting on `date`.
+    pub fn new(videos: &[VideoFile], date: NaiveDate) -> Self {
+        let mut items: Vec<Item> = Vec::new();
+        for (index, video) in videos.iter().enumerate() {
+            if items
+                .last()
+                .map_or(true, |item| item.hip != video.hip_number)
+            {
+                items.push(Item {
+                    hip: video.hip_number.clone(),
+                    file: video.name.clone(),
+                    index,
+                    verdict: None,
+                });
+            }
+        }
+        Self {
+            items,
+            position: 0,
+            date,
+            report: None,
+        }
+    }
+
+    pub fn current(&self) -> Option<&Item> {
+        self.items.get(self.position)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.items.len()
+    }
+
+    /// Mark the hip on screen and move to the next.
+    pub fn mark(&mut self, verdict: Verdict) {
+        if let Some(item) = self.items.get_mut(self.position) {
+            item.verdict = Some(verdict);
+            self.position += 1;
+        }
+    }
+
+    /// Move to the next hip without marking this one.
+    pub fn skip(&mut self) {
+        self.position = (self.position + 1).min(self.items.len());
+    }
+
+    /// Go back to the previous hip, to look at it again.
+    pub fn back(&mut self) {
+        self.position = self.position.saturating_sub(1);
+    }
+
+    /// How many hips are OK, need a fix, and aren't marked yet.
+    pub fn counts(&self) -> (usize, usize, usize) {
+        let count = |verdict| {
+            self.items
+                .iter()
+                .filter(|item| item.verdict == verdict)
+                .count()
+        };
+        (
+            count(Some(Verdict::Ok)),
+            count(Some(Verdict::NeedsFix)),
+            count(None),
+        )
+    }
+
+    pub fn report_csv(&self) -> String {
+        let mut out = csv::join_line(&HEADER);
+        out.push('\n');
+        for item in &self.items {
+            let verdict = item.verdict.map(|v| v.to_string()).unwrap_or_default();
+            out.push_str(&csv::join_line(&[&item.hip, &item.file, &verdict]));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Write the report into `dir`, replacing the one written before.
+    pub fn write_report(&mut self, dir: &Path) -> Result<(), String> {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        let path = dir.join(format!("review-{}.csv", self.date.format("%Y-%m-%d")));
+        fs::write(&path, self.report_csv()).map_err(|e| format!("{}: {}", path.display(), e))?;
+        self.report = Some(path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video(name: &str) -> VideoFile {
+        VideoFile {
+            path: format!("/videos/{}", name),
+            name: name.to_string(),
+            hip_number: name[..3].to_string(),
+        }
+    }
+
+    #[test]
+    fn test_review() {
+        let videos = [
+            video("001_a.mp4"),
+            video("001_b.mp4"),
+            video("002, walk.mp4"),
+            video("007.mp4"),
+        ];
+        let date = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+        let mut review = Review::new(&videos, date);
+        let hips: Vec<_> = review.items.iter().map(|i| (&*i.hip, i.index)).collect();
+        assert_eq!(hips, [("001", 0), ("002", 2), ("007", 3)]);
+
+        review.mark(Verdict::Ok);
+        review.mark(Verdict::Ok);
+        // Second thoughts about 002
+        review.back();
+        assert_eq!(review.current().unwrap().hip, "002");
+        review.mark(Verdict::NeedsFix);
+        review.skip();
+        assert!(review.is_finished());
+        review.skip();
+        assert_eq!(review.position, 3);
+        assert_eq!(review.counts(), (1, 1, 1));
+
+        let dir = tempfile::tempdir().unwrap();
+        review.write_report(&dir.path().join("analytics")).unwrap();
+        let path = review.report.clone().unwrap();
+        assert!(path.ends_with("analytics/review-2026-03-14.csv"));
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            "hip,file,result\n\
+             001,001_a.mp4,ok\n\
+             002,\"002, walk.mp4\",needs fix\n\
+             007,007.mp4,\n"
+        );
+    }
+}
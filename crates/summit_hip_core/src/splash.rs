@@ -0,0 +1,248 @@
+//! When the splash screen shows and which image it shows: at startup, every
+//! `[splash] interval` videos, and on a loop while there are no videos. The
+//! window draws [`Splash::image`] while [`Splash::showing`] is set.
+
+use crate::config::SplashConfig;
+use crate::file_scanner::scan_splash_images;
+use log::{info, warn};
+use rand::Rng;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub struct Splash {
+    pub showing: bool,
+    /// Seconds the current splash has been up.
+    pub timer: f64,
+    pub images: Vec<PathBuf>,
+    pub index: usize,
+}
+
+impl Default for Splash {
+    fn default() -> Self {
+        Self {
+            showing: true,
+            timer: 0.0,
+            images: Vec::new(),
+            index: 0,
+        }
+    }
+}
+
+/// Whether a splash is due before the next video, after `videos_played`.
+pub fn is_due(config: &SplashConfig, videos_played: usize) -> bool {
+    if !config.enabled {
+        return false;
+    }
+
+    // interval = 0 means only show at startup
+    if config.interval == 0 {
+        return videos_played == 0;
+    }
+
+    // Show splash every N videos
+    videos_played % config.interval == 0
+}
+
+impl Splash {
+    pub fn load_images(&mut self, splash_dir: &Path) {
+        self.images.clear();
+        if splash_dir.exists() {
+            self.images = scan_splash_images(splash_dir);
+            info!(
+                "Loaded {} splash images from {}",
+                self.images.len(),
+                splash_dir.display()
+            );
+        } else {
+            warn!("Splash directory {} does not exist", splash_dir.display());
+        }
+    }
+
+    /// The image to draw, if there is one.
+    pub fn image(&self) -> Option<&Path> {
+        self.images.get(self.index).map(PathBuf::as_path)
+    }
+
+    pub fn hide(&mut self) {
+        self.showing = false;
+    }
+
+    /// Count `dt` seconds of the splash on screen. The startup splash ends
+    /// after its duration once there are videos to play; without videos the
+    /// images keep cycling.
+    pub fn advance(
+        &mut self,
+        dt: f64,
+        config: &SplashConfig,
+        have_videos: bool,
+        videos_played: &mut usize,
+    ) {
+        if !self.showing {
+            return;
+        }
+        self.timer += dt;
+        if self.timer < config.duration_seconds.get() {
+            return;
+        }
+        // If videos are loaded and splash interval is 0 (only at startup), hide splash
+        if have_videos && config.interval == 0 {
+            info!("Hiding splash screen after duration (videos loaded, interval=0)");
+            self.showing = false;
+            // Count a video as played to prevent splash from showing again
+            if *videos_played == 0 {
+                *videos_played = 1;
+            }
+        } else if !have_videos && !self.images.is_empty() {
+            // If no videos are loaded, keep cycling splash screens
+            self.timer = 0.0;
+            if self.images.len() > 1 {
+                self.index = (self.index + 1) % self.images.len();
+                info!(
+                    "No videos loaded - cycling to next splash screen {}",
+                    self.index
+                );
+            }
+            // If only one splash, just reset timer to keep showing it
+        }
+    }
+
+    /// Put up the splash shown between videos, picking its image by
+    /// `[splash] rotation_mode`.
+    pub fn start(&mut self, config: &SplashConfig) {
+        self.showing = true;
+        self.timer = 0.0;
+
+        match config.rotation_mode.as_deref() {
+            Some("random") => {
+                if !self.images.is_empty() {
+                    self.index = rand::thread_rng().gen_range(0..self.images.len());
+                }
+            }
+            Some("static") => {
+                // Use the static splash if it is one of the images
+                if let Some(static_path) = &config.static_splash_path {
+                    if let Some(idx) = self
+                        .images
+                        .iter()
+                        .position(|p| p.to_string_lossy() == *static_path)
+                    {
+                        self.index = idx;
+                    }
+                }
+            }
+            // "cycle" and the default: through the images in order
+            _ => self.index = (self.index + 1) % self.images.len().max(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, Seconds};
+
+    fn config(interval: usize) -> SplashConfig {
+        SplashConfig {
+            interval,
+            duration_seconds: Seconds::clamped(1.0),
+            ..Config::default().splash
+        }
+    }
+
+    fn with_images(count: usize) -> Splash {
+        Splash {
+            images: (0..count)
+                .map(|i| PathBuf::from(format!("{}.png", i)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_is_due_once() {
+        // 0 means only at startup
+        assert!(is_due(&config(0), 0));
+        assert!(!is_due(&config(0), 1));
+    }
+
+    #[test]
+    fn test_is_due_every() {
+        assert!(is_due(&config(1), 0));
+        assert!(is_due(&config(1), 1));
+    }
+
+    #[test]
+    fn test_is_due_every_other() {
+        assert!(is_due(&config(2), 0));
+        assert!(!is_due(&config(2), 1));
+        assert!(is_due(&config(2), 2));
+    }
+
+    #[test]
+    fn test_is_due_every_third() {
+        let due: Vec<bool> = (0..4).map(|n| is_due(&config(3), n)).collect();
+        assert_eq!(due, vec![true, false, false, true]);
+    }
+
+    #[test]
+    fn test_is_due_disabled() {
+        let mut disabled = config(1);
+        disabled.enabled = false;
+        assert!(!is_due(&disabled, 0));
+    }
+
+    #[test]
+    fn test_startup_splash_ends_once_there_are_videos() {
+        let mut splash = with_images(2);
+        let mut played = 0;
+        splash.advance(0.6, &config(0), true, &mut played);
+        assert!(splash.showing);
+        splash.advance(0.6, &config(0), true, &mut played);
+        assert!(!splash.showing);
+        assert_eq!(played, 1);
+
+        // Hidden: time no longer counts
+        splash.advance(5.0, &config(0), true, &mut played);
+        assert_eq!(splash.timer, 1.2);
+    }
+
+    #[test]
+    fn test_cycles_without_videos() {
+        let mut splash = with_images(2);
+        let mut played = 0;
+        splash.advance(1.0, &config(0), false, &mut played);
+        assert!(splash.showing);
+        assert_eq!(splash.index, 1);
+        assert_eq!(splash.timer, 0.0);
+        splash.advance(1.0, &config(0), false, &mut played);
+        assert_eq!(splash.image(), Some(Path::new("0.png")));
+        assert_eq!(played, 0);
+    }
+
+    #[test]
+    fn test_start_by_rotation_mode() {
+        let mut splash = with_images(3);
+        splash.hide();
+        splash.timer = 4.0;
+        splash.start(&config(1));
+        assert!(splash.showing);
+        assert_eq!(splash.timer, 0.0);
+        assert_eq!(splash.index, 1);
+
+        let mut fixed = config(1);
+        fixed.rotation_mode = Some("static".to_string());
+        fixed.static_splash_path = Some("2.png".to_string());
+        splash.start(&fixed);
+        splash.start(&fixed);
+        assert_eq!(splash.index, 2);
+
+        fixed.rotation_mode = Some("random".to_string());
+        splash.start(&fixed);
+        assert!(splash.index < 3);
+
+        // Without images there is nothing to pick
+        let mut empty = Splash::default();
+        empty.start(&config(1));
+        assert_eq!(empty.image(), None);
+    }
+}
@@ -0,0 +1,119 @@
+//! The license window of the configuration GUI: the license status, this
+//! kiosk's machine ID to send with a purchase, and a button to load the
+//! license file that comes back. Also the screen the player shows when the
+//! demo time runs out.
+
+use crate::{fingerprint, license};
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+/// How long the evaluation-ended screen stays up before the player closes.
+const CLOSE_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+pub struct Activation {
+    pub open: bool,
+    message: Option<Result<String, String>>,
+}
+
+impl Activation {
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+        let mut open = self.open;
+        egui::Window::new("License")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("Status: {}", license::status().describe()));
+                ui.separator();
+
+                ui.label("Machine ID (send it with your order):");
+                ui.horizontal(|ui| match fingerprint::machine_id() {
+                    Ok(id) => {
+                        ui.label(egui::RichText::new(id).monospace().size(18.0));
+                        if ui.button("Copy").clicked() {
+                            ui.output_mut(|o| o.copied_text = id.to_string());
+                        }
+                    }
+                    Err(reason) => {
+                        ui.colored_label(egui::Color32::LIGHT_RED, reason);
+                    }
+                });
+                ui.separator();
+
+                if ui.button("Activate with license file...").clicked() {
+                    self.activate();
+                }
+                match &self.message {
+                    Some(Ok(message)) => {
+                        ui.colored_label(egui::Color32::LIGHT_GREEN, message);
+                    }
+                    Some(Err(message)) => {
+                        ui.colored_label(egui::Color32::LIGHT_RED, message);
+                    }
+                    None => {}
+                }
+            });
+        self.open = open;
+    }
+
+    fn activate(&mut self) {
+        let Some(src) = rfd::FileDialog::new()
+            .add_filter("License", &["toml"])
+            .pick_file()
+        else {
+            return;
+        };
+        self.message = Some(match license::activate(&src) {
+            Ok(dest) => Ok(format!(
+                "Activated; installed {}. Restart the player to leave demo mode.",
+                dest.display()
+            )),
+            Err(e) => Err(format!("Activation failed: {}", e)),
+        });
+    }
+}
+
+/// Shown instead of the player once the demo time is up, with how to buy a
+/// license. Closes the window after [`CLOSE_AFTER`], or on Esc.
+pub struct EvaluationEnded {
+    since: Instant,
+}
+
+impl EvaluationEnded {
+    pub fn new() -> Self {
+        Self {
+            since: Instant::now(),
+        }
+    }
+
+    pub fn show(&self, ctx: &egui::Context) {
+        let remaining = CLOSE_AFTER.saturating_sub(self.since.elapsed());
+        if remaining.is_zero() || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none().fill(egui::Color32::BLACK))
+            .show(ctx, |ui| {
+                ui.centered_and_justified(|ui| {
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "The evaluation has ended\n\n\
+                             Thank you for trying Summit Hip Numbers.\n{}\n\n\
+                             Machine ID: {}\n\n\
+                             Closing in {} seconds (Esc to close now).",
+                            license::PURCHASE_INFO,
+                            fingerprint::machine_id().unwrap_or("none"),
+                            remaining.as_secs() + 1
+                        ))
+                        .color(egui::Color32::WHITE)
+                        .size(24.0),
+                    );
+                });
+            });
+        ctx.request_repaint_after(Duration::from_millis(250));
+    }
+}
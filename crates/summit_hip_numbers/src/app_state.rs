@@ -0,0 +1,2331 @@
+//! Everything the player does apart from drawing: loading the config and
+//! videos, the keypad, the splash screen, playback, remote commands and the
+//! timers that tie them together. The window (see [`crate::render`]) and
+//! `--headless` both drive an [`AppState`] one tick at a time.
+
+use crate::activation;
+#[cfg(feature = "auction")]
+use crate::auction;
+use crate::clock;
+use crate::crash;
+use crate::fingerprint;
+use crate::heartbeat;
+use crate::hot_swap;
+#[cfg(feature = "http-api")]
+use crate::http_api;
+use crate::input::{self, Action, FrameInput, HipInput, ReviewAction};
+use crate::json_log;
+use crate::license::{self, Capability};
+use crate::lock;
+#[cfg(feature = "mdns")]
+use crate::mdns;
+use crate::media_cache;
+use crate::media_source;
+use crate::metrics;
+use crate::mixer;
+#[cfg(feature = "mqtt")]
+use crate::mqtt;
+use crate::perf;
+#[cfg(feature = "serial")]
+use crate::serial_control;
+use crate::supervisor;
+use crate::video_player::VideoPlayer;
+#[cfg(feature = "webhooks")]
+use crate::webhooks;
+use crate::{osc, sync, tcp_control};
+use eframe::egui;
+use log::{error, info, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use summit_hip_core::analytics;
+use summit_hip_core::arbitration::Arbiter;
+use summit_hip_core::config::{
+    self, BarWidget, Config, CountdownStart, LockMode, ResumeMode, SyncMode,
+};
+use summit_hip_core::control::{self, Source, TestPattern};
+use summit_hip_core::file_scanner::{self, scan_video_files};
+use summit_hip_core::playback::{Controller, FrameSender, PlayerBackend, PlayerState, Request};
+use summit_hip_core::review::{self, Review};
+use summit_hip_core::splash::{self, Splash};
+use supervisor::ResumeState;
+use tokio::sync::watch;
+
+/// How much one press of + or - turns the hip playing up or down, in dB.
+pub const TRIM_STEP_DB: f32 = 1.0;
+
+/// How often the bar's ticker is redrawn as it scrolls, in seconds.
+const TICKER_FRAME: f64 = 1.0 / 30.0;
+
+/// What the last session was playing when it stopped, and how long until
+/// it resumes without an answer.
+pub struct ResumeOffer {
+    pub state: ResumeState,
+    pub hip: String,
+    pub seconds_left: f64,
+}
+
+pub struct AppState<B: PlayerBackend = VideoPlayer> {
+    pub config: Config,
+    pub playback: Controller<B>,
+    pub input: HipInput,
+    pub splash: Splash,
+    /// The hip asked for when there was no video for it, while that is shown.
+    pub no_video_hip: Option<String>,
+    /// Why the hip's videos can't be played, when there are some.
+    pub no_video_reason: Option<String>,
+    no_video_popup_timer: f64,
+    /// Frames decoded by the player, for the window to show.
+    pub frames: watch::Receiver<Option<egui::ColorImage>>,
+    /// Set when a locked config fails verification in refuse mode.
+    pub lock_error: Option<String>,
+    pub control: control::Endpoint,
+    pub blackout: bool,
+    /// Shown instead of the player, with F5 or a remote command.
+    pub test_pattern: Option<TestPattern>,
+    /// Whether the video was paused already when the test pattern paused it.
+    paused_under_pattern: bool,
+    /// Lot in the ring, when following the auction software.
+    pub ring_lot: Option<String>,
+    /// What the video files were like when scanned, by path.
+    stamps: HashMap<String, hot_swap::Stamp>,
+    swap_watch: hot_swap::Watch,
+    /// Rescans of one hip's files, off the UI thread in case the share hangs.
+    disk_reads: media_source::Reader<Option<Vec<(String, String, Option<hot_swap::Stamp>)>>>,
+    /// The hip whose video was replaced on disk while it played, until the
+    /// operator says whether to swap the new file in.
+    pub swap_offer: Option<String>,
+    /// Checks on the video directory, under `[media_source]`.
+    media: Option<media_source::Monitor>,
+    /// Set while the checks find the video directory can't be read.
+    pub media_offline: bool,
+    /// Local copies of the videos, under `[media_cache]`.
+    cache: Option<media_cache::Cache>,
+    /// The copies the cache has made so far.
+    cached: media_cache::Copies,
+    /// The ring of `[[rings]]` on screen.
+    pub ring: usize,
+    /// The config before the ring's settings went over it, with `[[rings]]`.
+    ring_base: Option<Config>,
+    /// Which input may switch the hip, and what waits for it.
+    pub arbiter: Arbiter,
+    /// The hip last entered and when, to tell a repeat from a restart.
+    last_entry: Option<(String, Instant)>,
+    /// The lot countdown in the bar, under `[clock]`.
+    pub countdown: clock::Countdown,
+    pub heartbeat: heartbeat::Heartbeat,
+    /// Where to save what's playing when run under `--supervise`.
+    resume_file: Option<PathBuf>,
+    /// Where to save what's playing for the next start, after a power cut.
+    session_file: Option<PathBuf>,
+    resume_timer: f64,
+    /// The offer to pick up where the last session stopped, until answered.
+    pub resume_offer: Option<ResumeOffer>,
+    /// Set once the demo time is up.
+    pub evaluation_ended: Option<activation::EvaluationEnded>,
+    /// The thumbnail grid of the hips, toggled with F2.
+    pub show_browser: bool,
+    /// Frame timing graphs, toggled with F3.
+    pub show_perf_hud: bool,
+    perf_timer: f64,
+    /// Whether the video playing has gone quiet when it shouldn't have.
+    pub silence: mixer::SilenceWatch,
+    /// The content review under way, started with `--review`.
+    pub review: Option<Review>,
+    /// Unlicensed: demo limits and the watermark apply.
+    pub demo: bool,
+    /// Unlicensed but in the trial: shown in a banner.
+    pub trial_days_left: Option<u32>,
+    start_time: Instant,
+}
+
+impl<B: PlayerBackend> Default for AppState<B> {
+    fn default() -> Self {
+        let (tx, rx) = watch::channel(None);
+
+        Self {
+            config: Config::default(),
+            playback: Controller::new(FrameSender::new(tx)),
+            input: HipInput::default(),
+            splash: Splash::default(),
+            no_video_hip: None,
+            no_video_reason: None,
+            no_video_popup_timer: 0.0,
+            frames: rx,
+            lock_error: None,
+            control: control::Endpoint::default(),
+            blackout: false,
+            test_pattern: None,
+            paused_under_pattern: false,
+            ring_lot: None,
+            stamps: HashMap::new(),
+            swap_watch: hot_swap::Watch::default(),
+            disk_reads: media_source::Reader::new("media-rescan"),
+            swap_offer: None,
+            media: None,
+            media_offline: false,
+            cache: None,
+            cached: HashMap::new(),
+            ring: 0,
+            ring_base: None,
+            arbiter: Arbiter::default(),
+            last_entry: None,
+            countdown: clock::Countdown::default(),
+            heartbeat: heartbeat::Heartbeat::default(),
+            resume_file: None,
+            session_file: None,
+            resume_timer: 0.0,
+            resume_offer: None,
+            evaluation_ended: None,
+            show_browser: false,
+            show_perf_hud: false,
+            perf_timer: 0.0,
+            silence: mixer::SilenceWatch::default(),
+            review: None,
+            demo: false,
+            trial_days_left: None,
+            start_time: Instant::now(),
+        }
+    }
+}
+
+impl<B: PlayerBackend> AppState<B> {
+    pub fn new(config_path: Option<&Path>) -> Self {
+        let mut app = Self::load_config(config_path);
+        if license::permits(
+            app.config.analytics.enabled,
+            "analytics",
+            Capability::Analytics,
+        ) {
+            let dir = analytics::dir(&app.config);
+            info!("Recording play analytics in {}", dir.display());
+            app.playback.analytics = Some(analytics::Recorder::new(dir));
+        }
+        app.check_asset_integrity();
+        app.cache = media_cache::Cache::open(&app.config);
+        app.load_video_files();
+        app.watch_media_source();
+        app.start_remote_control();
+        if !app.playback.video_files.is_empty() {
+            app.playback.load_video_index = Some(0);
+        } else {
+            // If no videos but splash screens exist, ensure splash is showing
+            if !app.splash.images.is_empty() && app.config.splash.enabled {
+                app.splash.showing = true;
+                info!("No videos found - starting with splash screens");
+            }
+        }
+        let mut resumed = false;
+        if let Some(file) = std::env::var_os(supervisor::RESUME_FILE_ENV) {
+            resumed = app.resume_from(PathBuf::from(file));
+        }
+        if app.config.session.resume != ResumeMode::Never {
+            let file = supervisor::session_file(Path::new(&app.config.logging.file));
+            if !resumed {
+                app.offer_resume(&file);
+            }
+            app.session_file = Some(file);
+        }
+        app
+    }
+
+    /// Pick up where the player was before `--supervise` restarted it.
+    /// Returns whether it did.
+    fn resume_from(&mut self, file: PathBuf) -> bool {
+        let resumed = ResumeState::load(&file).is_some_and(|state| self.resume(&state));
+        self.resume_file = Some(file);
+        resumed
+    }
+
+    /// Offer to pick up where the last session stopped, or just do it with
+    /// `[session] resume = "always"`.
+    fn offer_resume(&mut self, file: &Path) {
+        let Some(state) = ResumeState::load(file) else {
+            return;
+        };
+        if self.config.session.resume == ResumeMode::Always {
+            self.resume(&state);
+            return;
+        }
+        let path = state.queued.as_ref().unwrap_or(&state.path);
+        let Some(video) = self.playback.video_files.iter().find(|v| &v.path == path) else {
+            return;
+        };
+        info!("Offering to resume hip {}", video.hip_number);
+        self.resume_offer = Some(ResumeOffer {
+            hip: video.hip_number.clone(),
+            state,
+            seconds_left: self.config.session.prompt_seconds.get(),
+        });
+    }
+
+    /// Answer the resume offer: pick up where the last session stopped, or
+    /// carry on from the first hip.
+    pub fn answer_resume(&mut self, resume: bool) {
+        let Some(offer) = self.resume_offer.take() else {
+            return;
+        };
+        if resume {
+            self.resume(&offer.state);
+        } else {
+            info!("Starting over instead of resuming hip {}", offer.hip);
+        }
+    }
+
+    /// Play `state`'s video from where it was, or the video it had queued.
+    fn resume(&mut self, state: &ResumeState) -> bool {
+        let (path, position_seconds) = match &state.queued {
+            Some(queued) => (queued, 0.0),
+            None => (&state.path, state.position_seconds),
+        };
+        let playback = &mut self.playback;
+        let Some(index) = playback.video_files.iter().position(|v| &v.path == path) else {
+            warn!("Can't resume {}: no longer in the video list", path);
+            return false;
+        };
+        info!("Resuming {} at {:.1}s", path, position_seconds);
+        playback.load_video_index = Some(index);
+        playback.resume_at = Some(Duration::from_secs_f64(position_seconds.max(0.0)));
+        playback.videos_played = playback.videos_played.max(state.videos_played).max(1);
+        self.splash.hide();
+        true
+    }
+
+    fn save_resume_state(&self) {
+        // Until answered, the offer's session is still the one to resume
+        if self.resume_offer.is_some() {
+            return;
+        }
+        let Some(video) = self.playback.current() else {
+            return;
+        };
+        let queued = self
+            .playback
+            .load_video_index
+            .and_then(|index| self.playback.video_files.get(index))
+            .map(|video| video.path.clone());
+        let position = match (self.playback.player(), &queued) {
+            (Some(player), _) => player.position(),
+            (None, Some(_)) => Duration::ZERO,
+            (None, None) => return,
+        };
+        let state = ResumeState {
+            path: video.path.clone(),
+            position_seconds: position.as_secs_f64(),
+            queued,
+            videos_played: self.playback.videos_played,
+        };
+        for file in self.resume_file.iter().chain(&self.session_file) {
+            if let Err(e) = state.save(file) {
+                warn!("Failed to save resume state: {}", e);
+            }
+        }
+    }
+
+    /// Forget the session on a clean exit, so the next start doesn't offer
+    /// to resume it.
+    pub fn end_session(&self) {
+        if let Some(file) = &self.session_file {
+            if let Err(e) = fs::remove_file(file) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to remove {}: {}", file.display(), e);
+                }
+            }
+        }
+    }
+
+    fn start_remote_control(&self) {
+        #[cfg(feature = "http-api")]
+        if license::permits(self.config.http.enabled, "http", Capability::RemoteControl) {
+            match http_api::start(
+                &self.config.http,
+                self.control.remote().tagged(Source::Http),
+            ) {
+                Ok(()) => info!("HTTP control API listening on {}", self.config.http.bind),
+                Err(e) => error!("Failed to start HTTP control API: {}", e),
+            }
+        }
+        #[cfg(not(feature = "http-api"))]
+        if self.config.http.enabled {
+            warn!("[http] is enabled but this build has no HTTP control API");
+        }
+        if license::permits(self.config.tcp.enabled, "tcp", Capability::RemoteControl) {
+            match tcp_control::start(&self.config.tcp, self.control.remote().tagged(Source::Tcp)) {
+                Ok(()) => info!("TCP control listening on {}", self.config.tcp.bind),
+                Err(e) => error!("Failed to start TCP control: {}", e),
+            }
+        }
+        #[cfg(feature = "serial")]
+        if license::permits(
+            self.config.serial.enabled,
+            "serial",
+            Capability::RemoteControl,
+        ) {
+            if let Err(e) = serial_control::start(
+                &self.config.serial,
+                self.control.remote().tagged(Source::Serial),
+            ) {
+                error!("Failed to start serial control: {}", e);
+            }
+        }
+        #[cfg(not(feature = "serial"))]
+        if self.config.serial.enabled {
+            warn!("[serial] is enabled but this build has no serial control");
+        }
+        #[cfg(feature = "mqtt")]
+        if license::permits(self.config.mqtt.enabled, "mqtt", Capability::RemoteControl) {
+            if let Err(e) = mqtt::start(
+                &self.config.mqtt,
+                self.control.remote().tagged(Source::Mqtt),
+            ) {
+                error!("Failed to start MQTT: {}", e);
+            }
+        }
+        #[cfg(not(feature = "mqtt"))]
+        if self.config.mqtt.enabled {
+            warn!("[mqtt] is enabled but this build has no MQTT support");
+        }
+        #[cfg(feature = "auction")]
+        if license::permits(
+            self.config.auction.enabled,
+            "auction",
+            Capability::Integrations,
+        ) {
+            if let Err(e) = auction::start(
+                &self.config.auction,
+                self.control.remote().tagged(Source::Auction),
+            ) {
+                error!("Failed to start auction polling: {}", e);
+            }
+        }
+        #[cfg(not(feature = "auction"))]
+        if self.config.auction.enabled {
+            warn!("[auction] is enabled but this build has no auction support");
+        }
+        #[cfg(feature = "webhooks")]
+        if license::permits(
+            self.config.webhooks.enabled,
+            "webhooks",
+            Capability::Integrations,
+        ) {
+            if let Err(e) = webhooks::start(&self.config.webhooks, self.control.remote()) {
+                error!("Failed to start webhooks: {}", e);
+            }
+        }
+        #[cfg(not(feature = "webhooks"))]
+        if self.config.webhooks.enabled {
+            warn!("[webhooks] is enabled but this build has no webhook support");
+        }
+        #[cfg(feature = "mdns")]
+        if license::permits(self.config.mdns.enabled, "mdns", Capability::RemoteControl) {
+            let http_port = (cfg!(feature = "http-api") && self.config.http.enabled)
+                .then(|| mdns::port_of(&self.config.http.bind))
+                .flatten();
+            let tcp_port = self
+                .config
+                .tcp
+                .enabled
+                .then(|| mdns::port_of(&self.config.tcp.bind))
+                .flatten();
+            if let Err(e) = mdns::start(&self.config.mdns, http_port, tcp_port) {
+                error!("Failed to start mDNS: {}", e);
+            }
+        }
+        #[cfg(not(feature = "mdns"))]
+        if self.config.mdns.enabled {
+            warn!("[mdns] is enabled but this build has no mDNS support");
+        }
+        let sync_on = self.config.sync.mode != SyncMode::Off;
+        if license::permits(sync_on, "sync", Capability::Sync) {
+            if let Err(e) = sync::start(
+                &self.config.sync,
+                self.control.remote().tagged(Source::Sync),
+            ) {
+                error!("Failed to start {:?} sync: {}", self.config.sync.mode, e);
+            }
+        }
+        if license::permits(self.config.osc.enabled, "osc", Capability::RemoteControl) {
+            match osc::start(&self.config.osc, self.control.remote().tagged(Source::Osc)) {
+                Ok(()) => info!("OSC listening on {}", self.config.osc.bind),
+                Err(e) => error!("Failed to start OSC: {}", e),
+            }
+        }
+    }
+
+    pub fn load_config(config_path: Option<&Path>) -> Self {
+        Self::load_config_under(config_path, lock::trust())
+    }
+
+    /// Load the config, checked against `trust` when the player is locked.
+    fn load_config_under(config_path: Option<&Path>, trust: Option<lock::Trust>) -> Self {
+        let mut app = Self::default();
+        let exe_dir = config::exe_dir();
+        let (config, lock_status) = match &trust {
+            Some(trust) => lock::load(trust, config_path, &exe_dir.join(config::CONFIG_FILE_NAME)),
+            None => (config::load(config_path), lock::Status::NotLocked),
+        };
+        app.config = config;
+        app.playback.frames().adjust(&app.config.output);
+        crash::set_config(&app.config);
+        app.splash.showing = app.config.splash.enabled;
+
+        match lock_status {
+            lock::Status::NotLocked => {}
+            lock::Status::Verified => info!("Config signature verified"),
+            lock::Status::Tampered(reason) => {
+                if trust.is_some_and(|trust| trust.mode == LockMode::Refuse) {
+                    error!("Refusing to start: {}", reason);
+                    app.lock_error = Some(reason);
+                } else {
+                    warn!("Config lock: {}", reason);
+                }
+            }
+        }
+
+        let license = license::status();
+        info!("License: {}", license.describe());
+        if let license::Status::Trial { days_left } = license {
+            app.trial_days_left = Some(*days_left);
+        }
+        if license.is_demo() {
+            match fingerprint::machine_id() {
+                Ok(id) => info!(
+                    "Machine ID {}; activate a license under Activate License in --config",
+                    id
+                ),
+                Err(reason) => warn!("No machine ID: {}", reason),
+            }
+            app.demo = true;
+            license::apply_demo_limits(&mut app.config);
+            app.splash.showing = true;
+        }
+
+        // Set default video directory relative to exe
+        if app.config.video.directory == "./videos" {
+            app.config.video.directory = exe_dir.join("videos").to_string_lossy().to_string();
+        }
+        app.start_rings();
+        app
+    }
+
+    /// Start on the first ring of `[[rings]]`, when there are any.
+    fn start_rings(&mut self) {
+        if let Some(ring) = self.config.rings.first() {
+            info!("Starting on ring {}", ring.name);
+            self.ring_base = Some(self.config.clone());
+            self.config = self.config.for_ring(0);
+        }
+    }
+
+    pub fn load_video_files(&mut self) {
+        let video_dir = config::resolve_asset_dir(&self.config.video.directory);
+        let hip_overrides = config::hip_overrides(&self.config, &video_dir);
+        info!("Loading video files from {}", video_dir.display());
+
+        let mut files = scan_video_files(&video_dir).unwrap_or_else(|e| {
+            error!("Failed to scan video files: {}", e);
+            Vec::new()
+        });
+
+        // Live streams from `[hips.<hip>] stream`, after any files of the same hip
+        for (hip, hip_override) in &hip_overrides {
+            if let Some(url) = &hip_override.stream {
+                if hip.len() == 3 && hip.chars().all(|c| c.is_ascii_digit()) {
+                    files.push(file_scanner::stream_file(hip, url));
+                } else {
+                    warn!("Ignoring stream for '{}': hip must be 3 digits", hip);
+                }
+            }
+        }
+        files.sort_by(|a, b| a.hip_number.cmp(&b.hip_number));
+
+        if self.demo && files.len() > self.config.demo.max_videos {
+            files.truncate(self.config.demo.max_videos);
+            info!(
+                "Demo mode: Limited to first {} videos",
+                self.config.demo.max_videos
+            );
+        }
+
+        info!("Scanned {} video files", files.len());
+        self.playback.set_videos(files, hip_overrides);
+        self.stamps = hot_swap::stamps(&self.playback.video_files);
+        if self.config.media_cache.copy_all {
+            for video in &self.playback.video_files {
+                self.cache_video(&video.path);
+            }
+        }
+        self.use_cached_copies();
+
+        self.load_splash_images();
+        self.trim_log();
+    }
+
+    pub fn load_splash_images(&mut self) {
+        let splash_dir = config::resolve_asset_dir(&self.config.splash.directory);
+        self.splash.load_images(&splash_dir);
+    }
+
+    fn check_asset_integrity(&self) {
+        let exe_dir = config::exe_dir();
+
+        let required_dirs = ["videos", "splash", "logo"];
+        let mut missing_dirs = Vec::new();
+
+        for dir in &required_dirs {
+            let dir_path = exe_dir.join(dir);
+            if !dir_path.exists() {
+                missing_dirs.push(dir.to_string());
+            }
+        }
+
+        if !missing_dirs.is_empty() {
+            warn!("Missing required directories: {:?}", missing_dirs);
+        }
+
+        // Check for at least one video file
+        let videos_dir = exe_dir.join("videos");
+        if videos_dir.exists() {
+            if let Ok(entries) = fs::read_dir(&videos_dir) {
+                let video_count = entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| {
+                        e.path()
+                            .extension()
+                            .map(|ext| {
+                                matches!(ext.to_str(), Some("mp4") | Some("avi") | Some("mkv"))
+                            })
+                            .unwrap_or(false)
+                    })
+                    .count();
+                if video_count == 0 {
+                    warn!("No video files found in videos directory");
+                } else {
+                    info!("Found {} video files", video_count);
+                }
+            }
+        }
+    }
+
+    pub fn trim_log(&self) {
+        let log_path = PathBuf::from(&self.config.logging.file);
+        if let Ok(content) = fs::read_to_string(&log_path) {
+            let lines: Vec<&str> = content.lines().collect();
+            if lines.len() > self.config.logging.max_lines {
+                let start = lines.len() - self.config.logging.max_lines;
+                let trimmed = lines[start..].join("\n");
+                if fs::write(&log_path, trimmed + "\n").is_ok() {
+                    info!(
+                        "Trimmed log file to {} lines",
+                        self.config.logging.max_lines
+                    );
+                } else {
+                    error!("Failed to trim log file");
+                }
+            }
+        } else {
+            warn!("Log file not found for trimming");
+        }
+    }
+
+    /// Switch to `input`'s video if there is one it can play, otherwise show
+    /// the "no video" popup when it is a hip number.
+    pub fn validate_and_switch(&mut self, input: &str) -> bool {
+        if self.is_repeat_entry(input) {
+            info!("Hip {} entered again; not restarting it", input);
+            self.last_entry = Some((input.to_string(), Instant::now()));
+            return true;
+        }
+        if self.media_offline {
+            // The offline overlay says why
+            warn!("Hip {} asked for while the media source is offline", input);
+            return false;
+        }
+        if !self.demo && self.hip_changed(input) {
+            info!("Hip {}'s videos changed on disk; rescanning", input);
+            self.rescan();
+        }
+        let hip_limit = self.demo.then_some(self.config.demo.hip_number_limit);
+        match self.playback.request(input, hip_limit) {
+            Request::Switched(_) => {
+                self.last_entry = Some((input.to_string(), Instant::now()));
+                true
+            }
+            Request::Unavailable => {
+                self.no_video_hip = Some(input.to_string());
+                self.no_video_reason = None;
+                self.no_video_popup_timer = self.config.ui.no_video_popup_timeout.get();
+                false
+            }
+            Request::Unplayable(reason) => {
+                self.no_video_hip = Some(input.to_string());
+                self.no_video_reason = Some(reason);
+                self.no_video_popup_timer = self.config.ui.no_video_popup_timeout.get();
+                false
+            }
+            Request::Malformed => false,
+        }
+    }
+
+    /// Whether the video folder's files of `hip` aren't the ones scanned:
+    /// one was copied over, renamed, added or taken away.
+    fn hip_changed(&mut self, hip: &str) -> bool {
+        let Some(on_disk) = self.hip_on_disk(hip) else {
+            return false;
+        };
+        let scanned = self
+            .playback
+            .video_files
+            .iter()
+            .filter(|video| video.hip_number == hip)
+            .filter(|video| !file_scanner::is_stream_url(&video.path))
+            .map(|video| (video.path.clone(), self.stamps.get(&video.path).copied()));
+        !on_disk.into_iter().eq(scanned)
+    }
+
+    /// The video folder's files of `hip` as they are now, `None` if the
+    /// folder can't be read within `[media_source] timeout_seconds`.
+    fn hip_on_disk(&mut self, hip: &str) -> Option<Vec<(String, Option<hot_swap::Stamp>)>> {
+        let video_dir = config::resolve_asset_dir(&self.config.video.directory);
+        let timeout = Duration::try_from_secs_f64(self.config.media_source.timeout_seconds.get())
+            .unwrap_or(Duration::MAX);
+        let files = self
+            .disk_reads
+            .read(timeout, move || {
+                let files = scan_video_files(&video_dir).ok()?;
+                Some(
+                    files
+                        .into_iter()
+                        .map(|video| {
+                            let stamp = hot_swap::stamp(&video.path);
+                            (video.hip_number, video.path, stamp)
+                        })
+                        .collect(),
+                )
+            })
+            .unwrap_or_else(|e| {
+                warn!("Failed to rescan the video folder: {}", e);
+                None
+            })?;
+        Some(
+            files
+                .into_iter()
+                .filter(|(hip_number, _, _)| hip_number == hip)
+                .map(|(_, path, stamp)| (path, stamp))
+                .collect(),
+        )
+    }
+
+    /// Offer to swap in the file of the video playing once it has been
+    /// replaced on disk.
+    fn watch_for_replacement(&mut self, dt: f64) {
+        let Some(video) = self.playback.current() else {
+            return;
+        };
+        if self.media_offline
+            || self.playback.player().is_none()
+            || file_scanner::is_stream_url(&video.path)
+        {
+            return;
+        }
+        let (hip, path, file) = (
+            video.hip_number.clone(),
+            video.path.clone(),
+            video.name.clone(),
+        );
+        if self
+            .swap_offer
+            .as_ref()
+            .is_some_and(|offered| *offered != hip)
+        {
+            // Switched away; the new file plays when the hip is asked for
+            self.swap_offer = None;
+        }
+        if self.swap_offer.is_some() {
+            return;
+        }
+        let scanned = self.stamps.get(&path).copied();
+        if !self.swap_watch.check(dt, &path, scanned) {
+            return;
+        }
+        if self
+            .hip_on_disk(&hip)
+            .map_or(true, |files| files.is_empty())
+        {
+            warn!("Hip {}'s video is gone from disk", hip);
+            self.swap_watch.decline(&path);
+            return;
+        }
+        info!(
+            "Hip {}'s video was replaced on disk; offering to swap it in",
+            hip
+        );
+        self.control.emit(control::Event::VideoReplaced {
+            hip: hip.clone(),
+            file,
+        });
+        self.swap_offer = Some(hip);
+    }
+
+    /// Answer the swap offer: carry on in the hip's new file from where the
+    /// old one was, or keep playing the old one.
+    pub fn answer_swap(&mut self, swap: bool) {
+        let Some(hip) = self.swap_offer.take() else {
+            return;
+        };
+        let Some(path) = self.playback.current().map(|video| video.path.clone()) else {
+            return;
+        };
+        if !swap {
+            info!("Keeping the old video of hip {} playing", hip);
+            self.swap_watch.decline(&path);
+            return;
+        }
+        let position = self
+            .playback
+            .player()
+            .map_or(Duration::ZERO, |player| player.position());
+        self.rescan();
+        let playback = &mut self.playback;
+        let index = playback
+            .video_files
+            .iter()
+            .position(|video| video.path == path)
+            .or_else(|| {
+                playback
+                    .video_files
+                    .iter()
+                    .position(|video| video.hip_number == hip)
+            });
+        let Some(index) = index else {
+            warn!("Can't swap in hip {}: it has no video now", hip);
+            return;
+        };
+        info!(
+            "Swapping in the new video of hip {} at {:.1}s",
+            hip,
+            position.as_secs_f64()
+        );
+        playback.load_video_index = Some(index);
+        playback.resume_at = Some(position);
+    }
+
+    /// Whether `hip` is the hip on screen, entered again within
+    /// `[ui] repeat_entry_seconds` of the last time, like a double press or a
+    /// scanner reading a tag twice.
+    fn is_repeat_entry(&self, hip: &str) -> bool {
+        let Some((last, at)) = &self.last_entry else {
+            return false;
+        };
+        last == hip
+            && at.elapsed() < self.config.ui.repeat_entry()
+            && self
+                .playback
+                .current()
+                .is_some_and(|video| video.hip_number == hip)
+    }
+
+    /// Step through every hip for staff to check before the sale, playing
+    /// the start of each and holding it for their verdict. Plays in the
+    /// review stay out of the analytics.
+    pub fn start_review(&mut self) {
+        let review = Review::new(
+            &self.playback.video_files,
+            chrono::Local::now().date_naive(),
+        );
+        info!("Starting a review of {} hips", review.items.len());
+        self.playback.hold_at_end = true;
+        self.playback.analytics = None;
+        self.splash.showing = false;
+        self.review = Some(review);
+        self.show_review_item();
+    }
+
+    /// Play the hip the review is on, or stop once past the last.
+    fn show_review_item(&mut self) {
+        let Some(review) = &self.review else {
+            return;
+        };
+        match review.current() {
+            Some(item) => {
+                self.playback.current_file_name = self.playback.display_name(item.index);
+                self.playback.load_video_index = Some(item.index);
+            }
+            None => {
+                self.playback.stop();
+                let (ok, needs_fix, unmarked) = review.counts();
+                info!(
+                    "Review finished: {} OK, {} need a fix, {} not marked",
+                    ok, needs_fix, unmarked
+                );
+            }
+        }
+    }
+
+    fn handle_review_action(&mut self, action: ReviewAction) {
+        let Some(review) = &mut self.review else {
+            return;
+        };
+        match action {
+            ReviewAction::Mark(verdict) => {
+                if let Some(item) = review.current() {
+                    info!("Review: hip {} {}", item.hip, verdict);
+                }
+                review.mark(verdict);
+                if let Err(e) = review.write_report(&analytics::dir(&self.config)) {
+                    error!("Failed to save the review: {}", e);
+                }
+            }
+            ReviewAction::Replay => {}
+            ReviewAction::Next => review.skip(),
+            ReviewAction::Previous => review.back(),
+        }
+        self.show_review_item();
+    }
+
+    /// Act on a frame's keys.
+    pub fn handle_input(&mut self, input: &FrameInput) {
+        if self.review.is_some() {
+            for action in input::review_actions(input) {
+                self.handle_review_action(action);
+            }
+            if input.trim != 0 {
+                self.trim_gain(input.trim);
+            }
+            return;
+        }
+        let actions = self.input.handle(
+            input,
+            self.config.ui.input_max_length,
+            self.config.ui.enable_arrow_nav,
+        );
+        for action in actions {
+            let command = match &action {
+                Action::Submit(hip) | Action::Restart(hip) => {
+                    Some(control::Command::PlayHip(hip.clone()))
+                }
+                Action::Forward => Some(control::Command::Next),
+                Action::Backward => Some(control::Command::Previous),
+                Action::Undo => Some(control::Command::Undo),
+                _ => None,
+            };
+            let switches = command.is_some();
+            if command.is_some_and(|command| !self.admit(Some(Source::Keyboard), &command)) {
+                continue;
+            }
+            if switches {
+                self.set_test_pattern(None);
+            }
+            match action {
+                Action::Submit(hip) => {
+                    if !self.validate_and_switch(&hip) {
+                        self.input
+                            .reject(self.config.ui.invalid_input_timeout.get());
+                    }
+                }
+                Action::Restart(hip) => {
+                    // Not a repeat, however soon after the last entry
+                    self.last_entry = None;
+                    if !self.validate_and_switch(&hip) {
+                        self.input
+                            .reject(self.config.ui.invalid_input_timeout.get());
+                    }
+                }
+                Action::Forward => {
+                    info!("Navigated forward");
+                    self.playback.navigate_forward();
+                }
+                Action::Backward => {
+                    info!("Navigated backward");
+                    self.playback.navigate_backward();
+                }
+                Action::Undo => self.undo_switch(),
+                Action::ToggleBrowser => self.show_browser = !self.show_browser,
+                Action::TogglePerfHud => self.show_perf_hud = !self.show_perf_hud,
+                Action::StartCountdown => {
+                    let hip = self
+                        .playback
+                        .current()
+                        .map(|video| video.hip_number.clone());
+                    self.start_countdown(hip.as_deref());
+                }
+                Action::NextTestPattern => self.set_test_pattern(match self.test_pattern {
+                    None => Some(TestPattern::Bars),
+                    Some(TestPattern::Bars) => Some(TestPattern::Grid),
+                    Some(TestPattern::Grid) => None,
+                }),
+                Action::NextRing => {
+                    let rings = self.config.rings.len();
+                    if rings > 1 {
+                        self.switch_ring((self.ring + 1) % rings);
+                    }
+                }
+                Action::Trim(steps) => self.trim_gain(steps),
+            }
+        }
+    }
+
+    /// Switch to ring `index` of `[[rings]]`: its videos from the first, and
+    /// its branding.
+    pub fn switch_ring(&mut self, index: usize) {
+        let Some(base) = &self.ring_base else {
+            return;
+        };
+        let Some(ring) = base.rings.get(index) else {
+            return;
+        };
+        if index == self.ring {
+            return;
+        }
+        info!("Switching to ring {}", ring.name);
+        self.config = base.for_ring(index);
+        self.ring = index;
+        self.set_test_pattern(None);
+        // What was going on belongs to the other ring
+        self.ring_lot = None;
+        self.arbiter = Arbiter::default();
+        self.last_entry = None;
+        self.countdown = clock::Countdown::default();
+        self.playback.stop();
+        self.load_video_files();
+        if !self.playback.video_files.is_empty() {
+            self.playback.load_video_index = Some(0);
+        }
+        if self.media.is_some() {
+            self.watch_media_source();
+        }
+    }
+
+    /// Check the video directory under `[media_source]`, from now on.
+    fn watch_media_source(&mut self) {
+        let video_dir = config::resolve_asset_dir(&self.config.video.directory);
+        // Drop the old checks first, so one directory has one checker
+        self.media = None;
+        self.media_offline = false;
+        self.media = media_source::Monitor::start(video_dir, &self.config.media_source);
+    }
+
+    /// Follow the video directory going offline and coming back: rescan it
+    /// once it's back, and play the hip the dropout stopped.
+    fn follow_media_source(&mut self) {
+        let offline = self.media.as_ref().is_some_and(|media| !media.online());
+        if offline == self.media_offline {
+            return;
+        }
+        self.media_offline = offline;
+        if offline {
+            self.control.emit(control::Event::MediaOffline);
+            return;
+        }
+        info!("The media source is back; rescanning");
+        self.control.emit(control::Event::MediaOnline);
+        self.rescan();
+        if matches!(
+            self.playback.state(),
+            PlayerState::Idle | PlayerState::Error(_)
+        ) && !self.playback.video_files.is_empty()
+            && self.playback.load_video_index.is_none()
+        {
+            self.playback.load_video_index = Some(self.playback.current_index);
+        }
+    }
+
+    /// Have the video file at `path` copied to `[media_cache]`, or its copy
+    /// kept as just played.
+    fn cache_video(&self, path: &str) {
+        if let (Some(cache), Some(stamp)) = (&self.cache, self.stamps.get(path)) {
+            cache.request(path, *stamp);
+        }
+    }
+
+    /// Play the copies made of the video files, those still matching the
+    /// files as scanned.
+    fn use_cached_copies(&mut self) {
+        self.playback.local_copies = self
+            .cached
+            .iter()
+            .filter(|(path, (stamp, _))| self.stamps.get(*path) == Some(stamp))
+            .map(|(path, (_, copy))| (path.clone(), copy.clone()))
+            .collect();
+    }
+
+    /// Show `pattern` instead of the player, pausing the video under it, or
+    /// go back to the player with `None`.
+    pub fn set_test_pattern(&mut self, pattern: Option<TestPattern>) {
+        if pattern == self.test_pattern {
+            return;
+        }
+        match (self.test_pattern, pattern) {
+            (None, Some(_)) => {
+                self.paused_under_pattern = self.playback.paused;
+                self.playback.set_paused(true);
+            }
+            (Some(_), None) => {
+                info!("Test pattern off");
+                self.playback.set_paused(self.paused_under_pattern);
+            }
+            _ => {}
+        }
+        if let Some(pattern) = pattern {
+            info!("Showing the {} test pattern", pattern);
+        }
+        self.test_pattern = pattern;
+    }
+
+    /// Go back to the hip playing before the last switch, for a hip number
+    /// typed by mistake.
+    fn undo_switch(&mut self) {
+        if self.playback.undo_switch().is_none() {
+            info!("No switch to undo");
+        }
+    }
+
+    /// Start the lot countdown again for `hip`, when `[clock]` has one.
+    fn start_countdown(&mut self, hip: Option<&str>) {
+        if let Some(seconds) = self.config.clock.countdown_seconds {
+            self.countdown.start(hip, seconds.get());
+        }
+    }
+
+    /// Play the hip picked in the thumbnail grid, and close it.
+    pub fn choose_from_browser(&mut self, hip: &str) {
+        info!("Picked hip {} from the thumbnails", hip);
+        self.show_browser = false;
+        let command = control::Command::PlayHip(hip.to_string());
+        if self.admit(Some(Source::Keyboard), &command) {
+            self.validate_and_switch(hip);
+        }
+    }
+
+    /// Turn the hip playing up or down by `steps` of [`TRIM_STEP_DB`], and
+    /// save its gain in the video folder's `hips.toml` for next time.
+    fn trim_gain(&mut self, steps: i32) {
+        let Some((hip, gain)) = self.playback.trim_gain(steps as f32 * TRIM_STEP_DB) else {
+            return;
+        };
+        let video_dir = config::resolve_asset_dir(&self.config.video.directory);
+        if let Err(e) = config::save_hip_gain(&video_dir, &hip, gain) {
+            error!("Failed to save the gain of hip {}: {}", hip, e);
+        }
+    }
+
+    /// Per-frame bookkeeping that doesn't need a window: splash and popup
+    /// timers, the demo timeout and the splash screen between videos.
+    pub fn advance_timers(&mut self, dt: f64) {
+        let have_videos = !self.playback.video_files.is_empty();
+        self.splash.advance(
+            dt,
+            &self.config.splash,
+            have_videos,
+            &mut self.playback.videos_played,
+        );
+
+        if self.demo
+            && self.evaluation_ended.is_none()
+            && self.start_time.elapsed() > Duration::from_secs(self.config.demo.timeout_seconds)
+        {
+            self.end_evaluation();
+        }
+
+        self.input.tick(dt);
+        self.countdown.tick(dt);
+
+        // The clip starts at the hip's in point, if it has one
+        let clip_end = self
+            .playback
+            .segment(self.playback.current_index)
+            .0
+            .unwrap_or_default()
+            + review::CLIP;
+        if self.review.is_some()
+            && self
+                .playback
+                .position()
+                .is_some_and(|position| position >= clip_end)
+        {
+            info!("Review clip of {} done", self.playback.current_file_name);
+            self.playback.stop();
+        }
+
+        let muted = self
+            .playback
+            .hip_override(self.playback.current_index)
+            .and_then(|hip| hip.gain)
+            == Some(0.0);
+        let expected = self.playback.has_audio() == Some(true) && !self.playback.paused && !muted;
+        self.silence.update(
+            dt,
+            expected,
+            mixer::level().program,
+            self.config.audio.silence_warning_seconds.get(),
+        );
+
+        if self.no_video_hip.is_some() {
+            self.no_video_popup_timer -= dt;
+            if self.no_video_popup_timer <= 0.0 {
+                self.no_video_hip = None;
+                self.no_video_reason = None;
+                self.no_video_popup_timer = 0.0;
+            }
+        }
+
+        self.perf_timer += dt;
+        if self.perf_timer >= 10.0 {
+            self.perf_timer = 0.0;
+            let path = perf::summary_file(Path::new(&self.config.logging.file));
+            if let Err(e) = fs::write(&path, perf::summary()) {
+                warn!("Failed to save {}: {}", path.display(), e);
+            }
+        }
+
+        if let Some(offer) = &mut self.resume_offer {
+            offer.seconds_left -= dt;
+            if offer.seconds_left <= 0.0 {
+                info!("No answer to the resume offer; resuming");
+                self.answer_resume(true);
+            }
+        }
+
+        self.follow_media_source();
+        if let Some(copies) = self.cache.as_ref().and_then(|cache| cache.copies()) {
+            self.cached = copies;
+            self.use_cached_copies();
+        }
+        self.watch_for_replacement(dt);
+
+        if self.resume_file.is_some() || self.session_file.is_some() {
+            self.resume_timer += dt;
+            if self.resume_timer >= 1.0 {
+                self.resume_timer = 0.0;
+                self.save_resume_state();
+            }
+        }
+
+        // Check if we should show splash between videos
+        let pending = self.playback.load_video_index;
+        let skip_splash = pending
+            .and_then(|index| self.playback.hip_override(index))
+            .and_then(|h| h.skip_splash)
+            .unwrap_or(false);
+        if splash::is_due(&self.config.splash, self.playback.videos_played)
+            && self.review.is_none()
+            && !self.splash.showing
+            && pending.is_some()
+            && !skip_splash
+        {
+            // Delay video loading until after splash
+            self.splash.start(&self.config.splash);
+            self.control.emit(control::Event::SplashShown);
+            info!(
+                "Showing splash screen {} before video {} (interval: {}, mode: {:?})",
+                self.splash.index,
+                self.playback.videos_played + 1,
+                self.config.splash.interval,
+                self.config.splash.rotation_mode
+            );
+        }
+    }
+
+    /// Apply remote commands, start a pending video and watch the current one.
+    pub fn advance_playback(&mut self) {
+        while let Some((source, command)) = self.control.try_recv_tagged() {
+            if self.admit(source, &command) {
+                self.handle_command(command);
+            }
+        }
+
+        // Load video only if splash screen is not showing
+        if !self.splash.showing {
+            if let Some(index) = self.playback.load_video_index.take() {
+                self.playback.load_video(index);
+                self.trim_log();
+            }
+        }
+
+        self.playback.update();
+        for event in self.playback.take_events() {
+            match &event {
+                control::Event::VideoStarted { hip, file } => {
+                    metrics::video_started();
+                    if let Some(video) = self.playback.video_files.get(self.playback.current_index)
+                    {
+                        self.cache_video(&video.path);
+                    }
+                    json_log::set_playing(hip, file);
+                    if self.config.clock.countdown_start == CountdownStart::Video
+                        && self.countdown.lot() != Some(hip.as_str())
+                    {
+                        self.start_countdown(Some(hip));
+                    }
+                }
+                control::Event::HipEntered { hip } => {
+                    let note = self.playback.hip_overrides.get(hip);
+                    if let Some(note) = note.and_then(|o| o.note.as_deref()) {
+                        info!("Note for hip {}: {}", hip, note);
+                    }
+                }
+                control::Event::VideoEnded { hip, .. } => {
+                    let next = self.playback.load_video_index;
+                    let next_hip = next.and_then(|index| self.playback.video_files.get(index));
+                    if next_hip.map_or(true, |video| &video.hip_number != hip) {
+                        self.hip_finished();
+                    }
+                }
+                control::Event::Error { .. } => metrics::playback_error(),
+                _ => {}
+            }
+            self.control.emit(event);
+        }
+    }
+
+    /// Whether a command from `source` may be applied now, under
+    /// `[arbitration]`. A hip that may not is queued.
+    fn admit(&mut self, source: Option<Source>, command: &control::Command) -> bool {
+        self.arbiter
+            .admit(&self.config.arbitration, source, command)
+    }
+
+    /// The hip switched to has played out: play the one queued behind it.
+    fn hip_finished(&mut self) {
+        if let Some(hip) = self.arbiter.release() {
+            info!("Playing queued hip {}", hip);
+            self.validate_and_switch(&hip);
+        }
+    }
+
+    pub fn handle_command(&mut self, command: control::Command) {
+        info!("Remote command: {:?}", command);
+        if matches!(
+            command,
+            control::Command::PlayHip(_)
+                | control::Command::Next
+                | control::Command::Previous
+                | control::Command::Undo
+        ) {
+            self.set_test_pattern(None);
+        }
+        match command {
+            control::Command::PlayHip(hip) => {
+                self.validate_and_switch(&hip);
+            }
+            control::Command::Pause | control::Command::Resume => {
+                self.playback.set_paused(command == control::Command::Pause);
+            }
+            control::Command::Next => self.playback.navigate_forward(),
+            control::Command::Previous => self.playback.navigate_backward(),
+            control::Command::Undo => self.undo_switch(),
+            control::Command::Blackout(on) => self.blackout = on,
+            control::Command::Rescan => self.rescan(),
+            control::Command::RingLot(lot) => self.ring_lot = Some(lot),
+            control::Command::SetNote { hip, note } => self.set_note(&hip, note),
+            control::Command::TestPattern(pattern) => self.set_test_pattern(pattern),
+            control::Command::SwitchRing(name) => match self.config.ring_index(&name) {
+                Some(index) => self.switch_ring(index),
+                None => warn!("No ring called {}", name),
+            },
+        }
+    }
+
+    /// Change the operator note of `hip`, and save it in the video folder's
+    /// `hips.toml`. An empty note clears it.
+    fn set_note(&mut self, hip: &str, note: Option<String>) {
+        let note = note
+            .map(|note| note.trim().to_string())
+            .filter(|note| !note.is_empty());
+        let video_dir = config::resolve_asset_dir(&self.config.video.directory);
+        if let Err(e) = config::save_hip_note(&video_dir, hip, note.as_deref()) {
+            error!("Failed to save the note of hip {}: {}", hip, e);
+        }
+        self.playback
+            .hip_overrides
+            .entry(hip.to_string())
+            .or_default()
+            .note = note;
+    }
+
+    /// Stop playback for good when the demo time is up; the window shows
+    /// the evaluation-ended screen and then closes.
+    fn end_evaluation(&mut self) {
+        warn!("Demo time limit reached; ending the evaluation");
+        self.playback.stop();
+        self.evaluation_ended = Some(activation::EvaluationEnded::new());
+    }
+
+    /// Reload the video directory, keeping track of the video that is playing.
+    fn rescan(&mut self) {
+        let current = self.playback.current().map(|video| video.path.clone());
+        self.load_video_files();
+        if let Some(path) = current {
+            self.playback.select_path(&path);
+        }
+    }
+
+    /// How long the window can wait for its next repaint if no frame or
+    /// input arrives: until the next timer on screen runs out, and at most
+    /// `[ui] idle_repaint_seconds`.
+    pub fn repaint_after(&self) -> Duration {
+        let mut timers = vec![];
+        if self.splash.showing {
+            timers.push(self.config.splash.duration_seconds.get() - self.splash.timer);
+        }
+        if self.no_video_hip.is_some() {
+            timers.push(self.no_video_popup_timer);
+        }
+        if self.input.is_rejected() {
+            timers.push(self.input.rejected_for());
+        }
+        let overlay = &self.config.entry_overlay;
+        if overlay.enabled {
+            let (hold, fade) = (overlay.hold_seconds.get(), overlay.fade_seconds.get());
+            timers.extend(self.input.echo_changes_in(hold, fade));
+        }
+        let bar = &self.config.bar;
+        if bar.shows(BarWidget::Clock) {
+            timers.extend(self.countdown.changes_in());
+            if self.config.clock.show_clock {
+                timers.push(clock::changes_in(&chrono::Local::now()));
+            }
+        }
+        if bar.shows(BarWidget::Stats) {
+            if let Some(elapsed) = self.sale_stats().and_then(|stats| stats.elapsed) {
+                timers.push(1.0 - elapsed.subsec_millis() as f64 / 1000.0);
+            }
+        }
+        if bar.shows(BarWidget::Ticker) && !bar.ticker_text.is_empty() && bar.ticker_speed > 0.0 {
+            timers.push(TICKER_FRAME);
+        }
+        if let Some(offer) = &self.resume_offer {
+            // The countdown on screen changes every second
+            timers.push(offer.seconds_left.fract());
+        }
+        let idle = self.config.ui.idle_repaint();
+        timers
+            .into_iter()
+            .filter(|seconds| *seconds > 0.0)
+            .map(Duration::from_secs_f64)
+            .fold(idle, Duration::min)
+    }
+
+    /// The sale so far, for the bar, with `[analytics] show_in_bar`.
+    pub fn sale_stats(&self) -> Option<analytics::SaleStats> {
+        if !self.config.analytics.show_in_bar {
+            return None;
+        }
+        let recorder = self.playback.analytics.as_ref()?;
+        Some(recorder.stats(&self.playback.hips(), chrono::Local::now()))
+    }
+
+    pub fn status(&self) -> control::Status {
+        let current = self.playback.current();
+        control::Status {
+            hip: current.map(|video| video.hip_number.clone()),
+            file: current.map(|video| video.name.clone()),
+            paused: self.playback.paused,
+            blackout: self.blackout,
+            splash: self.splash.showing,
+            ring_lot: self.ring_lot.clone(),
+            hips: self.playback.hips(),
+            labels: self
+                .playback
+                .hip_overrides
+                .iter()
+                .filter_map(|(hip, o)| Some((hip.clone(), o.label.clone()?)))
+                .collect(),
+            note: current
+                .and_then(|video| self.playback.hip_overrides.get(&video.hip_number))
+                .and_then(|o| o.note.clone()),
+            queued_hip: self.arbiter.queued_hip().map(str::to_string),
+            audio_track: self.playback.has_audio(),
+            audio_level: mixer::level(),
+            test_pattern: self.test_pattern,
+            media_offline: self.media_offline,
+            ring: self
+                .config
+                .rings
+                .get(self.ring)
+                .map(|ring| ring.name.clone()),
+            rings: self
+                .config
+                .rings
+                .iter()
+                .map(|ring| ring.name.clone())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use summit_hip_core::config::{HipOverride, RingConfig, Seconds};
+    use summit_hip_core::playback::mock::{self, MockPlayer};
+    use tempfile::TempDir;
+
+    type TestState = AppState<MockPlayer>;
+
+    /// A state playing the empty video files `names` in `dir`.
+    fn with_videos(dir: &Path, names: &[&str]) -> TestState {
+        for name in names {
+            fs::File::create(dir.join(name)).unwrap();
+        }
+        let mut app = TestState::default();
+        app.config.video.directory = dir.to_string_lossy().to_string();
+        app.load_video_files();
+        app
+    }
+
+    #[test]
+    fn test_refuse_mode_blocks_unsigned_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        config::save(&Config::default(), &config_path).unwrap();
+        let trust = lock::Trust {
+            mode: LockMode::Refuse,
+            public_key: lock::generate_key(&temp_dir.path().join("k")).unwrap(),
+        };
+
+        let app = TestState::load_config_under(Some(&config_path), Some(trust));
+        assert!(app.lock_error.is_some());
+        let app = TestState::load_config_under(Some(&config_path), None);
+        assert!(app.lock_error.is_none());
+    }
+
+    #[test]
+    fn test_app_state_default() {
+        let app = TestState::default();
+        assert_eq!(app.playback.video_files.len(), 0);
+        assert_eq!(app.playback.current_index, 0);
+        assert_eq!(app.input.buffer, "");
+        assert_eq!(app.playback.current_file_name, "No file loaded");
+        assert_eq!(app.splash.timer, 0.0);
+        assert!(app.splash.showing);
+        assert!(app.playback.player().is_none());
+        assert_eq!(app.playback.videos_played, 0);
+    }
+
+    #[test]
+    fn test_load_video_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let app = with_videos(temp_dir.path(), &["001.mp4", "002.mp4", "003.mp4"]);
+
+        let videos = &app.playback.video_files;
+        assert_eq!(videos.len(), 3);
+        assert_eq!(videos[0].hip_number, "001");
+        assert_eq!(videos[1].hip_number, "002");
+        assert_eq!(videos[2].hip_number, "003");
+        assert_eq!(app.playback.hips(), vec!["001", "002", "003"]);
+    }
+
+    #[test]
+    fn test_load_video_files_nonexistent_dir() {
+        let mut app = TestState::default();
+        app.config.video.directory = "/nonexistent".to_string();
+
+        // This should not panic, but log error
+        app.load_video_files();
+        assert_eq!(app.playback.video_files.len(), 0);
+    }
+
+    #[test]
+    fn test_load_splash_images() {
+        let temp_dir = TempDir::new().unwrap();
+        let splash_dir = temp_dir.path().join("splash");
+        fs::create_dir(&splash_dir).unwrap();
+
+        fs::File::create(splash_dir.join("image1.png")).unwrap();
+        fs::File::create(splash_dir.join("image2.jpg")).unwrap();
+        fs::File::create(splash_dir.join("text.txt")).unwrap(); // Should be ignored
+
+        let mut app = TestState::default();
+        app.config.splash.directory = splash_dir.to_string_lossy().to_string();
+
+        app.load_splash_images();
+
+        assert_eq!(app.splash.images.len(), 2);
+    }
+
+    #[test]
+    fn test_check_asset_integrity() {
+        let temp_dir = TempDir::new().unwrap();
+        let videos_dir = temp_dir.path().join("videos");
+        let splash_dir = temp_dir.path().join("splash");
+        let logo_dir = temp_dir.path().join("logo");
+
+        fs::create_dir(&videos_dir).unwrap();
+        fs::create_dir(&splash_dir).unwrap();
+        fs::create_dir(&logo_dir).unwrap();
+
+        fs::File::create(videos_dir.join("001.mp4")).unwrap();
+
+        // It only logs what is missing next to the executable
+        TestState::default().check_asset_integrity();
+    }
+
+    #[test]
+    fn test_trim_log() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_path = temp_dir.path().join("test.log");
+
+        // Create a log with more than max_lines
+        let lines: Vec<String> = (0..150).map(|i| format!("Line {}\n", i)).collect();
+        fs::write(&log_path, lines.join("")).unwrap();
+
+        let mut app = TestState::default();
+        app.config.logging.file = log_path.to_string_lossy().to_string();
+        app.config.logging.max_lines = 100;
+
+        app.trim_log();
+
+        let content = fs::read_to_string(&log_path).unwrap();
+        let trimmed_lines: Vec<&str> = content.lines().collect();
+        assert_eq!(trimmed_lines.len(), 100);
+    }
+
+    #[test]
+    fn test_validate_and_switch_valid() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "002.mp4"]);
+
+        let result = app.validate_and_switch("001");
+        assert!(result);
+        assert_eq!(app.playback.current_index, 0);
+        assert_eq!(app.playback.videos_played, 1);
+    }
+
+    #[test]
+    fn test_validate_and_switch_invalid() {
+        let mut app = TestState::default();
+        let result = app.validate_and_switch("999");
+        assert!(!result);
+        assert_eq!(app.playback.videos_played, 0);
+        assert_eq!(app.no_video_hip.as_deref(), Some("999"));
+    }
+
+    #[test]
+    fn test_validate_and_switch_invalid_length() {
+        let mut app = TestState::default();
+        let result = app.validate_and_switch("12");
+        assert!(!result);
+        assert_eq!(app.no_video_hip, None);
+    }
+
+    #[test]
+    fn test_validate_and_switch_non_digit() {
+        let mut app = TestState::default();
+        let result = app.validate_and_switch("abc");
+        assert!(!result);
+    }
+
+    #[test]
+    fn test_demo_hip_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "009.mp4"]);
+        app.demo = true;
+        app.config.demo.hip_number_limit = 5;
+        assert!(app.validate_and_switch("001"));
+        assert!(!app.validate_and_switch("009"));
+        assert_eq!(app.no_video_hip.as_deref(), Some("009"));
+    }
+
+    #[test]
+    fn test_unplayable_hip() {
+        let temp_dir = TempDir::new().unwrap();
+        mock::state().lock().unwrap().unplayable = Some("prores".to_string());
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "002 prores.mp4"]);
+        assert!(!app.validate_and_switch("002"));
+        assert_eq!(app.no_video_hip.as_deref(), Some("002"));
+        assert_eq!(
+            app.no_video_reason.as_deref(),
+            Some("Hip 002: unsupported codec (prores)")
+        );
+
+        // A hip with no video at all has no reason to give
+        assert!(!app.validate_and_switch("404"));
+        assert_eq!(app.no_video_reason, None);
+    }
+
+    #[test]
+    fn test_next_video() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "002.mp4"]);
+
+        app.playback.next_video();
+        assert_eq!(app.playback.load_video_index, Some(1));
+
+        app.playback.next_video(); // Wrap around
+        assert_eq!(app.playback.load_video_index, Some(0));
+    }
+
+    #[test]
+    fn test_hip_overrides() {
+        let temp_dir = TempDir::new().unwrap();
+        let video_dir = temp_dir.path().join("videos");
+        fs::create_dir(&video_dir).unwrap();
+        fs::write(
+            video_dir.join(config::HIPS_SIDECAR_FILE_NAME),
+            "[\"002\"]\nlabel = \"Lot 2 (quiet)\"\nhold_last_frame = true\n",
+        )
+        .unwrap();
+
+        let mut app = TestState::default();
+        app.config.hips.insert(
+            "001".to_string(),
+            HipOverride {
+                skip_splash: Some(true),
+                ..Default::default()
+            },
+        );
+        for name in ["001.mp4", "002_a.mp4", "002_b.mp4"] {
+            fs::File::create(video_dir.join(name)).unwrap();
+        }
+        app.config.video.directory = video_dir.to_string_lossy().to_string();
+        app.load_video_files();
+
+        let playback = &mut app.playback;
+        assert_eq!(playback.display_name(0), "001.mp4");
+        assert_eq!(playback.display_name(1), "Lot 2 (quiet)");
+        assert_eq!(playback.hip_override(0).unwrap().skip_splash, Some(true));
+
+        playback.current_index = 0;
+        assert!(!playback.holds_last_frame());
+        playback.current_index = 1;
+        assert!(!playback.holds_last_frame()); // 002_b still to play
+        playback.current_index = 2;
+        assert!(playback.holds_last_frame());
+    }
+
+    #[test]
+    fn test_stream_hips() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::File::create(temp_dir.path().join("001.mp4")).unwrap();
+        fs::File::create(temp_dir.path().join("950.mp4")).unwrap();
+
+        let mut app = TestState::default();
+        app.config.video.directory = temp_dir.path().to_string_lossy().to_string();
+        for hip in ["900", "9000"] {
+            app.config.hips.insert(
+                hip.to_string(),
+                HipOverride {
+                    stream: Some("rtsp://cam.local/ring".to_string()),
+                    ..Default::default()
+                },
+            );
+        }
+        app.load_video_files();
+
+        assert_eq!(app.status().hips, vec!["001", "900", "950"]);
+        assert_eq!(app.playback.video_files[1].path, "rtsp://cam.local/ring");
+        assert!(app.validate_and_switch("900"));
+        assert_eq!(app.playback.load_video_index, Some(1));
+
+        // Streams go to the player as they are
+        app.splash.hide();
+        app.advance_playback();
+        assert_eq!(
+            mock::state().lock().unwrap().opened,
+            vec!["rtsp://cam.local/ring"]
+        );
+    }
+
+    #[test]
+    fn test_remote_commands() {
+        let temp_dir = TempDir::new().unwrap();
+        let video_dir = temp_dir.path().join("videos");
+        fs::create_dir(&video_dir).unwrap();
+        let mut app = with_videos(&video_dir, &["001.mp4", "005.mp4"]);
+        assert_eq!(app.status().hips, vec!["001", "005"]);
+
+        app.handle_command(control::Command::PlayHip("005".to_string()));
+        assert_eq!(app.playback.load_video_index, Some(1));
+        app.handle_command(control::Command::Pause);
+        app.handle_command(control::Command::Blackout(true));
+        let status = app.status();
+        assert_eq!(status.hip.as_deref(), Some("005"));
+        assert!(status.paused && status.blackout);
+        // Nothing was playing to go back to
+        app.handle_command(control::Command::Undo);
+        assert_eq!(app.playback.load_video_index, Some(1));
+
+        app.handle_command(control::Command::SetNote {
+            hip: "005".to_string(),
+            note: Some(" No audio per consignor ".to_string()),
+        });
+        assert_eq!(app.status().note.as_deref(), Some("No audio per consignor"));
+        let hips = config::hip_overrides(&app.config, &video_dir);
+        assert_eq!(hips["005"].note.as_deref(), Some("No audio per consignor"));
+        app.handle_command(control::Command::SetNote {
+            hip: "005".to_string(),
+            note: Some(String::new()),
+        });
+        assert_eq!(app.status().note, None);
+
+        // A rescan keeps the current video even when files are added before it
+        fs::File::create(video_dir.join("003.mp4")).unwrap();
+        app.handle_command(control::Command::Rescan);
+        assert_eq!(app.status().hip.as_deref(), Some("005"));
+        assert_eq!(app.status().hips.len(), 3);
+    }
+
+    #[test]
+    fn test_test_pattern() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "002.mp4"]);
+        let f5 = FrameInput {
+            test_pattern: true,
+            ..Default::default()
+        };
+
+        // F5 steps through the patterns, pausing the video under them
+        app.handle_input(&f5);
+        assert_eq!(app.status().test_pattern, Some(TestPattern::Bars));
+        assert!(app.playback.paused);
+        app.handle_input(&f5);
+        assert_eq!(app.test_pattern, Some(TestPattern::Grid));
+        app.handle_input(&f5);
+        assert_eq!(app.test_pattern, None);
+        assert!(!app.playback.paused);
+
+        // A video paused beforehand stays paused
+        app.handle_command(control::Command::Pause);
+        app.handle_command(control::Command::TestPattern(Some(TestPattern::Grid)));
+        app.handle_command(control::Command::TestPattern(None));
+        assert!(app.playback.paused);
+
+        // Switching the hip goes back to the player
+        app.handle_command(control::Command::Resume);
+        app.handle_command(control::Command::TestPattern(Some(TestPattern::Bars)));
+        app.handle_command(control::Command::PlayHip("002".to_string()));
+        assert_eq!(app.test_pattern, None);
+        assert_eq!(app.playback.load_video_index, Some(1));
+    }
+
+    #[test]
+    fn test_switch_ring() {
+        let temp_dir = TempDir::new().unwrap();
+        let ring_dir = |name: &str, videos: &[&str]| {
+            let dir = temp_dir.path().join(name);
+            fs::create_dir(&dir).unwrap();
+            for video in videos {
+                fs::File::create(dir.join(video)).unwrap();
+            }
+            RingConfig {
+                name: name.to_string(),
+                video_directory: dir.to_string_lossy().to_string(),
+                company_label: Some(name.to_uppercase()),
+                background_color: None,
+                window_x: None,
+                window_y: None,
+            }
+        };
+        let mut app = TestState::default();
+        app.config.rings = vec![
+            ring_dir("north", &["001.mp4", "002.mp4"]),
+            ring_dir("south", &["101.mp4"]),
+        ];
+        app.start_rings();
+        app.load_video_files();
+        assert_eq!(app.status().hips, ["001", "002"]);
+        assert_eq!(app.config.ui.company_label, "NORTH");
+
+        app.ring_lot = Some("17".to_string());
+        app.handle_input(&FrameInput {
+            next_ring: true,
+            ..Default::default()
+        });
+        let status = app.status();
+        assert_eq!(status.ring.as_deref(), Some("south"));
+        assert_eq!(status.rings, ["north", "south"]);
+        assert_eq!(status.hips, ["101"]);
+        assert_eq!(status.ring_lot, None);
+        assert_eq!(app.config.ui.company_label, "SOUTH");
+        assert_eq!(app.playback.load_video_index, Some(0));
+
+        app.handle_command(control::Command::SwitchRing("NORTH".to_string()));
+        assert_eq!(app.ring, 0);
+        assert_eq!(app.status().hips, ["001", "002"]);
+        app.handle_command(control::Command::SwitchRing("east".to_string()));
+        assert_eq!(app.ring, 0);
+    }
+
+    #[test]
+    fn test_replaced_videos() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "002 old.mp4"]);
+        app.splash.showing = false;
+        let path = |name: &str| temp_dir.path().join(name);
+
+        // A hip whose file was renamed plays the new one when asked for
+        fs::rename(path("002 old.mp4"), path("002 new.mp4")).unwrap();
+        assert!(app.validate_and_switch("002"));
+        assert_eq!(app.playback.video_files[1].name, "002 new.mp4");
+        assert!(!app.validate_and_switch("003"));
+        fs::File::create(path("003.mp4")).unwrap();
+        assert!(app.validate_and_switch("003"));
+
+        // The file playing copied over is offered once the copy is done
+        assert!(app.validate_and_switch("001"));
+        app.advance_playback();
+        fs::write(path("001.mp4"), b"re-edit").unwrap();
+        app.advance_timers(hot_swap::CHECK_SECONDS);
+        assert_eq!(app.swap_offer, None);
+        app.advance_timers(hot_swap::CHECK_SECONDS);
+        assert_eq!(app.swap_offer.as_deref(), Some("001"));
+        app.answer_swap(false);
+        app.advance_timers(hot_swap::CHECK_SECONDS);
+        app.advance_timers(hot_swap::CHECK_SECONDS);
+        assert_eq!(app.swap_offer, None);
+
+        fs::write(path("001.mp4"), b"re-edit, again").unwrap();
+        app.advance_timers(hot_swap::CHECK_SECONDS);
+        app.advance_timers(hot_swap::CHECK_SECONDS);
+        assert_eq!(app.swap_offer.as_deref(), Some("001"));
+        app.answer_swap(true);
+        assert_eq!(app.playback.load_video_index, Some(0));
+        assert!(app.playback.resume_at.is_some());
+        assert!(!app.hip_changed("001"));
+    }
+
+    #[test]
+    fn test_media_source_offline() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4"]);
+        app.config.media_source.check_seconds = Seconds::new(60.0).unwrap();
+        app.watch_media_source();
+        app.media_offline = true;
+        assert!(app.status().media_offline);
+        assert!(!app.validate_and_switch("001"));
+        assert_eq!(app.no_video_hip, None);
+
+        // The first check found it readable: back, and the hip plays again
+        app.advance_timers(0.1);
+        assert!(!app.media_offline);
+        assert_eq!(app.playback.load_video_index, Some(0));
+        assert!(app.validate_and_switch("001"));
+    }
+
+    #[test]
+    fn test_cached_copies() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "002.mp4"]);
+        let path = |index: usize| app.playback.video_files[index].path.clone();
+        let (first, second) = (path(0), path(1));
+        let copy = temp_dir.path().join("cache").join("001.mp4");
+        app.cached = [&first, &second]
+            .into_iter()
+            .map(|path| (path.clone(), (app.stamps[path], copy.clone())))
+            .collect();
+        app.use_cached_copies();
+        assert_eq!(app.playback.local_copies.len(), 2);
+
+        // 002 replaced since it was copied: the new file plays instead
+        fs::write(&second, b"re-edit").unwrap();
+        app.rescan();
+        assert_eq!(app.playback.local_copies.get(&first), Some(&copy));
+        assert!(!app.playback.local_copies.contains_key(&second));
+    }
+
+    #[test]
+    fn test_advance_without_window() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4"]);
+        app.config.splash.duration_seconds = Seconds::new(1.0).unwrap();
+        app.config.splash.interval = 0;
+        app.splash.showing = true;
+
+        // The startup splash ends on time without anything drawing frames
+        app.advance_timers(0.6);
+        assert!(app.splash.showing);
+        app.advance_timers(0.6);
+        assert!(!app.splash.showing);
+
+        let remote = app.control.remote();
+        remote.send(control::Command::Blackout(true)).unwrap();
+        app.advance_playback();
+        assert!(app.blackout);
+    }
+
+    #[test]
+    fn test_plays_after_splash_and_advances_at_end() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "002.mp4"]);
+        app.config.splash.duration_seconds = Seconds::new(1.0).unwrap();
+        app.config.splash.interval = 1;
+        app.playback.load_video_index = Some(0);
+        let events = app.control.remote().subscribe();
+        let state = mock::state();
+
+        // Nothing loads behind the splash
+        app.advance_playback();
+        assert!(state.lock().unwrap().opened.is_empty());
+
+        app.splash.hide();
+        app.advance_playback();
+        assert!(state.lock().unwrap().playing);
+        assert_eq!(app.playback.current_file_name, "001.mp4");
+        assert_eq!(app.status().audio_track, None);
+        state.lock().unwrap().has_audio = Some(false);
+        assert_eq!(app.status().audio_track, Some(false));
+        assert!(matches!(
+            events.try_recv(),
+            Ok(control::Event::VideoStarted { hip, .. }) if hip == "001"
+        ));
+
+        // The end of the video queues the next, after a splash before every video
+        state.lock().unwrap().eos = true;
+        app.advance_playback();
+        assert_eq!(app.playback.load_video_index, Some(1));
+        assert!(matches!(
+            events.try_recv(),
+            Ok(control::Event::VideoEnded { .. })
+        ));
+        app.advance_timers(0.1);
+        assert!(app.splash.showing);
+        assert_eq!(events.try_recv(), Ok(control::Event::SplashShown));
+    }
+
+    #[test]
+    fn test_keypad() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "002.mp4"]);
+        let typed = |text: &str| FrameInput {
+            text: vec![text.to_string()],
+            enter: true,
+            ..Default::default()
+        };
+
+        app.handle_input(&typed("404"));
+        assert!(app.input.is_rejected());
+        assert_eq!(app.no_video_hip.as_deref(), Some("404"));
+        app.advance_timers(app.config.ui.no_video_popup_timeout.get());
+        assert_eq!(app.no_video_hip, None);
+        assert!(!app.input.is_rejected());
+
+        app.handle_input(&typed("002"));
+        assert_eq!(app.playback.load_video_index, Some(1));
+        assert!(!app.input.is_rejected());
+
+        app.handle_input(&FrameInput {
+            backward: true,
+            toggle_perf_hud: true,
+            ..Default::default()
+        });
+        assert_eq!(app.playback.current_index, 0);
+        assert!(app.show_perf_hud);
+
+        app.handle_input(&FrameInput {
+            toggle_browser: true,
+            ..Default::default()
+        });
+        assert!(app.show_browser);
+        app.choose_from_browser("002");
+        assert!(!app.show_browser);
+        assert_eq!(app.playback.load_video_index, Some(1));
+    }
+
+    #[test]
+    fn test_operator_lock_queues_remote_hips() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "002.mp4", "003.mp4"]);
+        app.config.arbitration.operator_lock = true;
+        app.splash.showing = false;
+        let auction = app.control.remote().tagged(Source::Auction);
+
+        app.handle_input(&FrameInput {
+            text: vec!["002".to_string()],
+            enter: true,
+            ..Default::default()
+        });
+        app.advance_playback();
+        assert_eq!(app.status().hip.as_deref(), Some("002"));
+
+        // The auction software waits for the operator's hip to finish
+        auction
+            .send(control::Command::PlayHip("001".to_string()))
+            .unwrap();
+        app.advance_playback();
+        assert_eq!(app.status().hip.as_deref(), Some("002"));
+        assert_eq!(app.status().queued_hip.as_deref(), Some("001"));
+
+        // Played in place of 003, next in the list
+        mock::state().lock().unwrap().eos = true;
+        app.advance_playback();
+        assert_eq!(app.playback.load_video_index, Some(0));
+        assert_eq!(app.status().queued_hip, None);
+    }
+
+    #[test]
+    fn test_repeat_entry_does_not_restart() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "002.mp4"]);
+        app.splash.showing = false;
+        let enter = |force| FrameInput {
+            text: vec!["002".to_string()],
+            enter: true,
+            force,
+            ..Default::default()
+        };
+
+        app.handle_input(&enter(false));
+        app.advance_playback();
+        let opened = || mock::state().lock().unwrap().opened.len();
+        let before = opened();
+        // A double press, or a scanner reading the tag twice
+        app.handle_input(&enter(false));
+        app.handle_command(control::Command::PlayHip("002".to_string()));
+        assert!(!app.input.is_rejected());
+        app.advance_playback();
+        assert_eq!(opened(), before);
+
+        // Shift+Enter restarts it anyway
+        app.handle_input(&enter(true));
+        app.advance_playback();
+        assert_eq!(opened(), before + 1);
+
+        app.config.ui.repeat_entry_seconds = Some(Seconds::clamped(0.0));
+        app.handle_input(&enter(false));
+        app.advance_playback();
+        assert_eq!(opened(), before + 2);
+    }
+
+    #[test]
+    fn test_lot_countdown() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "002.mp4"]);
+        app.splash.showing = false;
+        app.advance_playback();
+        // None set up
+        assert_eq!(app.countdown.left(), None);
+
+        app.config.clock.countdown_seconds = Some(Seconds::clamped(30.0));
+        app.validate_and_switch("002");
+        app.advance_playback();
+        assert_eq!(app.countdown.lot(), Some("002"));
+        app.advance_timers(10.0);
+        assert_eq!(app.countdown.left(), Some(20.0));
+        app.config.ui.idle_repaint_seconds = Some(Seconds::clamped(10.0));
+        assert_eq!(app.repaint_after(), Duration::from_secs(1));
+
+        // F4 starts it over
+        let f4 = FrameInput {
+            start_countdown: true,
+            ..Default::default()
+        };
+        app.handle_input(&f4);
+        assert_eq!(app.countdown.left(), Some(30.0));
+
+        // Or only F4 does
+        app.config.clock.countdown_start = CountdownStart::Hotkey;
+        app.advance_timers(5.0);
+        app.validate_and_switch("001");
+        app.advance_playback();
+        assert_eq!(app.countdown.lot(), Some("002"));
+        assert_eq!(app.countdown.left(), Some(25.0));
+        app.handle_input(&f4);
+        assert_eq!(app.countdown.lot(), Some("001"));
+        assert_eq!(app.countdown.left(), Some(30.0));
+    }
+
+    #[test]
+    fn test_repaint_after_next_timer() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4"]);
+        app.splash.hide();
+        assert_eq!(app.repaint_after(), config::DEFAULT_IDLE_REPAINT);
+
+        app.config.ui.idle_repaint_seconds = Some(Seconds::clamped(10.0));
+        app.splash.start(&app.config.splash);
+        app.advance_timers(1.0);
+        assert_eq!(app.repaint_after(), Duration::from_secs(2));
+
+        app.splash.hide();
+        app.handle_input(&FrameInput {
+            text: vec!["404".to_string()],
+            enter: true,
+            ..Default::default()
+        });
+        assert_eq!(app.repaint_after(), Duration::from_secs_f64(0.5));
+        app.advance_timers(0.5);
+        assert_eq!(app.repaint_after(), Duration::from_secs_f64(2.5));
+
+        // A ticker scrolls only where the bar has one
+        app.no_video_hip = None;
+        app.config.bar.ticker_text = "Lunch at noon".to_string();
+        assert_eq!(app.repaint_after(), Duration::from_secs(10));
+        app.config.bar.center.push(BarWidget::Ticker);
+        assert_eq!(app.repaint_after(), Duration::from_secs_f64(TICKER_FRAME));
+        app.config.bar.center.clear();
+
+        // 0 repaints continuously
+        app.config.ui.idle_repaint_seconds = Some(Seconds::clamped(0.0));
+        assert_eq!(app.repaint_after(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_resume_after_restart() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "002.mp4"]);
+
+        let resume_file = temp_dir.path().join("player.resume");
+        ResumeState {
+            path: app.playback.video_files[1].path.clone(),
+            position_seconds: 42.5,
+            queued: None,
+            videos_played: 0,
+        }
+        .save(&resume_file)
+        .unwrap();
+        assert!(app.resume_from(resume_file.clone()));
+
+        assert_eq!(app.playback.load_video_index, Some(1));
+        assert_eq!(app.playback.resume_at, Some(Duration::from_millis(42_500)));
+        assert!(!app.splash.showing);
+        assert_eq!(app.resume_file, Some(resume_file));
+    }
+
+    #[test]
+    fn test_resume_offer() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut app = with_videos(temp_dir.path(), &["001.mp4", "002.mp4", "003.mp4"]);
+        let session_file = temp_dir.path().join("session.json");
+        let state = ResumeState {
+            path: app.playback.video_files[0].path.clone(),
+            position_seconds: 80.0,
+            queued: Some(app.playback.video_files[1].path.clone()),
+            videos_played: 4,
+        };
+        state.save(&session_file).unwrap();
+
+        app.offer_resume(&session_file);
+        assert_eq!(app.resume_offer.as_ref().unwrap().hip, "002");
+        app.answer_resume(false);
+        assert!(app.resume_offer.is_none());
+        assert_eq!(app.playback.load_video_index, None);
+
+        // Unanswered, it resumes the video that was queued
+        app.offer_resume(&session_file);
+        app.advance_timers(1.0);
+        assert!(app.resume_offer.is_some());
+        app.advance_timers(app.config.session.prompt_seconds.get());
+        assert!(app.resume_offer.is_none());
+        assert_eq!(app.playback.load_video_index, Some(1));
+        assert_eq!(app.playback.videos_played, 4);
+
+        // Saved once a second for the next start, and forgotten on a clean exit
+        app.session_file = Some(session_file.clone());
+        app.advance_playback();
+        mock::state().lock().unwrap().position = Duration::from_secs(9);
+        app.advance_timers(1.0);
+        let saved = ResumeState::load(&session_file).unwrap();
+        assert_eq!(saved.path, app.playback.video_files[1].path);
+        assert_eq!((saved.position_seconds, saved.queued), (9.0, None));
+        app.end_session();
+        assert!(!session_file.exists());
+
+        app.config.session.resume = ResumeMode::Always;
+        state.save(&session_file).unwrap();
+        app.playback.load_video_index = None;
+        app.offer_resume(&session_file);
+        assert!(app.resume_offer.is_none());
+        assert_eq!(app.playback.load_video_index, Some(1));
+    }
+
+    #[test]
+    fn test_demo_time_limit() {
+        let mut app = TestState::default();
+        app.advance_timers(0.1);
+        assert!(app.evaluation_ended.is_none());
+
+        app.demo = true;
+        app.playback.load_video_index = Some(0);
+        app.start_time = Instant::now() - Duration::from_secs(2);
+        app.config.demo.timeout_seconds = 1;
+        app.advance_timers(0.1);
+        assert!(app.evaluation_ended.is_some());
+        assert!(app.playback.player().is_none());
+        assert_eq!(app.playback.load_video_index, None);
+    }
+
+    #[test]
+    fn test_play_sources_and_requests() {
+        let temp_dir = TempDir::new().unwrap();
+        let video_dir = temp_dir.path().join("videos");
+        fs::create_dir(&video_dir).unwrap();
+        let mut app = with_videos(&video_dir, &["001 a.mp4", "001 b.mp4", "002.mp4"]);
+        let analytics_dir = temp_dir.path().join("analytics");
+        app.playback.analytics = Some(analytics::Recorder::new(analytics_dir.clone()));
+        assert_eq!(app.playback.play_source, analytics::Source::Attract);
+
+        assert!(app.validate_and_switch("001"));
+        assert_eq!(app.playback.play_source, analytics::Source::Manual);
+        app.playback.next_video();
+        assert_eq!(app.playback.play_source, analytics::Source::Auto);
+        app.playback.next_video();
+        assert_eq!(app.playback.play_source, analytics::Source::Attract);
+        app.playback.navigate_backward();
+        assert_eq!(app.playback.play_source, analytics::Source::Manual);
+        assert!(!app.validate_and_switch("404"));
+
+        let records = fs::read_dir(&analytics_dir)
+            .unwrap()
+            .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect::<String>();
+        assert!(records.contains(",request,001,001 "));
+        assert!(records.contains(",request,404,,manual,\n"));
+
+        // Shown in the bar only when asked for
+        assert_eq!(app.sale_stats(), None);
+        app.config.analytics.show_in_bar = true;
+        let stats = app.sale_stats().unwrap();
+        assert_eq!((stats.lots_played, stats.remaining), (1, 1));
+    }
+
+    #[test]
+    fn test_review() {
+        let temp_dir = TempDir::new().unwrap();
+        let video_dir = temp_dir.path().join("videos");
+        fs::create_dir(&video_dir).unwrap();
+        let mut app = with_videos(&video_dir, &["001 a.mp4", "001 b.mp4", "002.mp4"]);
+        let analytics_dir = temp_dir.path().join("analytics");
+        app.config.analytics.directory = Some(analytics_dir.to_string_lossy().to_string());
+        app.splash.showing = true;
+        let state = mock::state();
+        let key = |text: &str| FrameInput {
+            text: vec![text.to_string()],
+            ..Default::default()
+        };
+
+        app.start_review();
+        assert!(!app.splash.showing);
+        app.advance_playback();
+        // Whichever of hip 001's videos the folder lists first
+        let first = app.review.as_ref().unwrap().items[0].file.clone();
+        assert!(state.lock().unwrap().opened[0].ends_with(&first));
+
+        // The clip stops after five seconds and holds for a verdict
+        state.lock().unwrap().position = review::CLIP;
+        app.advance_timers(0.1);
+        assert!(app.playback.player().is_none());
+        app.advance_playback();
+        assert_eq!(state.lock().unwrap().opened.len(), 1);
+
+        app.handle_input(&key("r"));
+        app.advance_playback();
+        assert_eq!(state.lock().unwrap().opened.len(), 2);
+
+        // The next hip, not the hip's second video
+        app.handle_input(&key("o"));
+        app.advance_playback();
+        assert!(state.lock().unwrap().opened[2].ends_with("002.mp4"));
+        app.handle_input(&key("f"));
+        assert!(app.review.as_ref().unwrap().is_finished());
+        assert_eq!(app.playback.load_video_index, None);
+
+        let report = app.review.as_ref().unwrap().report.clone().unwrap();
+        assert!(report.starts_with(&analytics_dir));
+        assert_eq!(
+            fs::read_to_string(report).unwrap(),
+            format!("hip,file,result\n001,{},ok\n002,002.mp4,needs fix\n", first)
+        );
+    }
+}
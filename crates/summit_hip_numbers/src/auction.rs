@@ -0,0 +1,256 @@
+//! Follow the lot in the ring from the sale's auction management software,
+//! built with `--features auction` and enabled with `[auction] enabled = true`.
+//!
+//! `[auction] source` is polled every `poll_seconds`. An `http://` or
+//! `https://` source may answer with plain text (`42`) or JSON, picked apart
+//! with `json_pointer`; any other source is a shared CSV file whose last row
+//! is the lot in the ring. The player is only told when the lot changes, so an
+//! operator can still pick another hip by hand in between.
+
+use std::fs;
+use std::thread;
+use std::time::Duration;
+use summit_hip_core::config::AuctionConfig;
+use summit_hip_core::control::{Command, HipError, Remote};
+use summit_hip_core::csv;
+
+pub fn start(config: &AuctionConfig, remote: Remote) -> Result<(), String> {
+    if config.source.trim().is_empty() {
+        return Err("[auction] source is not set".to_string());
+    }
+    let config = config.clone();
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(10))
+        .build();
+    thread::Builder::new()
+        .name("auction".to_string())
+        .spawn(move || {
+            let mut follower = Follower::default();
+            loop {
+                follower.update(fetch(&agent, &config), &config, &remote);
+                thread::sleep(Duration::from_secs(config.poll_seconds.max(1)));
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// What the source said last, so the player is only told of changes.
+#[derive(Default)]
+struct Follower {
+    lot: Option<String>,
+    error: Option<String>,
+}
+
+impl Follower {
+    /// Take the answer to one poll.
+    fn update(
+        &mut self,
+        fetched: Result<Option<String>, String>,
+        config: &AuctionConfig,
+        remote: &Remote,
+    ) {
+        match fetched {
+            Ok(Some(lot)) if self.lot.as_ref() != Some(&lot) => {
+                log::info!("Auction lot in the ring: {}", lot);
+                ring_lot_changed(&lot, config, remote);
+                self.lot = Some(lot);
+                self.error = None;
+            }
+            Ok(Some(_)) => self.error = None,
+            // No lot in the ring; the same lot coming back is a change too
+            Ok(None) => {
+                self.lot = None;
+                self.error = None;
+            }
+            // Only log a failing source once, not on every poll
+            Err(e) if self.error.as_ref() != Some(&e) => {
+                log::warn!("Auction source {}: {}", config.source, e);
+                self.error = Some(e);
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+fn ring_lot_changed(lot: &str, config: &AuctionConfig, remote: &Remote) {
+    let mut commands = vec![Command::RingLot(lot.to_string())];
+    if config.auto_switch {
+        match remote.check_hip(lot) {
+            Ok(()) => commands.push(Command::PlayHip(lot.to_string())),
+            Err(e @ HipError::NoVideo(_)) => log::info!("Not switching: {}", e),
+            Err(e) => log::warn!("Not switching to lot '{}': {}", lot, e),
+        }
+    }
+    for command in commands {
+        if let Err(e) = remote.send(command) {
+            log::warn!("Auction update failed: {}", e);
+        }
+    }
+}
+
+/// The current lot as a hip number, `None` if the source doesn't name one
+/// (e.g. between sessions).
+fn fetch(agent: &ureq::Agent, config: &AuctionConfig) -> Result<Option<String>, String> {
+    let source = config.source.trim();
+    let raw = if source.starts_with("http://") || source.starts_with("https://") {
+        let body = agent
+            .get(source)
+            .call()
+            .map_err(|e| e.to_string())?
+            .into_string()
+            .map_err(|e| e.to_string())?;
+        lot_from_response(&body, &config.json_pointer)?
+    } else {
+        let text = fs::read_to_string(source).map_err(|e| e.to_string())?;
+        lot_from_csv(&text, &config.column)?
+    };
+    raw.as_deref().map(normalize_lot).transpose()
+}
+
+/// Plain text is the lot itself; JSON is looked up with `pointer`.
+fn lot_from_response(body: &str, pointer: &str) -> Result<Option<String>, String> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return Ok(Some(body.trim().to_string()).filter(|lot| !lot.is_empty()));
+    };
+    match json.pointer(pointer) {
+        Some(serde_json::Value::String(lot)) => Ok(Some(lot.clone())),
+        Some(serde_json::Value::Number(lot)) => Ok(Some(lot.to_string())),
+        Some(serde_json::Value::Null) => Ok(None),
+        Some(other) => Err(format!("'{}' is not a lot number: {}", pointer, other)),
+        None if json.is_number() => Ok(Some(json.to_string())),
+        None => Err(format!("response has no '{}'", pointer)),
+    }
+}
+
+/// The value in `column` of the last row that has one.
+fn lot_from_csv(text: &str, column: &str) -> Result<Option<String>, String> {
+    let mut rows = text.lines().filter(|line| !line.trim().is_empty());
+    let header = csv::split_line(rows.next().unwrap_or_default());
+    let index = header
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(column))
+        .ok_or_else(|| format!("no '{}' column", column))?;
+    Ok(rows
+        .filter_map(|row| csv::split_line(row).into_iter().nth(index))
+        .rfind(|lot| !lot.is_empty()))
+}
+
+/// Lots are numbered `42` or `042` by sale software; hips are 3 digits.
+fn normalize_lot(lot: &str) -> Result<String, String> {
+    let lot = lot.trim();
+    if lot.is_empty() || lot.len() > 3 || !lot.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("'{}' is not a hip number", lot));
+    }
+    Ok(format!("{:0>3}", lot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use summit_hip_core::control::{Endpoint, Status};
+
+    #[test]
+    fn test_lot_from_response() {
+        assert_eq!(
+            lot_from_response(" 42\n", "/lot").unwrap().as_deref(),
+            Some("42")
+        );
+        assert_eq!(
+            lot_from_response(r#"{"ring":{"lot":"042"}}"#, "/ring/lot")
+                .unwrap()
+                .as_deref(),
+            Some("042")
+        );
+        assert_eq!(
+            lot_from_response(r#"{"lot":7}"#, "/lot")
+                .unwrap()
+                .as_deref(),
+            Some("7")
+        );
+        assert_eq!(lot_from_response(r#"{"lot":null}"#, "/lot").unwrap(), None);
+        assert!(lot_from_response(r#"{"other":1}"#, "/lot").is_err());
+    }
+
+    #[test]
+    fn test_lot_from_csv() {
+        let csv = "Lot,Consignor,Sold\n41,\"Smith, J\",yes\n42,\"Jones \"\"JJ\"\"\",\n\n";
+        assert_eq!(lot_from_csv(csv, "lot").unwrap().as_deref(), Some("42"));
+        assert_eq!(
+            lot_from_csv(csv, "consignor").unwrap().as_deref(),
+            Some("Jones \"JJ\"")
+        );
+        assert_eq!(lot_from_csv(csv, "sold").unwrap().as_deref(), Some("yes"));
+        assert!(lot_from_csv(csv, "hip").is_err());
+        assert_eq!(lot_from_csv("Lot\n", "lot").unwrap(), None);
+    }
+
+    #[test]
+    fn test_normalize_lot() {
+        assert_eq!(normalize_lot("7").unwrap(), "007");
+        assert_eq!(normalize_lot("042").unwrap(), "042");
+        assert!(normalize_lot("1042").is_err());
+        assert!(normalize_lot("42A").is_err());
+    }
+
+    #[test]
+    fn test_ring_lot_changed() {
+        let endpoint = Endpoint::default();
+        endpoint.publish(Status {
+            hips: vec!["042".to_string()],
+            ..Status::default()
+        });
+        let mut config = AuctionConfig::default();
+        ring_lot_changed("042", &config, &endpoint.remote());
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::RingLot("042".to_string()))
+        );
+        assert_eq!(endpoint.try_recv(), None);
+
+        config.auto_switch = true;
+        ring_lot_changed("042", &config, &endpoint.remote());
+        ring_lot_changed("043", &config, &endpoint.remote());
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::RingLot("042".to_string()))
+        );
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::PlayHip("042".to_string()))
+        );
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::RingLot("043".to_string()))
+        );
+        assert_eq!(endpoint.try_recv(), None);
+    }
+
+    #[test]
+    fn test_follower_sends_changes() {
+        let endpoint = Endpoint::default();
+        let config = AuctionConfig::default();
+        let remote = endpoint.remote();
+        let mut follower = Follower::default();
+        let lot = |lot: &str| Ok(Some(lot.to_string()));
+
+        follower.update(lot("042"), &config, &remote);
+        follower.update(lot("042"), &config, &remote);
+        follower.update(Err("timed out".to_string()), &config, &remote);
+        follower.update(lot("042"), &config, &remote);
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::RingLot("042".to_string()))
+        );
+        assert_eq!(endpoint.try_recv(), None);
+
+        // The ring empties and the same lot comes back
+        follower.update(Ok(None), &config, &remote);
+        follower.update(lot("042"), &config, &remote);
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::RingLot("042".to_string()))
+        );
+        assert_eq!(endpoint.try_recv(), None);
+    }
+}
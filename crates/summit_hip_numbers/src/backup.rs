@@ -0,0 +1,236 @@
+//! Copies of config.toml kept before it's overwritten, and bundles for
+//! moving a kiosk's setup to another. Saving from the configuration GUI, a
+//! restore and an import each copy the file first to a timestamped
+//! `backups/config-<time>.toml` next to it, keeping the last
+//! [`BACKUP_KEEP`]. A restore checks the backup parses as a config, then
+//! writes it over config.toml, so it can be undone from the list like any
+//! save. A bundle from `--export` or "Export..." holds the config and the
+//! logo SVG in one file.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use summit_hip_core::config::Config;
+
+/// Number of timestamped backups kept next to the config file.
+pub const BACKUP_KEEP: usize = 10;
+
+/// Folder, relative to the config file, holding the rotated backups.
+const BACKUP_DIR_NAME: &str = "backups";
+
+/// Version written into exported bundles so future formats can be detected.
+const BUNDLE_FORMAT: u32 = 1;
+
+/// A single-file export of a kiosk's look and settings: the full config plus
+/// the logo SVG, which is not part of config.toml.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Bundle {
+    pub format: u32,
+    pub exported: String,
+    pub logo_svg: Option<String>,
+    pub config: Config,
+}
+
+pub fn backup_dir(config_path: &Path) -> PathBuf {
+    config_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(BACKUP_DIR_NAME)
+}
+
+/// Copy the current config file into the backup folder and drop the oldest
+/// backups beyond [`BACKUP_KEEP`]. Returns `None` when there is no file yet.
+pub fn create(config_path: &Path) -> Result<Option<PathBuf>, String> {
+    if !config_path.is_file() {
+        return Ok(None);
+    }
+    let dir = backup_dir(config_path);
+    fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let name = format!(
+        "config-{}.toml",
+        chrono::Local::now().format("%Y%m%d-%H%M%S%.3f")
+    );
+    let backup = dir.join(name);
+    fs::copy(config_path, &backup).map_err(|e| format!("{}: {}", backup.display(), e))?;
+    prune(&dir, BACKUP_KEEP);
+    Ok(Some(backup))
+}
+
+/// Backups for `config_path`, newest first.
+pub fn list(config_path: &Path) -> Vec<PathBuf> {
+    list_dir(&backup_dir(config_path))
+}
+
+fn list_dir(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("config-") && n.ends_with(".toml"))
+        })
+        .collect();
+    // Timestamps in the names sort chronologically
+    backups.sort();
+    backups.reverse();
+    backups
+}
+
+fn prune(dir: &Path, keep: usize) {
+    for old in list_dir(dir).into_iter().skip(keep) {
+        if let Err(e) = fs::remove_file(&old) {
+            log::warn!("Failed to remove old backup {}: {}", old.display(), e);
+        }
+    }
+}
+
+/// Replace the config file with a backup. The current file is backed up first
+/// so a restore can itself be undone.
+pub fn restore(backup: &Path, config_path: &Path) -> Result<(), String> {
+    let contents =
+        fs::read_to_string(backup).map_err(|e| format!("{}: {}", backup.display(), e))?;
+    toml::from_str::<Config>(&contents)
+        .map_err(|e| format!("{} is not a valid config: {}", backup.display(), e))?;
+    create(config_path)?;
+    fs::write(config_path, contents).map_err(|e| format!("{}: {}", config_path.display(), e))
+}
+
+/// Write `config` and the logo (if it exists) to a bundle file.
+pub fn export(config: &Config, logo: &Path, dest: &Path) -> Result<(), String> {
+    let bundle = Bundle {
+        format: BUNDLE_FORMAT,
+        exported: chrono::Local::now().to_rfc3339(),
+        logo_svg: fs::read_to_string(logo).ok(),
+        config: config.clone(),
+    };
+    let toml_str = toml::to_string(&bundle).map_err(|e| e.to_string())?;
+    fs::write(dest, toml_str).map_err(|e| format!("{}: {}", dest.display(), e))
+}
+
+/// Read a bundle written by [`export`].
+pub fn read_bundle(src: &Path) -> Result<Bundle, String> {
+    let contents = fs::read_to_string(src).map_err(|e| format!("{}: {}", src.display(), e))?;
+    let bundle: Bundle = toml::from_str(&contents)
+        .map_err(|e| format!("{} is not a config bundle: {}", src.display(), e))?;
+    if bundle.format > BUNDLE_FORMAT {
+        return Err(format!(
+            "{} was exported by a newer version (format {})",
+            src.display(),
+            bundle.format
+        ));
+    }
+    Ok(bundle)
+}
+
+/// Apply a bundle: save its config to `config_path` (backing up the old one)
+/// and write its logo to `logo`.
+pub fn import(bundle: &Bundle, config_path: &Path, logo: &Path) -> Result<(), String> {
+    create(config_path)?;
+    summit_hip_core::config::save(&bundle.config, config_path)?;
+    if let Some(svg) = &bundle.logo_svg {
+        if let Some(dir) = logo.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        fs::write(logo, svg).map_err(|e| format!("{}: {}", logo.display(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_create_without_config_is_noop() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        assert_eq!(create(&config_path).unwrap(), None);
+        assert!(list(&config_path).is_empty());
+    }
+
+    #[test]
+    fn test_backups_rotate() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        summit_hip_core::config::save(&Config::default(), &config_path).unwrap();
+
+        let dir = backup_dir(&config_path);
+        fs::create_dir_all(&dir).unwrap();
+        for i in 0..BACKUP_KEEP + 3 {
+            fs::write(
+                dir.join(format!("config-20240101-0000{:02}.000.toml", i)),
+                "",
+            )
+            .unwrap();
+        }
+        let newest = create(&config_path).unwrap().unwrap();
+
+        let backups = list(&config_path);
+        assert_eq!(backups.len(), BACKUP_KEEP);
+        assert_eq!(backups[0], newest);
+        assert!(!dir.join("config-20240101-000000.000.toml").exists());
+    }
+
+    #[test]
+    fn test_restore_backs_up_current() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let mut config = Config::default();
+        config.video.directory = "/media/old".to_string();
+        summit_hip_core::config::save(&config, &config_path).unwrap();
+        let backup = create(&config_path).unwrap().unwrap();
+
+        config.video.directory = "/media/new".to_string();
+        summit_hip_core::config::save(&config, &config_path).unwrap();
+        restore(&backup, &config_path).unwrap();
+
+        let restored = summit_hip_core::config::load_file(Some(&config_path)).unwrap();
+        assert_eq!(restored.video.directory, "/media/old");
+        assert_eq!(list(&config_path).len(), 2);
+    }
+
+    #[test]
+    fn test_restore_rejects_invalid_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let backup = temp_dir.path().join("broken.toml");
+        fs::write(&backup, "not = [valid").unwrap();
+        assert!(restore(&backup, &config_path).is_err());
+        assert!(!config_path.exists());
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let logo = temp_dir.path().join("logo").join("logo.svg");
+        fs::create_dir_all(logo.parent().unwrap()).unwrap();
+        fs::write(&logo, "<svg/>").unwrap();
+        let mut config = Config::default();
+        config.ui.background_color = "#112233".parse().unwrap();
+        let bundle_path = temp_dir.path().join("kiosk.toml");
+        export(&config, &logo, &bundle_path).unwrap();
+
+        let target = temp_dir.path().join("other");
+        let target_config = target.join("config.toml");
+        let target_logo = target.join("logo").join("logo.svg");
+        let bundle = read_bundle(&bundle_path).unwrap();
+        import(&bundle, &target_config, &target_logo).unwrap();
+
+        let imported = summit_hip_core::config::load_file(Some(&target_config)).unwrap();
+        assert_eq!(imported.ui.background_color.to_string(), "#112233");
+        assert_eq!(fs::read_to_string(target_logo).unwrap(), "<svg/>");
+    }
+
+    #[test]
+    fn test_read_bundle_rejects_plain_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        summit_hip_core::config::save(&Config::default(), &path).unwrap();
+        assert!(read_bundle(&path).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+//! The time of day and the lot countdown shown in the bar (`[clock]`), which
+//! auction staff pace each bidding window by.
+
+use std::fmt::Write;
+
+/// Used when `[clock] format` isn't a format chrono can write.
+const FALLBACK_FORMAT: &str = "%H:%M";
+
+/// The time of day in `format`.
+pub fn time_of_day(now: &chrono::DateTime<chrono::Local>, format: &str) -> String {
+    let mut text = String::new();
+    if write!(text, "{}", now.format(format)).is_err() {
+        text.clear();
+        write!(text, "{}", now.format(FALLBACK_FORMAT)).ok();
+    }
+    text
+}
+
+/// Seconds until the time of day shown next changes, at most a second.
+pub fn changes_in(now: &chrono::DateTime<chrono::Local>) -> f64 {
+    1.0 - now.timestamp_subsec_millis() as f64 / 1000.0
+}
+
+/// The countdown of the lot on screen, started as its video starts or with F4.
+#[derive(Debug, Default)]
+pub struct Countdown {
+    /// The hip it was started for.
+    lot: Option<String>,
+    /// Seconds left, once started.
+    left: Option<f64>,
+}
+
+impl Countdown {
+    /// Start again from `seconds` for `lot`.
+    pub fn start(&mut self, lot: Option<&str>, seconds: f64) {
+        self.lot = lot.map(str::to_string);
+        self.left = Some(seconds);
+    }
+
+    /// The hip it was last started for.
+    pub fn lot(&self) -> Option<&str> {
+        self.lot.as_deref()
+    }
+
+    pub fn tick(&mut self, dt: f64) {
+        if let Some(left) = &mut self.left {
+            *left = (*left - dt).max(0.0);
+        }
+    }
+
+    /// Seconds left, once started; it stays at zero when it runs out.
+    pub fn left(&self) -> Option<f64> {
+        self.left
+    }
+
+    /// Seconds until the whole seconds shown next change, while it runs.
+    pub fn changes_in(&self) -> Option<f64> {
+        let left = self.left.filter(|left| *left > 0.0)?;
+        Some(left - (left.ceil() - 1.0))
+    }
+}
+
+/// Seconds left as `m:ss`, rounded up so it reads `0:00` only once it's out.
+pub fn format_left(seconds: f64) -> String {
+    let seconds = seconds.ceil() as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_countdown() {
+        let mut countdown = Countdown::default();
+        countdown.tick(1.0);
+        assert_eq!(countdown.left(), None);
+        assert_eq!(countdown.changes_in(), None);
+
+        countdown.start(Some("007"), 90.0);
+        assert_eq!(countdown.lot(), Some("007"));
+        assert_eq!(format_left(countdown.left().unwrap()), "1:30");
+        assert_eq!(countdown.changes_in(), Some(1.0));
+        countdown.tick(0.25);
+        assert_eq!(format_left(countdown.left().unwrap()), "1:30");
+        assert_eq!(countdown.changes_in(), Some(0.75));
+        countdown.tick(89.0);
+        assert_eq!(format_left(countdown.left().unwrap()), "0:01");
+
+        // It holds at zero until the next lot
+        countdown.tick(5.0);
+        assert_eq!(countdown.left(), Some(0.0));
+        assert_eq!(format_left(0.0), "0:00");
+        assert_eq!(countdown.changes_in(), None);
+    }
+
+    #[test]
+    fn test_time_of_day() {
+        let now = chrono::Local
+            .with_ymd_and_hms(2026, 5, 2, 14, 7, 9)
+            .unwrap();
+        assert_eq!(time_of_day(&now, "%H:%M"), "14:07");
+        assert_eq!(time_of_day(&now, "%-I:%M:%S %p"), "2:07:09 PM");
+        // A bad format falls back rather than failing
+        assert_eq!(time_of_day(&now, "%Q"), "14:07");
+    }
+}
@@ -0,0 +1,250 @@
+//! Crash reports. A panic anywhere writes `crashes/crash-<time>.txt` next to
+//! the log file with the panic message, a backtrace, the end of the log, the
+//! config (secrets removed) and some system details, so support has
+//! something to go on after a kiosk fell over unattended.
+//!
+//! Panics in the UI loop are also caught so the kiosk shows a restart screen
+//! instead of disappearing; see [`RestartScreen`].
+
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use summit_hip_core::config::Config;
+
+/// Log lines included in a report.
+const LOG_LINES: usize = 200;
+/// Config keys whose values never go into a report.
+const SECRET_KEYS: [&str; 3] = ["token", "password", "pin_hash"];
+/// How long the restart screen shows before the player restarts itself.
+const RESTART_AFTER: Duration = Duration::from_secs(10);
+
+static LOG_FILE: OnceLock<PathBuf> = OnceLock::new();
+static CONFIG: Mutex<Option<String>> = Mutex::new(None);
+/// Report of the most recent panic, for the restart screen.
+static LAST_REPORT: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Write a crash report for every panic, on top of the default message.
+pub fn install(log_file: &Path) {
+    let _ = LOG_FILE.set(log_file.to_path_buf());
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let message = info.to_string();
+        match write_report(&message) {
+            Ok(path) => {
+                log::error!("Panic: {} (crash report: {})", message, path.display());
+                *LAST_REPORT.lock().unwrap_or_else(|e| e.into_inner()) = Some(path);
+            }
+            Err(e) => log::error!("Panic: {} (no crash report: {})", message, e),
+        }
+        default_hook(info);
+    }));
+}
+
+/// Keep a copy of the config for reports, without its secrets.
+pub fn set_config(config: &Config) {
+    *CONFIG.lock().unwrap_or_else(|e| e.into_inner()) = Some(snapshot(config));
+}
+
+/// The config as TOML, without its secrets.
+pub fn snapshot(config: &Config) -> String {
+    toml::Value::try_from(config)
+        .and_then(|mut value| {
+            redact(&mut value);
+            toml::to_string(&value)
+        })
+        .unwrap_or_else(|e| format!("(config not available: {})", e))
+}
+
+fn redact(value: &mut toml::Value) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                if SECRET_KEYS.contains(&key.as_str()) {
+                    *value = toml::Value::String("<redacted>".to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        toml::Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Where reports go for a player logging to `log_file`.
+pub fn dir(log_file: &Path) -> PathBuf {
+    log_file.parent().unwrap_or(Path::new("")).join("crashes")
+}
+
+fn write_report(message: &str) -> Result<PathBuf, String> {
+    let dir = dir(LOG_FILE.get().map_or(Path::new(""), PathBuf::as_path));
+    fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let now = chrono::Local::now();
+    let path = dir.join(format!("crash-{}.txt", now.format("%Y%m%d-%H%M%S")));
+    let report = report(message, &now.to_rfc3339());
+    fs::write(&path, report).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+fn report(message: &str, time: &str) -> String {
+    let thread = std::thread::current();
+    let log_tail = LOG_FILE
+        .get()
+        .and_then(|file| fs::read_to_string(file).ok())
+        .map(|log| last_lines(&log, LOG_LINES))
+        .unwrap_or_else(|| "(log file not available)".to_string());
+    let config = CONFIG
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| "(config not loaded yet)".to_string());
+    format!(
+        "Summit Hip Numbers crash report\n\n\
+         == Panic ==\n{}\nThread: {}\nTime: {}\n\n\
+         == System ==\n{}\n\
+         == Backtrace ==\n{}\n\n\
+         == Last {} log lines ==\n{}\n\n\
+         == Config ==\n{}",
+        message,
+        thread.name().unwrap_or("unnamed"),
+        time,
+        system_info(),
+        Backtrace::force_capture(),
+        LOG_LINES,
+        log_tail,
+        config
+    )
+}
+
+/// Version, OS and command line of this player.
+pub fn system_info() -> String {
+    let exe = std::env::current_exe()
+        .map(|exe| exe.display().to_string())
+        .unwrap_or_default();
+    let cpus = std::thread::available_parallelism().map_or(0, |n| n.get());
+    format!(
+        "Version: {}\nOS: {} ({})\nExecutable: {}\nArguments: {:?}\nCPUs: {}\nUptime: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        exe,
+        std::env::args().skip(1).collect::<Vec<_>>(),
+        cpus,
+        crate::metrics::uptime().map_or("unknown".to_string(), |t| format!(
+            "{:.0}s",
+            t.as_secs_f64()
+        )),
+    )
+}
+
+fn last_lines(text: &str, count: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    lines[lines.len().saturating_sub(count)..].join("\n")
+}
+
+/// Shown instead of the player after the UI loop panicked. It restarts the
+/// player after a few seconds: by exiting when `--supervise` is watching,
+/// otherwise by launching a new player first.
+pub struct RestartScreen {
+    since: Instant,
+    report: Option<PathBuf>,
+}
+
+impl RestartScreen {
+    pub fn new() -> Self {
+        Self {
+            since: Instant::now(),
+            report: LAST_REPORT
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+        }
+    }
+
+    pub fn show(&self, ctx: &egui::Context) {
+        let remaining = RESTART_AFTER.saturating_sub(self.since.elapsed());
+        if remaining.is_zero() {
+            restart();
+        }
+        let report = self
+            .report
+            .as_ref()
+            .map(|path| format!("\n\nA crash report was saved to\n{}", path.display()))
+            .unwrap_or_default();
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none().fill(egui::Color32::BLACK))
+            .show(ctx, |ui| {
+                ui.centered_and_justified(|ui| {
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "Something went wrong\n\nThe player restarts in {} seconds.{}",
+                            remaining.as_secs() + 1,
+                            report
+                        ))
+                        .color(egui::Color32::WHITE)
+                        .size(24.0),
+                    );
+                });
+            });
+        ctx.request_repaint_after(Duration::from_millis(250));
+    }
+}
+
+fn restart() -> ! {
+    if std::env::var_os(crate::supervisor::RESUME_FILE_ENV).is_none() {
+        match std::env::current_exe() {
+            Ok(exe) => {
+                if let Err(e) = std::process::Command::new(exe)
+                    .args(std::env::args_os().skip(1))
+                    .spawn()
+                {
+                    log::error!("Failed to restart the player: {}", e);
+                }
+            }
+            Err(e) => log::error!("Failed to restart the player: {}", e),
+        }
+    }
+    log::info!("Restarting after a crash");
+    std::process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        let mut config = Config::default();
+        config.http.token = Some("hunter2".to_string());
+        let snapshot = snapshot(&config);
+        assert!(!snapshot.contains("hunter2"));
+        assert!(snapshot.contains("token = \"<redacted>\""));
+        assert!(snapshot.contains("[video]"));
+    }
+
+    #[test]
+    fn test_last_lines() {
+        assert_eq!(last_lines("a\nb\nc\n", 2), "b\nc");
+        assert_eq!(last_lines("a", 5), "a");
+    }
+
+    #[test]
+    fn test_report_sections() {
+        let report = report(
+            "panicked at src/video_player.rs:1:1:\ndecoder fell over",
+            "now",
+        );
+        for section in [
+            "== Panic ==",
+            "== System ==",
+            "== Backtrace ==",
+            "== Config ==",
+        ] {
+            assert!(report.contains(section), "{}", section);
+        }
+        assert!(report.contains("decoder fell over"));
+    }
+}
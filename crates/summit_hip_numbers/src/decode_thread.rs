@@ -0,0 +1,134 @@
+//! The FFmpeg player's threads, supervised. A thread that panics or fails
+//! ends playback with the reason in the player's error slot instead of
+//! leaving it frozen on the last frame, and the controller moves on to the
+//! next video on its next update, as after any playback error.
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+
+/// How a player's threads report the end of playback: `eos` stops them all,
+/// `error` holds why playback ended early.
+#[derive(Clone)]
+pub struct Outcome {
+    pub eos: Arc<AtomicBool>,
+    pub error: Arc<Mutex<Option<String>>>,
+}
+
+impl Outcome {
+    pub fn new() -> Self {
+        Self {
+            eos: Arc::new(AtomicBool::new(false)),
+            error: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Stop playback because of `message`. The first failure is kept; the
+    /// others are usually its consequences.
+    pub fn fail(&self, message: String) {
+        let mut error = self.error.lock().unwrap_or_else(PoisonError::into_inner);
+        if error.is_none() {
+            *error = Some(message);
+        }
+        drop(error);
+        self.eos.store(true, Ordering::SeqCst);
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Run `body` on a thread called `name`. An error it returns or a panic goes
+/// to [`Outcome::fail`], which stops the player's other threads too.
+pub fn spawn(
+    name: &str,
+    outcome: Outcome,
+    body: impl FnOnce() -> anyhow::Result<()> + Send + 'static,
+) -> std::io::Result<JoinHandle<()>> {
+    let name = name.to_string();
+    thread::Builder::new().name(name.clone()).spawn(move || {
+        match panic::catch_unwind(AssertUnwindSafe(body)) {
+            Ok(Ok(())) => log::info!("{} finished", name),
+            Ok(Err(e)) => {
+                log::error!("{} failed: {}", name, e);
+                outcome.fail(e.to_string());
+            }
+            Err(payload) => {
+                let message = panic_message(&*payload);
+                log::error!("{} panicked: {}", name, message);
+                outcome.fail(format!("{} crashed: {}", name, message));
+            }
+        }
+    })
+}
+
+/// Wait for a thread started with [`spawn`], which never panics out.
+pub fn join(handle: JoinHandle<()>) {
+    let name = handle
+        .thread()
+        .name()
+        .unwrap_or("decode thread")
+        .to_string();
+    if handle.join().is_err() {
+        log::error!("{} ended without reporting", name);
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_failures_end_playback() {
+        let outcome = Outcome::new();
+        join(spawn("audio decoder", outcome.clone(), || Ok(())).unwrap());
+        // Audio ending first leaves the video playing
+        assert!(!outcome.eos.load(Ordering::SeqCst));
+        assert_eq!(outcome.error(), None);
+
+        let outcome = Outcome::new();
+        join(
+            spawn("video decoder", outcome.clone(), || {
+                Err(anyhow!("bad packet"))
+            })
+            .unwrap(),
+        );
+        assert!(outcome.eos.load(Ordering::SeqCst));
+        assert_eq!(outcome.error().as_deref(), Some("bad packet"));
+
+        let outcome = Outcome::new();
+        let handle = spawn("audio decoder", outcome.clone(), || {
+            panic!("sample format {} unsupported", 7)
+        });
+        join(handle.unwrap());
+        assert!(outcome.eos.load(Ordering::SeqCst));
+        assert_eq!(
+            outcome.error().as_deref(),
+            Some("audio decoder crashed: sample format 7 unsupported")
+        );
+
+        // The first failure stays
+        outcome.fail("audio device gone".to_string());
+        assert_eq!(
+            outcome.error().as_deref(),
+            Some("audio decoder crashed: sample format 7 unsupported")
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! A single zip file with everything support asks for: logs and crash
+//! reports, the config (secrets removed), `VERSION.txt`, what the video scan
+//! found, recent frame timings and details of the machine. Written by
+//! `--diagnostics` and the "Diagnostics..." button in the configuration GUI.
+
+use crate::crash;
+use crate::perf;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use summit_hip_core::config::{self, Config};
+use summit_hip_core::file_scanner::{scan_splash_images, scan_video_files};
+
+/// Default file name for a bundle written now.
+pub fn file_name() -> String {
+    format!(
+        "summit-diagnostics-{}.zip",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    )
+}
+
+/// `dest` itself, or a new bundle name inside it when it is a directory.
+pub fn bundle_path(dest: &Path) -> PathBuf {
+    if dest.is_dir() {
+        dest.join(file_name())
+    } else {
+        dest.to_path_buf()
+    }
+}
+
+/// Write the bundle for `config`, loaded from `config_path`, to `dest`.
+pub fn export(config: &Config, config_path: Option<&Path>, dest: &Path) -> Result<(), String> {
+    let file = fs::File::create(dest).map_err(|e| format!("{}: {}", dest.display(), e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let mut add = |name: &str, contents: &[u8]| -> Result<(), String> {
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(contents).map_err(|e| e.to_string())
+    };
+
+    add("config.toml", crash::snapshot(config).as_bytes())?;
+    add("environment.txt", environment(config_path).as_bytes())?;
+    add("scan.txt", scan_report(config).as_bytes())?;
+    let version = config::exe_dir().join("VERSION.txt");
+    if let Ok(contents) = fs::read(&version) {
+        add("VERSION.txt", &contents)?;
+    }
+
+    let log_file = Path::new(&config.logging.file);
+    if let Ok(contents) = fs::read(log_file) {
+        let name = log_file.file_name().unwrap_or_default().to_string_lossy();
+        add(&format!("logs/{}", name), &contents)?;
+    }
+    if let Ok(contents) = fs::read(perf::summary_file(log_file)) {
+        add("perf.txt", &contents)?;
+    }
+    let crashes = fs::read_dir(crash::dir(log_file))
+        .into_iter()
+        .flatten()
+        .flatten();
+    for entry in crashes {
+        if let Ok(contents) = fs::read(entry.path()) {
+            let name = entry.file_name();
+            add(&format!("crashes/{}", name.to_string_lossy()), &contents)?;
+        }
+    }
+
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn environment(config_path: Option<&Path>) -> String {
+    let mut out = crash::system_info();
+    let config_file = config::locate(config_path)
+        .map_or("(none, using defaults)".to_string(), |p| {
+            p.display().to_string()
+        });
+    let _ = writeln!(out, "Config file: {}", config_file);
+    let _ = writeln!(
+        out,
+        "Working directory: {}",
+        std::env::current_dir().unwrap_or_default().display()
+    );
+    let _ = writeln!(out, "Created: {}", chrono::Local::now().to_rfc3339());
+    // Overrides change what the player does without showing in config.toml
+    for (key, value) in std::env::vars().filter(|(key, _)| key.starts_with(config::ENV_PREFIX)) {
+        let secret = key.ends_with("_TOKEN") || key.ends_with("_PASSWORD");
+        let value = if secret { "<redacted>" } else { &value };
+        let _ = writeln!(out, "{}={}", key, value);
+    }
+    out
+}
+
+/// What the player would find in the video and splash folders.
+fn scan_report(config: &Config) -> String {
+    let mut out = String::new();
+    let video_dir = config::resolve_asset_dir(&config.video.directory);
+    let _ = writeln!(
+        out,
+        "Video directory: {} ({})",
+        config.video.directory,
+        video_dir.display()
+    );
+    match scan_video_files(&video_dir) {
+        Ok(files) => {
+            let _ = writeln!(out, "Videos: {}", files.len());
+            for file in &files {
+                let size = fs::metadata(&file.path).map_or(0, |m| m.len());
+                let _ = writeln!(out, "  {}  {}  {} bytes", file.hip_number, file.name, size);
+            }
+        }
+        Err(e) => {
+            let _ = writeln!(out, "Scan failed: {}", e);
+        }
+    }
+    let streams: Vec<_> = config
+        .hips
+        .iter()
+        .filter_map(|(hip, hip_override)| Some((hip, hip_override.stream.as_ref()?)))
+        .collect();
+    if !streams.is_empty() {
+        let _ = writeln!(out, "Streams: {}", streams.len());
+        for (hip, url) in streams {
+            let _ = writeln!(
+                out,
+                "  {}  {}",
+                hip,
+                summit_hip_core::file_scanner::stream_file(hip, url).name
+            );
+        }
+    }
+
+    let splash_dir = config::resolve_asset_dir(&config.splash.directory);
+    let _ = writeln!(
+        out,
+        "\nSplash directory: {} ({})\nSplash images: {}",
+        config.splash.directory,
+        splash_dir.display(),
+        scan_splash_images(&splash_dir).len()
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export() {
+        let temp_dir = TempDir::new().unwrap();
+        let video_dir = temp_dir.path().join("videos");
+        fs::create_dir(&video_dir).unwrap();
+        fs::write(video_dir.join("042.mp4"), b"video").unwrap();
+        let log_file = temp_dir.path().join("player.log");
+        fs::write(&log_file, "2024-05-01 10:00:00[summit][INFO] hello\n").unwrap();
+        fs::create_dir(temp_dir.path().join("crashes")).unwrap();
+        fs::write(temp_dir.path().join("crashes/crash-1.txt"), "boom").unwrap();
+        fs::write(perf::summary_file(&log_file), perf::summary()).unwrap();
+
+        let mut config = Config::default();
+        config.video.directory = video_dir.to_string_lossy().into_owned();
+        config.logging.file = log_file.to_string_lossy().into_owned();
+        config.http.token = Some("hunter2".to_string());
+        let dest = bundle_path(temp_dir.path());
+        export(&config, None, &dest).unwrap();
+
+        let mut zip = zip::ZipArchive::new(fs::File::open(&dest).unwrap()).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            [
+                "config.toml",
+                "crashes/crash-1.txt",
+                "environment.txt",
+                "logs/player.log",
+                "perf.txt",
+                "scan.txt"
+            ]
+        );
+        let mut read = |name: &str| {
+            let mut text = String::new();
+            std::io::Read::read_to_string(&mut zip.by_name(name).unwrap(), &mut text).unwrap();
+            text
+        };
+        assert!(!read("config.toml").contains("hunter2"));
+        assert!(read("scan.txt").contains("042  042.mp4  5 bytes"));
+    }
+}
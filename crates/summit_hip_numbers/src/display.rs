@@ -0,0 +1,105 @@
+//! Opening the windows as `[display]` has them: on X11 or Wayland, drawn with
+//! OpenGL or wgpu, for kiosk hardware whose graphics driver fails at one of
+//! them (a WGL error at startup, a black window under Wayland).
+
+use eframe::egui;
+use eframe::egui_wgpu::WgpuConfiguration;
+use eframe::wgpu;
+use log::{info, warn};
+use summit_hip_core::config::{DisplayBackend, DisplayConfig, GraphicsApi, Renderer};
+
+/// The options for a window showing `viewport`.
+pub fn native_options(
+    display: &DisplayConfig,
+    viewport: egui::ViewportBuilder,
+) -> eframe::NativeOptions {
+    match display.renderer {
+        Renderer::Glow => {
+            if display.graphics_api != GraphicsApi::Auto {
+                warn!("[display] graphics_api is only used with renderer = \"wgpu\"");
+            }
+        }
+        Renderer::Wgpu => info!("Drawing with wgpu on {:?}", display.graphics_api),
+    }
+    eframe::NativeOptions {
+        viewport,
+        renderer: renderer(display.renderer),
+        wgpu_options: WgpuConfiguration {
+            supported_backends: backends(display.graphics_api),
+            ..Default::default()
+        },
+        event_loop_builder: event_loop_builder(display.backend),
+        ..Default::default()
+    }
+}
+
+fn renderer(renderer: Renderer) -> eframe::Renderer {
+    match renderer {
+        Renderer::Glow => eframe::Renderer::Glow,
+        Renderer::Wgpu => eframe::Renderer::Wgpu,
+    }
+}
+
+/// What wgpu may draw with; `auto` leaves it to wgpu and `WGPU_BACKEND`.
+fn backends(api: GraphicsApi) -> wgpu::Backends {
+    match api {
+        GraphicsApi::Auto => WgpuConfiguration::default().supported_backends,
+        GraphicsApi::Vulkan => wgpu::Backends::VULKAN,
+        GraphicsApi::Dx12 => wgpu::Backends::DX12,
+        GraphicsApi::Metal => wgpu::Backends::METAL,
+        GraphicsApi::Gl => wgpu::Backends::GL,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn event_loop_builder(backend: DisplayBackend) -> Option<eframe::EventLoopBuilderHook> {
+    use winit::platform::wayland::EventLoopBuilderExtWayland;
+    use winit::platform::x11::EventLoopBuilderExtX11;
+
+    match backend {
+        DisplayBackend::Auto => None,
+        DisplayBackend::X11 => {
+            info!("Opening the window on X11");
+            Some(Box::new(|builder| {
+                builder.with_x11();
+            }))
+        }
+        DisplayBackend::Wayland => {
+            info!("Opening the window on Wayland");
+            Some(Box::new(|builder| {
+                builder.with_wayland();
+            }))
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn event_loop_builder(backend: DisplayBackend) -> Option<eframe::EventLoopBuilderHook> {
+    if backend != DisplayBackend::Auto {
+        warn!("[display] backend is only used on Linux");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_native_options() {
+        let options = native_options(&DisplayConfig::default(), egui::ViewportBuilder::default());
+        assert_eq!(options.renderer, eframe::Renderer::Glow);
+        assert!(options.event_loop_builder.is_none());
+
+        let display = DisplayConfig {
+            backend: DisplayBackend::X11,
+            renderer: Renderer::Wgpu,
+            graphics_api: GraphicsApi::Gl,
+        };
+        let options = native_options(&display, egui::ViewportBuilder::default());
+        assert_eq!(options.renderer, eframe::Renderer::Wgpu);
+        assert_eq!(options.wgpu_options.supported_backends, wgpu::Backends::GL);
+        #[cfg(target_os = "linux")]
+        assert!(options.event_loop_builder.is_some());
+    }
+}
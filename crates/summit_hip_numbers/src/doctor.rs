@@ -0,0 +1,412 @@
+//! `--doctor`: checks the things most support tickets turn out to be, and
+//! prints a pass/fail line for each. FFmpeg, whether every video in the
+//! library has a decoder, the audio outputs, OpenGL, the config and the
+//! folders the player reads and writes.
+//!
+//! `--self-check` is the part a build pipeline can run: no window, audio
+//! device or network, but the first frame of every video decoded and
+//! converted the way the player shows it.
+
+use crate::lock;
+use crate::mixer;
+use crate::probe;
+use cpal::traits::DeviceTrait;
+use ffmpeg_next as ffmpeg;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use summit_hip_core::analytics;
+use summit_hip_core::config::{self, Config};
+use summit_hip_core::file_scanner::scan_video_files;
+
+/// One line of the report: what passed, or why it failed.
+struct Check {
+    name: &'static str,
+    result: Result<String, String>,
+}
+
+/// Run every check for the config at `config_path`. Returns the report and
+/// whether everything passed.
+pub fn run(config_path: Option<&Path>) -> (String, bool) {
+    let config = config::load(config_path);
+    let mut checks = vec![
+        Check {
+            name: "Config",
+            result: check_config(config_path, &config),
+        },
+        Check {
+            name: "FFmpeg",
+            result: check_ffmpeg(),
+        },
+    ];
+    checks.extend(check_folders(&config));
+    checks.push(Check {
+        name: "Videos",
+        result: check_videos(&config),
+    });
+    checks.push(Check {
+        name: "Audio output",
+        result: check_audio(config.audio.device.as_deref()),
+    });
+    if let Some(broadcast) = &config.audio.broadcast {
+        checks.push(Check {
+            name: "Broadcast output",
+            result: check_audio(Some(&broadcast.device)),
+        });
+    }
+    checks.push(Check {
+        name: "OpenGL",
+        result: check_opengl(),
+    });
+    report(&checks)
+}
+
+/// Run the checks that need no window or audio device, decoding the first
+/// frame of every video. Returns the report and whether everything passed.
+pub fn self_check(config_path: Option<&Path>) -> (String, bool) {
+    let config = config::load(config_path);
+    let mut checks = vec![
+        Check {
+            name: "Config",
+            result: check_config(config_path, &config),
+        },
+        Check {
+            name: "FFmpeg",
+            result: check_ffmpeg(),
+        },
+    ];
+    checks.extend(check_folders(&config));
+    checks.push(Check {
+        name: "Decoding",
+        result: check_decoding(&config),
+    });
+    report(&checks)
+}
+
+fn report(checks: &[Check]) -> (String, bool) {
+    let mut out = String::new();
+    for check in checks {
+        let (status, detail) = match &check.result {
+            Ok(detail) => ("PASS", detail),
+            Err(detail) => ("FAIL", detail),
+        };
+        let _ = writeln!(out, "[{}] {}: {}", status, check.name, detail);
+    }
+    let failed = checks.iter().filter(|c| c.result.is_err()).count();
+    if failed == 0 {
+        let _ = write!(out, "\nAll {} checks passed", checks.len());
+    } else {
+        let _ = write!(out, "\n{} of {} checks failed", failed, checks.len());
+    }
+    (out, failed == 0)
+}
+
+fn check_config(config_path: Option<&Path>, config: &Config) -> Result<String, String> {
+    let Some(path) = config::locate(config_path) else {
+        return Ok("no config file found, using defaults".to_string());
+    };
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    toml::from_str::<Config>(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if let Some(trust) = lock::trust() {
+        let locked_path = config::exe_dir().join(config::CONFIG_FILE_NAME);
+        if let lock::Status::Tampered(reason) = lock::load(&trust, config_path, &locked_path).1 {
+            return Err(format!("{}: {}", locked_path.display(), reason));
+        }
+    }
+    let levels = config
+        .logging
+        .level
+        .iter()
+        .chain(config.logging.levels.values());
+    for level in levels {
+        config::parse_level(level).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(path.display().to_string())
+}
+
+fn check_ffmpeg() -> Result<String, String> {
+    ffmpeg::init().map_err(|e| format!("failed to initialize: {}", e))?;
+    let version = |v: u32| format!("{}.{}.{}", v >> 16, (v >> 8) & 0xff, v & 0xff);
+    Ok(format!(
+        "libavformat {}, libavcodec {}",
+        version(ffmpeg::format::version()),
+        version(ffmpeg::codec::version())
+    ))
+}
+
+/// Folders the player reads from and writes to.
+fn check_folders(config: &Config) -> Vec<Check> {
+    let video_dir = config::resolve_asset_dir(&config.video.directory);
+    let mut checks = vec![Check {
+        name: "Video folder",
+        result: readable(&video_dir),
+    }];
+    if config.splash.enabled {
+        checks.push(Check {
+            name: "Splash folder",
+            result: readable(&config::resolve_asset_dir(&config.splash.directory)),
+        });
+    }
+    let log_dir = Path::new(&config.logging.file)
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    checks.push(Check {
+        name: "Log folder",
+        result: writable(log_dir),
+    });
+    if config.analytics.enabled {
+        let dir = analytics::dir(config);
+        checks.push(Check {
+            name: "Analytics folder",
+            result: fs::create_dir_all(&dir)
+                .map_err(|e| format!("{}: {}", dir.display(), e))
+                .and_then(|()| writable(&dir)),
+        });
+    }
+    checks
+}
+
+fn readable(dir: &Path) -> Result<String, String> {
+    fs::read_dir(dir)
+        .map(|_| format!("{} is readable", dir.display()))
+        .map_err(|e| format!("{}: {}", dir.display(), e))
+}
+
+fn writable(dir: &Path) -> Result<String, String> {
+    let probe = dir.join(format!(".summit-doctor-{}", std::process::id()));
+    fs::write(&probe, b"")
+        .and_then(|()| fs::remove_file(&probe))
+        .map(|()| format!("{} is writable", dir.display()))
+        .map_err(|e| format!("{} is not writable: {}", dir.display(), e))
+}
+
+/// Every video in the library must open and have a decoder for its video
+/// and audio streams.
+fn check_videos(config: &Config) -> Result<String, String> {
+    let video_dir = config::resolve_asset_dir(&config.video.directory);
+    let files = scan_video_files(&video_dir).map_err(|e| e.to_string())?;
+    if files.is_empty() {
+        return Err(format!("no hip videos in {}", video_dir.display()));
+    }
+    let mut codecs = std::collections::BTreeSet::new();
+    let mut failures = Vec::new();
+    for file in &files {
+        match probe::codecs(&file.path) {
+            Ok(names) => codecs.extend(names),
+            Err(e) => failures.push(format!("  {}: {}", file.name, e)),
+        }
+    }
+    if failures.is_empty() {
+        let codecs: Vec<_> = codecs.into_iter().collect();
+        Ok(format!(
+            "{} files play ({})",
+            files.len(),
+            codecs.join(", ")
+        ))
+    } else {
+        Err(format!(
+            "{} of {} files can't be played\n{}",
+            failures.len(),
+            files.len(),
+            failures.join("\n")
+        ))
+    }
+}
+
+/// The first frame of every video in the library must decode and convert.
+fn check_decoding(config: &Config) -> Result<String, String> {
+    let video_dir = config::resolve_asset_dir(&config.video.directory);
+    let files = scan_video_files(&video_dir).map_err(|e| e.to_string())?;
+    if files.is_empty() {
+        return Err(format!("no hip videos in {}", video_dir.display()));
+    }
+    let failures: Vec<String> = files
+        .iter()
+        .filter_map(|file| {
+            first_frame(&file.path)
+                .err()
+                .map(|e| format!("  {}: {}", file.name, e))
+        })
+        .collect();
+    if failures.is_empty() {
+        Ok(format!("first frame of {} files decoded", files.len()))
+    } else {
+        Err(format!(
+            "{} of {} files don't decode\n{}",
+            failures.len(),
+            files.len(),
+            failures.join("\n")
+        ))
+    }
+}
+
+/// Decode the first video frame of `path` and convert it to RGBA, as the
+/// player does before showing it.
+fn first_frame(path: &str) -> Result<(), String> {
+    let mut ictx = ffmpeg::format::input(path).map_err(|e| e.to_string())?;
+    let (index, mut decoder) = {
+        let stream = ictx
+            .streams()
+            .best(ffmpeg::media::Type::Video)
+            .ok_or("no video stream")?;
+        let decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())
+            .and_then(|context| context.decoder().video())
+            .map_err(|e| format!("no decoder: {}", e))?;
+        (stream.index(), decoder)
+    };
+    let mut scaler = ffmpeg::software::scaling::context::Context::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        ffmpeg::format::Pixel::RGBA,
+        decoder.width(),
+        decoder.height(),
+        ffmpeg::software::scaling::flag::Flags::BILINEAR,
+    )
+    .map_err(|e| format!("no scaler: {}", e))?;
+
+    let mut decoded = ffmpeg::util::frame::video::Video::empty();
+    let mut got_frame = false;
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != index {
+            continue;
+        }
+        decoder
+            .send_packet(&packet)
+            .map_err(|e| format!("decoding failed: {}", e))?;
+        if decoder.receive_frame(&mut decoded).is_ok() {
+            got_frame = true;
+            break;
+        }
+    }
+    if !got_frame {
+        decoder.send_eof().ok();
+        if decoder.receive_frame(&mut decoded).is_err() {
+            return Err("no frame decoded".to_string());
+        }
+    }
+    let mut rgba = ffmpeg::util::frame::video::Video::empty();
+    scaler
+        .run(&decoded, &mut rgba)
+        .map_err(|e| format!("converting the frame failed: {}", e))
+}
+
+fn check_audio(name: Option<&str>) -> Result<String, String> {
+    let device = mixer::device(name).map_err(|e| e.to_string())?;
+    Ok(device
+        .name()
+        .unwrap_or_else(|_| "unnamed device".to_string()))
+}
+
+/// Open a small window for a moment and ask its OpenGL context what it is.
+fn check_opengl() -> Result<String, String> {
+    let found = Arc::new(Mutex::new(None));
+    let slot = found.clone();
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size([200.0, 50.0])
+            .with_title("Summit Hip Numbers doctor"),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "Summit Hip Numbers doctor",
+        options,
+        Box::new(move |cc| {
+            if let Some(gl) = &cc.gl {
+                use eframe::glow::HasContext;
+                // SAFETY: the context is current while the app is created
+                let (renderer, version) = unsafe {
+                    (
+                        gl.get_parameter_string(eframe::glow::RENDERER),
+                        gl.get_parameter_string(eframe::glow::VERSION),
+                    )
+                };
+                *slot.lock().unwrap() = Some(format!("{}, OpenGL {}", renderer, version));
+            }
+            Ok(Box::new(CloseAtOnce))
+        }),
+    )
+    .map_err(|e| format!("can't open a window: {}", e))?;
+    let found = found.lock().unwrap().take();
+    found.ok_or_else(|| "the window has no OpenGL context".to_string())
+}
+
+struct CloseAtOnce;
+
+impl eframe::App for CloseAtOnce {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let checks = [
+            Check {
+                name: "FFmpeg",
+                result: Ok("libavformat 61.7.100".to_string()),
+            },
+            Check {
+                name: "Audio output",
+                result: Err("no default audio output device".to_string()),
+            },
+        ];
+        let (text, ok) = report(&checks);
+        assert!(!ok);
+        assert_eq!(
+            text,
+            "[PASS] FFmpeg: libavformat 61.7.100\n\
+             [FAIL] Audio output: no default audio output device\n\
+             \n1 of 2 checks failed"
+        );
+        assert!(report(&checks[..1]).1);
+    }
+
+    #[test]
+    fn test_folders() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.video.directory = temp_dir.path().join("missing").to_string_lossy().into();
+        config.splash.enabled = false;
+        config.logging.file = temp_dir.path().join("player.log").to_string_lossy().into();
+        let checks = check_folders(&config);
+        assert_eq!(checks.len(), 2);
+        assert!(checks[0].result.is_err());
+        assert!(checks[1].result.is_ok());
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_decoding() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.video.directory = temp_dir.path().to_string_lossy().into();
+        let error = check_decoding(&config).unwrap_err();
+        assert!(error.starts_with("no hip videos"), "{}", error);
+
+        fs::write(temp_dir.path().join("001.mp4"), b"not a video").unwrap();
+        let error = check_decoding(&config).unwrap_err();
+        assert!(error.starts_with("1 of 1 files don't decode"), "{}", error);
+        assert!(error.contains("001.mp4"), "{}", error);
+    }
+
+    #[test]
+    fn test_config() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "[video\n").unwrap();
+        assert!(check_config(Some(&path), &Config::default()).is_err());
+
+        let mut config = Config::default();
+        config.logging.level = Some("loud".to_string());
+        fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+        let error = check_config(Some(&path), &config).unwrap_err();
+        assert!(error.contains("'loud' is not a log level"), "{}", error);
+    }
+}
@@ -0,0 +1,201 @@
+//! A machine ID for binding licenses to one kiosk: a hash of the built-in
+//! network cards' MAC addresses and the internal disks' serial numbers.
+//! Replacing either means activating again. Only what stays put goes in:
+//! USB network adapters, tethered phones and randomized MAC addresses come
+//! and go, and would change the ID. A machine none of these can be read on
+//! has no ID, and a license or trial isn't bound to it: one ID shared by all
+//! such machines would let one license activate them all. Nothing is sent
+//! anywhere; the operator reads the ID off the activation dialog.
+
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+/// Why a machine has no ID.
+const NO_HARDWARE_ID: &str = "no hardware ID found to bind a license to";
+
+static MACHINE_ID: OnceLock<Option<String>> = OnceLock::new();
+
+/// This machine's ID, e.g. `7F3A-09C2-D415-88E0`, or why it has none.
+pub fn machine_id() -> Result<&'static str, &'static str> {
+    MACHINE_ID
+        .get_or_init(|| fingerprint(&hardware_ids()))
+        .as_deref()
+        .ok_or(NO_HARDWARE_ID)
+}
+
+/// Hash the IDs in a stable order into four groups of hex digits; `None`
+/// without any.
+fn fingerprint(ids: &[String]) -> Option<String> {
+    let mut ids: Vec<String> = ids
+        .iter()
+        .map(|id| id.trim().to_ascii_lowercase())
+        .filter(|id| !id.is_empty())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    if ids.is_empty() {
+        return None;
+    }
+    let digest = Sha256::digest(ids.join("\n").as_bytes());
+    let hex = hex::encode_upper(&digest[..8]);
+    let groups: Vec<&str> = (0..4).map(|i| &hex[i * 4..i * 4 + 4]).collect();
+    Some(groups.join("-"))
+}
+
+#[cfg(target_os = "linux")]
+fn hardware_ids() -> Vec<String> {
+    use std::fs;
+    let mut ids = Vec::new();
+    // Interfaces backed by a device not on USB; skips lo, bridges, VPNs,
+    // containers, dongles and tethered phones
+    if let Ok(entries) = fs::read_dir("/sys/class/net") {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(subsystem) = fs::read_link(path.join("device").join("subsystem")) else {
+                continue;
+            };
+            if subsystem.ends_with("usb") {
+                continue;
+            }
+            if let Ok(mac) = fs::read_to_string(path.join("address")) {
+                if is_burned_in(&mac) {
+                    ids.push(mac);
+                }
+            }
+        }
+    }
+    // Internal disks by model and serial; skips partitions and USB sticks
+    if let Ok(entries) = fs::read_dir("/dev/disk/by-id") {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let internal = name.starts_with("ata-")
+                || (name.starts_with("nvme-") && !name.starts_with("nvme-eui."));
+            if internal && !name.contains("-part") {
+                ids.push(name);
+            }
+        }
+    }
+    if ids.is_empty() {
+        // Containers and VMs without either
+        if let Ok(id) = fs::read_to_string("/etc/machine-id") {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+#[cfg(target_os = "macos")]
+fn hardware_ids() -> Vec<String> {
+    let mut ids = Vec::new();
+    if let Some(out) = command_output("ioreg", &["-rd1", "-c", "IOPlatformExpertDevice"]) {
+        ids.extend(
+            out.lines()
+                .filter(|line| line.contains("\"IOPlatformSerialNumber\""))
+                .filter_map(|line| line.split('"').nth(3))
+                .map(str::to_string),
+        );
+    }
+    if let Some(out) = command_output("ifconfig", &["en0"]) {
+        ids.extend(
+            out.lines()
+                .filter_map(|line| line.trim().strip_prefix("ether "))
+                .filter(|mac| is_burned_in(mac))
+                .map(str::to_string),
+        );
+    }
+    ids
+}
+
+#[cfg(target_os = "windows")]
+fn hardware_ids() -> Vec<String> {
+    // PCI cards only, not USB adapters
+    let script = "Get-CimInstance Win32_NetworkAdapter \
+                  -Filter \"PhysicalAdapter=true AND PNPDeviceID LIKE 'PCI%'\" | \
+                  ForEach-Object MACAddress; \
+                  Get-CimInstance Win32_DiskDrive -Filter \"MediaType='Fixed hard disk media'\" | \
+                  ForEach-Object SerialNumber";
+    command_output("powershell", &["-NoProfile", "-Command", script])
+        .map(|out| {
+            out.lines()
+                .filter(|line| !line.contains(':') || is_burned_in(line))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn hardware_ids() -> Vec<String> {
+    Vec::new()
+}
+
+/// Whether `mac` is the address a card was made with: not all zeros, and
+/// not a locally administered one, like the random addresses Wi-Fi uses for
+/// privacy.
+#[cfg_attr(
+    not(any(target_os = "linux", target_os = "macos", target_os = "windows")),
+    allow(dead_code)
+)]
+fn is_burned_in(mac: &str) -> bool {
+    let mac = mac.trim();
+    let Some(first) = mac
+        .get(..2)
+        .and_then(|octet| u8::from_str_radix(octet, 16).ok())
+    else {
+        return false;
+    };
+    first & 0x02 == 0 && mac.chars().any(|c| c.is_ascii_hexdigit() && c != '0')
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        let ids = [
+            "00:1B:44:11:3A:B7".to_string(),
+            "ata-Samsung_SSD_870_S5Y1NX0R123".to_string(),
+        ];
+        let id = fingerprint(&ids).unwrap();
+        assert_eq!(id.len(), 19);
+        assert_eq!(id.split('-').count(), 4);
+        // Order, case and duplicates don't change it
+        let shuffled = [
+            "ata-samsung_ssd_870_s5y1nx0r123".to_string(),
+            "00:1b:44:11:3a:b7\n".to_string(),
+            "00:1B:44:11:3A:B7".to_string(),
+        ];
+        assert_eq!(fingerprint(&shuffled).as_ref(), Some(&id));
+        assert_ne!(fingerprint(&ids[..1]).as_ref(), Some(&id));
+        assert_eq!(machine_id(), machine_id());
+    }
+
+    #[test]
+    fn test_no_hardware_ids_no_machine_id() {
+        assert_eq!(fingerprint(&[]), None);
+        assert_eq!(fingerprint(&[" \n".to_string(), String::new()]), None);
+    }
+
+    #[test]
+    fn test_is_burned_in() {
+        assert!(is_burned_in("00:1b:44:11:3a:b7\n"));
+        assert!(is_burned_in("3C-52-82-0A-11-F0"));
+        // Randomized, as phones and Wi-Fi privacy settings make them
+        assert!(!is_burned_in("da:a1:19:5e:22:01"));
+        assert!(!is_burned_in("00:00:00:00:00:00"));
+        assert!(!is_burned_in(""));
+    }
+}
@@ -0,0 +1,112 @@
+//! Keeping the player in front under `[ui] keep_focus`. When another program
+//! takes the focus or knocks the window out of fullscreen, like a Windows
+//! Update prompt or a driver popup, it is logged and taken back, again every
+//! [`RETRY_SECONDS`] until the window has it.
+
+use std::fmt;
+
+/// How often to take the window back while another program keeps it.
+pub const RETRY_SECONDS: f64 = 1.0;
+
+/// What the window lost.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Lost {
+    Focus,
+    Fullscreen,
+}
+
+impl fmt::Display for Lost {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Lost::Focus => "focus",
+            Lost::Fullscreen => "fullscreen",
+        })
+    }
+}
+
+/// What a frame's [`FocusGuard::check`] found.
+#[derive(Debug, Default, PartialEq)]
+pub struct Check {
+    /// Lost since the last frame.
+    pub stolen: Vec<Lost>,
+    /// Got everything back this frame, after this many seconds.
+    pub regained_after: Option<f64>,
+    /// Take the window back now.
+    pub reclaim: bool,
+}
+
+/// Whether the window has what it should, from frame to frame.
+#[derive(Debug, Default)]
+pub struct FocusGuard {
+    focus_lost: bool,
+    fullscreen_lost: bool,
+    /// Seconds since it lost the first of them.
+    lost_for: f64,
+    retry_in: f64,
+}
+
+impl FocusGuard {
+    /// Check the window once a frame. `focused` and `fullscreen` are what it
+    /// reports, `None` when unknown or, for `fullscreen`, not wanted.
+    pub fn check(&mut self, dt: f64, focused: Option<bool>, fullscreen: Option<bool>) -> Check {
+        let focus_lost = focused == Some(false);
+        let fullscreen_lost = fullscreen == Some(false);
+        let was_lost = self.focus_lost || self.fullscreen_lost;
+        let mut check = Check::default();
+        if focus_lost && !self.focus_lost {
+            check.stolen.push(Lost::Focus);
+        }
+        if fullscreen_lost && !self.fullscreen_lost {
+            check.stolen.push(Lost::Fullscreen);
+        }
+        self.focus_lost = focus_lost;
+        self.fullscreen_lost = fullscreen_lost;
+
+        if !focus_lost && !fullscreen_lost {
+            if was_lost {
+                check.regained_after = Some(self.lost_for + dt);
+            }
+            self.lost_for = 0.0;
+            self.retry_in = 0.0;
+            return check;
+        }
+        if was_lost {
+            self.lost_for += dt;
+        }
+        self.retry_in -= dt;
+        if !check.stolen.is_empty() || self.retry_in <= 0.0 {
+            self.retry_in = RETRY_SECONDS;
+            check.reclaim = true;
+        }
+        check
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_guard() {
+        let mut guard = FocusGuard::default();
+        assert_eq!(guard.check(0.1, Some(true), Some(true)), Check::default());
+        // Nothing to go on
+        assert_eq!(guard.check(0.1, None, None), Check::default());
+
+        let check = guard.check(0.1, Some(false), Some(true));
+        assert_eq!(check.stolen, [Lost::Focus]);
+        assert!(check.reclaim);
+        // Not again until the retry is due
+        assert_eq!(guard.check(0.5, Some(false), Some(true)), Check::default());
+        let check = guard.check(0.25, Some(false), Some(false));
+        assert_eq!(check.stolen, [Lost::Fullscreen]);
+        assert!(check.reclaim);
+        assert!(!guard.check(0.5, Some(false), Some(false)).reclaim);
+        assert!(guard.check(0.5, Some(false), Some(false)).reclaim);
+
+        let check = guard.check(0.25, Some(true), Some(true));
+        assert_eq!(check.regained_after, Some(2.0));
+        assert!(!check.reclaim);
+        assert_eq!(guard.check(0.1, Some(true), None), Check::default());
+    }
+}
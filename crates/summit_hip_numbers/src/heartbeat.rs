@@ -0,0 +1,157 @@
+//! Signs of life for external watchdogs. The UI loop calls
+//! [`Heartbeat::beat`] every frame; with `[heartbeat] enabled = true` that
+//! rewrites the heartbeat file and pings the systemd watchdog every
+//! `interval_seconds`. `GET /healthz` on the HTTP API reports whether the loop
+//! is still beating, whatever the config says.
+//!
+//! A hung UI stops beating, so the watchdog can restart the kiosk.
+
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use summit_hip_core::config::HeartbeatConfig;
+
+static CLOCK: OnceLock<Instant> = OnceLock::new();
+/// Milliseconds on `CLOCK` of the last beat, 0 before the first one.
+static LAST_BEAT_MS: AtomicU64 = AtomicU64::new(0);
+/// How long without a beat before `/healthz` reports a hang.
+static STALE_AFTER_MS: AtomicU64 = AtomicU64::new(15_000);
+
+fn now_ms() -> u64 {
+    // Never 0, which means "no beat yet"
+    CLOCK.get_or_init(Instant::now).elapsed().as_millis() as u64 + 1
+}
+
+#[derive(Default)]
+pub struct Heartbeat {
+    last_signal: Option<Instant>,
+    failing: bool,
+}
+
+impl Heartbeat {
+    pub fn beat(&mut self, config: &HeartbeatConfig) {
+        let interval = Duration::from_secs(config.interval_seconds.max(1));
+        LAST_BEAT_MS.store(now_ms(), Ordering::Relaxed);
+        STALE_AFTER_MS.store(interval.as_millis() as u64 * 3, Ordering::Relaxed);
+        if !config.enabled || self.last_signal.is_some_and(|at| at.elapsed() < interval) {
+            return;
+        }
+
+        let first = self.last_signal.is_none();
+        self.last_signal = Some(Instant::now());
+        let result = signal(config, first);
+        // Only log a failing watchdog once, not every few seconds
+        match result {
+            Ok(()) => self.failing = false,
+            Err(e) if !self.failing => {
+                log::warn!("Heartbeat failed: {}", e);
+                self.failing = true;
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+fn signal(config: &HeartbeatConfig, first: bool) -> Result<(), String> {
+    if let Some(file) = &config.file {
+        fs::write(file, chrono::Local::now().to_rfc3339())
+            .map_err(|e| format!("{}: {}", file, e))?;
+    }
+    if config.systemd {
+        let state = if first {
+            "READY=1\nWATCHDOG=1"
+        } else {
+            "WATCHDOG=1"
+        };
+        notify_systemd(state)?;
+    }
+    Ok(())
+}
+
+/// Send `state` to systemd's notification socket, if it gave us one.
+#[cfg(unix)]
+fn notify_systemd(state: &str) -> Result<(), String> {
+    use std::os::unix::net::UnixDatagram;
+
+    let Some(path) = std::env::var("NOTIFY_SOCKET")
+        .ok()
+        .filter(|p| !p.is_empty())
+    else {
+        return Ok(());
+    };
+    let socket = UnixDatagram::unbound().map_err(|e| e.to_string())?;
+    let sent = match path.strip_prefix('@') {
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            std::os::unix::net::SocketAddr::from_abstract_name(name)
+                .and_then(|addr| socket.send_to_addr(state.as_bytes(), &addr))
+        }
+        _ => socket.send_to(state.as_bytes(), &path),
+    };
+    sent.map(|_| ())
+        .map_err(|e| format!("NOTIFY_SOCKET {}: {}", path, e))
+}
+
+#[cfg(not(unix))]
+fn notify_systemd(_state: &str) -> Result<(), String> {
+    Ok(())
+}
+
+/// Time since the UI loop last beat, `None` before the first beat.
+pub fn since_last_beat() -> Option<Duration> {
+    match LAST_BEAT_MS.load(Ordering::Relaxed) {
+        0 => None,
+        last => Some(Duration::from_millis(now_ms().saturating_sub(last))),
+    }
+}
+
+/// Whether the UI loop has beaten recently enough.
+#[cfg_attr(not(feature = "http-api"), allow(dead_code))]
+pub fn healthy() -> bool {
+    since_last_beat()
+        .is_some_and(|age| age.as_millis() as u64 <= STALE_AFTER_MS.load(Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beat_writes_file_once_per_interval() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("heartbeat");
+        let config = HeartbeatConfig {
+            enabled: true,
+            file: Some(file.to_string_lossy().into_owned()),
+            systemd: false,
+            ..HeartbeatConfig::default()
+        };
+        let mut heartbeat = Heartbeat::default();
+        heartbeat.beat(&config);
+        assert!(healthy());
+        assert!(since_last_beat().unwrap() < Duration::from_secs(1));
+
+        fs::remove_file(&file).unwrap();
+        heartbeat.beat(&config);
+        assert!(!file.exists(), "rewritten before the interval passed");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_notify_systemd() {
+        use std::os::unix::net::UnixDatagram;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notify");
+        let systemd = UnixDatagram::bind(&path).unwrap();
+        std::env::set_var("NOTIFY_SOCKET", &path);
+        notify_systemd("WATCHDOG=1").unwrap();
+        std::env::remove_var("NOTIFY_SOCKET");
+
+        let mut buf = [0u8; 64];
+        let len = systemd.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"WATCHDOG=1");
+    }
+}
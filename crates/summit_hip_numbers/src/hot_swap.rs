@@ -0,0 +1,111 @@
+//! Videos replaced on disk while the player runs, like a consignor's late
+//! re-edit copied over the old file. A hip asked for is checked against the
+//! folder first, so it plays the new file rather than the one scanned; the
+//! file of the video playing is checked every few seconds, and once it has
+//! changed and stopped changing the operator is asked whether to swap it in.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::time::SystemTime;
+
+use summit_hip_core::file_scanner::{self, VideoFile};
+
+/// How often the file of the video playing is checked, in seconds.
+pub const CHECK_SECONDS: f64 = 2.0;
+
+/// What a file looked like when checked: a copy over it changes one or both.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Stamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+/// The stamp of the file at `path`, `None` if it's gone.
+pub fn stamp(path: &str) -> Option<Stamp> {
+    let metadata = fs::metadata(path).ok()?;
+    Some(Stamp {
+        len: metadata.len(),
+        modified: metadata.modified().ok(),
+    })
+}
+
+/// The stamps of the files of `videos`, leaving out live streams.
+pub fn stamps(videos: &[VideoFile]) -> HashMap<String, Stamp> {
+    videos
+        .iter()
+        .filter(|video| !file_scanner::is_stream_url(&video.path))
+        .filter_map(|video| Some((video.path.clone(), stamp(&video.path)?)))
+        .collect()
+}
+
+/// Watches the file of the video playing.
+#[derive(Debug, Default)]
+pub struct Watch {
+    timer: f64,
+    /// The file as the last check found it, when it differed from the scan.
+    changing: Option<(String, Option<Stamp>)>,
+    /// A change the operator chose to keep playing through.
+    declined: Option<(String, Option<Stamp>)>,
+}
+
+impl Watch {
+    /// Whether `path`, scanned as `scanned`, has been replaced: it has
+    /// changed, and stayed the same over a whole check, so a copy still
+    /// being written isn't taken for the new file.
+    pub fn check(&mut self, dt: f64, path: &str, scanned: Option<Stamp>) -> bool {
+        self.timer += dt;
+        if self.timer < CHECK_SECONDS {
+            return false;
+        }
+        self.timer = 0.0;
+        let now = (path.to_string(), stamp(path));
+        if now.1 == scanned || self.declined.as_ref() == Some(&now) {
+            self.changing = None;
+            return false;
+        }
+        let settled = self.changing.as_ref() == Some(&now);
+        self.changing = Some(now);
+        settled
+    }
+
+    /// Keep playing `path` as it is now, until it changes again.
+    pub fn decline(&mut self, path: &str) {
+        self.declined = Some((path.to_string(), stamp(path)));
+        self.changing = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_watch_waits_for_the_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("042.mp4");
+        let path_str = path.to_str().unwrap();
+        fs::write(&path, b"old").unwrap();
+        let scanned = stamp(path_str);
+        let mut watch = Watch::default();
+        assert!(!watch.check(CHECK_SECONDS, path_str, scanned));
+
+        // Still being copied over at the next check
+        fs::write(&path, b"new, half").unwrap();
+        assert!(!watch.check(1.0, path_str, scanned));
+        assert!(!watch.check(1.0, path_str, scanned));
+        fs::write(&path, b"new, all of it").unwrap();
+        assert!(!watch.check(CHECK_SECONDS, path_str, scanned));
+        assert!(watch.check(CHECK_SECONDS, path_str, scanned));
+
+        watch.decline(path_str);
+        assert!(!watch.check(CHECK_SECONDS, path_str, scanned));
+        assert!(!watch.check(CHECK_SECONDS, path_str, scanned));
+
+        // Gone altogether
+        fs::remove_file(&path).unwrap();
+        assert!(!watch.check(CHECK_SECONDS, path_str, scanned));
+        assert!(watch.check(CHECK_SECONDS, path_str, scanned));
+    }
+}
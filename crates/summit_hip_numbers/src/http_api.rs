@@ -0,0 +1,526 @@
+//! HTTP control API for show-control systems, built with `--features http-api`
+//! and switched on with `[http] enabled = true`.
+//!
+//! | Method | Path                        | Action                         |
+//! |--------|-----------------------------|--------------------------------|
+//! | GET    | `/api/status`               | current hip, file and state    |
+//! | POST   | `/api/play/<hip>`           | play a hip number              |
+//! | POST   | `/api/pause`, `/api/resume` | pause or resume the video      |
+//! | POST   | `/api/next`, `/api/previous`| step through the video list    |
+//! | POST   | `/api/undo`                 | back to the hip switched from  |
+//! | POST   | `/api/blackout/<on\|off>`   | blank the screen               |
+//! | POST   | `/api/rescan`               | reload the video directory     |
+//! | POST   | `/api/note/<hip>?text=...`  | set or clear a hip's note      |
+//! | POST   | `/api/test_pattern/<bars\|grid\|off>` | show a test pattern |
+//! | POST   | `/api/ring/<name>`          | switch to another sale ring    |
+//! | GET    | `/api/events` (WebSocket)   | push player events as JSON     |
+//! | GET    | `/api/companion`            | discovery for Companion modules|
+//! | GET    | `/api/hip/<hip>`            | per-hip button feedback        |
+//! | GET    | `/api/kiosks`               | kiosks found over mDNS         |
+//! | GET    | `/metrics`                  | Prometheus metrics             |
+//!
+//! `/` serves a small operator dashboard for phones on the sale floor.
+
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use summit_hip_core::config::HttpConfig;
+use summit_hip_core::control::{Command, Event, HipError, Remote, TestPattern};
+use tiny_http::{Header, Method, Request, Response, Server};
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+/// The operator dashboard, a single page using the API above.
+const DASHBOARD_HTML: &str = include_str!("../web/dashboard.html");
+
+/// How long the event stream may go quiet before the client is pinged, to
+/// read what it sent meanwhile and notice when it's gone.
+const EVENTS_PING: Duration = Duration::from_secs(10);
+
+/// Bumped whenever a route or field a Companion module relies on changes
+/// incompatibly; new routes and fields don't bump it.
+const COMPANION_API_VERSION: u32 = 1;
+
+/// Start serving in a background thread.
+pub fn start(config: &HttpConfig, remote: Remote) -> Result<(), String> {
+    let server = Server::http(&config.bind).map_err(|e| format!("{}: {}", config.bind, e))?;
+    let token = config.token.clone();
+    thread::Builder::new()
+        .name("http-api".to_string())
+        .spawn(move || serve(&server, &remote, token.as_deref()))
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn serve(server: &Server, remote: &Remote, token: Option<&str>) {
+    for request in server.incoming_requests() {
+        handle(request, remote, token);
+    }
+}
+
+fn handle(request: Request, remote: &Remote, token: Option<&str>) {
+    let path = request.url().split('?').next().unwrap_or_default();
+    if request.method() == &Method::Get && (path == "/" || path == "/index.html") {
+        // The page itself is public; it asks for the token before calling the API.
+        let content_type = Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap();
+        if let Err(e) =
+            request.respond(Response::from_string(DASHBOARD_HTML).with_header(content_type))
+        {
+            log::warn!("Failed to send dashboard: {}", e);
+        }
+        return;
+    }
+
+    if request.method() == &Method::Get && path == "/healthz" {
+        // Public, so watchdogs don't need the token
+        let (code, body) = healthz();
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let response = Response::from_string(body.to_string())
+            .with_status_code(code)
+            .with_header(content_type);
+        if let Err(e) = request.respond(response) {
+            log::warn!("Failed to send health check: {}", e);
+        }
+        return;
+    }
+
+    let authorized = authorized(&request, token);
+    if authorized && request.method() == &Method::Get && path == "/api/events" {
+        if let Some(key) = header(&request, "Sec-WebSocket-Key") {
+            stream_events(request, &key, remote);
+            return;
+        }
+    }
+
+    if authorized && request.method() == &Method::Get && path == "/metrics" {
+        let content_type = Header::from_bytes("Content-Type", "text/plain; version=0.0.4").unwrap();
+        let response = Response::from_string(crate::metrics::render()).with_header(content_type);
+        if let Err(e) = request.respond(response) {
+            log::warn!("Failed to send metrics: {}", e);
+        }
+        return;
+    }
+
+    let (code, body) = if authorized {
+        route(request.method(), request.url(), remote)
+    } else {
+        (401, json!({ "error": "missing or wrong bearer token" }))
+    };
+    log::debug!("HTTP {} {} -> {}", request.method(), request.url(), code);
+
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = Response::from_string(body.to_string())
+        .with_status_code(code)
+        .with_header(content_type);
+    if let Err(e) = request.respond(response) {
+        log::warn!("Failed to send HTTP response: {}", e);
+    }
+}
+
+fn header(request: &Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.to_string())
+}
+
+/// Check the bearer token, also accepted as a `token` query parameter because
+/// browsers can't set headers on WebSocket connections.
+fn authorized(request: &Request, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    let value = header(request, "Authorization");
+    value.as_deref().and_then(|v| v.strip_prefix("Bearer ")) == Some(token)
+        || query_param(request.url(), "token").as_deref() == Some(token)
+}
+
+fn query_param(url: &str, name: &str) -> Option<String> {
+    let (_, query) = url.split_once('?')?;
+    query.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key == name).then(|| percent_decode(value))
+    })
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Upgrade to a WebSocket and forward every player event to it as a JSON text
+/// message, on a thread of its own until the client goes away.
+fn stream_events(request: Request, key: &str, remote: &Remote) {
+    let events = remote.subscribe();
+    let accept =
+        Header::from_bytes("Sec-WebSocket-Accept", derive_accept_key(key.as_bytes())).unwrap();
+    let stream = request.upgrade("websocket", Response::empty(101).with_header(accept));
+    let spawned = thread::Builder::new()
+        .name("http-api-events".to_string())
+        .spawn(move || {
+            let socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+            forward_events(socket, events, EVENTS_PING);
+            log::debug!("WebSocket event client disconnected");
+        });
+    if let Err(e) = spawned {
+        log::warn!("Failed to start WebSocket event stream: {}", e);
+    }
+}
+
+/// Send `events` down `socket`, pinging it after `ping` without any. The
+/// upgraded stream has no read timeout, so the client is read only then,
+/// up to its pong; until it closes or a send or read fails.
+fn forward_events<S: Read + Write>(
+    mut socket: WebSocket<S>,
+    events: Receiver<Event>,
+    ping: Duration,
+) {
+    loop {
+        let message = match events.recv_timeout(ping) {
+            Ok(event) => Message::text(serde_json::to_string(&event).unwrap_or_default()),
+            Err(RecvTimeoutError::Timeout) => Message::Ping(Vec::new()),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let pinged = message.is_ping();
+        if socket.send(message).is_err() || (pinged && !hear_back(&mut socket)) {
+            break;
+        }
+    }
+}
+
+/// Read what the client sent up to its pong, answering its pings and its
+/// close on the way; false once it's closed or gone.
+fn hear_back<S: Read + Write>(socket: &mut WebSocket<S>) -> bool {
+    loop {
+        match socket.read() {
+            Ok(Message::Pong(_)) => return true,
+            Ok(Message::Close(_)) => {
+                let _ = socket.flush();
+                return false;
+            }
+            Ok(_) => {}
+            Err(_) => return false,
+        }
+    }
+}
+
+/// Map a request to a command and return the status code and JSON body.
+fn route(method: &Method, url: &str, remote: &Remote) -> (u16, Value) {
+    let path = url.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let command = match (method, segments.as_slice()) {
+        (Method::Get, ["api", "status"]) => return (200, json!(remote.status())),
+        (Method::Get, ["api", "companion"]) => return (200, companion(remote)),
+        (Method::Get, ["api", "kiosks"]) => return (200, kiosks()),
+        (Method::Get, ["api", "hip", hip]) => {
+            let status = remote.status();
+            return (
+                200,
+                json!({
+                    "hip": hip,
+                    "available": status.hips.iter().any(|h| h == hip),
+                    "active": status.hip.as_deref() == Some(*hip),
+                }),
+            );
+        }
+        (Method::Post, ["api", "play", hip]) => match remote.check_hip(hip) {
+            Ok(()) => Command::PlayHip(hip.to_string()),
+            Err(e @ HipError::Malformed) => return (400, json!({ "error": e.to_string() })),
+            Err(e @ HipError::NoVideo(_)) => return (404, json!({ "error": e.to_string() })),
+        },
+        (Method::Post, ["api", "pause"]) => Command::Pause,
+        (Method::Post, ["api", "resume"]) => Command::Resume,
+        (Method::Post, ["api", "next"]) => Command::Next,
+        (Method::Post, ["api", "previous"]) => Command::Previous,
+        (Method::Post, ["api", "undo"]) => Command::Undo,
+        (Method::Post, ["api", "blackout", "on"]) => Command::Blackout(true),
+        (Method::Post, ["api", "blackout", "off"]) => Command::Blackout(false),
+        (Method::Post, ["api", "rescan"]) => Command::Rescan,
+        (Method::Post, ["api", "test_pattern", "bars"]) => {
+            Command::TestPattern(Some(TestPattern::Bars))
+        }
+        (Method::Post, ["api", "test_pattern", "grid"]) => {
+            Command::TestPattern(Some(TestPattern::Grid))
+        }
+        (Method::Post, ["api", "test_pattern", "off"]) => Command::TestPattern(None),
+        (Method::Post, ["api", "ring", name]) => {
+            let name = percent_decode(name);
+            match remote.find_ring(&name) {
+                Some(ring) => Command::SwitchRing(ring),
+                None => return (404, json!({ "error": format!("no ring called {}", name) })),
+            }
+        }
+        (Method::Post, ["api", "note", hip]) => match remote.check_hip(hip) {
+            Ok(()) => Command::SetNote {
+                hip: hip.to_string(),
+                note: query_param(url, "text"),
+            },
+            Err(e @ HipError::Malformed) => return (400, json!({ "error": e.to_string() })),
+            Err(e @ HipError::NoVideo(_)) => return (404, json!({ "error": e.to_string() })),
+        },
+        _ => return (404, json!({ "error": "not found" })),
+    };
+
+    match remote.send(command) {
+        Ok(()) => (202, json!({ "ok": true })),
+        Err(e) => (503, json!({ "error": e })),
+    }
+}
+
+/// Kiosks found over mDNS; always empty in builds without it.
+#[cfg(feature = "mdns")]
+fn kiosks() -> Value {
+    json!(crate::mdns::peers())
+}
+
+#[cfg(not(feature = "mdns"))]
+fn kiosks() -> Value {
+    json!([])
+}
+
+/// 200 while the UI loop keeps beating, 503 once it has hung.
+fn healthz() -> (u16, Value) {
+    let healthy = crate::heartbeat::healthy();
+    let age = crate::heartbeat::since_last_beat();
+    let body = json!({
+        "healthy": healthy,
+        "seconds_since_heartbeat": age.map(|age| age.as_secs_f64()),
+    });
+    (if healthy { 200 } else { 503 }, body)
+}
+
+/// Everything a Bitfocus Companion module needs to build its actions,
+/// feedbacks and one press-to-play preset per hip.
+fn companion(remote: &Remote) -> Value {
+    let status = remote.status();
+    let presets: Vec<Value> = status
+        .hips
+        .iter()
+        .map(|hip| {
+            json!({
+                "hip": hip,
+                "label": status.labels.get(hip).unwrap_or(hip),
+                "press": { "method": "POST", "path": format!("/api/play/{}", hip) },
+                "feedback": format!("/api/hip/{}", hip),
+            })
+        })
+        .collect();
+    let action = |id: &str, path: &str| json!({ "id": id, "method": "POST", "path": path });
+    json!({
+        "product": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "api_version": COMPANION_API_VERSION,
+        "actions": [
+            action("play", "/api/play/{hip}"),
+            action("pause", "/api/pause"),
+            action("resume", "/api/resume"),
+            action("next", "/api/next"),
+            action("previous", "/api/previous"),
+            action("undo", "/api/undo"),
+            action("blackout_on", "/api/blackout/on"),
+            action("blackout_off", "/api/blackout/off"),
+            action("rescan", "/api/rescan"),
+        ],
+        "feedback": { "status": "/api/status", "events": "/api/events" },
+        "presets": presets,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc;
+    use summit_hip_core::control::{Endpoint, Status};
+
+    #[test]
+    fn test_route_commands() {
+        let endpoint = Endpoint::default();
+        let remote = endpoint.remote();
+        endpoint.publish(Status {
+            hips: vec!["001".to_string(), "042".to_string()],
+            rings: vec!["Ring A".to_string(), "Ring B".to_string()],
+            ..Status::default()
+        });
+
+        assert_eq!(route(&Method::Post, "/api/play/042", &remote).0, 202);
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::PlayHip("042".to_string()))
+        );
+        assert_eq!(route(&Method::Post, "/api/blackout/on", &remote).0, 202);
+        assert_eq!(endpoint.try_recv(), Some(Command::Blackout(true)));
+        assert_eq!(route(&Method::Post, "/api/next?from=panel", &remote).0, 202);
+        assert_eq!(endpoint.try_recv(), Some(Command::Next));
+        assert_eq!(route(&Method::Post, "/api/undo", &remote).0, 202);
+        assert_eq!(endpoint.try_recv(), Some(Command::Undo));
+        assert_eq!(route(&Method::Post, "/api/ring/ring%20b", &remote).0, 202);
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::SwitchRing("Ring B".to_string()))
+        );
+        assert_eq!(route(&Method::Post, "/api/ring/Ring%20C", &remote).0, 404);
+        assert_eq!(
+            route(&Method::Post, "/api/note/042?text=No+audio%21", &remote).0,
+            202
+        );
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::SetNote {
+                hip: "042".to_string(),
+                note: Some("No audio!".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn test_route_rejects_bad_requests() {
+        let endpoint = Endpoint::default();
+        let remote = endpoint.remote();
+        assert_eq!(route(&Method::Post, "/api/play/42", &remote).0, 400);
+        assert_eq!(route(&Method::Post, "/api/play/099", &remote).0, 404);
+        assert_eq!(route(&Method::Get, "/api/pause", &remote).0, 404);
+        assert_eq!(route(&Method::Post, "/api/blackout/maybe", &remote).0, 404);
+        assert_eq!(endpoint.try_recv(), None);
+    }
+
+    #[test]
+    fn test_status() {
+        let endpoint = Endpoint::default();
+        endpoint.publish(Status {
+            hip: Some("007".to_string()),
+            paused: true,
+            ..Status::default()
+        });
+        let (code, body) = route(&Method::Get, "/api/status", &endpoint.remote());
+        assert_eq!(code, 200);
+        assert_eq!(body["hip"], "007");
+        assert_eq!(body["paused"], true);
+    }
+
+    #[test]
+    fn test_companion_discovery_and_feedback() {
+        let endpoint = Endpoint::default();
+        let remote = endpoint.remote();
+        endpoint.publish(Status {
+            hip: Some("042".to_string()),
+            hips: vec!["001".to_string(), "042".to_string()],
+            labels: [("042".to_string(), "Smith Farms".to_string())].into(),
+            ..Status::default()
+        });
+
+        let (code, body) = route(&Method::Get, "/api/companion", &remote);
+        assert_eq!(code, 200);
+        assert_eq!(body["api_version"], COMPANION_API_VERSION);
+        assert_eq!(body["presets"][0]["label"], "001");
+        assert_eq!(body["presets"][1]["label"], "Smith Farms");
+        assert_eq!(body["presets"][1]["press"]["path"], "/api/play/042");
+
+        let (_, body) = route(&Method::Get, "/api/hip/042", &remote);
+        assert_eq!(
+            body,
+            json!({ "hip": "042", "available": true, "active": true })
+        );
+        let (_, body) = route(&Method::Get, "/api/hip/001", &remote);
+        assert_eq!(body["active"], false);
+        let (_, body) = route(&Method::Get, "/api/hip/099", &remote);
+        assert_eq!(body["available"], false);
+    }
+
+    #[test]
+    fn test_query_param() {
+        assert_eq!(
+            query_param("/api/events?x=1&token=a%2Bb%20c", "token").as_deref(),
+            Some("a+b c")
+        );
+        assert_eq!(query_param("/api/events", "token"), None);
+        assert_eq!(query_param("/api/events?tokens=x", "token"), None);
+    }
+
+    #[test]
+    fn test_event_stream() {
+        let endpoint = Endpoint::default();
+        let remote = endpoint.remote();
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap();
+        thread::spawn(move || serve(&server, &remote, Some("s3cret")));
+
+        let url = format!("ws://{}/api/events", addr);
+        let stream = TcpStream::connect(addr).unwrap();
+        assert!(tungstenite::client(url.as_str(), stream).is_err());
+
+        let mut request =
+            tungstenite::client::IntoClientRequest::into_client_request(url.as_str()).unwrap();
+        request
+            .headers_mut()
+            .insert("Authorization", "Bearer s3cret".parse().unwrap());
+        let stream = TcpStream::connect(addr).unwrap();
+        let (mut socket, _) = tungstenite::client(request, stream).unwrap();
+
+        endpoint.emit(Event::HipEntered {
+            hip: "042".to_string(),
+        });
+        let message = socket.read().unwrap();
+        assert_eq!(
+            message.into_text().unwrap().as_str(),
+            r#"{"event":"hip_entered","hip":"042"}"#
+        );
+    }
+
+    #[test]
+    fn test_event_stream_answers_and_closes() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (_sender, events) = mpsc::channel();
+        let forwarding = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let socket = tungstenite::accept(stream).unwrap();
+            forward_events(socket, events, Duration::from_millis(50));
+        });
+
+        let url = format!("ws://{}/api/events", addr);
+        let stream = TcpStream::connect(addr).unwrap();
+        let (mut socket, _) = tungstenite::client(url.as_str(), stream).unwrap();
+        socket.send(Message::Ping(b"hello".to_vec())).unwrap();
+        loop {
+            match socket.read().unwrap() {
+                Message::Pong(data) => {
+                    assert_eq!(data, b"hello");
+                    break;
+                }
+                Message::Ping(_) => {}
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+
+        socket.close(None).unwrap();
+        while socket.read().is_ok() {}
+        forwarding.join().unwrap();
+    }
+}
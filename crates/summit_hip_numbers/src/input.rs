@@ -0,0 +1,411 @@
+//! The hip number keypad. The window reads its keys into a [`FrameInput`]
+//! each frame and [`HipInput::handle`] turns them into [`Action`]s, so what a
+//! key press does can be tested without a window.
+
+use summit_hip_core::review::Verdict;
+
+/// The keys of one frame that the player cares about.
+#[derive(Debug, Default)]
+pub struct FrameInput {
+    /// Typed text, in order.
+    pub text: Vec<String>,
+    pub enter: bool,
+    /// Shift held with Enter, to restart the hip even if it just started.
+    pub force: bool,
+    /// Up or right arrow.
+    pub forward: bool,
+    /// Down or left arrow.
+    pub backward: bool,
+    /// Backspace.
+    pub undo: bool,
+    /// F2.
+    pub toggle_browser: bool,
+    /// F3.
+    pub toggle_perf_hud: bool,
+    /// F4.
+    pub start_countdown: bool,
+    /// F5.
+    pub test_pattern: bool,
+    /// F6.
+    pub next_ring: bool,
+    /// Presses of + less presses of -.
+    pub trim: i32,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    /// Play the hip typed in.
+    Submit(String),
+    /// Play the hip typed in from the start, even if it was just entered.
+    Restart(String),
+    Forward,
+    Backward,
+    /// Go back to the hip playing before the last switch.
+    Undo,
+    ToggleBrowser,
+    TogglePerfHud,
+    /// Start the lot countdown again.
+    StartCountdown,
+    /// Go on to the next test pattern, or back to the player after the last.
+    NextTestPattern,
+    /// Switch to the next ring of `[[rings]]`, after the last the first.
+    NextRing,
+    /// Turn the hip playing up or down by this many trim steps.
+    Trim(i32),
+}
+
+/// What a key does during the `--review`.
+#[derive(Debug, PartialEq)]
+pub enum ReviewAction {
+    /// Mark the hip on screen and go on to the next.
+    Mark(Verdict),
+    /// Play the hip on screen again.
+    Replay,
+    Next,
+    Previous,
+}
+
+/// The review's keys in a frame: O or Enter for OK, F for needs fix, R to
+/// replay, and the arrows to move without marking.
+pub fn review_actions(input: &FrameInput) -> Vec<ReviewAction> {
+    let mut actions: Vec<ReviewAction> = input
+        .text
+        .iter()
+        .filter_map(|text| match text.to_lowercase().as_str() {
+            "o" => Some(ReviewAction::Mark(Verdict::Ok)),
+            "f" => Some(ReviewAction::Mark(Verdict::NeedsFix)),
+            "r" => Some(ReviewAction::Replay),
+            _ => None,
+        })
+        .collect();
+    if input.enter {
+        actions.push(ReviewAction::Mark(Verdict::Ok));
+    }
+    if input.forward {
+        actions.push(ReviewAction::Next);
+    } else if input.backward {
+        actions.push(ReviewAction::Previous);
+    }
+    actions
+}
+
+/// How often a fading overlay is redrawn, in seconds.
+const FADE_FRAME: f64 = 1.0 / 30.0;
+
+/// The digits typed so far, and how long to show them as rejected.
+#[derive(Debug, Default)]
+pub struct HipInput {
+    pub buffer: String,
+    invalid_timer: f64,
+    /// The number for the overlay: the digits typed, or the last entered.
+    echo: String,
+    /// Seconds since `echo` last changed.
+    echo_age: f64,
+}
+
+impl HipInput {
+    /// Apply a frame's keys. Digits are added up to `max_length`; Enter
+    /// submits and clears them. The arrows navigate only with `arrow_nav` and
+    /// nothing typed; Backspace undoes the last switch with nothing typed,
+    /// otherwise it is editing.
+    pub fn handle(
+        &mut self,
+        input: &FrameInput,
+        max_length: usize,
+        arrow_nav: bool,
+    ) -> Vec<Action> {
+        let mut actions = Vec::new();
+        for text in &input.text {
+            if self.buffer.len() < max_length && text.chars().all(|c| c.is_ascii_digit()) {
+                self.buffer.push_str(text);
+                self.set_echo(self.buffer.clone());
+            }
+        }
+
+        if input.enter && !self.buffer.is_empty() {
+            // The overlay keeps the number entered until it fades
+            self.set_echo(self.buffer.clone());
+            let hip = std::mem::take(&mut self.buffer);
+            actions.push(if input.force {
+                Action::Restart(hip)
+            } else {
+                Action::Submit(hip)
+            });
+        }
+
+        if arrow_nav && self.buffer.is_empty() {
+            if input.forward {
+                actions.push(Action::Forward);
+            } else if input.backward {
+                actions.push(Action::Backward);
+            }
+        }
+        if input.undo && self.buffer.is_empty() {
+            actions.push(Action::Undo);
+        }
+
+        if input.toggle_browser {
+            actions.push(Action::ToggleBrowser);
+        }
+        if input.toggle_perf_hud {
+            actions.push(Action::TogglePerfHud);
+        }
+        if input.start_countdown {
+            actions.push(Action::StartCountdown);
+        }
+        if input.test_pattern {
+            actions.push(Action::NextTestPattern);
+        }
+        if input.next_ring {
+            actions.push(Action::NextRing);
+        }
+        if input.trim != 0 {
+            actions.push(Action::Trim(input.trim));
+        }
+        actions
+    }
+
+    /// Replace the digits with what was edited in the input field, dropping
+    /// anything else.
+    pub fn set_text(&mut self, text: &str, max_length: usize) {
+        let buffer: String = text
+            .chars()
+            .filter(|c| c.is_ascii_digit())
+            .take(max_length)
+            .collect();
+        if buffer != self.buffer {
+            self.set_echo(buffer.clone());
+            self.buffer = buffer;
+        }
+    }
+
+    fn set_echo(&mut self, echo: String) {
+        self.echo = echo;
+        self.echo_age = 0.0;
+    }
+
+    /// The number to show over the video and its opacity: fully there for
+    /// `hold` seconds after it last changed, then fading out over `fade`.
+    pub fn echo(&self, hold: f64, fade: f64) -> Option<(&str, f32)> {
+        if self.echo.is_empty() {
+            return None;
+        }
+        let fading = self.echo_age - hold;
+        if fading < 0.0 {
+            Some((&self.echo, 1.0))
+        } else if fading < fade {
+            Some((&self.echo, (1.0 - fading / fade) as f32))
+        } else {
+            None
+        }
+    }
+
+    /// Seconds until the overlay next changes, while there is one.
+    pub fn echo_changes_in(&self, hold: f64, fade: f64) -> Option<f64> {
+        let (_, opacity) = self.echo(hold, fade)?;
+        Some(if opacity < 1.0 {
+            FADE_FRAME
+        } else {
+            hold - self.echo_age
+        })
+    }
+
+    /// Show the input as rejected for `seconds`.
+    pub fn reject(&mut self, seconds: f64) {
+        self.invalid_timer = seconds;
+    }
+
+    pub fn is_rejected(&self) -> bool {
+        self.invalid_timer > 0.0
+    }
+
+    /// Seconds until the input stops showing as rejected.
+    pub fn rejected_for(&self) -> f64 {
+        self.invalid_timer.max(0.0)
+    }
+
+    pub fn tick(&mut self, dt: f64) {
+        self.echo_age += dt;
+        if self.invalid_timer > 0.0 {
+            self.invalid_timer -= dt;
+        } else if self.invalid_timer < 0.0 {
+            self.invalid_timer = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(text: &[&str]) -> FrameInput {
+        FrameInput {
+            text: text.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_digits_up_to_max_length() {
+        let mut input = HipInput::default();
+        assert!(input
+            .handle(&typed(&["1", "a", "23", "4"]), 3, true)
+            .is_empty());
+        assert_eq!(input.buffer, "123");
+    }
+
+    #[test]
+    fn test_enter_submits_and_clears() {
+        let mut input = HipInput::default();
+        let mut frame = typed(&["0", "0", "7"]);
+        frame.enter = true;
+        assert_eq!(
+            input.handle(&frame, 3, true),
+            vec![Action::Submit("007".to_string())]
+        );
+        assert!(input.buffer.is_empty());
+
+        let mut frame = typed(&["0", "0", "7"]);
+        frame.enter = true;
+        frame.force = true;
+        assert_eq!(
+            input.handle(&frame, 3, true),
+            vec![Action::Restart("007".to_string())]
+        );
+
+        // Nothing typed: Enter does nothing
+        let frame = FrameInput {
+            enter: true,
+            ..Default::default()
+        };
+        assert!(input.handle(&frame, 3, true).is_empty());
+    }
+
+    #[test]
+    fn test_arrows_only_with_nothing_typed() {
+        let mut input = HipInput::default();
+        let mut frame = FrameInput {
+            forward: true,
+            backward: true,
+            ..Default::default()
+        };
+        assert_eq!(input.handle(&frame, 3, true), vec![Action::Forward]);
+        assert!(input.handle(&frame, 3, false).is_empty());
+
+        frame.text = vec!["1".to_string()];
+        assert!(input.handle(&frame, 3, true).is_empty());
+        frame.text.clear();
+        frame.forward = false;
+        frame.toggle_perf_hud = true;
+        assert_eq!(input.handle(&frame, 3, true), vec![Action::TogglePerfHud]);
+        input.buffer.clear();
+        frame.toggle_browser = true;
+        frame.start_countdown = true;
+        frame.test_pattern = true;
+        frame.next_ring = true;
+        assert_eq!(
+            input.handle(&frame, 3, true),
+            vec![
+                Action::Backward,
+                Action::ToggleBrowser,
+                Action::TogglePerfHud,
+                Action::StartCountdown,
+                Action::NextTestPattern,
+                Action::NextRing
+            ]
+        );
+    }
+
+    #[test]
+    fn test_backspace_undoes_with_nothing_typed() {
+        let mut input = HipInput::default();
+        let mut frame = FrameInput {
+            undo: true,
+            ..Default::default()
+        };
+        assert_eq!(input.handle(&frame, 3, false), vec![Action::Undo]);
+        input.buffer = "04".to_string();
+        assert!(input.handle(&frame, 3, false).is_empty());
+        frame.undo = false;
+        assert!(input.handle(&frame, 3, false).is_empty());
+    }
+
+    #[test]
+    fn test_set_text_and_rejection() {
+        let mut input = HipInput::default();
+        input.set_text("1x2345", 3);
+        assert_eq!(input.buffer, "123");
+
+        input.reject(0.5);
+        assert!(input.is_rejected());
+        input.tick(0.3);
+        assert!(input.is_rejected());
+        assert!((input.rejected_for() - 0.2).abs() < 1e-9);
+        input.tick(0.3);
+        assert!(!input.is_rejected());
+        assert_eq!(input.rejected_for(), 0.0);
+        input.tick(0.1);
+        assert!(!input.is_rejected());
+    }
+
+    #[test]
+    fn test_echo() {
+        let mut input = HipInput::default();
+        assert_eq!(input.echo(2.0, 0.5), None);
+        input.handle(&typed(&["0", "4"]), 3, false);
+        assert_eq!(input.echo(2.0, 0.5), Some(("04", 1.0)));
+
+        // Still there after Enter, then fading
+        input.tick(1.5);
+        let mut frame = typed(&["2"]);
+        frame.enter = true;
+        input.handle(&frame, 3, false);
+        assert_eq!(input.buffer, "");
+        assert_eq!(input.echo(2.0, 0.5), Some(("042", 1.0)));
+        // The input field hands back the cleared buffer every frame
+        input.set_text("", 3);
+        assert_eq!(input.echo_changes_in(2.0, 0.5), Some(2.0));
+        input.tick(2.25);
+        let (_, opacity) = input.echo(2.0, 0.5).unwrap();
+        assert!((opacity - 0.5).abs() < 1e-6);
+        assert_eq!(input.echo_changes_in(2.0, 0.5), Some(FADE_FRAME));
+        input.tick(0.25);
+        assert_eq!(input.echo(2.0, 0.5), None);
+
+        // Editing in the field shows too
+        input.set_text("7", 3);
+        assert_eq!(input.echo(2.0, 0.0), Some(("7", 1.0)));
+        input.tick(2.0);
+        assert_eq!(input.echo(2.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_trim_keys() {
+        let mut input = HipInput::default();
+        let frame = FrameInput {
+            text: vec!["4".to_string(), "+".to_string()],
+            trim: 1,
+            ..Default::default()
+        };
+        assert_eq!(input.handle(&frame, 3, true), vec![Action::Trim(1)]);
+        assert_eq!(input.buffer, "4");
+    }
+
+    #[test]
+    fn test_review_keys() {
+        let mut frame = typed(&["O", "7", "f", "r"]);
+        frame.enter = true;
+        frame.backward = true;
+        assert_eq!(
+            review_actions(&frame),
+            vec![
+                ReviewAction::Mark(Verdict::Ok),
+                ReviewAction::Mark(Verdict::NeedsFix),
+                ReviewAction::Replay,
+                ReviewAction::Mark(Verdict::Ok),
+                ReviewAction::Previous,
+            ]
+        );
+        assert!(review_actions(&FrameInput::default()).is_empty());
+    }
+}
@@ -0,0 +1,81 @@
+//! `[logging] format = "json"`: one JSON object per log line, for Loki, ELK
+//! and other collectors that parse fields rather than text.
+//!
+//! Besides the record itself, each line carries the hip and file playing
+//! when it was logged, so a venue's IT can filter by hip.
+
+use serde::Serialize;
+use std::fmt;
+use std::sync::Mutex;
+
+/// What was playing, as `(hip, file)`.
+static PLAYING: Mutex<Option<(String, String)>> = Mutex::new(None);
+
+/// Tag later log lines with the video now playing.
+pub fn set_playing(hip: &str, file: &str) {
+    *PLAYING.lock().unwrap() = Some((hip.to_string(), file.to_string()));
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    timestamp: String,
+    level: String,
+    target: &'a str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hip: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+}
+
+/// Format a record as a line of JSON, for `fern::Dispatch::format`.
+pub fn format(out: fern::FormatCallback, message: &fmt::Arguments, record: &log::Record) {
+    let playing = PLAYING.lock().unwrap().clone();
+    let (hip, file) = playing.unzip();
+    let line = Line {
+        timestamp: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+        level: record.level().to_string(),
+        target: record.target(),
+        message: message.to_string(),
+        hip,
+        file,
+    };
+    let json = serde_json::to_string(&line).unwrap_or_default();
+    out.finish(format_args!("{}", json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_line() {
+        let output = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let sink = output.clone();
+        let (level, logger) = fern::Dispatch::new()
+            .format(format)
+            .chain(fern::Output::call(move |record| {
+                sink.lock().unwrap().push(record.args().to_string())
+            }))
+            .into_log();
+        assert_eq!(level, log::LevelFilter::Trace);
+
+        set_playing("042", "042.mp4");
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("Loading \"042\""))
+                .level(log::Level::Info)
+                .target("summit_hip_numbers")
+                .build(),
+        );
+
+        let lines = output.lock().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(json["level"], "INFO");
+        assert_eq!(json["target"], "summit_hip_numbers");
+        assert_eq!(json["message"], "Loading \"042\"");
+        assert_eq!(json["hip"], "042");
+        assert_eq!(json["file"], "042.mp4");
+        assert!(json["timestamp"].as_str().unwrap().contains('T'));
+    }
+}
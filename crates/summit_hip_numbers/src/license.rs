@@ -0,0 +1,583 @@
+//! Runtime licensing. One binary serves both customers and evaluators: a
+//! signed `license.toml` unlocks full mode. Without one the player runs a
+//! [`crate::trial`] and then the demo limits (watermark, five hips,
+//! five-minute timeout).
+//!
+//! Licenses are signed with a key made by `--gen-signing-key` and issued with
+//! `--issue-license`, usually for one machine ID (see [`crate::fingerprint`])
+//! so the file only works on the kiosk it was activated on. Checking it is
+//! entirely offline. Each license names a [`Tier`], which decides the
+//! [`Capability`]s the player turns on. Release builds bake the matching public key in with
+//! `SHN_LICENSE_PUBLIC_KEY=<hex> cargo build`; builds without it are
+//! development builds and are never limited.
+
+use crate::{fingerprint, lock, trial};
+use chrono::NaiveDate;
+use ed25519_dalek::{Signature, Signer, Verifier, VerifyingKey};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use summit_hip_core::config::{self, Config, KioskStyle, Seconds};
+
+/// Public key that license files must be signed with. CI sets it from a
+/// secret, which is empty on forks, so empty counts as unset.
+const BUILD_PUBLIC_KEY: Option<&str> = option_env!("SHN_LICENSE_PUBLIC_KEY");
+
+pub const LICENSE_FILE_NAME: &str = "license.toml";
+
+static STATUS: OnceLock<Status> = OnceLock::new();
+
+/// Set by `--demo`.
+static FORCE_DEMO: AtomicBool = AtomicBool::new(false);
+
+/// How to buy a license, shown when the demo time runs out. Resellers set
+/// their own with `SHN_PURCHASE_INFO` at build time.
+pub const PURCHASE_INFO: &str = match option_env!("SHN_PURCHASE_INFO") {
+    Some(info) if !info.is_empty() => info,
+    _ => "To buy a license, contact your Summit Hip Numbers dealer with the machine ID below.",
+};
+
+/// What a license is sold as.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+    /// Playback and the play reports.
+    Standard,
+    /// Everything, including remote control and multi-kiosk features.
+    Pro,
+}
+
+impl Tier {
+    pub fn allows(self, capability: Capability) -> bool {
+        match self {
+            Tier::Pro => true,
+            Tier::Standard => capability == Capability::Analytics,
+        }
+    }
+}
+
+impl fmt::Display for Tier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Tier::Standard => "standard",
+            Tier::Pro => "pro",
+        })
+    }
+}
+
+impl FromStr for Tier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "standard" => Ok(Tier::Standard),
+            "pro" => Ok(Tier::Pro),
+            _ => Err(format!("'{}' is not a tier (standard or pro)", s)),
+        }
+    }
+}
+
+/// Features that depend on the license tier, checked where they start.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Capability {
+    /// The HTTP API, TCP, serial, MQTT and OSC control, and mDNS.
+    RemoteControl,
+    /// Webhooks and auction management polling.
+    #[cfg_attr(not(any(feature = "webhooks", feature = "auction")), allow(dead_code))]
+    Integrations,
+    /// Leader/follower sync between kiosks.
+    Sync,
+    /// Play analytics and reports.
+    Analytics,
+}
+
+impl Capability {
+    /// The cheapest tier that has it.
+    pub fn tier(self) -> Tier {
+        if Tier::Standard.allows(self) {
+            Tier::Standard
+        } else {
+            Tier::Pro
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct License {
+    pub licensee: String,
+    /// YYYY-MM-DD.
+    pub issued: String,
+    /// YYYY-MM-DD, the last day the license is valid; forever if unset.
+    pub expires: Option<String>,
+    /// The only machine ID the license is valid on; any machine if unset.
+    pub machine: Option<String>,
+    /// Pro if unset, as licenses issued before tiers were.
+    pub tier: Option<Tier>,
+    /// Hex Ed25519 signature of [`License::payload`].
+    pub signature: String,
+}
+
+impl License {
+    /// The signed text. Everything but the signature, one field per line, so
+    /// no field can be moved into another.
+    fn payload(&self) -> String {
+        let mut payload = format!(
+            "summit_hip_numbers license\nlicensee={}\nissued={}\nexpires={}\n",
+            self.licensee,
+            self.issued,
+            self.expires.as_deref().unwrap_or("never")
+        );
+        if let Some(machine) = &self.machine {
+            payload.push_str(&format!("machine={}\n", machine));
+        }
+        if let Some(tier) = self.tier {
+            payload.push_str(&format!("tier={}\n", tier));
+        }
+        payload
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Status {
+    /// A development build without a license key: nothing is limited.
+    Unenforced,
+    Licensed(License),
+    /// No license file yet; nothing is limited for the rest of the trial.
+    Trial {
+        days_left: u32,
+    },
+    /// Running with the demo limits, and why.
+    Demo(String),
+}
+
+impl Status {
+    pub fn is_demo(&self) -> bool {
+        matches!(self, Status::Demo(_))
+    }
+
+    /// The tier in effect; none in demo mode.
+    pub fn tier(&self) -> Option<Tier> {
+        match self {
+            Status::Licensed(license) => Some(license.tier.unwrap_or(Tier::Pro)),
+            Status::Unenforced | Status::Trial { .. } => Some(Tier::Pro),
+            Status::Demo(_) => None,
+        }
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        self.tier().is_some_and(|tier| tier.allows(capability))
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Status::Unenforced => "development build, not licensed".to_string(),
+            Status::Licensed(license) => {
+                let tier = license.tier.unwrap_or(Tier::Pro);
+                match &license.expires {
+                    Some(expires) => format!(
+                        "licensed to {} ({}) until {}",
+                        license.licensee, tier, expires
+                    ),
+                    None => format!("licensed to {} ({})", license.licensee, tier),
+                }
+            }
+            Status::Trial { days_left: 1 } => "trial, last day".to_string(),
+            Status::Trial { days_left } => format!("trial, {} days left", days_left),
+            Status::Demo(reason) => format!("demo mode ({})", reason),
+        }
+    }
+}
+
+/// Locations checked for a license file: the per-user config folder, then
+/// next to the executable.
+pub fn search_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(user) = config::user_config_path() {
+        paths.push(user.with_file_name(LICENSE_FILE_NAME));
+    }
+    paths.push(config::exe_dir().join(LICENSE_FILE_NAME));
+    paths
+}
+
+/// Where an activated license is installed: next to the config file the
+/// GUI saves to.
+pub fn install_path() -> PathBuf {
+    config::save_path(None).with_file_name(LICENSE_FILE_NAME)
+}
+
+fn build_public_key() -> Option<&'static str> {
+    BUILD_PUBLIC_KEY.filter(|key| !key.trim().is_empty())
+}
+
+fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+/// Run with the demo limits whatever the license, for `--demo`. Must come
+/// before the first [`status`].
+pub fn force_demo() {
+    FORCE_DEMO.store(true, Ordering::Relaxed);
+}
+
+/// The license status of this run, checked once.
+pub fn status() -> &'static Status {
+    STATUS.get_or_init(|| {
+        if FORCE_DEMO.load(Ordering::Relaxed) {
+            return Status::Demo("started with --demo".to_string());
+        }
+        let public_key = build_public_key();
+        match fingerprint::machine_id() {
+            Ok(machine) => check(
+                public_key,
+                &search_paths(),
+                &trial::paths(),
+                today(),
+                machine,
+            ),
+            // Nothing to bind a license or a trial to
+            Err(reason) if public_key.is_some() => Status::Demo(reason.to_string()),
+            Err(_) => Status::Unenforced,
+        }
+    })
+}
+
+fn check(
+    public_key: Option<&str>,
+    paths: &[PathBuf],
+    trial_paths: &[PathBuf],
+    today: NaiveDate,
+    machine: &str,
+) -> Status {
+    let Some(public_key) = public_key else {
+        return Status::Unenforced;
+    };
+    let key = match lock::parse_public_key(public_key) {
+        Ok(key) => key,
+        Err(e) => return Status::Demo(format!("build license key: {}", e)),
+    };
+    let Some(path) = paths.iter().find(|p| p.is_file()) else {
+        return match trial::check(trial_paths, today, machine) {
+            Ok(days_left) => Status::Trial { days_left },
+            Err(reason) => Status::Demo(reason),
+        };
+    };
+    match verify(path, &key, today, machine) {
+        Ok(license) => Status::Licensed(license),
+        Err(reason) => Status::Demo(reason),
+    }
+}
+
+/// Whether a feature enabled in the config (in `[section]`) may start. Logs
+/// why not if the license doesn't allow it.
+pub fn permits(enabled: bool, section: &str, capability: Capability) -> bool {
+    if enabled && !status().allows(capability) {
+        warn!(
+            "[{}] needs a {} license and stays off ({})",
+            section,
+            capability.tier(),
+            status().describe()
+        );
+        return false;
+    }
+    enabled
+}
+
+/// Check the license file at `src` for this machine and install it. It takes
+/// effect the next time the player starts.
+pub fn activate(src: &Path) -> Result<PathBuf, String> {
+    let key =
+        lock::parse_public_key(build_public_key().ok_or("this build doesn't need a license")?)?;
+    let dest = install_path();
+    install(&key, src, &dest, today(), fingerprint::machine_id()?)?;
+    Ok(dest)
+}
+
+fn install(
+    key: &VerifyingKey,
+    src: &Path,
+    dest: &Path,
+    today: NaiveDate,
+    machine: &str,
+) -> Result<License, String> {
+    let license = verify(src, key, today, machine)?;
+    if let Some(dir) = dest.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    fs::copy(src, dest).map_err(|e| format!("{}: {}", dest.display(), e))?;
+    Ok(license)
+}
+
+fn verify(
+    path: &Path,
+    key: &VerifyingKey,
+    today: NaiveDate,
+    machine: &str,
+) -> Result<License, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let license: License =
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let signature: [u8; 64] = hex::decode(license.signature.trim())
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| format!("{} has a corrupt signature", path.display()))?;
+    key.verify(
+        license.payload().as_bytes(),
+        &Signature::from_bytes(&signature),
+    )
+    .map_err(|_| format!("{} is not a valid license", path.display()))?;
+    if let Some(licensed) = &license.machine {
+        if !licensed.eq_ignore_ascii_case(machine) {
+            return Err(format!(
+                "the license is for machine {}, this is {}",
+                licensed, machine
+            ));
+        }
+    }
+    if let Some(expires) = &license.expires {
+        if today > parse_date(expires)? {
+            return Err(format!("the license expired on {}", expires));
+        }
+    }
+    Ok(license)
+}
+
+fn parse_date(text: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map_err(|_| format!("'{}' is not a YYYY-MM-DD date", text))
+}
+
+/// Sign a `tier` license for `licensee`, valid on `machine` only if given,
+/// with the key in `key_path` and write it to `out`. Returns the public key
+/// the player must be built with.
+pub fn issue(
+    key_path: &Path,
+    licensee: &str,
+    tier: Tier,
+    expires: Option<&str>,
+    machine: Option<&str>,
+    out: &Path,
+) -> Result<String, String> {
+    if out.exists() {
+        return Err(format!("{} already exists", out.display()));
+    }
+    if let Some(expires) = expires {
+        parse_date(expires)?;
+    }
+    let key = lock::read_signing_key(key_path)?;
+    let mut license = License {
+        licensee: licensee.to_string(),
+        issued: chrono::Local::now().date_naive().to_string(),
+        expires: expires.map(str::to_string),
+        machine: machine.map(|m| m.trim().to_ascii_uppercase()),
+        tier: Some(tier),
+        signature: String::new(),
+    };
+    license.signature = hex::encode(key.sign(license.payload().as_bytes()).to_bytes());
+    let text = toml::to_string(&license).map_err(|e| e.to_string())?;
+    fs::write(out, text).map_err(|e| format!("{}: {}", out.display(), e))?;
+    Ok(hex::encode(key.verifying_key().to_bytes()))
+}
+
+/// Replace the settings an evaluator could use to get around the demo.
+pub fn apply_demo_limits(config: &mut Config) {
+    config.video.directory = "./videos".to_string();
+    config.rings.clear();
+    config.demo.timeout_seconds = 300;
+    config.demo.max_videos = 5;
+    config.demo.hip_number_limit = 5;
+    config.ui.window_width = 1920.0;
+    config.ui.window_height = 1080.0;
+    config.ui.kiosk_mode = true;
+    config.ui.kiosk_style = Some(KioskStyle::Fullscreen);
+    config.ui.enable_arrow_nav = true;
+    config.splash.enabled = true;
+    config.splash.duration_seconds = Seconds::clamped(3.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const MACHINE: &str = "7F3A-09C2-D415-88E0";
+
+    fn day(text: &str) -> NaiveDate {
+        parse_date(text).unwrap()
+    }
+
+    #[test]
+    fn test_issue_and_check() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("vendor.key");
+        let public = lock::generate_key(&key_path).unwrap();
+        let license_path = temp_dir.path().join(LICENSE_FILE_NAME);
+        let paths = [temp_dir.path().join("missing.toml"), license_path.clone()];
+
+        assert_eq!(
+            check(Some(&public), &paths, &[], day("2026-01-01"), MACHINE),
+            Status::Trial {
+                days_left: trial::TRIAL_DAYS
+            }
+        );
+        assert_eq!(
+            issue(
+                &key_path,
+                "Summit Sales",
+                Tier::Pro,
+                Some("2026-12-31"),
+                None,
+                &license_path
+            ),
+            Ok(public.clone())
+        );
+        assert!(issue(
+            &key_path,
+            "Summit Sales",
+            Tier::Pro,
+            None,
+            None,
+            &license_path
+        )
+        .is_err());
+
+        let status = check(Some(&public), &paths, &[], day("2026-12-31"), MACHINE);
+        assert!(!status.is_demo());
+        assert_eq!(
+            status.describe(),
+            "licensed to Summit Sales (pro) until 2026-12-31"
+        );
+        assert!(matches!(
+            check(Some(&public), &paths, &[], day("2027-01-01"), MACHINE),
+            Status::Demo(reason) if reason.contains("expired")
+        ));
+        assert_eq!(
+            check(None, &paths, &[], day("2027-01-01"), MACHINE),
+            Status::Unenforced
+        );
+    }
+
+    #[test]
+    fn test_tampered_license() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("vendor.key");
+        let public = lock::generate_key(&key_path).unwrap();
+        let license_path = temp_dir.path().join(LICENSE_FILE_NAME);
+        issue(
+            &key_path,
+            "Summit Sales",
+            Tier::Pro,
+            Some("2026-12-31"),
+            None,
+            &license_path,
+        )
+        .unwrap();
+        let paths = [license_path.clone()];
+
+        let text = fs::read_to_string(&license_path).unwrap();
+        fs::write(&license_path, text.replace("2026-12-31", "2099-12-31")).unwrap();
+        assert!(matches!(
+            check(Some(&public), &paths, &[], day("2026-01-01"), MACHINE),
+            Status::Demo(reason) if reason.contains("not a valid license")
+        ));
+
+        let other_key = temp_dir.path().join("other.key");
+        let other_public = lock::generate_key(&other_key).unwrap();
+        assert!(check(Some(&other_public), &paths, &[], day("2026-01-01"), MACHINE).is_demo());
+    }
+
+    #[test]
+    fn test_machine_bound_license() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("vendor.key");
+        let public = lock::generate_key(&key_path).unwrap();
+        let key = lock::parse_public_key(&public).unwrap();
+        let src = temp_dir.path().join("download.toml");
+        issue(
+            &key_path,
+            "Summit Sales",
+            Tier::Pro,
+            None,
+            Some("7f3a-09c2-d415-88e0"),
+            &src,
+        )
+        .unwrap();
+
+        let dest = temp_dir.path().join("config").join(LICENSE_FILE_NAME);
+        let error =
+            install(&key, &src, &dest, day("2026-01-01"), "0000-0000-0000-0000").unwrap_err();
+        assert!(
+            error.contains("for machine 7F3A-09C2-D415-88E0"),
+            "{}",
+            error
+        );
+        assert!(!dest.exists());
+
+        let license = install(&key, &src, &dest, day("2026-01-01"), MACHINE).unwrap();
+        let paths = [dest.clone()];
+        assert_eq!(license.machine.as_deref(), Some(MACHINE));
+        assert!(!check(Some(&public), &paths, &[], day("2026-01-01"), MACHINE).is_demo());
+
+        // The machine line is signed too
+        let text = fs::read_to_string(&dest).unwrap();
+        fs::write(&dest, text.replace(MACHINE, "0000-0000-0000-0000")).unwrap();
+        assert!(check(
+            Some(&public),
+            &[dest],
+            &[],
+            day("2026-01-01"),
+            "0000-0000-0000-0000"
+        )
+        .is_demo());
+    }
+
+    #[test]
+    fn test_tiers() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("vendor.key");
+        let public = lock::generate_key(&key_path).unwrap();
+        let paths = [temp_dir.path().join(LICENSE_FILE_NAME)];
+        issue(
+            &key_path,
+            "Summit Sales",
+            Tier::Standard,
+            None,
+            None,
+            &paths[0],
+        )
+        .unwrap();
+
+        let status = check(Some(&public), &paths, &[], day("2026-01-01"), MACHINE);
+        assert_eq!(status.tier(), Some(Tier::Standard));
+        assert!(status.allows(Capability::Analytics));
+        assert!(!status.allows(Capability::RemoteControl));
+        assert_eq!(Capability::Sync.tier(), Tier::Pro);
+
+        // The tier is signed
+        let text = fs::read_to_string(&paths[0]).unwrap();
+        fs::write(&paths[0], text.replace("standard", "pro")).unwrap();
+        let status = check(Some(&public), &paths, &[], day("2026-01-01"), MACHINE);
+        assert!(!status.allows(Capability::Analytics));
+
+        assert!(Status::Trial { days_left: 3 }.allows(Capability::RemoteControl));
+        assert_eq!("Pro".parse(), Ok(Tier::Pro));
+        assert!("gold".parse::<Tier>().is_err());
+    }
+
+    #[test]
+    fn test_demo_limits() {
+        let mut config = Config::default();
+        config.demo.hip_number_limit = 999;
+        config.ui.kiosk_mode = false;
+        config.ui.kiosk_style = Some(KioskStyle::Windowed);
+        apply_demo_limits(&mut config);
+        assert_eq!(config.demo.hip_number_limit, 5);
+        assert!(config.ui.kiosk_mode);
+        assert_eq!(config.ui.kiosk_style(), KioskStyle::Fullscreen);
+    }
+}
@@ -0,0 +1,216 @@
+//! Plain-text command protocol for AV control processors (Crestron, Extron and
+//! the like), one command per line:
+//!
+//! ```text
+//! PLAY 042        -> OK | ERR no video for hip 042
+//! PAUSE / RESUME  -> OK
+//! NEXT / PREVIOUS -> OK
+//! UNDO            -> OK
+//! BLACKOUT ON|OFF -> OK
+//! RESCAN          -> OK
+//! TESTPATTERN BARS|GRID|OFF -> OK
+//! RING Ring B     -> OK | ERR no ring called Ring C
+//! STATUS          -> STATUS hip=042 paused=0 blackout=0 splash=0 file=042_intro.mp4
+//! PING            -> PONG
+//! ```
+//!
+//! Commands are case-insensitive and lines may end in CR, LF or CRLF. Replies
+//! end in CRLF. A line longer than 256 bytes is answered with
+//! `ERR line too long` and ignored.
+
+use std::io::{self, Read, Write};
+use summit_hip_core::control::{Command, Remote, TestPattern};
+
+/// Answer one line of input; `None` for blank lines.
+pub fn respond(line: &str, remote: &Remote) -> Option<String> {
+    let mut words = line.split_whitespace();
+    let verb = words.next()?.to_ascii_uppercase();
+    if verb == "RING" {
+        // Ring names may have spaces in them
+        let name = words.collect::<Vec<_>>().join(" ");
+        let command = match remote.find_ring(&name) {
+            Some(ring) => Command::SwitchRing(ring),
+            None => return Some(format!("ERR no ring called {}", name)),
+        };
+        return Some(match remote.send(command) {
+            Ok(()) => "OK".to_string(),
+            Err(e) => format!("ERR {}", e),
+        });
+    }
+    let arg = words.next().map(str::to_ascii_uppercase);
+    if words.next().is_some() {
+        return Some("ERR too many arguments".to_string());
+    }
+
+    let command = match (verb.as_str(), arg.as_deref()) {
+        ("PING", None) => return Some("PONG".to_string()),
+        ("STATUS", None) => return Some(status_line(remote)),
+        ("PLAY", Some(hip)) => match remote.check_hip(hip) {
+            Ok(()) => Command::PlayHip(hip.to_string()),
+            Err(e) => return Some(format!("ERR {}", e)),
+        },
+        ("PAUSE", None) => Command::Pause,
+        ("RESUME", None) => Command::Resume,
+        ("NEXT", None) => Command::Next,
+        ("PREVIOUS" | "PREV", None) => Command::Previous,
+        ("UNDO", None) => Command::Undo,
+        ("BLACKOUT", Some("ON")) => Command::Blackout(true),
+        ("BLACKOUT", Some("OFF")) => Command::Blackout(false),
+        ("RESCAN", None) => Command::Rescan,
+        ("TESTPATTERN", Some("BARS")) => Command::TestPattern(Some(TestPattern::Bars)),
+        ("TESTPATTERN", Some("GRID")) => Command::TestPattern(Some(TestPattern::Grid)),
+        ("TESTPATTERN", Some("OFF")) => Command::TestPattern(None),
+        _ => return Some(format!("ERR unknown command: {}", line.trim())),
+    };
+    Some(match remote.send(command) {
+        Ok(()) => "OK".to_string(),
+        Err(e) => format!("ERR {}", e),
+    })
+}
+
+fn status_line(remote: &Remote) -> String {
+    let status = remote.status();
+    let flag = |on: bool| if on { 1 } else { 0 };
+    format!(
+        "STATUS hip={} paused={} blackout={} splash={} file={}",
+        status.hip.as_deref().unwrap_or("---"),
+        flag(status.paused),
+        flag(status.blackout),
+        flag(status.splash),
+        status.file.as_deref().unwrap_or("")
+    )
+}
+
+/// Longest line accepted; a longer one is dropped whole, up to its end.
+const MAX_LINE: usize = 256;
+
+/// Read commands from `reader` and write replies to `writer` until the input
+/// ends.
+pub fn serve<R: Read, W: Write>(reader: R, mut writer: W, remote: &Remote) -> io::Result<()> {
+    let mut line = Vec::new();
+    // Set from the byte past `MAX_LINE` until the line ends, so no part of
+    // it runs as a command
+    let mut discarding = false;
+    for byte in io::BufReader::new(reader).bytes() {
+        match byte? {
+            b'\r' | b'\n' => {
+                let reply = if discarding {
+                    discarding = false;
+                    Some("ERR line too long".to_string())
+                } else {
+                    let text = String::from_utf8_lossy(&line).into_owned();
+                    let reply = respond(&text, remote);
+                    if let Some(reply) = &reply {
+                        log::debug!("Control command '{}' -> '{}'", text.trim(), reply);
+                    }
+                    reply
+                };
+                line.clear();
+                if let Some(reply) = reply {
+                    writer.write_all(reply.as_bytes())?;
+                    writer.write_all(b"\r\n")?;
+                    writer.flush()?;
+                }
+            }
+            _ if discarding => {}
+            _ if line.len() >= MAX_LINE => {
+                line.clear();
+                discarding = true;
+            }
+            byte => line.push(byte),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use summit_hip_core::control::{Endpoint, Status};
+
+    fn endpoint_with_hips() -> Endpoint {
+        let endpoint = Endpoint::default();
+        endpoint.publish(Status {
+            hip: Some("001".to_string()),
+            file: Some("001 intro.mp4".to_string()),
+            paused: true,
+            hips: vec!["001".to_string(), "042".to_string()],
+            rings: vec!["Ring A".to_string(), "Ring B".to_string()],
+            ..Status::default()
+        });
+        endpoint
+    }
+
+    #[test]
+    fn test_respond() {
+        let endpoint = endpoint_with_hips();
+        let remote = endpoint.remote();
+
+        assert_eq!(respond("play 042", &remote).as_deref(), Some("OK"));
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::PlayHip("042".to_string()))
+        );
+        assert_eq!(respond("BLACKOUT on", &remote).as_deref(), Some("OK"));
+        assert_eq!(endpoint.try_recv(), Some(Command::Blackout(true)));
+        assert_eq!(respond("testpattern grid", &remote).as_deref(), Some("OK"));
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::TestPattern(Some(TestPattern::Grid)))
+        );
+        assert_eq!(respond("ring  ring b", &remote).as_deref(), Some("OK"));
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::SwitchRing("Ring B".to_string()))
+        );
+        assert_eq!(
+            respond("RING Ring C", &remote).as_deref(),
+            Some("ERR no ring called Ring C")
+        );
+        assert_eq!(respond("undo", &remote).as_deref(), Some("OK"));
+        assert_eq!(endpoint.try_recv(), Some(Command::Undo));
+        assert_eq!(
+            respond("STATUS", &remote).as_deref(),
+            Some("STATUS hip=001 paused=1 blackout=0 splash=0 file=001 intro.mp4")
+        );
+        assert_eq!(respond("   ", &remote), None);
+
+        assert_eq!(
+            respond("PLAY 099", &remote).as_deref(),
+            Some("ERR no video for hip 099")
+        );
+        assert_eq!(
+            respond("PLAY 42", &remote).as_deref(),
+            Some("ERR hip must be 3 digits")
+        );
+        assert!(respond("JUMP", &remote).unwrap().starts_with("ERR"));
+        assert!(respond("PAUSE NOW", &remote).unwrap().starts_with("ERR"));
+        assert_eq!(endpoint.try_recv(), None);
+    }
+
+    #[test]
+    fn test_serve_handles_any_line_ending() {
+        let endpoint = endpoint_with_hips();
+        let mut output = Vec::new();
+        serve(
+            &b"PING\rNEXT\r\n\nprev\n"[..],
+            &mut output,
+            &endpoint.remote(),
+        )
+        .unwrap();
+        assert_eq!(output, b"PONG\r\nOK\r\nOK\r\n");
+        assert_eq!(endpoint.try_recv(), Some(Command::Next));
+        assert_eq!(endpoint.try_recv(), Some(Command::Previous));
+    }
+
+    #[test]
+    fn test_serve_drops_overlong_lines() {
+        let endpoint = endpoint_with_hips();
+        let mut input = vec![b'x'; MAX_LINE];
+        input.extend_from_slice(b"NEXT\r\nPING\n");
+        let mut output = Vec::new();
+        serve(&input[..], &mut output, &endpoint.remote()).unwrap();
+        assert_eq!(output, b"ERR line too long\r\nPONG\r\n");
+        assert_eq!(endpoint.try_recv(), None);
+    }
+}
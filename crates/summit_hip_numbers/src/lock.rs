@@ -0,0 +1,358 @@
+//! Config signing and the configuration GUI PIN.
+//!
+//! An administrator generates a key pair with `--gen-signing-key`, signs the
+//! finished config with `--sign-config`, and gives the player the public key
+//! where whoever can edit `config.toml` can't change it: baked into the build
+//! with `SHN_CONFIG_PUBLIC_KEY`, or in `config_lock.toml` next to the
+//! executable on an install folder the kiosk user can't write to. At startup
+//! a locked player reads only the `config.toml` next to the executable, checks
+//! it against `config.toml.sig` and runs exactly what was checked, with no
+//! environment overrides; depending on the mode it warns or refuses to play.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use log::{error, warn};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::Sha256;
+use std::fs;
+use std::path::{Path, PathBuf};
+use summit_hip_core::config::{self, Config, LockMode};
+
+/// Public key baked in at build time (`SHN_CONFIG_PUBLIC_KEY=<hex> cargo build`).
+/// It takes precedence over `config_lock.toml`, and modified or unsigned
+/// configs are always refused.
+const BUILD_PUBLIC_KEY: Option<&str> = option_env!("SHN_CONFIG_PUBLIC_KEY");
+
+/// The lock, next to the executable.
+pub const TRUST_FILE_NAME: &str = "config_lock.toml";
+
+/// Prefix of stored PIN hashes, leaving room for other schemes later.
+const PIN_SCHEME: &str = "pbkdf2-sha256";
+
+/// PBKDF2 rounds for a new PIN hash; the count is stored with the hash.
+#[cfg(not(test))]
+const PIN_ROUNDS: u32 = 600_000;
+/// Fewer under test, where the unoptimized hash takes seconds.
+#[cfg(test)]
+const PIN_ROUNDS: u32 = 1000;
+
+/// Outcome of checking the config file at startup.
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    /// Locking is off (or there is no config file to check).
+    NotLocked,
+    Verified,
+    Tampered(String),
+}
+
+/// What the config file is checked against, from the build or
+/// `config_lock.toml`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Trust {
+    #[serde(default = "refuse")]
+    pub mode: LockMode,
+    /// Hex ed25519 public key that `config.toml.sig` must verify against.
+    pub public_key: String,
+}
+
+fn refuse() -> LockMode {
+    LockMode::Refuse
+}
+
+/// `config.toml` -> `config.toml.sig`.
+pub fn signature_path(config_path: &Path) -> PathBuf {
+    let mut name = config_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".sig");
+    config_path.with_file_name(name)
+}
+
+/// The lock the player is under, if any.
+pub fn trust() -> Option<Trust> {
+    trust_from(BUILD_PUBLIC_KEY, &config::exe_dir().join(TRUST_FILE_NAME))
+}
+
+fn trust_from(build_key: Option<&str>, trust_file: &Path) -> Option<Trust> {
+    if let Some(key) = build_key {
+        return Some(Trust {
+            mode: LockMode::Refuse,
+            public_key: key.to_string(),
+        });
+    }
+    let text = fs::read_to_string(trust_file).ok()?;
+    match toml::from_str(&text) {
+        Ok(trust) => Some(trust),
+        Err(e) => {
+            // A lock that can't be read stays locked
+            error!("Failed to read {}: {}", trust_file.display(), e);
+            Some(Trust {
+                mode: LockMode::Refuse,
+                public_key: String::new(),
+            })
+        }
+    }
+}
+
+pub fn parse_public_key(hex_key: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = hex::decode(hex_key.trim())
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or("public key must be 64 hex characters")?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("invalid public key: {}", e))
+}
+
+pub fn read_signing_key(key_path: &Path) -> Result<SigningKey, String> {
+    let text =
+        fs::read_to_string(key_path).map_err(|e| format!("{}: {}", key_path.display(), e))?;
+    let bytes: [u8; 32] = hex::decode(text.trim())
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| format!("{} is not a signing key", key_path.display()))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+/// Create a new signing key at `key_path` and return its public key as hex.
+/// Keep the key file off the kiosk; only the public key goes in the config.
+pub fn generate_key(key_path: &Path) -> Result<String, String> {
+    if key_path.exists() {
+        return Err(format!("{} already exists", key_path.display()));
+    }
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    let key = SigningKey::from_bytes(&secret);
+    fs::write(key_path, hex::encode(secret))
+        .map_err(|e| format!("{}: {}", key_path.display(), e))?;
+    Ok(hex::encode(key.verifying_key().to_bytes()))
+}
+
+/// Sign the config file as it is on disk, writing the signature next to it.
+/// With a `trust` at hand the key must be the one it holds.
+pub fn sign(config_path: &Path, key_path: &Path, trust: Option<&Trust>) -> Result<PathBuf, String> {
+    let bytes = fs::read(config_path).map_err(|e| format!("{}: {}", config_path.display(), e))?;
+    toml::from_str::<Config>(&String::from_utf8_lossy(&bytes))
+        .map_err(|e| format!("{}: {}", config_path.display(), e))?;
+    let key = read_signing_key(key_path)?;
+
+    let public = hex::encode(key.verifying_key().to_bytes());
+    if let Some(trust) = trust {
+        if trust.public_key.trim() != public {
+            return Err(format!(
+                "the key doesn't match the player's; put public_key = \"{}\" in {} first",
+                public, TRUST_FILE_NAME
+            ));
+        }
+    }
+
+    let sig_path = signature_path(config_path);
+    let signature = key.sign(&bytes);
+    fs::write(&sig_path, hex::encode(signature.to_bytes()))
+        .map_err(|e| format!("{}: {}", sig_path.display(), e))?;
+    Ok(sig_path)
+}
+
+/// Load the config under `trust`. A locked player only reads `locked_path`,
+/// the `config.toml` next to the executable, and runs the config parsed from
+/// the very bytes its signature was checked against, without environment
+/// overrides. When the check fails the config loads as it would unlocked,
+/// for `warn` mode to run.
+pub fn load(trust: &Trust, explicit: Option<&Path>, locked_path: &Path) -> (Config, Status) {
+    if trust.mode == LockMode::Off {
+        return (config::load(explicit), Status::NotLocked);
+    }
+    let checked = match explicit {
+        Some(path) if path != locked_path => Err(format!(
+            "{} isn't the locked config {}",
+            path.display(),
+            locked_path.display()
+        )),
+        _ => verify(trust, locked_path),
+    };
+    match checked {
+        Ok(config) => {
+            if let Some(user) = config::user_config_path().filter(|path| path.is_file()) {
+                if explicit.is_none() {
+                    warn!("Ignoring {}: the config is locked", user.display());
+                }
+            }
+            let overrides = std::env::vars().filter(|(key, _)| key.starts_with(config::ENV_PREFIX));
+            for (key, _) in overrides {
+                warn!("Ignoring {}: the config is locked", key);
+            }
+            (config, Status::Verified)
+        }
+        Err(reason) => (config::load(explicit), Status::Tampered(reason)),
+    }
+}
+
+fn verify(trust: &Trust, config_path: &Path) -> Result<Config, String> {
+    let key = parse_public_key(&trust.public_key)?;
+    let sig_path = signature_path(config_path);
+    let sig_hex = fs::read_to_string(&sig_path)
+        .map_err(|_| format!("signature {} is missing", sig_path.display()))?;
+    let sig_bytes: [u8; 64] = hex::decode(sig_hex.trim())
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| format!("signature {} is corrupt", sig_path.display()))?;
+    let bytes = fs::read(config_path).map_err(|e| format!("{}: {}", config_path.display(), e))?;
+    key.verify(&bytes, &Signature::from_bytes(&sig_bytes))
+        .map_err(|_| format!("{} was modified after it was signed", config_path.display()))?;
+    let text = String::from_utf8(bytes).map_err(|e| format!("{}: {}", config_path.display(), e))?;
+    toml::from_str(&text).map_err(|e| format!("{}: {}", config_path.display(), e))
+}
+
+/// Salted, slow hash of a PIN for `[lock] pin_hash`.
+pub fn hash_pin(pin: &str) -> String {
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    format!(
+        "{}${}${}${}",
+        PIN_SCHEME,
+        PIN_ROUNDS,
+        hex::encode(salt),
+        hex::encode(pin_digest(&salt, pin, PIN_ROUNDS))
+    )
+}
+
+pub fn verify_pin(pin: &str, stored: &str) -> bool {
+    let mut parts = stored.split('$');
+    let (Some(PIN_SCHEME), Some(rounds), Some(salt), Some(digest), None) = (
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+    ) else {
+        return false;
+    };
+    match (rounds.parse(), hex::decode(salt), hex::decode(digest)) {
+        (Ok(rounds), Ok(salt), Ok(digest)) if rounds > 0 => {
+            pin_digest(&salt, pin, rounds).as_slice() == digest.as_slice()
+        }
+        _ => false,
+    }
+}
+
+fn pin_digest(salt: &[u8], pin: &str, rounds: u32) -> [u8; 32] {
+    pbkdf2::pbkdf2_hmac_array::<Sha256, 32>(pin.as_bytes(), salt, rounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A config in `dir` to sign, the key to sign it with and the lock.
+    fn locked_config(dir: &Path, mode: LockMode) -> (PathBuf, PathBuf, Trust) {
+        let key_path = dir.join("admin.key");
+        let public_key = generate_key(&key_path).unwrap();
+        let config_path = dir.join("config.toml");
+        config::save(&Config::default(), &config_path).unwrap();
+        (config_path, key_path, Trust { mode, public_key })
+    }
+
+    #[test]
+    fn test_signature_path() {
+        assert_eq!(
+            signature_path(Path::new("/kiosk/config.toml")),
+            PathBuf::from("/kiosk/config.toml.sig")
+        );
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let temp_dir = TempDir::new().unwrap();
+        let (config_path, key_path, trust) = locked_config(temp_dir.path(), LockMode::Refuse);
+        assert!(matches!(
+            load(&trust, None, &config_path).1,
+            Status::Tampered(reason) if reason.contains("missing")
+        ));
+
+        sign(&config_path, &key_path, Some(&trust)).unwrap();
+        let (config, status) = load(&trust, None, &config_path);
+        assert_eq!(status, Status::Verified);
+        assert_eq!(config.video.directory, "./videos");
+
+        let mut text = fs::read_to_string(&config_path).unwrap();
+        text = text.replace("./videos", "/tmp/other");
+        fs::write(&config_path, text).unwrap();
+        assert!(matches!(
+            load(&trust, None, &config_path).1,
+            Status::Tampered(reason) if reason.contains("modified")
+        ));
+    }
+
+    #[test]
+    fn test_locked_reads_only_its_own_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let (config_path, key_path, trust) = locked_config(temp_dir.path(), LockMode::Refuse);
+        sign(&config_path, &key_path, Some(&trust)).unwrap();
+        let elsewhere = temp_dir.path().join("elsewhere.toml");
+        fs::copy(&config_path, &elsewhere).unwrap();
+        fs::copy(signature_path(&config_path), signature_path(&elsewhere)).unwrap();
+        assert!(matches!(
+            load(&trust, Some(&elsewhere), &config_path).1,
+            Status::Tampered(reason) if reason.contains("isn't the locked config")
+        ));
+        assert_eq!(
+            load(&trust, Some(&config_path), &config_path).1,
+            Status::Verified
+        );
+    }
+
+    #[test]
+    fn test_sign_rejects_mismatched_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let (config_path, _, trust) = locked_config(temp_dir.path(), LockMode::Warn);
+        let other_key = temp_dir.path().join("other.key");
+        generate_key(&other_key).unwrap();
+        assert!(sign(&config_path, &other_key, Some(&trust)).is_err());
+        assert!(sign(&config_path, &other_key, None).is_ok());
+        assert!(generate_key(&other_key).is_err());
+    }
+
+    #[test]
+    fn test_trust() {
+        let temp_dir = TempDir::new().unwrap();
+        let trust_file = temp_dir.path().join(TRUST_FILE_NAME);
+        assert!(trust_from(None, &trust_file).is_none());
+        let built = trust_from(Some("ab"), &trust_file).unwrap();
+        assert_eq!(built.mode, LockMode::Refuse);
+
+        fs::write(&trust_file, "public_key = \"ab\"\n").unwrap();
+        assert_eq!(
+            trust_from(None, &trust_file).unwrap().mode,
+            LockMode::Refuse
+        );
+        fs::write(&trust_file, "mode = \"warn\"\npublic_key = \"ab\"\n").unwrap();
+        assert_eq!(trust_from(None, &trust_file).unwrap().mode, LockMode::Warn);
+
+        // Unreadable, so locked with a key nothing verifies against
+        fs::write(&trust_file, "mode = \"off\"\n").unwrap();
+        let trust = trust_from(None, &trust_file).unwrap();
+        assert_eq!(trust.mode, LockMode::Refuse);
+        assert!(parse_public_key(&trust.public_key).is_err());
+    }
+
+    #[test]
+    fn test_lock_off_is_not_checked() {
+        let temp_dir = TempDir::new().unwrap();
+        let (config_path, _, trust) = locked_config(temp_dir.path(), LockMode::Off);
+        assert_eq!(
+            load(&trust, Some(&config_path), &config_path).1,
+            Status::NotLocked
+        );
+    }
+
+    #[test]
+    fn test_pin_hash() {
+        let stored = hash_pin("4321");
+        assert!(stored.starts_with("pbkdf2-sha256$1000$"));
+        assert!(verify_pin("4321", &stored));
+        assert!(!verify_pin("1234", &stored));
+        assert_ne!(hash_pin("4321"), stored);
+        assert!(!verify_pin("4321", "garbage"));
+        assert!(!verify_pin("4321", &stored.replace("$1000$", "$0$")));
+        // A plain salted SHA-256 is no longer accepted
+        assert!(!verify_pin("4321", "sha256$00$00"));
+    }
+}
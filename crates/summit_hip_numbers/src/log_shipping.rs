@@ -0,0 +1,273 @@
+//! Forwarding log records to a remote collector, set with
+//! `[logging] remote`:
+//!
+//! - `udp://host:514` or `tcp://host:601`: RFC 5424 syslog, facility local0
+//! - `http://...` or `https://...`: batches of JSON records POSTed once a
+//!   second (builds with `--features log-shipping`)
+//!
+//! Records go through a bounded queue to a background thread, so a slow or
+//! unreachable collector never holds up playback; when the queue is full,
+//! records are dropped.
+
+use serde::Serialize;
+use std::io::Write;
+use std::net::{TcpStream, UdpSocket};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use summit_hip_core::config::LoggingConfig;
+
+const QUEUE_LENGTH: usize = 1000;
+/// Syslog facility local0.
+const FACILITY: u8 = 16;
+const APP_NAME: &str = "summit_hip_numbers";
+
+#[derive(Debug, PartialEq)]
+enum Target {
+    Udp(String),
+    Tcp(String),
+    Http(String),
+}
+
+fn parse_target(remote: &str) -> Result<Target, String> {
+    match remote.split_once("://") {
+        Some(("udp", addr)) => Ok(Target::Udp(addr.to_string())),
+        Some(("tcp", addr)) => Ok(Target::Tcp(addr.to_string())),
+        Some(("http" | "https", _)) => Ok(Target::Http(remote.to_string())),
+        _ => Err(format!(
+            "'{}' should start with udp://, tcp://, http:// or https://",
+            remote
+        )),
+    }
+}
+
+/// One log record, as sent to HTTP collectors.
+#[derive(Clone, Debug, Serialize)]
+struct Entry {
+    timestamp: String,
+    host: String,
+    app: &'static str,
+    level: String,
+    target: String,
+    message: String,
+}
+
+/// A fern output forwarding records at `remote_level` or above to
+/// `[logging] remote`, or `None` if no remote is set.
+pub fn dispatch(config: &LoggingConfig) -> Result<Option<fern::Dispatch>, String> {
+    let Some(remote) = &config.remote else {
+        return Ok(None);
+    };
+    let level =
+        summit_hip_core::config::parse_level(config.remote_level.as_deref().unwrap_or("info"))?;
+    let target = parse_target(remote)?;
+    #[cfg(not(feature = "log-shipping"))]
+    if let Target::Http(_) = target {
+        return Err("this build can't ship logs over HTTP; use udp:// or tcp://".to_string());
+    }
+
+    let host = hostname();
+    let (sender, receiver) = mpsc::sync_channel::<Entry>(QUEUE_LENGTH);
+    thread::Builder::new()
+        .name("log-shipping".to_string())
+        .spawn(move || match target {
+            Target::Udp(addr) => ship_udp(&addr, receiver),
+            Target::Tcp(addr) => ship_tcp(&addr, receiver),
+            #[cfg(feature = "log-shipping")]
+            Target::Http(url) => ship_http(&url, receiver),
+            #[cfg(not(feature = "log-shipping"))]
+            Target::Http(_) => {}
+        })
+        .map_err(|e| e.to_string())?;
+
+    let output = fern::Output::call(move |record| {
+        let entry = Entry {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            host: host.clone(),
+            app: APP_NAME,
+            level: record.level().to_string(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        // Dropping beats blocking the UI thread while the collector is away
+        let _ = sender.try_send(entry);
+    });
+    Ok(Some(
+        fern::Dispatch::new()
+            .level(level)
+            // The HTTP client logs its own requests, which would ship forever
+            .filter(|metadata| !metadata.target().starts_with("ureq"))
+            .chain(output),
+    ))
+}
+
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| {
+            std::fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|h| h.trim().to_string())
+        })
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Format a record as an RFC 5424 syslog message.
+fn syslog_line(entry: &Entry) -> String {
+    let severity = match entry.level.as_str() {
+        "ERROR" => 3,
+        "WARN" => 4,
+        "INFO" => 6,
+        _ => 7,
+    };
+    format!(
+        "<{}>1 {} {} {} {} - - {}: {}",
+        FACILITY * 8 + severity,
+        entry.timestamp,
+        entry.host.replace(' ', "_"),
+        entry.app,
+        std::process::id(),
+        entry.target,
+        entry.message
+    )
+}
+
+/// Report a collector failure once, not for every record.
+fn report(failing: &mut bool, result: Result<(), String>, remote: &str) {
+    match result {
+        Ok(()) if *failing => {
+            eprintln!("Log shipping to {} recovered", remote);
+            *failing = false;
+        }
+        Err(e) if !*failing => {
+            eprintln!("Log shipping to {} failed: {}", remote, e);
+            *failing = true;
+        }
+        _ => {}
+    }
+}
+
+fn ship_udp(addr: &str, receiver: Receiver<Entry>) {
+    let socket = match UdpSocket::bind("0.0.0.0:0") {
+        Ok(socket) => socket,
+        Err(e) => return eprintln!("Log shipping disabled: {}", e),
+    };
+    let mut failing = false;
+    for entry in receiver {
+        let result = socket
+            .send_to(syslog_line(&entry).as_bytes(), addr)
+            .map(|_| ())
+            .map_err(|e| e.to_string());
+        report(&mut failing, result, addr);
+    }
+}
+
+fn ship_tcp(addr: &str, receiver: Receiver<Entry>) {
+    let mut stream: Option<TcpStream> = None;
+    let mut failing = false;
+    for entry in receiver {
+        let line = syslog_line(&entry);
+        // RFC 6587 octet counting, reconnecting after any failure
+        let result = stream
+            .take()
+            .map_or_else(|| TcpStream::connect(addr), Ok)
+            .and_then(|mut s| {
+                write!(s, "{} {}", line.len(), line)?;
+                Ok(s)
+            });
+        let result = match result {
+            Ok(s) => {
+                stream = Some(s);
+                Ok(())
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        report(&mut failing, result, addr);
+    }
+}
+
+#[cfg(feature = "log-shipping")]
+fn ship_http(url: &str, receiver: Receiver<Entry>) {
+    use std::time::{Duration, Instant};
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(10))
+        .build();
+    let mut failing = false;
+    loop {
+        // Block for the first record, then gather what arrives within a second
+        let Ok(first) = receiver.recv() else {
+            return;
+        };
+        let mut batch = vec![first];
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while let Some(wait) = deadline.checked_duration_since(Instant::now()) {
+            match receiver.recv_timeout(wait) {
+                Ok(entry) => batch.push(entry),
+                Err(_) => break,
+            }
+        }
+        let result = agent
+            .post(url)
+            .send_json(&batch)
+            .map(|_| ())
+            .map_err(|e| e.to_string());
+        report(&mut failing, result, url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn entry(level: &str) -> Entry {
+        Entry {
+            timestamp: "2024-05-01T10:00:00-05:00".to_string(),
+            host: "ring 1".to_string(),
+            app: APP_NAME,
+            level: level.to_string(),
+            target: "summit_hip_numbers".to_string(),
+            message: "Loading video: 042.mp4".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            parse_target("udp://logs:514"),
+            Ok(Target::Udp("logs:514".to_string()))
+        );
+        assert_eq!(
+            parse_target("https://logs/in"),
+            Ok(Target::Http("https://logs/in".to_string()))
+        );
+        assert!(parse_target("logs:514").is_err());
+    }
+
+    #[test]
+    fn test_syslog_line() {
+        let line = syslog_line(&entry("WARN"));
+        assert!(line.starts_with("<132>1 2024-05-01T10:00:00-05:00 ring_1 summit_hip_numbers "));
+        assert!(line.ends_with(" - - summit_hip_numbers: Loading video: 042.mp4"));
+        assert!(syslog_line(&entry("ERROR")).starts_with("<131>"));
+    }
+
+    #[test]
+    fn test_udp_shipping() {
+        let collector = UdpSocket::bind("127.0.0.1:0").unwrap();
+        collector
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let addr = collector.local_addr().unwrap().to_string();
+        let (sender, receiver) = mpsc::sync_channel(1);
+        thread::spawn(move || ship_udp(&addr, receiver));
+        sender.send(entry("INFO")).unwrap();
+
+        let mut buf = [0u8; 512];
+        let len = collector.recv(&mut buf).unwrap();
+        let line = String::from_utf8_lossy(&buf[..len]);
+        assert!(line.starts_with("<134>1 "), "{}", line);
+    }
+}
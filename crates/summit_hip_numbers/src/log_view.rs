@@ -0,0 +1,247 @@
+//! The log window of the configuration GUI: the end of the player's log file,
+//! filtered by level and text and following new lines as they are written, so
+//! operators can check recent errors on a fullscreen kiosk without hunting
+//! for the file.
+
+use eframe::egui;
+use log::Level;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Lines kept from the end of the file.
+const MAX_LINES: usize = 5000;
+/// How often the file is checked for new lines.
+const REFRESH: Duration = Duration::from_secs(1);
+
+struct Line {
+    /// Continuation lines take the level of the record they belong to.
+    level: Option<Level>,
+    text: String,
+}
+
+pub struct LogView {
+    pub open: bool,
+    /// Least severe level shown.
+    level: Level,
+    search: String,
+    follow: bool,
+    lines: Vec<Line>,
+    /// Size and modification time when last read, to skip unchanged files.
+    read_stamp: Option<(u64, Option<SystemTime>)>,
+    checked: Option<Instant>,
+    error: Option<String>,
+}
+
+impl Default for LogView {
+    fn default() -> Self {
+        Self {
+            open: false,
+            level: Level::Info,
+            search: String::new(),
+            follow: true,
+            lines: Vec::new(),
+            read_stamp: None,
+            checked: None,
+            error: None,
+        }
+    }
+}
+
+impl LogView {
+    pub fn show(&mut self, ctx: &egui::Context, log_file: &Path) {
+        if !self.open {
+            return;
+        }
+        if self.checked.map_or(true, |t| t.elapsed() >= REFRESH) {
+            self.refresh(log_file);
+        }
+        let mut open = self.open;
+        egui::Window::new(format!("Log: {}", log_file.display()))
+            .open(&mut open)
+            .default_size([800.0, 480.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for level in [Level::Error, Level::Warn, Level::Info, Level::Debug] {
+                        ui.radio_value(&mut self.level, level, level.as_str());
+                    }
+                    ui.radio_value(&mut self.level, Level::Trace, "all");
+                    ui.separator();
+                    ui.label("Search:");
+                    ui.text_edit_singleline(&mut self.search);
+                    ui.checkbox(&mut self.follow, "Follow");
+                });
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::LIGHT_RED, error);
+                }
+
+                let shown = self.filtered();
+                ui.label(format!("{} of {} lines", shown.len(), self.lines.len()));
+                ui.separator();
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                egui::ScrollArea::both()
+                    .auto_shrink(false)
+                    .stick_to_bottom(self.follow)
+                    .show_rows(ui, row_height, shown.len(), |ui, rows| {
+                        for line in &shown[rows] {
+                            let text = egui::RichText::new(&line.text).monospace();
+                            let text = match line.level {
+                                Some(Level::Error) => text.color(egui::Color32::LIGHT_RED),
+                                Some(Level::Warn) => text.color(egui::Color32::YELLOW),
+                                _ => text,
+                            };
+                            ui.add(egui::Label::new(text).extend());
+                        }
+                    });
+            });
+        self.open = open;
+        ctx.request_repaint_after(REFRESH);
+    }
+
+    /// Reread the file if it changed since the last read.
+    fn refresh(&mut self, log_file: &Path) {
+        self.checked = Some(Instant::now());
+        let stamp = match fs::metadata(log_file) {
+            Ok(meta) => (meta.len(), meta.modified().ok()),
+            Err(e) => {
+                self.error = Some(format!("{}: {}", log_file.display(), e));
+                self.lines.clear();
+                self.read_stamp = None;
+                return;
+            }
+        };
+        if self.read_stamp == Some(stamp) {
+            return;
+        }
+        match fs::read(log_file) {
+            Ok(bytes) => {
+                self.lines = parse(&String::from_utf8_lossy(&bytes));
+                self.read_stamp = Some(stamp);
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("{}: {}", log_file.display(), e)),
+        }
+    }
+
+    fn filtered(&self) -> Vec<&Line> {
+        let search = self.search.to_lowercase();
+        self.lines
+            .iter()
+            .filter(|line| line.level.map_or(true, |level| level <= self.level))
+            .filter(|line| search.is_empty() || line.text.to_lowercase().contains(&search))
+            .collect()
+    }
+}
+
+/// The last [`MAX_LINES`] lines of a log written as text or JSON.
+fn parse(log: &str) -> Vec<Line> {
+    let lines: Vec<&str> = log.lines().collect();
+    let mut level = None;
+    lines[lines.len().saturating_sub(MAX_LINES)..]
+        .iter()
+        .map(|text| {
+            let (line_level, text) = parse_line(text);
+            level = line_level.or(level);
+            Line { level, text }
+        })
+        .collect()
+}
+
+/// The level of a `time[target][LEVEL] message` or JSON line, and the text
+/// to show for it.
+fn parse_line(line: &str) -> (Option<Level>, String) {
+    if line.starts_with('{') {
+        if let Ok(json) = serde_json::from_str::<serde_json::Value>(line) {
+            let field = |name: &str| json[name].as_str().unwrap_or_default().to_string();
+            let level = json["level"].as_str().and_then(|l| l.parse().ok());
+            let text = format!(
+                "{}[{}][{}] {}",
+                field("timestamp"),
+                field("target"),
+                field("level"),
+                field("message")
+            );
+            return (level, text);
+        }
+    }
+    let level = line
+        .match_indices("][")
+        .filter_map(|(at, _)| {
+            let rest = &line[at + 2..];
+            rest[..rest.find(']')?].parse().ok()
+        })
+        .next();
+    (level, line.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let (level, text) =
+            parse_line("2024-05-01 10:00:00[summit_hip_numbers][WARN] Logo not found");
+        assert_eq!(level, Some(Level::Warn));
+        assert_eq!(
+            text,
+            "2024-05-01 10:00:00[summit_hip_numbers][WARN] Logo not found"
+        );
+        let (level, text) = parse_line(
+            r#"{"timestamp":"2024-05-01T10:00:00.000+02:00","level":"ERROR","target":"summit","message":"boom","hip":"042"}"#,
+        );
+        assert_eq!(level, Some(Level::Error));
+        assert_eq!(text, "2024-05-01T10:00:00.000+02:00[summit][ERROR] boom");
+        assert_eq!(parse_line("  at src/main.rs:1").0, None);
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut view = LogView {
+            lines: parse(
+                "t[a][INFO] started\n\
+                 t[a][ERROR] decoder failed\n\
+                 stack line\n\
+                 t[b][DEBUG] frame 1\n\
+                 t[b][WARN] slow frame\n",
+            ),
+            ..LogView::default()
+        };
+        assert_eq!(view.lines[2].level, Some(Level::Error));
+        let texts = |view: &LogView| -> Vec<String> {
+            view.filtered().iter().map(|l| l.text.clone()).collect()
+        };
+
+        view.level = Level::Warn;
+        assert_eq!(
+            texts(&view),
+            [
+                "t[a][ERROR] decoder failed",
+                "stack line",
+                "t[b][WARN] slow frame"
+            ]
+        );
+        view.level = Level::Trace;
+        view.search = "FRAME".to_string();
+        assert_eq!(
+            texts(&view),
+            ["t[b][DEBUG] frame 1", "t[b][WARN] slow frame"]
+        );
+    }
+
+    #[test]
+    fn test_refresh() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log_file = temp_dir.path().join("player.log");
+        let mut view = LogView::default();
+        view.refresh(&log_file);
+        assert!(view.error.is_some());
+
+        fs::write(&log_file, "t[a][INFO] one\n").unwrap();
+        view.refresh(&log_file);
+        assert!(view.error.is_none());
+        fs::write(&log_file, "t[a][INFO] one\nt[a][INFO] two\n").unwrap();
+        view.refresh(&log_file);
+        assert_eq!(view.lines.len(), 2);
+    }
+}
@@ -0,0 +1,156 @@
+//! Zeroconf advertising and discovery, built with `--features mdns` and
+//! enabled with `[mdns] enabled = true`.
+//!
+//! Each kiosk advertises `_summithip._tcp` with its control ports in the TXT
+//! record (`http`, `tcp`, `version`) and browses for the others, which the
+//! dashboard lists via `GET /api/kiosks`.
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::thread;
+use summit_hip_core::config::MdnsConfig;
+
+pub const SERVICE_TYPE: &str = "_summithip._tcp.local.";
+
+/// Another kiosk seen on the network.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Peer {
+    pub name: String,
+    pub address: String,
+    pub http_port: Option<u16>,
+    pub tcp_port: Option<u16>,
+    pub version: Option<String>,
+}
+
+/// Kiosks found so far, by mDNS full name.
+static PEERS: Mutex<BTreeMap<String, Peer>> = Mutex::new(BTreeMap::new());
+
+/// Kiosks currently on the network, this one included.
+pub fn peers() -> Vec<Peer> {
+    PEERS.lock().unwrap().values().cloned().collect()
+}
+
+/// Advertise this kiosk's control ports and start browsing for others.
+pub fn start(
+    config: &MdnsConfig,
+    http_port: Option<u16>,
+    tcp_port: Option<u16>,
+) -> Result<(), String> {
+    let Some(port) = http_port.or(tcp_port) else {
+        return Err("neither [http] nor [tcp] is enabled, nothing to advertise".to_string());
+    };
+    let host = hostname::get()
+        .map(|h| h.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| "summit-kiosk".to_string());
+    let name = config.name.clone().unwrap_or_else(|| host.clone());
+
+    let mut properties = vec![("version", env!("CARGO_PKG_VERSION").to_string())];
+    if let Some(port) = http_port {
+        properties.push(("http", port.to_string()));
+    }
+    if let Some(port) = tcp_port {
+        properties.push(("tcp", port.to_string()));
+    }
+
+    let daemon = ServiceDaemon::new().map_err(|e| e.to_string())?;
+    let service = ServiceInfo::new(
+        SERVICE_TYPE,
+        &name,
+        &format!("{}.local.", host),
+        "",
+        port,
+        &properties[..],
+    )
+    .map_err(|e| e.to_string())?
+    .enable_addr_auto();
+    daemon.register(service).map_err(|e| e.to_string())?;
+    log::info!("Advertising '{}' as {}", name, SERVICE_TYPE);
+
+    let events = daemon.browse(SERVICE_TYPE).map_err(|e| e.to_string())?;
+    thread::Builder::new()
+        .name("mdns".to_string())
+        .spawn(move || {
+            // Keep the daemon alive as long as we're browsing
+            let _daemon = daemon;
+            while let Ok(event) = events.recv() {
+                let mut peers = PEERS.lock().unwrap();
+                match event {
+                    ServiceEvent::ServiceResolved(info) => {
+                        if let Some(peer) = peer_from(&info) {
+                            log::debug!("Found kiosk {} at {}", peer.name, peer.address);
+                            peers.insert(info.get_fullname().to_string(), peer);
+                        }
+                    }
+                    ServiceEvent::ServiceRemoved(_, fullname) => {
+                        peers.remove(&fullname);
+                    }
+                    _ => {}
+                }
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn peer_from(info: &ServiceInfo) -> Option<Peer> {
+    let address = info.get_addresses_v4().into_iter().min()?.to_string();
+    let port = |key| info.get_property_val_str(key).and_then(|p| p.parse().ok());
+    Some(Peer {
+        name: instance_name(info.get_fullname()).to_string(),
+        address,
+        http_port: port("http"),
+        tcp_port: port("tcp"),
+        version: info.get_property_val_str("version").map(str::to_string),
+    })
+}
+
+/// `Ring 1._summithip._tcp.local.` -> `Ring 1`
+fn instance_name(fullname: &str) -> &str {
+    fullname
+        .strip_suffix(SERVICE_TYPE)
+        .map(|name| name.trim_end_matches('.'))
+        .unwrap_or(fullname)
+}
+
+/// The port of a `host:port` bind address.
+pub fn port_of(bind: &str) -> Option<u16> {
+    bind.rsplit(':').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peer_from_service_info() {
+        let properties = [("version", "1.2.3"), ("http", "8080"), ("tcp", "5250")];
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            "Ring 1",
+            "ring1.local.",
+            "192.168.1.20",
+            8080,
+            &properties[..],
+        )
+        .unwrap();
+        assert_eq!(
+            peer_from(&info),
+            Some(Peer {
+                name: "Ring 1".to_string(),
+                address: "192.168.1.20".to_string(),
+                http_port: Some(8080),
+                tcp_port: Some(5250),
+                version: Some("1.2.3".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_port_of() {
+        assert_eq!(port_of("0.0.0.0:8080"), Some(8080));
+        assert_eq!(port_of("[::]:5250"), Some(5250));
+        assert_eq!(port_of("localhost"), None);
+    }
+}
@@ -0,0 +1,368 @@
+//! Copies of the videos on the local disk, under `[media_cache]`, for a
+//! video directory on a USB stick or another slow or removable drive: a
+//! hiccup reading the stick mid-lot freezes the video, where a copy on the
+//! local disk plays straight through. The directory itself is only read.
+//!
+//! A background thread copies each video the first time it plays, or all of
+//! them with `copy_all`, checking the copy's SHA-256 against what was read
+//! before it's used. Every copy is checked again when the player starts, and
+//! the copies played longest ago are removed to stay under `max_size_mb`.
+//! What's cached is kept in `index.json` in the cache folder.
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+use summit_hip_core::config::Config;
+
+use crate::hot_swap::{self, Stamp};
+
+const INDEX: &str = "index.json";
+
+/// How much of a video is read at a time.
+const CHUNK: usize = 1024 * 1024;
+
+/// The copies to play, by the paths of the video files, with what the files
+/// looked like when copied.
+pub type Copies = HashMap<String, (Stamp, PathBuf)>;
+
+/// Where the copies go.
+pub fn dir(config: &Config) -> PathBuf {
+    match &config.media_cache.directory {
+        Some(dir) => PathBuf::from(dir),
+        None => Path::new(&config.logging.file)
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join("media_cache"),
+    }
+}
+
+/// One video in the cache.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Entry {
+    /// The copy's file name in the cache folder.
+    file: String,
+    /// The video file when it was copied.
+    stamp: Stamp,
+    sha256: String,
+    size: u64,
+    last_used: SystemTime,
+}
+
+/// The cache and the thread filling it; the thread stops when it's dropped.
+pub struct Cache {
+    jobs: mpsc::Sender<(String, Stamp)>,
+    copies: Arc<Mutex<Option<Copies>>>,
+}
+
+impl Cache {
+    /// Open the cache, `None` unless it's enabled.
+    pub fn open(config: &Config) -> Option<Self> {
+        if !config.media_cache.enabled {
+            return None;
+        }
+        let dir = dir(config);
+        if let Err(e) = fs::create_dir_all(&dir) {
+            warn!("Failed to create the media cache {}: {}", dir.display(), e);
+            return None;
+        }
+        let max = config.media_cache.max_size_mb.saturating_mul(1024 * 1024);
+        let (jobs, requests) = mpsc::channel::<(String, Stamp)>();
+        let copies = Arc::new(Mutex::new(None));
+        let published = copies.clone();
+        info!("Caching videos in {}", dir.display());
+        let spawned = thread::Builder::new()
+            .name("media-cache".to_string())
+            .spawn(move || {
+                let mut store = Store::load(dir, max, published);
+                store.verify();
+                // Until the Cache is dropped
+                for (path, stamp) in requests {
+                    store.request(&path, stamp);
+                }
+            });
+        if let Err(e) = spawned {
+            warn!("Failed to start the media cache: {}", e);
+            return None;
+        }
+        Some(Self { jobs, copies })
+    }
+
+    /// Have the video file at `path` copied, if it isn't already, or mark
+    /// its copy as just played.
+    pub fn request(&self, path: &str, stamp: Stamp) {
+        let _ = self.jobs.send((path.to_string(), stamp));
+    }
+
+    /// The copies there are, if they've changed since last asked.
+    pub fn copies(&self) -> Option<Copies> {
+        self.copies.lock().ok()?.take()
+    }
+}
+
+/// The cache folder, as the thread filling it sees it.
+struct Store {
+    dir: PathBuf,
+    max: u64,
+    entries: BTreeMap<String, Entry>,
+    published: Arc<Mutex<Option<Copies>>>,
+}
+
+impl Store {
+    fn load(dir: PathBuf, max: u64, published: Arc<Mutex<Option<Copies>>>) -> Self {
+        let entries = match fs::read_to_string(dir.join(INDEX)) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                warn!("Starting the media cache afresh: {}", e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        Self {
+            dir,
+            max,
+            entries,
+            published,
+        }
+    }
+
+    fn save(&self) {
+        let saved = serde_json::to_string_pretty(&self.entries)
+            .map_err(io::Error::from)
+            .and_then(|text| fs::write(self.dir.join(INDEX), text));
+        if let Err(e) = saved {
+            warn!("Failed to save the media cache index: {}", e);
+        }
+    }
+
+    /// Hand the copies to the player.
+    fn publish(&self) {
+        let copies = self
+            .entries
+            .iter()
+            .map(|(path, entry)| (path.clone(), (entry.stamp, self.dir.join(&entry.file))))
+            .collect();
+        if let Ok(mut published) = self.published.lock() {
+            *published = Some(copies);
+        }
+    }
+
+    /// Drop the copies that no longer match their SHA-256, and any file the
+    /// index doesn't know, like a copy cut short by a crash.
+    fn verify(&mut self) {
+        self.entries
+            .retain(|path, entry| match hash_file(&self.dir.join(&entry.file)) {
+                Ok(sha256) if sha256 == entry.sha256 => true,
+                Ok(_) => {
+                    warn!("The cached copy of {} is damaged; removing it", path);
+                    false
+                }
+                Err(e) => {
+                    warn!("The cached copy of {} can't be read: {}", path, e);
+                    false
+                }
+            });
+        let known: Vec<&str> = self.entries.values().map(|e| e.file.as_str()).collect();
+        if let Ok(files) = fs::read_dir(&self.dir) {
+            for file in files.flatten() {
+                let name = file.file_name();
+                let name = name.to_string_lossy();
+                if name != INDEX && !known.contains(&name.as_ref()) {
+                    let _ = fs::remove_file(file.path());
+                }
+            }
+        }
+        self.save();
+        self.publish();
+    }
+
+    /// Copy the video file at `path`, unless its copy is up to date.
+    fn request(&mut self, path: &str, stamp: Stamp) {
+        if let Some(entry) = self.entries.get_mut(path) {
+            if entry.stamp == stamp {
+                entry.last_used = SystemTime::now();
+                self.save();
+                return;
+            }
+            info!("{} has changed since it was cached", path);
+            self.remove(path);
+        }
+        let size = match fs::metadata(path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) => {
+                warn!("Failed to cache {}: {}", path, e);
+                return;
+            }
+        };
+        if size > self.max {
+            info!("{} is too big for the media cache", path);
+            return;
+        }
+        self.make_room(size);
+        let file = file_name(path);
+        info!("Copying {} to the media cache", path);
+        match self.copy(path, &file, stamp) {
+            Ok(sha256) => {
+                self.entries.insert(
+                    path.to_string(),
+                    Entry {
+                        file,
+                        stamp,
+                        sha256,
+                        size,
+                        last_used: SystemTime::now(),
+                    },
+                );
+                info!("Cached {}", path);
+                self.save();
+                self.publish();
+            }
+            Err(e) => warn!("Failed to cache {}: {}", path, e),
+        }
+    }
+
+    /// Remove the copies played longest ago until `size` more fits.
+    fn make_room(&mut self, size: u64) {
+        while self.entries.values().map(|e| e.size).sum::<u64>() + size > self.max {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone())
+            else {
+                return;
+            };
+            info!("Removing the cached copy of {} to make room", oldest);
+            self.remove(&oldest);
+        }
+    }
+
+    fn remove(&mut self, path: &str) {
+        if let Some(entry) = self.entries.remove(path) {
+            // The player mustn't reach for it once it's gone
+            self.publish();
+            let _ = fs::remove_file(self.dir.join(&entry.file));
+            self.save();
+        }
+    }
+
+    /// Copy `path` to `file` in the cache folder, through a `.part` file
+    /// that's read back and checked before it's renamed, returning the
+    /// SHA-256 of the video.
+    fn copy(&self, path: &str, file: &str, stamp: Stamp) -> io::Result<String> {
+        let part = self.dir.join(format!("{}.part", file));
+        let copied = (|| -> io::Result<String> {
+            let mut source = File::open(path)?;
+            let mut target = File::create(&part)?;
+            let mut hasher = Sha256::new();
+            let mut buffer = vec![0; CHUNK];
+            loop {
+                let read = source.read(&mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+                target.write_all(&buffer[..read])?;
+            }
+            target.sync_all()?;
+            let sha256 = hex::encode(hasher.finalize());
+            if hot_swap::stamp(path) != Some(stamp) {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "the video changed while it was copied",
+                ));
+            }
+            if hash_file(&part)? != sha256 {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "the copy doesn't match the video",
+                ));
+            }
+            fs::rename(&part, self.dir.join(file))?;
+            Ok(sha256)
+        })();
+        if copied.is_err() {
+            let _ = fs::remove_file(&part);
+        }
+        copied
+    }
+}
+
+/// The copy's name: a hash of the video's path, so videos of the same name
+/// in different rings don't clash, keeping its extension for the decoder.
+fn file_name(path: &str) -> String {
+    let hash = hex::encode(&Sha256::digest(path.as_bytes())[..16]);
+    match Path::new(path).extension() {
+        Some(extension) => format!("{}.{}", hash, extension.to_string_lossy()),
+        None => hash,
+    }
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; CHUNK];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hex::encode(hasher.finalize()));
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_store() {
+        let videos = TempDir::new().unwrap();
+        let cache = TempDir::new().unwrap();
+        let path = |name: &str| videos.path().join(name).to_str().unwrap().to_string();
+        for (name, len) in [("001.mp4", 400), ("002.mp4", 400), ("003.mp4", 2000)] {
+            fs::write(path(name), vec![b'v'; len]).unwrap();
+        }
+        let published = Arc::new(Mutex::new(None));
+        let mut store = Store::load(cache.path().to_path_buf(), 1000, published.clone());
+        let request = |store: &mut Store, name: &str| {
+            store.request(&path(name), hot_swap::stamp(&path(name)).unwrap())
+        };
+
+        request(&mut store, "001.mp4");
+        request(&mut store, "002.mp4");
+        let copies: Copies = published.lock().unwrap().take().unwrap();
+        assert_eq!(copies.len(), 2);
+        let (_, copy) = &copies[&path("001.mp4")];
+        assert_eq!(fs::read(copy).unwrap(), vec![b'v'; 400]);
+
+        // Too big for the cache
+        request(&mut store, "003.mp4");
+        assert!(published.lock().unwrap().is_none());
+
+        // 001 played longest ago, so it makes room for 003
+        fs::write(path("003.mp4"), vec![b'v'; 500]).unwrap();
+        request(&mut store, "002.mp4");
+        request(&mut store, "003.mp4");
+        let copies = published.lock().unwrap().take().unwrap();
+        assert!(!copies.contains_key(&path("001.mp4")));
+        assert!(copies.contains_key(&path("003.mp4")));
+        assert!(!copy.exists());
+
+        // A damaged copy is dropped when the cache is opened again
+        let (_, copy) = &copies[&path("002.mp4")];
+        fs::write(copy, b"damaged").unwrap();
+        let mut store = Store::load(cache.path().to_path_buf(), 1000, published.clone());
+        store.verify();
+        let copies = published.lock().unwrap().take().unwrap();
+        assert_eq!(copies.len(), 1);
+        assert!(copies.contains_key(&path("003.mp4")));
+        assert!(!copy.exists());
+    }
+}
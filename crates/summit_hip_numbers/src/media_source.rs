@@ -0,0 +1,206 @@
+//! Watching a video directory on an SMB or NFS share, under
+//! `[media_source]`. A background thread checks every `check_seconds` that
+//! the directory can be read, since a dropped share can leave any file call
+//! hanging; the player shows "media source offline" while it can't, runs the
+//! `reconnect_command` after each failed check, and rescans once it's back.
+//!
+//! An empty directory counts as offline too: it's what the mount point of a
+//! share that isn't mounted looks like.
+
+use log::{info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+use summit_hip_core::config::MediaSourceConfig;
+
+/// The checks on one video directory; they stop when it's dropped.
+pub struct Monitor {
+    online: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Monitor {
+    /// Start checking `dir`, `None` with `check_seconds = 0`.
+    pub fn start(dir: PathBuf, config: &MediaSourceConfig) -> Option<Self> {
+        let every = Duration::try_from_secs_f64(config.check_seconds.get()).ok()?;
+        if every.is_zero() {
+            return None;
+        }
+        let timeout =
+            Duration::try_from_secs_f64(config.timeout_seconds.get()).unwrap_or(Duration::MAX);
+        let reconnect = config.reconnect_command.clone();
+        let online = Arc::new(AtomicBool::new(true));
+        let stop = Arc::new(AtomicBool::new(false));
+        let monitor = Self {
+            online: online.clone(),
+            stop: stop.clone(),
+        };
+        info!("Checking {} every {:?}", dir.display(), every);
+        let spawned = thread::Builder::new()
+            .name("media-source".to_string())
+            .spawn(move || {
+                let mut reads = Reader::new("media-check");
+                while !stop.load(Ordering::SeqCst) {
+                    let result = check(&mut reads, &dir, timeout);
+                    if let Err(e) = &result {
+                        if online.load(Ordering::SeqCst) {
+                            warn!("Media source {} is offline: {}", dir.display(), e);
+                        }
+                        if let Some(command) = &reconnect {
+                            run(command);
+                        }
+                    }
+                    online.store(result.is_ok(), Ordering::SeqCst);
+                    thread::sleep(every);
+                }
+            });
+        if let Err(e) = spawned {
+            warn!("Failed to start the media source checks: {}", e);
+            return None;
+        }
+        Some(monitor)
+    }
+
+    pub fn online(&self) -> bool {
+        self.online.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Reads that can hang on a dropped share, each run on a thread of its own
+/// and given up on after a timeout. While one is stuck the next waits on its
+/// answer instead of starting another, so a dead share leaves one thread
+/// behind rather than one per read.
+pub struct Reader<T> {
+    name: &'static str,
+    in_flight: Option<mpsc::Receiver<T>>,
+}
+
+impl<T: Send + 'static> Reader<T> {
+    /// Reads on threads called `name`.
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            in_flight: None,
+        }
+    }
+
+    /// What `read` returns within `timeout`, or what the read still in
+    /// flight from before returns, in which case `read` isn't run.
+    pub fn read(
+        &mut self,
+        timeout: Duration,
+        read: impl FnOnce() -> T + Send + 'static,
+    ) -> Result<T, String> {
+        let answer = match self.in_flight.take() {
+            Some(answer) => answer,
+            None => {
+                let (sender, answer) = mpsc::channel();
+                thread::Builder::new()
+                    .name(self.name.to_string())
+                    .spawn(move || {
+                        let _ = sender.send(read());
+                    })
+                    .map_err(|e| e.to_string())?;
+                answer
+            }
+        };
+        match answer.recv_timeout(timeout) {
+            Ok(value) => Ok(value),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                self.in_flight = Some(answer);
+                Err(format!("no answer within {:?}", timeout))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Err("the read stopped without an answer".to_string())
+            }
+        }
+    }
+}
+
+/// Whether `dir` can be read and has something in it, within `timeout`.
+fn check(
+    reads: &mut Reader<Result<(), String>>,
+    dir: &Path,
+    timeout: Duration,
+) -> Result<(), String> {
+    let dir = dir.to_path_buf();
+    reads.read(timeout, move || {
+        let mut entries = fs::read_dir(&dir).map_err(|e| e.to_string())?;
+        match entries.next() {
+            Some(_) => Ok(()),
+            None => Err("the directory is empty".to_string()),
+        }
+    })?
+}
+
+/// Run `command` through the shell, logging how it went.
+fn run(command: &str) {
+    info!("Reconnecting the media source: {}", command);
+    #[cfg(windows)]
+    let status = Command::new("cmd").args(["/C", command]).status();
+    #[cfg(not(windows))]
+    let status = Command::new("sh").args(["-c", command]).status();
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => warn!("'{}' failed: {}", command, status),
+        Err(e) => warn!("Failed to run '{}': {}", command, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_check() {
+        let temp_dir = TempDir::new().unwrap();
+        let timeout = Duration::from_secs(5);
+        let mut reads = Reader::new("media-check");
+        assert!(check(&mut reads, temp_dir.path(), timeout).is_err());
+        fs::File::create(temp_dir.path().join("001.mp4")).unwrap();
+        assert_eq!(check(&mut reads, temp_dir.path(), timeout), Ok(()));
+        assert!(check(&mut reads, &temp_dir.path().join("gone"), timeout).is_err());
+    }
+
+    #[test]
+    fn test_one_read_in_flight() {
+        let started = Arc::new(AtomicUsize::new(0));
+        let (release, hang) = mpsc::channel::<()>();
+        let mut reads = Reader::new("media-check");
+        let timeout = Duration::from_millis(50);
+        let stuck = started.clone();
+        assert!(reads
+            .read(timeout, move || {
+                stuck.fetch_add(1, Ordering::SeqCst);
+                hang.recv().unwrap();
+                "stuck"
+            })
+            .is_err());
+        let next = started.clone();
+        let read = move || {
+            next.fetch_add(1, Ordering::SeqCst);
+            "next"
+        };
+        assert!(reads.read(timeout, read.clone()).is_err());
+        release.send(()).unwrap();
+        assert_eq!(
+            reads.read(Duration::from_secs(5), read.clone()),
+            Ok("stuck")
+        );
+        assert_eq!(started.load(Ordering::SeqCst), 1);
+        assert_eq!(reads.read(Duration::from_secs(5), read), Ok("next"));
+        assert_eq!(started.load(Ordering::SeqCst), 2);
+    }
+}
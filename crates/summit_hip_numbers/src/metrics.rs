@@ -0,0 +1,192 @@
+//! Playback counters for the Prometheus `/metrics` endpoint of the HTTP API.
+//! They are plain atomics so the decoder threads can update them without
+//! locking, and are rendered in the Prometheus text format on request.
+
+use crate::mixer;
+use crate::resources;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static VIDEOS_PLAYED: AtomicU64 = AtomicU64::new(0);
+static PLAYBACK_ERRORS: AtomicU64 = AtomicU64::new(0);
+static FRAMES_SHOWN: AtomicU64 = AtomicU64::new(0);
+static FRAMES_LATE: AtomicU64 = AtomicU64::new(0);
+static DECODE_NANOS: AtomicU64 = AtomicU64::new(0);
+/// Frames per second of the current video, times 1000.
+static FRAME_RATE_MILLI: AtomicU64 = AtomicU64::new(0);
+
+/// Start the uptime clock; later calls do nothing.
+pub fn start_clock() {
+    STARTED.get_or_init(Instant::now);
+}
+
+/// Time since [`start_clock`], if it was started.
+pub fn uptime() -> Option<Duration> {
+    STARTED.get().map(Instant::elapsed)
+}
+
+pub fn video_started() {
+    VIDEOS_PLAYED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn playback_error() {
+    PLAYBACK_ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Record one frame handed to the UI: how long decoding and converting it
+/// took, and whether it was more than a frame behind schedule.
+pub fn frame_shown(decode_time: Duration, late: bool) {
+    FRAMES_SHOWN.fetch_add(1, Ordering::Relaxed);
+    DECODE_NANOS.fetch_add(decode_time.as_nanos() as u64, Ordering::Relaxed);
+    if late {
+        FRAMES_LATE.fetch_add(1, Ordering::Relaxed);
+    }
+    crate::perf::frame_decoded(decode_time, late);
+}
+
+/// Measures the frame rate of one video over roughly one-second windows.
+pub struct FrameRateMeter {
+    window_start: Instant,
+    frames: u32,
+}
+
+impl FrameRateMeter {
+    pub fn new() -> Self {
+        Self {
+            window_start: Instant::now(),
+            frames: 0,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.frames += 1;
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            let fps = self.frames as f64 / elapsed.as_secs_f64();
+            FRAME_RATE_MILLI.store((fps * 1000.0) as u64, Ordering::Relaxed);
+            *self = Self::new();
+        }
+    }
+}
+
+impl Drop for FrameRateMeter {
+    /// Nothing is playing once the video's decoder loop is gone.
+    fn drop(&mut self) {
+        FRAME_RATE_MILLI.store(0, Ordering::Relaxed);
+    }
+}
+
+/// All metrics in the Prometheus text exposition format.
+#[cfg_attr(not(feature = "http-api"), allow(dead_code))]
+pub fn render() -> String {
+    let uptime = uptime().map_or(0.0, |t| t.as_secs_f64());
+    let frames = FRAMES_SHOWN.load(Ordering::Relaxed);
+    let decode_seconds = DECODE_NANOS.load(Ordering::Relaxed) as f64 / 1e9;
+    let frame_rate = FRAME_RATE_MILLI.load(Ordering::Relaxed) as f64 / 1000.0;
+
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+        out.push_str(&format!(
+            "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n"
+        ));
+    };
+    metric(
+        "summit_uptime_seconds",
+        "gauge",
+        "Seconds since the player started.",
+        format!("{:.3}", uptime),
+    );
+    metric(
+        "summit_videos_played_total",
+        "counter",
+        "Videos started.",
+        VIDEOS_PLAYED.load(Ordering::Relaxed).to_string(),
+    );
+    metric(
+        "summit_playback_errors_total",
+        "counter",
+        "Videos that failed to open or play.",
+        PLAYBACK_ERRORS.load(Ordering::Relaxed).to_string(),
+    );
+    metric(
+        "summit_frame_rate",
+        "gauge",
+        "Frames per second of the current video, 0 when none is playing.",
+        format!("{:.2}", frame_rate),
+    );
+    metric(
+        "summit_frames_total",
+        "counter",
+        "Video frames decoded and handed to the display.",
+        frames.to_string(),
+    );
+    metric(
+        "summit_frames_dropped_total",
+        "counter",
+        "Frames shown more than one frame interval late.",
+        FRAMES_LATE.load(Ordering::Relaxed).to_string(),
+    );
+    out.push_str(&format!(
+        "# HELP summit_frame_decode_seconds Time to decode and convert a frame.\n\
+         # TYPE summit_frame_decode_seconds summary\n\
+         summit_frame_decode_seconds_sum {:.6}\n\
+         summit_frame_decode_seconds_count {}\n",
+        decode_seconds, frames
+    ));
+    out.push_str(
+        "# HELP summit_memory_bytes Memory held for playback, by kind.\n\
+         # TYPE summit_memory_bytes gauge\n",
+    );
+    for kind in resources::Kind::ALL {
+        out.push_str(&format!(
+            "summit_memory_bytes{{kind=\"{}\"}} {}\n",
+            kind.name(),
+            resources::used(kind)
+        ));
+    }
+    let level = mixer::level();
+    out.push_str(&format!(
+        "# HELP summit_audio_level Peak audio level, 0 to 1.\n\
+         # TYPE summit_audio_level gauge\n\
+         summit_audio_level{{signal=\"program\"}} {:.4}\n\
+         summit_audio_level{{signal=\"output\"}} {:.4}\n",
+        level.program, level.output
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        start_clock();
+        video_started();
+        frame_shown(Duration::from_millis(4), true);
+        let text = render();
+
+        // Other tests may count too, so only check the format
+        for name in [
+            "summit_uptime_seconds",
+            "summit_videos_played_total",
+            "summit_playback_errors_total",
+            "summit_frame_rate",
+            "summit_frames_total",
+            "summit_frames_dropped_total",
+            "summit_frame_decode_seconds",
+            "summit_memory_bytes",
+            "summit_audio_level",
+        ] {
+            assert!(text.contains(&format!("# TYPE {} ", name)), "{}", name);
+        }
+        assert!(text.contains("summit_frame_decode_seconds_count "));
+        assert!(text.contains("summit_memory_bytes{kind=\"audio_buffer\"} "));
+        for line in text.lines().filter(|l| !l.starts_with('#')) {
+            let (_, value) = line.split_once(' ').unwrap();
+            assert!(value.parse::<f64>().is_ok(), "{}", line);
+        }
+    }
+}
@@ -0,0 +1,616 @@
+//! The audio output. Everything the player sounds goes through one mixer
+//! above cpal: the audio of the video playing and the background music are
+//! each a [`Source`] the mixer adds up for the output device, lowering the
+//! music while a video with sound plays. A second device, such as the
+//! broadcast mixer, can get a copy of the videos' sound at its own gain.
+//!
+//! Samples are interleaved `f32` in the device's default format; decoders
+//! resample and remix to its rate and channel count before pushing.
+
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::resources::{self, Kind, Usage};
+use summit_hip_core::config::AudioConfig;
+use summit_hip_core::control::AudioLevel;
+
+/// How long the music takes to duck and to come back up.
+const DUCK_SECONDS: f32 = 0.5;
+
+/// How far the second output may fall behind the first.
+const FEED_SECONDS: f32 = 0.2;
+
+/// What's left of a peak level after a second.
+const LEVEL_FALL: f32 = 0.1;
+
+/// Peak level below which the program counts as silent, -60 dB.
+const SILENCE_LEVEL: f32 = 0.001;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// A video's own audio.
+    Program,
+    /// Background music, ducked while program audio plays.
+    Music,
+}
+
+/// Decoded audio waiting for the output device.
+struct AudioBuffer {
+    samples: VecDeque<f32>,
+    usage: Usage<'static>,
+}
+
+impl AudioBuffer {
+    fn count(&mut self) {
+        self.usage
+            .set((self.samples.len() * std::mem::size_of::<f32>()) as u64);
+    }
+}
+
+/// One producer's audio: a decoder pushes samples, the mixer plays them at
+/// the source's gain.
+pub struct Source {
+    role: Role,
+    buffer: Mutex<AudioBuffer>,
+    /// Volume multipliers stored as `f32` bits so the output callback can
+    /// read them without locking.
+    gain: AtomicU32,
+    /// Music only: the gain while program audio plays.
+    ducked_gain: AtomicU32,
+    paused: AtomicBool,
+    closed: AtomicBool,
+    /// Samples buffered at most; without, decoding runs ahead up to the
+    /// `[resources]` ceiling.
+    ahead: Option<usize>,
+}
+
+impl Source {
+    pub fn new(role: Role) -> Self {
+        Self {
+            role,
+            buffer: Mutex::new(AudioBuffer {
+                samples: VecDeque::new(),
+                usage: Usage::new(Kind::AudioBuffer),
+            }),
+            gain: AtomicU32::new(1.0f32.to_bits()),
+            ducked_gain: AtomicU32::new(1.0f32.to_bits()),
+            paused: AtomicBool::new(false),
+            closed: AtomicBool::new(false),
+            ahead: None,
+        }
+    }
+
+    /// Buffer at most `samples` ahead of the device.
+    pub fn with_ahead(mut self, samples: usize) -> Self {
+        self.ahead = Some(samples);
+        self
+    }
+
+    /// Set the volume multiplier (1.0 = unchanged). Negative values are
+    /// treated as 0.0.
+    pub fn set_gain(&self, gain: f32) {
+        self.gain.store(gain.max(0.0).to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn set_ducked_gain(&self, gain: f32) {
+        self.ducked_gain
+            .store(gain.max(0.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Silence the source, keeping what's buffered for when it resumes.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Drop what's buffered and leave the mixer.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.samples.clear();
+        buffer.count();
+    }
+
+    /// Queue `samples` to play. Decoding runs ahead of playback; when the
+    /// buffer is full this waits for the device to catch up, and the decoder
+    /// waits on it. Returns false once `stop` is set or the source is closed,
+    /// when the decoder should stop.
+    pub fn push(&self, samples: Vec<f32>, stop: &AtomicBool) -> bool {
+        let bytes = (samples.len() * std::mem::size_of::<f32>()) as u64;
+        loop {
+            if stop.load(Ordering::SeqCst) || self.closed.load(Ordering::SeqCst) {
+                return false;
+            }
+            let mut buffer = self.buffer.lock().unwrap();
+            let full = match self.ahead {
+                Some(ahead) => buffer.samples.len() + samples.len() > ahead,
+                None => resources::room(Kind::AudioBuffer) < bytes,
+            };
+            if !full || buffer.samples.is_empty() {
+                buffer.samples.extend(samples);
+                buffer.count();
+                return true;
+            }
+            drop(buffer);
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Whether it would sound now.
+    fn is_playing(&self) -> bool {
+        !self.paused.load(Ordering::Relaxed) && !self.buffer.lock().unwrap().samples.is_empty()
+    }
+
+    /// Add the next `out.len()` samples into `out`, at `duck` (0.0 to 1.0)
+    /// of the way from the gain to the ducked gain.
+    fn mix_into(&self, out: &mut [f32], duck: f32) {
+        if self.paused.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut gain = f32::from_bits(self.gain.load(Ordering::Relaxed));
+        if self.role == Role::Music {
+            let ducked = f32::from_bits(self.ducked_gain.load(Ordering::Relaxed));
+            gain += (ducked - gain) * duck;
+        }
+        let mut buffer = self.buffer.lock().unwrap();
+        let len = out.len().min(buffer.samples.len());
+        for (out, sample) in out[..len].iter_mut().zip(buffer.samples.drain(..len)) {
+            *out += sample * gain;
+        }
+        if len > 0 {
+            buffer.count();
+        }
+    }
+}
+
+struct Mix {
+    sources: Vec<Arc<Source>>,
+    /// How far the music is ducked, 0.0 to 1.0.
+    duck: f32,
+    /// The program sources added up, before the music goes in.
+    program: Vec<f32>,
+}
+
+/// The program audio on its way to a second device. The devices' clocks
+/// drift apart, so it keeps at most `max` samples, dropping the oldest, and
+/// plays silence when it runs out.
+struct Feed {
+    samples: VecDeque<f32>,
+    gain: f32,
+    max: usize,
+}
+
+/// A peak level, as `f32` bits, that falls by [`LEVEL_FALL`] a second.
+struct Meter(AtomicU32);
+
+impl Meter {
+    fn new() -> Self {
+        Self(AtomicU32::new(0.0f32.to_bits()))
+    }
+
+    fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    /// Take the peak of `samples`, `seconds` of sound after the last ones.
+    fn update(&self, samples: &[f32], seconds: f32) {
+        let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        let held = self.get() * LEVEL_FALL.powf(seconds);
+        self.0.store(peak.max(held).to_bits(), Ordering::Relaxed);
+    }
+}
+
+pub struct Mixer {
+    /// Samples per second across all channels.
+    rate: usize,
+    channels: usize,
+    /// Volume multiplier on the first output, as `f32` bits.
+    gain: AtomicU32,
+    mix: Mutex<Mix>,
+    feed: Mutex<Option<Feed>>,
+    program_level: Meter,
+    output_level: Meter,
+}
+
+impl Mixer {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            rate: sample_rate as usize * channels as usize,
+            channels: channels.max(1) as usize,
+            gain: AtomicU32::new(1.0f32.to_bits()),
+            mix: Mutex::new(Mix {
+                sources: Vec::new(),
+                duck: 0.0,
+                program: Vec::new(),
+            }),
+            feed: Mutex::new(None),
+            program_level: Meter::new(),
+            output_level: Meter::new(),
+        }
+    }
+
+    pub fn level(&self) -> AudioLevel {
+        AudioLevel {
+            program: self.program_level.get(),
+            output: self.output_level.get(),
+        }
+    }
+
+    /// Samples one second of output takes, across all channels.
+    pub fn samples_per_second(&self) -> usize {
+        self.rate
+    }
+
+    /// Samples one second of output takes on each channel.
+    pub fn sample_rate(&self) -> u32 {
+        (self.rate / self.channels) as u32
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn set_gain(&self, gain: f32) {
+        self.gain.store(gain.max(0.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Copy the program audio, at `gain`, for [`fill_feed`](Self::fill_feed).
+    fn start_feed(&self, gain: f32) {
+        let max = (self.rate as f32 * FEED_SECONDS) as usize;
+        *self.feed.lock().unwrap() = Some(Feed {
+            samples: VecDeque::new(),
+            gain: gain.max(0.0),
+            max: max - max % self.channels,
+        });
+    }
+
+    /// Play `source` until it's closed or only the mixer holds it.
+    pub fn add(&self, source: Arc<Source>) {
+        self.mix.lock().unwrap().sources.push(source);
+    }
+
+    /// Fill `out` with the next samples of every source, added up, for the
+    /// first output.
+    pub fn fill(&self, out: &mut [f32]) {
+        out.fill(0.0);
+        let mut guard = self.mix.lock().unwrap();
+        let mix = &mut *guard;
+        mix.sources.retain(|source| {
+            !source.closed.load(Ordering::SeqCst) && Arc::strong_count(source) > 1
+        });
+
+        let program = mix
+            .sources
+            .iter()
+            .any(|source| source.role == Role::Program && source.is_playing());
+        let step = out.len() as f32 / (self.rate as f32 * DUCK_SECONDS);
+        mix.duck = if program {
+            (mix.duck + step).min(1.0)
+        } else {
+            (mix.duck - step).max(0.0)
+        };
+
+        mix.program.clear();
+        mix.program.resize(out.len(), 0.0);
+        for source in &mix.sources {
+            match source.role {
+                Role::Program => source.mix_into(&mut mix.program, mix.duck),
+                Role::Music => source.mix_into(out, mix.duck),
+            }
+        }
+        let gain = f32::from_bits(self.gain.load(Ordering::Relaxed));
+        for (sample, program) in out.iter_mut().zip(&mix.program) {
+            *sample = ((*sample + program) * gain).clamp(-1.0, 1.0);
+        }
+        let seconds = out.len() as f32 / self.rate as f32;
+        self.program_level.update(&mix.program, seconds);
+        self.output_level.update(out, seconds);
+
+        if let Some(feed) = self.feed.lock().unwrap().as_mut() {
+            feed.samples.extend(
+                mix.program
+                    .iter()
+                    .map(|sample| (sample * feed.gain).clamp(-1.0, 1.0)),
+            );
+            if feed.samples.len() > feed.max {
+                let excess = feed.samples.len() - feed.max;
+                feed.samples.drain(..excess);
+            }
+        }
+    }
+
+    /// Fill `out` with the program audio for the second output.
+    pub fn fill_feed(&self, out: &mut [f32]) {
+        out.fill(0.0);
+        if let Some(feed) = self.feed.lock().unwrap().as_mut() {
+            let len = out.len().min(feed.samples.len());
+            for (out, sample) in out[..len].iter_mut().zip(feed.samples.drain(..len)) {
+                *out = sample;
+            }
+        }
+    }
+}
+
+/// How long a video that should be making sound has been silent, for the
+/// operator's "no sound" warning.
+#[derive(Debug, Default)]
+pub struct SilenceWatch {
+    silent_for: f64,
+    warning: bool,
+}
+
+impl SilenceWatch {
+    /// Add `dt` seconds at program `level`. `expected` is whether there
+    /// should be sound: a video with an audio track, playing and not muted.
+    /// Warns after `after` seconds of silence; 0 never does.
+    pub fn update(&mut self, dt: f64, expected: bool, level: f32, after: f64) {
+        if !expected || level > SILENCE_LEVEL || after <= 0.0 {
+            self.silent_for = 0.0;
+            self.warning = false;
+            return;
+        }
+        self.silent_for += dt;
+        if self.silent_for >= after && !self.warning {
+            log::warn!(
+                "The video has an audio track but no sound for {:.0}s",
+                self.silent_for
+            );
+            self.warning = true;
+        }
+    }
+
+    pub fn warning(&self) -> bool {
+        self.warning
+    }
+}
+
+static OUTPUT: OnceLock<Option<Arc<Mixer>>> = OnceLock::new();
+
+/// Open the outputs in `[audio]`. The first call opens them; after that, and
+/// for [`output`], they stay as they are.
+pub fn open(config: &AudioConfig) -> Option<Arc<Mixer>> {
+    OUTPUT
+        .get_or_init(|| match start(config.clone()) {
+            Ok(mixer) => Some(mixer),
+            Err(e) => {
+                log::warn!(
+                    "Failed to open the audio output: {}, continuing without audio",
+                    e
+                );
+                None
+            }
+        })
+        .clone()
+}
+
+/// The mixer playing to the output device, or `None` without one. Opens the
+/// system default if [`open`] wasn't called first.
+pub fn output() -> Option<Arc<Mixer>> {
+    open(&AudioConfig::default())
+}
+
+/// The levels of the output, silent until it's opened.
+pub fn level() -> AudioLevel {
+    match OUTPUT.get() {
+        Some(Some(mixer)) => mixer.level(),
+        _ => AudioLevel::default(),
+    }
+}
+
+/// The output device called `name`, or the system default.
+pub fn device(name: Option<&str>) -> Result<cpal::Device> {
+    let host = cpal::default_host();
+    let Some(name) = name else {
+        return host
+            .default_output_device()
+            .ok_or_else(|| anyhow!("No audio output device found"));
+    };
+    let mut found = Vec::new();
+    for device in host.output_devices()? {
+        let device_name = device.name().unwrap_or_default();
+        if device_name == name {
+            return Ok(device);
+        }
+        found.push(device_name);
+    }
+    Err(anyhow!(
+        "No audio output device named '{}' (found: {})",
+        name,
+        found.join(", ")
+    ))
+}
+
+/// Open the outputs on a thread of their own, which keeps the streams
+/// playing for the life of the process.
+fn start(config: AudioConfig) -> Result<Arc<Mixer>> {
+    let (opened_tx, opened_rx) = mpsc::channel();
+    thread::Builder::new()
+        .name("audio output".to_string())
+        .spawn(move || match open_streams(&config) {
+            Ok((mixer, _streams)) => {
+                opened_tx.send(Ok(mixer)).ok();
+                loop {
+                    thread::park();
+                }
+            }
+            Err(e) => {
+                opened_tx.send(Err(e)).ok();
+            }
+        })?;
+    opened_rx
+        .recv()
+        .map_err(|_| anyhow!("audio output thread ended"))?
+}
+
+fn open_streams(config: &AudioConfig) -> Result<(Arc<Mixer>, Vec<cpal::Stream>)> {
+    let main = device(config.device.as_deref())?;
+    let supported = main.default_output_config()?;
+    log::info!("Audio output config: {:?}", supported);
+
+    let mixer = Arc::new(Mixer::new(supported.sample_rate().0, supported.channels()));
+    mixer.set_gain(config.gain);
+    let stream_config: cpal::StreamConfig = supported.into();
+    let mixing = mixer.clone();
+    let stream = main.build_output_stream(
+        &stream_config,
+        move |data: &mut [f32], _: &cpal::OutputCallbackInfo| mixing.fill(data),
+        |err| log::error!("Audio stream error: {}", err),
+        None,
+    )?;
+    stream.play()?;
+    log::info!("Audio stream started");
+    let mut streams = vec![stream];
+
+    if let Some(broadcast) = &config.broadcast {
+        // Same rate and channels as the first, so the feed needs no converting
+        let opened =
+            device(Some(broadcast.device.as_str())).and_then(|device| -> Result<cpal::Stream> {
+                let feeding = mixer.clone();
+                let stream = device.build_output_stream(
+                    &stream_config,
+                    move |data: &mut [f32], _: &cpal::OutputCallbackInfo| feeding.fill_feed(data),
+                    |err| log::error!("Broadcast audio stream error: {}", err),
+                    None,
+                )?;
+                stream.play()?;
+                Ok(stream)
+            });
+        match opened {
+            Ok(stream) => {
+                mixer.start_feed(broadcast.gain);
+                streams.push(stream);
+                log::info!("Broadcast audio stream started on {}", broadcast.device);
+            }
+            Err(e) => log::error!(
+                "Failed to open the broadcast output {} at {:?}: {}",
+                broadcast.device,
+                stream_config,
+                e
+            ),
+        }
+    }
+    Ok((mixer, streams))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_music_ducks_under_program() {
+        // 10 samples a second: the duck takes 5
+        let mixer = Mixer::new(10, 1);
+        let stop = AtomicBool::new(false);
+        let music = Arc::new(Source::new(Role::Music));
+        music.set_gain(0.5);
+        music.set_ducked_gain(0.1);
+        mixer.add(music.clone());
+        assert!(music.push(vec![1.0; 100], &stop));
+
+        let mut out = [0.0; 5];
+        mixer.fill(&mut out);
+        assert_eq!(out, [0.5; 5]);
+
+        let program = Arc::new(Source::new(Role::Program));
+        mixer.add(program.clone());
+        assert!(program.push(vec![0.25; 20], &stop));
+        let mut out = [0.0; 1];
+        mixer.fill(&mut out);
+        assert!((out[0] - (0.25 + 0.5 - 0.4 / 5.0)).abs() < 1e-6);
+        let mut out = [0.0; 10];
+        mixer.fill(&mut out);
+        assert!((out[0] - 0.35).abs() < 1e-6);
+
+        // Paused program audio doesn't duck the music
+        program.set_paused(true);
+        let mut out = [0.0; 5];
+        mixer.fill(&mut out);
+        assert!((out[0] - 0.5).abs() < 1e-6);
+
+        // Closed sources leave the mix; so do ones only the mixer holds
+        program.close();
+        assert!(!program.push(vec![0.25], &stop));
+        drop(music);
+        mixer.fill(&mut out);
+        assert_eq!(out, [0.0; 5]);
+        assert!(mixer.mix.lock().unwrap().sources.is_empty());
+    }
+
+    #[test]
+    fn test_broadcast_feed() {
+        // Stereo, 10 frames a second: the feed holds 4 samples
+        let mixer = Mixer::new(10, 2);
+        mixer.set_gain(0.5);
+        mixer.start_feed(2.0);
+        let stop = AtomicBool::new(false);
+        let music = Arc::new(Source::new(Role::Music));
+        music.set_ducked_gain(1.0);
+        mixer.add(music.clone());
+        assert!(music.push(vec![0.25; 6], &stop));
+        let program = Arc::new(Source::new(Role::Program));
+        mixer.add(program.clone());
+        assert!(program.push(vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6], &stop));
+
+        let mut out = [0.0; 6];
+        mixer.fill(&mut out);
+        assert!((out[0] - (0.25 + 0.1) * 0.5).abs() < 1e-6);
+        let level = mixer.level();
+        assert!((level.program - 0.6).abs() < 1e-6);
+        assert!((level.output - 0.425).abs() < 1e-6);
+
+        // The feed gets the program only, at its own gain, and the newest
+        // samples when the second device falls behind
+        let mut feed = [0.0; 6];
+        mixer.fill_feed(&mut feed);
+        let expected = [0.6, 0.8, 1.0, 1.0, 0.0, 0.0];
+        for (sample, expected) in feed.iter().zip(expected) {
+            assert!((sample - expected).abs() < 1e-6);
+        }
+
+        // Muting the output leaves the program level up, and the output
+        // level falling by 10x a second
+        mixer.set_gain(0.0);
+        mixer.fill(&mut out);
+        let level = mixer.level();
+        assert!(level.program > 0.0);
+        assert!((level.output - 0.425 * 0.1f32.powf(0.3)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_silence_warning() {
+        let mut silence = SilenceWatch::default();
+        silence.update(6.0, true, 0.0, 10.0);
+        assert!(!silence.warning());
+        silence.update(6.0, true, 0.0, 10.0);
+        assert!(silence.warning());
+
+        // Sound, a pause or no track starts it over
+        silence.update(0.1, true, 0.2, 10.0);
+        assert!(!silence.warning());
+        silence.update(9.0, true, 0.0, 10.0);
+        silence.update(9.0, false, 0.0, 10.0);
+        silence.update(9.0, true, 0.0, 10.0);
+        assert!(!silence.warning());
+        silence.update(99.0, true, 0.0, 0.0);
+        assert!(!silence.warning());
+    }
+
+    #[test]
+    fn test_push_waits_for_room() {
+        let stop = Arc::new(AtomicBool::new(false));
+        let source = Arc::new(Source::new(Role::Music).with_ahead(4));
+        assert!(source.push(vec![0.0; 3], &stop));
+
+        let pushing = source.clone();
+        let stopping = stop.clone();
+        let handle = thread::spawn(move || pushing.push(vec![0.0; 3], &stopping));
+        thread::sleep(Duration::from_millis(30));
+        assert!(!handle.is_finished());
+        stop.store(true, Ordering::SeqCst);
+        assert!(!handle.join().unwrap());
+    }
+}
@@ -0,0 +1,183 @@
+//! MQTT status and control, built with `--features mqtt` and enabled with
+//! `[mqtt] enabled = true`. Topics live under `[mqtt] topic_prefix`:
+//!
+//! | Topic                | Direction | Payload                                      |
+//! |----------------------|-----------|----------------------------------------------|
+//! | `<prefix>/status`    | out       | player status JSON, retained                 |
+//! | `<prefix>/event`     | out       | one JSON object per player event             |
+//! | `<prefix>/heartbeat` | out       | RFC 3339 timestamp every `heartbeat_seconds` |
+//! | `<prefix>/online`    | out       | `true`, or `false` via last will, retained   |
+//! | `<prefix>/command`   | in        | a line protocol command, e.g. `PLAY 042`     |
+//! | `<prefix>/reply`     | out       | the reply to that command                    |
+
+use crate::line_protocol;
+use rumqttc::{Client, Connection, Event, LastWill, MqttOptions, Packet, QoS};
+use std::thread;
+use std::time::{Duration, Instant};
+use summit_hip_core::config::MqttConfig;
+use summit_hip_core::control::Remote;
+
+/// Wait before polling the connection again after an error; rumqttc
+/// reconnects on the next poll.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+pub fn start(config: &MqttConfig, remote: Remote) -> Result<(), String> {
+    let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        topic(config, "online"),
+        "false",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if let Some(username) = &config.username {
+        options.set_credentials(username, config.password.clone().unwrap_or_default());
+    }
+    let (client, connection) = Client::new(options, 64);
+
+    let (conn_config, conn_client, conn_remote) = (config.clone(), client.clone(), remote.clone());
+    thread::Builder::new()
+        .name("mqtt".to_string())
+        .spawn(move || run_connection(connection, &conn_client, &conn_config, &conn_remote))
+        .map_err(|e| e.to_string())?;
+
+    let config = config.clone();
+    thread::Builder::new()
+        .name("mqtt-publish".to_string())
+        .spawn(move || run_publisher(&client, &config, &remote))
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn topic(config: &MqttConfig, name: &str) -> String {
+    format!("{}/{}", config.topic_prefix.trim_end_matches('/'), name)
+}
+
+/// Drive the connection: (re)subscribe after connecting and answer commands.
+fn run_connection(
+    mut connection: Connection,
+    client: &Client,
+    config: &MqttConfig,
+    remote: &Remote,
+) {
+    for notification in connection.iter() {
+        match notification {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                log::info!("MQTT connected to {}:{}", config.host, config.port);
+                let subscribed = client
+                    .subscribe(topic(config, "command"), QoS::AtLeastOnce)
+                    .and_then(|()| {
+                        client.publish(topic(config, "online"), QoS::AtLeastOnce, true, "true")
+                    });
+                if let Err(e) = subscribed {
+                    log::warn!("MQTT subscribe failed: {}", e);
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                let payload = String::from_utf8_lossy(&publish.payload);
+                if let Some((reply_topic, reply)) =
+                    handle_publish(&publish.topic, &payload, config, remote)
+                {
+                    if let Err(e) = client.publish(reply_topic, QoS::AtLeastOnce, false, reply) {
+                        log::warn!("MQTT reply failed: {}", e);
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                log::warn!("MQTT connection to {}:{}: {}", config.host, config.port, e);
+                thread::sleep(RECONNECT_DELAY);
+            }
+        }
+    }
+}
+
+/// Answer a message on the command topic with the topic and text of the reply.
+fn handle_publish(
+    topic_name: &str,
+    payload: &str,
+    config: &MqttConfig,
+    remote: &Remote,
+) -> Option<(String, String)> {
+    if topic_name != topic(config, "command") {
+        return None;
+    }
+    let reply = line_protocol::respond(payload, remote)?;
+    log::debug!("MQTT command '{}' -> '{}'", payload.trim(), reply);
+    Some((topic(config, "reply"), reply))
+}
+
+/// Publish status changes, events and the heartbeat. Messages are dropped
+/// rather than queued up while the broker is unreachable.
+fn run_publisher(client: &Client, config: &MqttConfig, remote: &Remote) {
+    let events = remote.subscribe();
+    let heartbeat = Duration::from_secs(config.heartbeat_seconds.max(1));
+    let mut last_heartbeat: Option<Instant> = None;
+    let mut last_status = None;
+
+    loop {
+        let result = match events.recv_timeout(Duration::from_secs(1)) {
+            Ok(event) => client.try_publish(
+                topic(config, "event"),
+                QoS::AtLeastOnce,
+                false,
+                serde_json::to_string(&event).unwrap_or_default(),
+            ),
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => Ok(()),
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return,
+        };
+        if let Err(e) = result {
+            log::warn!("MQTT publish failed: {}", e);
+        }
+
+        let status = remote.status();
+        if last_status.as_ref() != Some(&status) {
+            let json = serde_json::to_string(&status).unwrap_or_default();
+            if client
+                .try_publish(topic(config, "status"), QoS::AtLeastOnce, true, json)
+                .is_ok()
+            {
+                last_status = Some(status);
+            }
+        }
+
+        if config.heartbeat_seconds > 0 && last_heartbeat.map_or(true, |t| t.elapsed() >= heartbeat)
+        {
+            let now = chrono::Local::now().to_rfc3339();
+            if client
+                .try_publish(topic(config, "heartbeat"), QoS::AtMostOnce, false, now)
+                .is_ok()
+            {
+                last_heartbeat = Some(Instant::now());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use summit_hip_core::control::{Command, Endpoint};
+
+    #[test]
+    fn test_topics_and_commands() {
+        let config = MqttConfig {
+            topic_prefix: "summit/ring1/".to_string(),
+            ..MqttConfig::default()
+        };
+        assert_eq!(topic(&config, "status"), "summit/ring1/status");
+
+        let endpoint = Endpoint::default();
+        let remote = endpoint.remote();
+        assert_eq!(
+            handle_publish("summit/ring1/command", "blackout on", &config, &remote),
+            Some(("summit/ring1/reply".to_string(), "OK".to_string()))
+        );
+        assert_eq!(endpoint.try_recv(), Some(Command::Blackout(true)));
+        assert_eq!(
+            handle_publish("summit/ring2/command", "PAUSE", &config, &remote),
+            None
+        );
+        assert_eq!(endpoint.try_recv(), None);
+    }
+}
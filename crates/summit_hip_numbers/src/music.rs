@@ -0,0 +1,168 @@
+//! Background music for the splash screen and between videos: the `[music]`
+//! file or folder, looped through the [`mixer`](crate::mixer), which ducks
+//! it while a video with sound plays.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::decode_thread::{self, Outcome};
+use crate::mixer::{self, Role, Source};
+use crate::video_player::decode_audio;
+use summit_hip_core::config::{self, MusicConfig};
+
+const AUDIO_EXTENSIONS: [&str; 7] = ["mp3", "m4a", "aac", "wav", "flac", "ogg", "opus"];
+
+/// Seconds of music decoded ahead of the device.
+const AHEAD_SECONDS: usize = 2;
+
+/// Start the music if `[music]` is enabled; it plays until the player exits.
+pub fn start(config: &MusicConfig) {
+    if !config.enabled {
+        return;
+    }
+    let path = config::resolve_asset_dir(&config.path);
+    let files = playlist(&path);
+    if files.is_empty() {
+        log::warn!("No music to play in {}", path.display());
+        return;
+    }
+    let Some(mixer) = mixer::output() else {
+        return;
+    };
+    log::info!(
+        "Playing {} music file(s) from {}",
+        files.len(),
+        path.display()
+    );
+
+    let source =
+        Arc::new(Source::new(Role::Music).with_ahead(mixer.samples_per_second() * AHEAD_SECONDS));
+    source.set_gain(config.volume.get());
+    source.set_ducked_gain(config.ducked_volume.get());
+    mixer.add(source.clone());
+    let (rate, channels) = (mixer.sample_rate(), mixer.channels() as u16);
+
+    let started = decode_thread::spawn("music", Outcome::new(), move || {
+        let never = AtomicBool::new(false);
+        loop {
+            let mut played = false;
+            for file in &files {
+                let file = file.to_string_lossy();
+                match decode_audio(&file, &source, rate, channels, Duration::ZERO, &never) {
+                    Ok(()) => played = true,
+                    Err(e) => log::warn!("Can't play music {}: {}", file, e),
+                }
+            }
+            if !played {
+                return Err(anyhow::anyhow!("none of the music files can be played"));
+            }
+        }
+    });
+    if let Err(e) = started {
+        log::error!("Failed to start the music: {}", e);
+    }
+}
+
+/// `path` if it's a file, otherwise the audio files in it by name.
+fn playlist(path: &Path) -> Vec<PathBuf> {
+    if path.is_file() {
+        return vec![path.to_path_buf()];
+    }
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|file| {
+            file.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| AUDIO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_playlist() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["b.MP3", "a.wav", "cover.jpg", "notes.txt"] {
+            fs::write(dir.path().join(name), b"").unwrap();
+        }
+        assert_eq!(
+            playlist(dir.path()),
+            vec![dir.path().join("a.wav"), dir.path().join("b.MP3")]
+        );
+        assert_eq!(
+            playlist(&dir.path().join("a.wav")),
+            vec![dir.path().join("a.wav")]
+        );
+        assert!(playlist(&dir.path().join("missing")).is_empty());
+    }
+
+    /// A 16-bit PCM WAV file of `samples` on one channel at `rate`.
+    fn mono_wav(rate: u32, samples: &[i16]) -> Vec<u8> {
+        let data = (samples.len() * 2) as u32;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes()); // channels
+        wav.extend_from_slice(&rate.to_le_bytes());
+        wav.extend_from_slice(&(rate * 2).to_le_bytes()); // bytes a second
+        wav.extend_from_slice(&2u16.to_le_bytes()); // bytes a sample
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data.to_le_bytes());
+        for sample in samples {
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+        wav
+    }
+
+    #[test]
+    fn test_music_resampled_to_device() {
+        ffmpeg_next::init().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("music.wav");
+        // A second at 44.1 kHz on one channel
+        fs::write(&file, mono_wav(44_100, &[16_384; 44_100])).unwrap();
+
+        // Played on a 48 kHz stereo device
+        let mixer = mixer::Mixer::new(48_000, 2);
+        let source = Arc::new(Source::new(Role::Music));
+        mixer.add(source.clone());
+        let never = AtomicBool::new(false);
+        decode_audio(
+            file.to_str().unwrap(),
+            &source,
+            mixer.sample_rate(),
+            mixer.channels() as u16,
+            Duration::ZERO,
+            &never,
+        )
+        .unwrap();
+
+        // Still a second long, on both channels
+        let mut out = vec![0.0; 2 * 96_000];
+        mixer.fill(&mut out);
+        let sounding = out.iter().filter(|sample| sample.abs() > 0.1).count();
+        assert!(
+            (95_000..=96_200).contains(&sounding),
+            "{} samples sound",
+            sounding
+        );
+        for frame in out.chunks(2) {
+            assert!((frame[0] - frame[1]).abs() < 1e-6);
+        }
+    }
+}
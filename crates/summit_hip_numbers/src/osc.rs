@@ -0,0 +1,294 @@
+//! OSC (Open Sound Control) over UDP for QLab, Companion and video switchers,
+//! enabled with `[osc] enabled = true`.
+//!
+//! Incoming messages: `/summit/play <hip>` (string or int), `/summit/next`,
+//! `/summit/previous`, `/summit/undo`, `/summit/pause`, `/summit/resume`,
+//! `/summit/blackout [1|0]` and `/summit/rescan`. When `[osc] send_to` is set,
+//! `/summit/nowplaying <hip> <file>` is sent there whenever a video starts.
+//!
+//! Only the parts of OSC 1.0 needed for this are implemented: int, float,
+//! string and true/false arguments, and bundles, whose time tags are ignored.
+
+use std::net::UdpSocket;
+use std::thread;
+use summit_hip_core::config::OscConfig;
+use summit_hip_core::control::{Command, Event, Remote};
+
+const ADDRESS_PREFIX: &str = "/summit/";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Arg {
+    Int(i32),
+    Float(f32),
+    Str(String),
+    Bool(bool),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Message {
+    pub address: String,
+    pub args: Vec<Arg>,
+}
+
+pub fn start(config: &OscConfig, remote: Remote) -> Result<(), String> {
+    let socket = UdpSocket::bind(&config.bind).map_err(|e| format!("{}: {}", config.bind, e))?;
+
+    if let Some(target) = config.send_to.clone() {
+        let sender = socket.try_clone().map_err(|e| e.to_string())?;
+        let events = remote.subscribe();
+        thread::Builder::new()
+            .name("osc-send".to_string())
+            .spawn(move || {
+                for event in events {
+                    if let Event::VideoStarted { hip, file } = event {
+                        let packet = encode("/summit/nowplaying", &[Arg::Str(hip), Arg::Str(file)]);
+                        if let Err(e) = sender.send_to(&packet, &target) {
+                            log::warn!("Failed to send OSC to {}: {}", target, e);
+                        }
+                    }
+                }
+            })
+            .map_err(|e| e.to_string())?;
+    }
+
+    thread::Builder::new()
+        .name("osc".to_string())
+        .spawn(move || {
+            let mut buf = [0u8; 4096];
+            loop {
+                let (len, from) = match socket.recv_from(&mut buf) {
+                    Ok(received) => received,
+                    Err(e) => {
+                        log::warn!("OSC receive failed: {}", e);
+                        continue;
+                    }
+                };
+                match decode(&buf[..len]) {
+                    Ok(messages) => {
+                        for message in messages {
+                            if let Err(e) = apply(&message, &remote) {
+                                log::warn!("OSC {} from {}: {}", message.address, from, e);
+                            }
+                        }
+                    }
+                    Err(e) => log::warn!("Bad OSC packet from {}: {}", from, e),
+                }
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Turn one incoming message into a player command and send it.
+fn apply(message: &Message, remote: &Remote) -> Result<(), String> {
+    let Some(action) = message.address.strip_prefix(ADDRESS_PREFIX) else {
+        return Err("unknown address".to_string());
+    };
+    let arg = message.args.first();
+    let command = match action {
+        "play" => {
+            let hip = match arg {
+                Some(Arg::Str(hip)) => hip.clone(),
+                Some(Arg::Int(n)) => format!("{:03}", n),
+                Some(Arg::Float(n)) => format!("{:03}", *n as i32),
+                _ => return Err("expected a hip number argument".to_string()),
+            };
+            remote.check_hip(&hip).map_err(|e| e.to_string())?;
+            Command::PlayHip(hip)
+        }
+        "next" => Command::Next,
+        "previous" => Command::Previous,
+        "undo" => Command::Undo,
+        "pause" => Command::Pause,
+        "resume" => Command::Resume,
+        "rescan" => Command::Rescan,
+        "blackout" => Command::Blackout(match arg {
+            None => true,
+            Some(Arg::Int(n)) => *n != 0,
+            Some(Arg::Float(n)) => *n != 0.0,
+            Some(Arg::Bool(on)) => *on,
+            Some(Arg::Str(s)) => !matches!(s.to_ascii_lowercase().as_str(), "0" | "off"),
+        }),
+        _ => return Err("unknown address".to_string()),
+    };
+    remote.send(command)
+}
+
+/// Encode a single message.
+pub fn encode(address: &str, args: &[Arg]) -> Vec<u8> {
+    let mut packet = Vec::new();
+    push_string(&mut packet, address);
+    let tags: String = std::iter::once(',')
+        .chain(args.iter().map(|arg| match arg {
+            Arg::Int(_) => 'i',
+            Arg::Float(_) => 'f',
+            Arg::Str(_) => 's',
+            Arg::Bool(true) => 'T',
+            Arg::Bool(false) => 'F',
+        }))
+        .collect();
+    push_string(&mut packet, &tags);
+    for arg in args {
+        match arg {
+            Arg::Int(n) => packet.extend_from_slice(&n.to_be_bytes()),
+            Arg::Float(n) => packet.extend_from_slice(&n.to_be_bytes()),
+            Arg::Str(s) => push_string(&mut packet, s),
+            Arg::Bool(_) => {}
+        }
+    }
+    packet
+}
+
+fn push_string(packet: &mut Vec<u8>, s: &str) {
+    packet.extend_from_slice(s.as_bytes());
+    // Null terminated, padded to a multiple of 4 bytes
+    packet.resize((packet.len() / 4 + 1) * 4, 0);
+}
+
+/// Decode a packet into its messages; a bundle may hold several.
+pub fn decode(packet: &[u8]) -> Result<Vec<Message>, String> {
+    let mut reader = Reader {
+        data: packet,
+        pos: 0,
+    };
+    if packet.starts_with(b"#bundle\0") {
+        reader.pos = 16; // "#bundle\0" and the time tag
+        let mut messages = Vec::new();
+        while reader.pos < packet.len() {
+            let size = usize::try_from(reader.int()?)
+                .map_err(|_| "negative bundle element size".to_string())?;
+            let element = reader.take(size)?;
+            messages.extend(decode(element)?);
+        }
+        return Ok(messages);
+    }
+
+    let address = reader.string()?;
+    if !address.starts_with('/') {
+        return Err(format!("invalid address '{}'", address));
+    }
+    // Very old senders omit the type tags entirely
+    let tags = if reader.pos < packet.len() {
+        reader.string()?
+    } else {
+        ",".to_string()
+    };
+    let mut args = Vec::new();
+    for tag in tags.chars().skip(1) {
+        args.push(match tag {
+            'i' => Arg::Int(reader.int()?),
+            'f' => Arg::Float(f32::from_bits(reader.int()? as u32)),
+            's' => Arg::Str(reader.string()?),
+            'T' => Arg::Bool(true),
+            'F' => Arg::Bool(false),
+            other => return Err(format!("unsupported argument type '{}'", other)),
+        });
+    }
+    Ok(vec![Message { address, args }])
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).ok_or("packet too short")?;
+        let bytes = self.data.get(self.pos..end).ok_or("packet too short")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn int(&mut self) -> Result<i32, String> {
+        let bytes = self.take(4)?;
+        Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let rest = self.data.get(self.pos..).unwrap_or_default();
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or("unterminated string")?;
+        let s = String::from_utf8_lossy(&rest[..len]).into_owned();
+        self.take((len / 4 + 1) * 4)?;
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use summit_hip_core::control::{Endpoint, Status};
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let args = vec![
+            Arg::Str("042".to_string()),
+            Arg::Int(-7),
+            Arg::Float(0.5),
+            Arg::Bool(true),
+        ];
+        let packet = encode("/summit/nowplaying", &args);
+        assert_eq!(packet.len() % 4, 0);
+        assert_eq!(&packet[..20], b"/summit/nowplaying\0\0");
+        assert_eq!(
+            decode(&packet).unwrap(),
+            vec![Message {
+                address: "/summit/nowplaying".to_string(),
+                args,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_decode_bundle() {
+        let first = encode("/summit/next", &[]);
+        let second = encode("/summit/blackout", &[Arg::Int(0)]);
+        let mut bundle = b"#bundle\0\0\0\0\0\0\0\0\x01".to_vec();
+        for element in [&first, &second] {
+            bundle.extend_from_slice(&(element.len() as i32).to_be_bytes());
+            bundle.extend_from_slice(element);
+        }
+        let messages = decode(&bundle).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].args, vec![Arg::Int(0)]);
+        assert!(decode(&bundle[..bundle.len() - 2]).is_err());
+
+        // Sizes from the network can be anything
+        for size in [-4, i32::MAX] {
+            let mut malformed = b"#bundle\0\0\0\0\0\0\0\0\x01".to_vec();
+            malformed.extend_from_slice(&size.to_be_bytes());
+            malformed.extend_from_slice(&first);
+            assert!(decode(&malformed).is_err());
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let endpoint = Endpoint::default();
+        endpoint.publish(Status {
+            hips: vec!["007".to_string()],
+            ..Status::default()
+        });
+        let remote = endpoint.remote();
+        let message = |address: &str, args: Vec<Arg>| Message {
+            address: address.to_string(),
+            args,
+        };
+
+        apply(&message("/summit/play", vec![Arg::Int(7)]), &remote).unwrap();
+        assert_eq!(
+            endpoint.try_recv(),
+            Some(Command::PlayHip("007".to_string()))
+        );
+        apply(&message("/summit/blackout", vec![Arg::Float(1.0)]), &remote).unwrap();
+        assert_eq!(endpoint.try_recv(), Some(Command::Blackout(true)));
+
+        assert!(apply(&message("/summit/play", vec![Arg::Int(8)]), &remote).is_err());
+        assert!(apply(&message("/summit/play", vec![]), &remote).is_err());
+        assert!(apply(&message("/other/next", vec![]), &remote).is_err());
+        assert_eq!(endpoint.try_recv(), None);
+    }
+}
@@ -0,0 +1,269 @@
+//! Recent frame timings for the performance HUD (F3 in the player) and the
+//! diagnostics bundle: decode time per video frame, texture upload time and
+//! UI frame time, plus frames dropped per second.
+//!
+//! Each series keeps its last [`SAMPLES`] values in a ring buffer, so the
+//! numbers describe the last few seconds rather than the whole run that
+//! [`crate::metrics`] counts for Prometheus.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Values kept per series.
+const SAMPLES: usize = 300;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Series {
+    /// Milliseconds to decode and convert a video frame.
+    Decode,
+    /// Milliseconds to upload a video frame to the GPU.
+    Upload,
+    /// Milliseconds between UI frames.
+    Frame,
+    /// Video frames dropped in each second.
+    Dropped,
+}
+
+impl Series {
+    const ALL: [Series; 4] = [
+        Series::Decode,
+        Series::Upload,
+        Series::Frame,
+        Series::Dropped,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Series::Decode => "decode ms",
+            Series::Upload => "upload ms",
+            Series::Frame => "UI frame ms",
+            Series::Dropped => "dropped/s",
+        }
+    }
+}
+
+/// A fixed number of the latest values.
+#[derive(Clone, Debug, Default)]
+pub struct Ring {
+    values: VecDeque<f32>,
+}
+
+impl Ring {
+    pub fn push(&mut self, value: f32) {
+        if self.values.len() == SAMPLES {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = f32> + '_ {
+        self.values.iter().copied()
+    }
+
+    pub fn last(&self) -> Option<f32> {
+        self.values.back().copied()
+    }
+
+    pub fn max(&self) -> f32 {
+        self.values().fold(0.0, f32::max)
+    }
+
+    pub fn mean(&self) -> f32 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        self.values().sum::<f32>() / self.values.len() as f32
+    }
+
+    /// The value `p` (0.0-1.0) of the way up the sorted values.
+    pub fn percentile(&self, p: f32) -> f32 {
+        let mut sorted: Vec<f32> = self.values().collect();
+        if sorted.is_empty() {
+            return 0.0;
+        }
+        sorted.sort_by(f32::total_cmp);
+        let index = ((sorted.len() - 1) as f32 * p.clamp(0.0, 1.0)).round() as usize;
+        sorted[index]
+    }
+}
+
+struct Stats {
+    series: [Ring; 4],
+    dropped_total: u64,
+    /// Frames dropped since `second_start`, pushed to `Dropped` each second.
+    dropped_this_second: u32,
+    second_start: Option<Instant>,
+}
+
+const EMPTY: Ring = Ring {
+    values: VecDeque::new(),
+};
+
+static STATS: Mutex<Stats> = Mutex::new(Stats {
+    series: [EMPTY; 4],
+    dropped_total: 0,
+    dropped_this_second: 0,
+    second_start: None,
+});
+
+fn stats() -> std::sync::MutexGuard<'static, Stats> {
+    STATS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn millis(time: Duration) -> f32 {
+    time.as_secs_f32() * 1000.0
+}
+
+/// A video frame was decoded in `decode_time`; `dropped` when it was shown
+/// more than a frame late.
+pub fn frame_decoded(decode_time: Duration, dropped: bool) {
+    let mut stats = stats();
+    stats.series[Series::Decode as usize].push(millis(decode_time));
+    if dropped {
+        stats.dropped_total += 1;
+        stats.dropped_this_second += 1;
+    }
+}
+
+pub fn frame_uploaded(upload_time: Duration) {
+    stats().series[Series::Upload as usize].push(millis(upload_time));
+}
+
+/// Called once per UI frame with the time since the previous one.
+pub fn ui_frame(frame_time: Duration) {
+    let mut stats = stats();
+    stats.series[Series::Frame as usize].push(millis(frame_time));
+    let second_start = *stats.second_start.get_or_insert_with(Instant::now);
+    if second_start.elapsed() >= Duration::from_secs(1) {
+        let dropped = stats.dropped_this_second;
+        stats.series[Series::Dropped as usize].push(dropped as f32);
+        stats.dropped_this_second = 0;
+        stats.second_start = Some(Instant::now());
+    }
+}
+
+/// A copy of one series.
+pub fn series(series: Series) -> Ring {
+    stats().series[series as usize].clone()
+}
+
+/// The series summarized as text, for the diagnostics bundle.
+pub fn summary() -> String {
+    let stats = stats();
+    let mut out = format!(
+        "Performance over the last {} samples\nWritten: {}\nDropped frames since start: {}\n\n",
+        SAMPLES,
+        chrono::Local::now().to_rfc3339(),
+        stats.dropped_total
+    );
+    let _ = writeln!(
+        out,
+        "{:<12} {:>8} {:>8} {:>8} {:>8} {:>8}",
+        "series", "samples", "mean", "p95", "max", "last"
+    );
+    for series in Series::ALL {
+        let ring = &stats.series[series as usize];
+        let _ = writeln!(
+            out,
+            "{:<12} {:>8} {:>8.2} {:>8.2} {:>8.2} {:>8.2}",
+            series.label(),
+            ring.values.len(),
+            ring.mean(),
+            ring.percentile(0.95),
+            ring.max(),
+            ring.last().unwrap_or(0.0)
+        );
+    }
+    out
+}
+
+/// Where the player saves [`summary`] for a log written to `log_file`.
+pub fn summary_file(log_file: &Path) -> PathBuf {
+    log_file.parent().unwrap_or(Path::new("")).join("perf.txt")
+}
+
+/// Small graphs of each series, drawn over the player.
+pub fn show_hud(ctx: &egui::Context) {
+    egui::Area::new(egui::Id::new("perf_hud"))
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10.0, 10.0))
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style())
+                .fill(egui::Color32::from_black_alpha(200))
+                .show(ui, |ui| {
+                    for series in Series::ALL {
+                        graph(ui, series, &self::series(series));
+                    }
+                });
+        });
+}
+
+fn graph(ui: &mut egui::Ui, series: Series, ring: &Ring) {
+    ui.label(
+        egui::RichText::new(format!(
+            "{} {:.1} (mean {:.1}, max {:.1})",
+            series.label(),
+            ring.last().unwrap_or(0.0),
+            ring.mean(),
+            ring.max()
+        ))
+        .monospace()
+        .color(egui::Color32::WHITE),
+    );
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(240.0, 36.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, egui::Color32::from_gray(30));
+    let top = ring.max().max(1.0);
+    let points: Vec<egui::Pos2> = ring
+        .values()
+        .enumerate()
+        .map(|(i, value)| {
+            egui::pos2(
+                rect.left() + rect.width() * i as f32 / (SAMPLES - 1) as f32,
+                rect.bottom() - rect.height() * value / top,
+            )
+        })
+        .collect();
+    painter.add(egui::Shape::line(
+        points,
+        egui::Stroke::new(1.0, egui::Color32::LIGHT_GREEN),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring() {
+        let mut ring = Ring::default();
+        assert_eq!(
+            (ring.mean(), ring.max(), ring.percentile(0.95)),
+            (0.0, 0.0, 0.0)
+        );
+        for value in 0..SAMPLES + 10 {
+            ring.push(value as f32);
+        }
+        assert_eq!(ring.values().count(), SAMPLES);
+        assert_eq!(ring.values().next(), Some(10.0));
+        assert_eq!(ring.last(), Some((SAMPLES + 9) as f32));
+        assert_eq!(ring.max(), (SAMPLES + 9) as f32);
+        assert_eq!(ring.percentile(0.0), 10.0);
+        assert_eq!(ring.percentile(1.0), ring.max());
+    }
+
+    #[test]
+    fn test_summary() {
+        frame_decoded(Duration::from_millis(4), true);
+        frame_uploaded(Duration::from_millis(1));
+        ui_frame(Duration::from_millis(16));
+        let summary = summary();
+        // Other tests may record frames too, so only check the layout
+        for label in ["decode ms", "upload ms", "UI frame ms", "dropped/s"] {
+            assert!(summary.contains(label), "{}", label);
+        }
+        assert!(summary.contains("Dropped frames since start: "));
+    }
+}
@@ -0,0 +1,87 @@
+//! The mouse outside a normal window (`[ui] hide_cursor` and
+//! `ignore_video_clicks`): the cursor hides once the mouse is left alone, and
+//! clicks on the video are dropped before egui sees them, so a bump of the
+//! mouse on a kiosk can't pause, drag or focus anything.
+
+use eframe::egui;
+use summit_hip_core::config::HideCursor;
+
+/// Whether the cursor shows, from frame to frame.
+#[derive(Debug, Default)]
+pub struct CursorHider {
+    /// Seconds since the mouse last moved or clicked.
+    still_for: f64,
+}
+
+impl CursorHider {
+    /// Advance by a frame in which the mouse was `used` or not, and say
+    /// whether the cursor is hidden now.
+    pub fn update(&mut self, dt: f64, used: bool, mode: HideCursor, idle: f64) -> bool {
+        self.still_for = if used { 0.0 } else { self.still_for + dt };
+        match mode {
+            HideCursor::Never => false,
+            HideCursor::Idle => self.still_for >= idle,
+            HideCursor::Always => true,
+        }
+    }
+
+    /// Seconds until the cursor hides, while it shows and is going to.
+    pub fn hides_in(&self, mode: HideCursor, idle: f64) -> Option<f64> {
+        (mode == HideCursor::Idle && self.still_for < idle).then_some(idle - self.still_for)
+    }
+}
+
+/// Drop the mouse presses and releases that `on_video` says land on the
+/// video.
+pub fn drop_video_clicks(events: &mut Vec<egui::Event>, on_video: impl Fn(egui::Pos2) -> bool) {
+    events
+        .retain(|event| !matches!(event, egui::Event::PointerButton { pos, .. } if on_video(*pos)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_hider() {
+        let mut cursor = CursorHider::default();
+        assert!(!cursor.update(1.0, false, HideCursor::Idle, 3.0));
+        assert_eq!(cursor.hides_in(HideCursor::Idle, 3.0), Some(2.0));
+        assert!(cursor.update(2.0, false, HideCursor::Idle, 3.0));
+        assert_eq!(cursor.hides_in(HideCursor::Idle, 3.0), None);
+
+        // Moving the mouse shows it again
+        assert!(!cursor.update(0.1, true, HideCursor::Idle, 3.0));
+        assert_eq!(cursor.hides_in(HideCursor::Idle, 3.0), Some(3.0));
+        assert!(!cursor.update(10.0, false, HideCursor::Never, 3.0));
+        assert_eq!(cursor.hides_in(HideCursor::Never, 3.0), None);
+        assert!(cursor.update(0.1, true, HideCursor::Always, 3.0));
+    }
+
+    #[test]
+    fn test_drop_video_clicks() {
+        let click = |x: f32, pressed: bool| egui::Event::PointerButton {
+            pos: egui::pos2(x, 10.0),
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::NONE,
+        };
+        let key = egui::Event::Text("7".to_string());
+        let mut events = vec![
+            click(50.0, true),
+            egui::Event::PointerMoved(egui::pos2(50.0, 10.0)),
+            click(50.0, false),
+            key.clone(),
+            click(150.0, true),
+        ];
+        drop_video_clicks(&mut events, |pos| pos.x < 100.0);
+        assert_eq!(
+            events,
+            [
+                egui::Event::PointerMoved(egui::pos2(50.0, 10.0)),
+                key,
+                click(150.0, true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use crate::license;
+use eframe::egui;
+use summit_hip_core::config::Config;
+
+/// Font size the player uses for the bottom bar text (egui's body style).
+const BAR_FONT_SIZE: f32 = 14.0;
+
+/// Paint a scaled-down mock of the player window for the given settings:
+/// the video area (or splash screen) above the bottom bar, using the
+/// configured ratios, colors, labels and font sizes.
+pub fn show(ui: &mut egui::Ui, config: &Config, splash: bool) {
+    let ui_config = &config.ui;
+    let window_width = ui_config.window_width.max(1.0);
+    let window_height = ui_config.window_height.max(1.0);
+
+    let width = ui.available_width();
+    let (rect, _) = ui.allocate_exact_size(
+        egui::vec2(width, width * window_height / window_width),
+        egui::Sense::hover(),
+    );
+    let scale = rect.width() / window_width;
+    let painter = ui.painter_at(rect);
+    let font = |size: f32| egui::FontId::proportional((size * scale).max(1.0));
+
+    let video_rect = egui::Rect::from_min_size(
+        rect.min,
+        egui::vec2(
+            rect.width(),
+            rect.height() * ui_config.video_height_ratio.get(),
+        ),
+    );
+    let bar_rect = egui::Rect::from_min_size(
+        egui::pos2(rect.min.x, video_rect.bottom()),
+        egui::vec2(
+            rect.width(),
+            rect.height() * ui_config.bar_height_ratio.get(),
+        ),
+    );
+
+    if splash {
+        painter.rect_filled(video_rect, 0.0, config.splash.background_color.color32());
+        painter.text(
+            video_rect.center(),
+            egui::Align2::CENTER_CENTER,
+            &config.splash.text,
+            font(ui_config.splash_font_size),
+            config.splash.text_color.color32(),
+        );
+    } else {
+        painter.rect_filled(video_rect, 0.0, ui_config.background_color.color32());
+        painter.text(
+            video_rect.center(),
+            egui::Align2::CENTER_CENTER,
+            "🎬 VIDEO DISPLAY AREA",
+            font(ui_config.placeholder_font_size),
+            ui_config.label_color.color32(),
+        );
+
+        if license::status().is_demo() {
+            painter.text(
+                egui::pos2(
+                    video_rect.right() - ui_config.demo_watermark_x_offset * scale,
+                    video_rect.top() + ui_config.demo_watermark_y_offset * scale,
+                ),
+                egui::Align2::LEFT_TOP,
+                "DEMO ONLY",
+                font(ui_config.demo_watermark_font_size),
+                egui::Color32::from_rgb(255, 0, 0),
+            );
+        }
+    }
+
+    painter.rect_filled(bar_rect, 0.0, ui_config.background_color.color32());
+    let spacing = ui_config.ui_spacing * scale;
+    let label_color = ui_config.label_color.color32();
+
+    // Left: input label above the outlined input field
+    let label = painter.text(
+        egui::pos2(bar_rect.left() + spacing, bar_rect.top() + spacing / 2.0),
+        egui::Align2::LEFT_TOP,
+        &ui_config.input_label,
+        font(BAR_FONT_SIZE),
+        label_color,
+    );
+    let input_rect = egui::Rect::from_min_size(
+        egui::pos2(label.left(), label.bottom() + 2.0 * scale),
+        egui::vec2(
+            ui_config.input_field_width * scale,
+            BAR_FONT_SIZE * 1.2 * scale,
+        ),
+    );
+    painter.rect_stroke(
+        input_rect,
+        0.0,
+        egui::Stroke::new(
+            (ui_config.stroke_width * scale).max(0.5),
+            ui_config.input_stroke_color.color32(),
+        ),
+    );
+    painter.text(
+        input_rect.left_center(),
+        egui::Align2::LEFT_CENTER,
+        "001",
+        font(BAR_FONT_SIZE),
+        ui_config.input_text_color.color32(),
+    );
+
+    // Center: now playing
+    painter.text(
+        bar_rect.center(),
+        egui::Align2::CENTER_CENTER,
+        format!("{} 001_example.mp4", ui_config.now_playing_label),
+        font(BAR_FONT_SIZE),
+        label_color,
+    );
+
+    // Right: company label standing in for the logo
+    painter.text(
+        egui::pos2(bar_rect.right() - spacing * 3.0, bar_rect.center().y),
+        egui::Align2::RIGHT_CENTER,
+        &ui_config.company_label,
+        font(BAR_FONT_SIZE),
+        label_color,
+    );
+
+    ui.label(format!(
+        "{} x {} window, {:.0}% video / {:.0}% bar",
+        ui_config.window_width,
+        ui_config.window_height,
+        ui_config.video_height_ratio.get() * 100.0,
+        ui_config.bar_height_ratio.get() * 100.0
+    ));
+}
@@ -0,0 +1,76 @@
+//! What FFmpeg can tell about a video without playing it: which codecs it
+//! needs, and when this build can't decode one, what to re-encode it to.
+//! The player probes its library at scan time, `--doctor` on request.
+
+use ffmpeg_next as ffmpeg;
+use std::fmt;
+
+/// Why a video can't be played.
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    /// FFmpeg can't open or read the file.
+    Unreadable(String),
+    NoVideoStream,
+    /// No decoder for the codec of its video or audio stream.
+    UnsupportedVideo(String),
+    UnsupportedAudio(String),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Unreadable(e) => write!(f, "can't be read ({})", e),
+            Problem::NoVideoStream => write!(f, "no video stream"),
+            Problem::UnsupportedVideo(codec) => {
+                write!(f, "unsupported codec ({}) — re-encode to H.264", codec)
+            }
+            Problem::UnsupportedAudio(codec) => {
+                write!(
+                    f,
+                    "unsupported audio codec ({}) — re-encode the audio to AAC",
+                    codec
+                )
+            }
+        }
+    }
+}
+
+/// Open `path` like the player does and return the codecs it needs.
+pub fn codecs(path: &str) -> Result<Vec<&'static str>, Problem> {
+    ffmpeg::init().map_err(|e| Problem::Unreadable(e.to_string()))?;
+    let ictx = ffmpeg::format::input(path).map_err(|e| Problem::Unreadable(e.to_string()))?;
+    let video = ictx
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or(Problem::NoVideoStream)?;
+    let name = video.parameters().id().name();
+    ffmpeg::codec::context::Context::from_parameters(video.parameters())
+        .and_then(|context| context.decoder().video())
+        .map_err(|_| Problem::UnsupportedVideo(name.to_string()))?;
+    let mut codecs = vec![name];
+    if let Some(audio) = ictx.streams().best(ffmpeg::media::Type::Audio) {
+        let name = audio.parameters().id().name();
+        ffmpeg::codec::context::Context::from_parameters(audio.parameters())
+            .and_then(|context| context.decoder().audio())
+            .map_err(|_| Problem::UnsupportedAudio(name.to_string()))?;
+        codecs.push(name);
+    }
+    Ok(codecs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_problem_messages() {
+        assert_eq!(
+            Problem::UnsupportedVideo("prores".to_string()).to_string(),
+            "unsupported codec (prores) — re-encode to H.264"
+        );
+        assert_eq!(
+            Problem::UnsupportedAudio("truehd".to_string()).to_string(),
+            "unsupported audio codec (truehd) — re-encode the audio to AAC"
+        );
+    }
+}
@@ -0,0 +1,1215 @@
+//! The player window: reads the keys into the [`AppState`], uploads the
+//! decoded frames and splash images as textures, and draws the video, the
+//! input bar and the popups over them.
+
+use crate::app_state::AppState;
+use crate::clock;
+use crate::crash;
+use crate::focus::FocusGuard;
+use crate::input::FrameInput;
+use crate::mixer;
+use crate::music;
+use crate::perf;
+use crate::pointer::{self, CursorHider};
+use crate::resources::{self, Kind, Lru, Usage};
+use crate::test_pattern::{self, Tone};
+use crate::thumbnails::{self, Thumbnails};
+use crate::video_texture::VideoTexture;
+use eframe::egui;
+use log::{error, info, warn};
+use std::path::{Path, PathBuf};
+use summit_hip_core::analytics;
+use summit_hip_core::config::{self, BarWidget, KioskStyle};
+use summit_hip_core::control::{self, TestPattern};
+use summit_hip_core::playback::{Adjustment, PlayerState};
+use summit_hip_core::review::Review;
+
+pub struct MediaPlayerApp {
+    pub state: AppState,
+    video: VideoTexture,
+    video_usage: Usage<'static>,
+    /// The splash image on screen, and the file it came from.
+    splash_texture: Option<(PathBuf, egui::TextureHandle)>,
+    splash_usage: Usage<'static>,
+    thumbnails: Thumbnails,
+    /// Thumbnails loaded for the grid and as posters, by file.
+    thumbnail_textures: ThumbnailTextures,
+    /// The thumbnail shown while the video loads, and the file it came from.
+    poster_texture: Option<(PathBuf, egui::TextureHandle)>,
+    logo_uri: Option<String>,
+    /// Set once the UI loop has panicked.
+    restart_screen: Option<crash::RestartScreen>,
+    focus: FocusGuard,
+    /// The test pattern on screen, and the pixel size it was drawn at.
+    pattern_texture: Option<(TestPattern, [usize; 2], egui::TextureHandle)>,
+    test_tone: Option<Tone>,
+    cursor: CursorHider,
+    /// Where the video was drawn last frame.
+    video_rect: egui::Rect,
+    /// The ring the window was placed for.
+    placed_ring: usize,
+}
+
+impl MediaPlayerApp {
+    pub fn new(cc: &eframe::CreationContext, config_path: Option<&Path>) -> Self {
+        let ctx = &cc.egui_ctx;
+        let state = AppState::new(config_path);
+        state.playback.frames().repaint(ctx);
+        resources::set_limits(&state.config.resources);
+        mixer::open(&state.config.audio);
+        music::start(&state.config.music);
+        let thumbnails =
+            Thumbnails::start(&state.playback.video_files, thumbnails::dir(&state.config));
+        Self {
+            state,
+            video: VideoTexture::new(cc.wgpu_render_state.clone()),
+            video_usage: Usage::new(Kind::Textures),
+            splash_texture: None,
+            splash_usage: Usage::new(Kind::Textures),
+            thumbnails,
+            thumbnail_textures: Lru::new(Kind::Textures),
+            poster_texture: None,
+            logo_uri: load_logo(),
+            restart_screen: None,
+            focus: FocusGuard::default(),
+            pattern_texture: None,
+            test_tone: None,
+            cursor: CursorHider::default(),
+            video_rect: egui::Rect::NOTHING,
+            placed_ring: 0,
+        }
+    }
+
+    /// One frame of the player, run by `update` so a panic in it can be
+    /// caught.
+    fn show_player(&mut self, ctx: &egui::Context) {
+        self.state.heartbeat.beat(&self.state.config.heartbeat);
+        if let Some(reason) = &self.state.lock_error {
+            egui::CentralPanel::default()
+                .frame(egui::Frame::none().fill(egui::Color32::BLACK))
+                .show(ctx, |ui| {
+                    ui.centered_and_justified(|ui| {
+                        ui.label(
+                            egui::RichText::new(format!(
+                                "Configuration locked\n\n{}\n\nContact your administrator.",
+                                reason
+                            ))
+                            .color(egui::Color32::WHITE)
+                            .size(24.0),
+                        );
+                    });
+                });
+            // Keep beating; a restart won't unlock the config
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+            return;
+        }
+
+        let dt = ctx.input(|i| i.unstable_dt);
+        perf::ui_frame(std::time::Duration::from_secs_f32(dt.max(0.0)));
+        self.state.advance_timers(dt as f64);
+        self.keep_focus(ctx, dt as f64);
+        self.load_splash_texture(ctx);
+        self.state.handle_input(&frame_input(ctx));
+        self.place_for_ring(ctx);
+        self.state.advance_playback();
+
+        if self.state.frames.has_changed().unwrap_or(false) {
+            if let Some(image) = self.state.frames.borrow().clone() {
+                let upload_start = std::time::Instant::now();
+                self.video_usage.set(texture_bytes(&image));
+                // Cached thumbnails give way to the video
+                self.thumbnail_textures.make_room(0);
+                self.video.upload(ctx, image);
+                perf::frame_uploaded(upload_start.elapsed());
+            }
+        }
+        self.load_poster(ctx);
+
+        if self.state.show_perf_hud {
+            perf::show_hud(ctx);
+            // The HUD measures the frame rate the UI can reach
+            ctx.request_repaint();
+        } else {
+            // New frames and input wake the window sooner
+            ctx.request_repaint_after(self.state.repaint_after());
+        }
+        self.state.control.publish(self.state.status());
+
+        if self.state.blackout {
+            egui::CentralPanel::default()
+                .frame(egui::Frame::none().fill(egui::Color32::BLACK))
+                .show(ctx, |_| {});
+            return;
+        }
+        if let Some(pattern) = self.state.test_pattern {
+            self.show_test_pattern(ctx, pattern);
+            return;
+        }
+        self.pattern_texture = None;
+        self.test_tone = None;
+        self.draw(ctx);
+        if self.state.show_browser {
+            self.show_browser(ctx);
+        }
+    }
+
+    /// Take the focus, and fullscreen, back from another program under
+    /// `[ui] keep_focus`.
+    fn keep_focus(&mut self, ctx: &egui::Context, dt: f64) {
+        let ui = &self.state.config.ui;
+        if !ui.keep_focus {
+            return;
+        }
+        let want_fullscreen = ui.kiosk_style() == KioskStyle::Fullscreen;
+        let (focused, fullscreen) = ctx.input(|i| {
+            let viewport = i.viewport();
+            (
+                viewport.focused,
+                viewport.fullscreen.filter(|_| want_fullscreen),
+            )
+        });
+        let check = self.focus.check(dt, focused, fullscreen);
+        for lost in check.stolen {
+            warn!("Another program took the player's {}; taking it back", lost);
+            self.state.control.emit(control::Event::FocusStolen {
+                lost: lost.to_string(),
+            });
+        }
+        if let Some(seconds) = check.regained_after {
+            info!("The player is back in front after {:.1}s", seconds);
+        }
+        if check.reclaim {
+            if want_fullscreen {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+            }
+            if ui.always_on_top {
+                ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(
+                    egui::WindowLevel::AlwaysOnTop,
+                ));
+            }
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+    }
+
+    /// Move the window to where the ring switched to has it, e.g. onto
+    /// that ring's monitor.
+    fn place_for_ring(&mut self, ctx: &egui::Context) {
+        if self.placed_ring == self.state.ring {
+            return;
+        }
+        self.placed_ring = self.state.ring;
+        let ui = &self.state.config.ui;
+        let position = match (ui.window_x, ui.window_y) {
+            (None, None) => return,
+            (x, y) => egui::pos2(x.unwrap_or(0.0), y.unwrap_or(0.0)),
+        };
+        info!("Moving the window to {},{}", position.x, position.y);
+        // A fullscreen window stays on its monitor until it's a window again
+        let fullscreen = ui.kiosk_style() == KioskStyle::Fullscreen;
+        if fullscreen {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(false));
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(position));
+        if fullscreen {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+        }
+    }
+
+    /// `pattern` over the whole window, pixel for pixel, with the tone.
+    fn show_test_pattern(&mut self, ctx: &egui::Context, pattern: TestPattern) {
+        if self.test_tone.is_none() {
+            self.test_tone = Tone::start();
+        }
+        let pixels = ctx.screen_rect().size() * ctx.pixels_per_point();
+        let size = [pixels.x.round() as usize, pixels.y.round() as usize];
+        let stale = self
+            .pattern_texture
+            .as_ref()
+            .map_or(true, |(shown, shown_size, _)| {
+                (*shown, *shown_size) != (pattern, size)
+            });
+        if stale {
+            let mut image = test_pattern::image(pattern, size);
+            if let Some(adjustment) = Adjustment::new(&self.state.config.output) {
+                adjustment.apply(&mut image);
+            }
+            let texture = ctx.load_texture("test_pattern", image, egui::TextureOptions::NEAREST);
+            self.pattern_texture = Some((pattern, size, texture));
+        }
+        let Some((_, _, texture)) = &self.pattern_texture else {
+            return;
+        };
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none().fill(egui::Color32::BLACK))
+            .show(ctx, |ui| {
+                ui.painter().image(
+                    texture.id(),
+                    ui.max_rect(),
+                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    egui::Color32::WHITE,
+                );
+            });
+    }
+
+    /// Hide the cursor under `[ui] hide_cursor`, last in the frame so no
+    /// widget's hover cursor shows over it.
+    fn hide_cursor(&mut self, ctx: &egui::Context) {
+        let ui = &self.state.config.ui;
+        let mode = match ui.kiosk_style() {
+            KioskStyle::Windowed => config::HideCursor::Never,
+            _ => ui.hide_cursor,
+        };
+        let idle = ui.cursor_idle().as_secs_f64();
+        let (dt, used) = ctx.input(|i| {
+            (
+                i.unstable_dt as f64,
+                i.pointer.delta() != egui::Vec2::ZERO || i.pointer.any_down(),
+            )
+        });
+        if self.cursor.update(dt, used, mode, idle) {
+            ctx.set_cursor_icon(egui::CursorIcon::None);
+        } else if let Some(seconds) = self.cursor.hides_in(mode, idle) {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(seconds));
+        }
+    }
+
+    /// The thumbnail grid: every hip, with its picture where there is one,
+    /// scrolled through and clicked to play.
+    fn show_browser(&mut self, ctx: &egui::Context) {
+        if !self.thumbnails.is_for(&self.state.playback.video_files) {
+            // Rescanned since
+            self.thumbnails = Thumbnails::start(
+                &self.state.playback.video_files,
+                thumbnails::dir(&self.state.config),
+            );
+            self.thumbnail_textures.clear();
+        }
+        let Self {
+            state,
+            thumbnails,
+            thumbnail_textures,
+            ..
+        } = self;
+        let width = thumbnails::WIDTH as f32;
+        let tile = egui::vec2(width, width * 9.0 / 16.0);
+        let mut loads = THUMBNAILS_PER_FRAME;
+        let mut picked = None;
+        let mut open = true;
+        egui::Window::new("Hips")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .fixed_size(ctx.screen_rect().size() * 0.8)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        for hip in state.playback.hips() {
+                            let mut texture = None;
+                            if let Some(Some(path)) = thumbnails.get(&hip) {
+                                match thumbnail_textures.get(&path) {
+                                    Some(cached) => texture = cached.clone(),
+                                    None if loads > 0 => {
+                                        loads -= 1;
+                                        texture = load_thumbnail(ctx, &path, thumbnail_textures);
+                                    }
+                                    None => {}
+                                }
+                            }
+                            let label = state
+                                .playback
+                                .hip_overrides
+                                .get(&hip)
+                                .and_then(|o| o.label.clone())
+                                .unwrap_or_else(|| hip.clone());
+                            ui.vertical(|ui| {
+                                ui.set_width(tile.x);
+                                let button = match &texture {
+                                    Some(texture) => ui.add(egui::ImageButton::new(
+                                        egui::Image::new(egui::load::SizedTexture::from_handle(
+                                            texture,
+                                        ))
+                                        .fit_to_exact_size(tile),
+                                    )),
+                                    _ => ui.add_sized(tile, egui::Button::new(&hip)),
+                                };
+                                ui.label(label);
+                                if button.clicked() {
+                                    picked = Some(hip.clone());
+                                }
+                            });
+                        }
+                    });
+                });
+            });
+        if loads == 0 {
+            // More to load next frame
+            ctx.request_repaint();
+        }
+        if !open {
+            state.show_browser = false;
+        }
+        if let Some(hip) = picked {
+            state.choose_from_browser(&hip);
+        }
+    }
+
+    /// Load the poster of the video loading, if it has one, so it shows
+    /// instead of the last video's frame until the first frame arrives.
+    fn load_poster(&mut self, ctx: &egui::Context) {
+        let poster = match self.state.playback.state() {
+            PlayerState::Loading => self
+                .state
+                .playback
+                .current()
+                .and_then(|video| self.thumbnails.poster(video)),
+            _ => None,
+        };
+        let Some(path) = poster else {
+            self.poster_texture = None;
+            return;
+        };
+        if self
+            .poster_texture
+            .as_ref()
+            .is_some_and(|(loaded, _)| *loaded == path)
+        {
+            return;
+        }
+        let texture = match self.thumbnail_textures.get(&path) {
+            Some(cached) => cached.clone(),
+            None => load_thumbnail(ctx, &path, &mut self.thumbnail_textures),
+        };
+        self.poster_texture = texture.map(|texture| (path, texture));
+    }
+
+    /// Load the splash image to show, if it isn't loaded already.
+    fn load_splash_texture(&mut self, ctx: &egui::Context) {
+        let splash = &self.state.splash;
+        if !splash.showing {
+            self.splash_texture = None;
+            self.splash_usage.set(0);
+            return;
+        }
+        if splash.timer >= self.state.config.splash.duration_seconds.get() {
+            return;
+        }
+        let Some(path) = splash.image() else {
+            return;
+        };
+        if self
+            .splash_texture
+            .as_ref()
+            .is_some_and(|(loaded, _)| loaded == path)
+        {
+            return;
+        }
+        match image::open(path) {
+            Ok(img) => {
+                // Cached thumbnails give way to the splash image
+                let bytes = img.width() as u64 * img.height() as u64 * 4;
+                self.thumbnail_textures
+                    .make_room(bytes.saturating_sub(self.splash_usage.bytes()));
+                let img = fit_texture_room(
+                    img,
+                    resources::room(Kind::Textures) + self.splash_usage.bytes(),
+                    path,
+                );
+                let rgba = img.to_rgba8();
+                let size = [rgba.width() as usize, rgba.height() as usize];
+                let pixels = rgba.into_raw();
+                let color_image = egui::ColorImage::from_rgba_unmultiplied(size, &pixels);
+                self.splash_usage.set(texture_bytes(&color_image));
+                let texture = ctx.load_texture("splash", color_image, Default::default());
+                self.splash_texture = Some((path.to_path_buf(), texture));
+                info!("Loaded splash image {}", path.display());
+            }
+            Err(e) => {
+                self.splash_texture = None;
+                self.splash_usage.set(0);
+                error!("Failed to load splash image {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &egui::Context) {
+        let Self {
+            state,
+            video,
+            splash_texture,
+            poster_texture,
+            logo_uri,
+            video_rect: drawn_video_rect,
+            ..
+        } = self;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let available_rect = ui.max_rect();
+            let video_height = available_rect.height() * state.config.ui.video_height_ratio.get();
+            let video_rect = egui::Rect::from_min_size(
+                available_rect.min,
+                egui::vec2(available_rect.width(), video_height),
+            );
+            *drawn_video_rect = video_rect;
+
+            ui.allocate_new_ui(egui::UiBuilder::new().max_rect(video_rect), |ui| {
+                if state.splash.showing {
+                    let bg_color = state.config.splash.background_color.color32();
+                    ui.painter().rect_filled(ui.max_rect(), 0.0, bg_color);
+                    if let Some((_, texture)) = splash_texture {
+                        ui.centered_and_justified(|ui| {
+                            ui.image((texture.id(), ui.available_size()));
+                        });
+                    } else {
+                        // No text splash - just show black screen while loading
+                        // This ensures we only show image splashes
+                    }
+                } else {
+                    ui.painter().rect_filled(
+                        ui.max_rect(),
+                        0.0,
+                        state.config.ui.background_color.color32(),
+                    );
+                    let poster = poster_texture.as_ref().map(|(_, texture)| texture.id());
+                    if let Some(id) = poster.or(video.id()) {
+                        ui.image((id, ui.available_size()));
+                    } else {
+                        ui.centered_and_justified(|ui| {
+                            ui.label(
+                                egui::RichText::new("🎬 VIDEO DISPLAY AREA")
+                                    .size(state.config.ui.placeholder_font_size)
+                                    .color(state.config.ui.label_color.color32()),
+                            );
+                        });
+                    }
+                    show_player_state(ui, state, poster.is_some());
+
+                    // Demo mode watermark
+                    if state.demo {
+                        ui.allocate_new_ui(
+                            egui::UiBuilder::new().max_rect(egui::Rect::from_min_size(
+                                egui::pos2(
+                                    video_rect.right() - state.config.ui.demo_watermark_x_offset,
+                                    video_rect.top() + state.config.ui.demo_watermark_y_offset,
+                                ),
+                                egui::vec2(
+                                    state.config.ui.demo_watermark_width,
+                                    state.config.ui.demo_watermark_height,
+                                ),
+                            )),
+                            |ui| {
+                                ui.label(
+                                    egui::RichText::new("DEMO ONLY")
+                                        .size(state.config.ui.demo_watermark_font_size)
+                                        .color(egui::Color32::from_rgb(255, 0, 0))
+                                        .strong(),
+                                );
+                            },
+                        );
+                    }
+                }
+
+                show_entry_overlay(ui, state, video_rect);
+
+                if let Some(days_left) = state.trial_days_left {
+                    let text = match days_left {
+                        1 => "Trial version: last day".to_string(),
+                        _ => format!("Trial version: {} days left", days_left),
+                    };
+                    show_banner(ui, video_rect, text);
+                }
+            });
+
+            let bar_height = available_rect.height() * state.config.ui.bar_height_ratio.get();
+            let bar_rect = egui::Rect::from_min_size(
+                egui::pos2(available_rect.min.x, available_rect.min.y + video_height),
+                egui::vec2(available_rect.width(), bar_height),
+            );
+
+            ui.painter()
+                .rect_filled(bar_rect, 0.0, state.config.ui.background_color.color32());
+
+            show_bar(ui, state, bar_rect, logo_uri.is_some());
+        });
+
+        if let Some(hip) = &state.no_video_hip {
+            egui::Window::new("No Video Available")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    match &state.no_video_reason {
+                        Some(reason) => ui.label(reason),
+                        None => ui.label(format!("No video available for hip number {}.", hip)),
+                    };
+                    ui.label("Please try another number.");
+                });
+        }
+
+        if let Some(review) = &state.review {
+            show_review(ctx, review);
+        }
+
+        if let Some(offer) = &state.resume_offer {
+            let mut answer = None;
+            egui::Window::new("Resume?")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    let at = offer.state.position_seconds as u64;
+                    match offer.state.queued {
+                        Some(_) => {
+                            ui.label(format!("The player stopped just before hip {}.", offer.hip))
+                        }
+                        None => ui.label(format!(
+                            "The player stopped at hip {}, {}:{:02} in.",
+                            offer.hip,
+                            at / 60,
+                            at % 60
+                        )),
+                    };
+                    ui.horizontal(|ui| {
+                        if ui.button("Resume").clicked() {
+                            answer = Some(true);
+                        }
+                        if ui.button("Start over").clicked() {
+                            answer = Some(false);
+                        }
+                    });
+                    ui.label(format!(
+                        "Resuming in {} s",
+                        offer.seconds_left.ceil().max(0.0)
+                    ));
+                });
+            if let Some(resume) = answer {
+                state.answer_resume(resume);
+            }
+        }
+
+        if let Some(hip) = &state.swap_offer {
+            let mut answer = None;
+            egui::Window::new("Video replaced")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -40.0))
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "Hip {}'s video was replaced on disk while it played.",
+                        hip
+                    ));
+                    ui.horizontal(|ui| {
+                        if ui.button("Swap in the new video").clicked() {
+                            answer = Some(true);
+                        }
+                        if ui.button("Keep playing").clicked() {
+                            answer = Some(false);
+                        }
+                    });
+                });
+            if let Some(swap) = answer {
+                state.answer_swap(swap);
+            }
+        }
+    }
+}
+
+impl eframe::App for MediaPlayerApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.state.end_session();
+    }
+
+    /// Drop clicks on the video under `[ui] ignore_video_clicks`, leaving
+    /// those on the popups over it.
+    fn raw_input_hook(&mut self, ctx: &egui::Context, raw_input: &mut egui::RawInput) {
+        let ui = &self.state.config.ui;
+        if !ui.ignore_video_clicks || ui.kiosk_style() == KioskStyle::Windowed {
+            return;
+        }
+        let video_rect = self.video_rect;
+        pointer::drop_video_clicks(&mut raw_input.events, |pos| {
+            video_rect.contains(pos)
+                && ctx
+                    .layer_id_at(pos)
+                    .map_or(true, |layer| layer.order == egui::Order::Background)
+        });
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if let Some(screen) = &self.restart_screen {
+            screen.show(ctx);
+            return;
+        }
+        if let Some(screen) = &self.state.evaluation_ended {
+            screen.show(ctx);
+            return;
+        }
+        let shown = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            self.show_player(ctx);
+            self.hide_cursor(ctx);
+        }));
+        if shown.is_err() {
+            // The panic hook has written the crash report by now
+            self.state.playback.stop();
+            self.restart_screen = Some(crash::RestartScreen::new());
+            ctx.request_repaint();
+        }
+    }
+}
+
+/// The bar under the video: the widgets of `[bar]`'s left slot from the left
+/// edge, the right slot's from the right edge, and the center slot's in the
+/// room between, one above the other when there are several.
+fn show_bar(ui: &mut egui::Ui, state: &mut AppState, bar_rect: egui::Rect, have_logo: bool) {
+    let bar = state.config.bar.clone();
+    let spacing = state.config.ui.ui_spacing;
+    let mut slot =
+        |ui: &mut egui::Ui, rect: egui::Rect, layout: egui::Layout, widgets: &[BarWidget]| {
+            ui.allocate_new_ui(egui::UiBuilder::new().max_rect(rect).layout(layout), |ui| {
+                let mut first = true;
+                for widget in widgets {
+                    if !first {
+                        ui.add_space(spacing);
+                    }
+                    first &= !show_bar_widget(ui, state, *widget, have_logo, bar_rect.height());
+                }
+            })
+            .response
+            .rect
+        };
+
+    let mut left_rect = bar_rect;
+    left_rect.min.x += spacing;
+    let left = slot(
+        ui,
+        left_rect,
+        egui::Layout::left_to_right(egui::Align::Center),
+        &bar.left,
+    );
+    let mut right_rect = bar_rect;
+    right_rect.max.x -= spacing * 3.0; // More padding from the right edge
+                                       // Laid out from the right edge, so the last widget goes first
+    let right_widgets: Vec<BarWidget> = bar.right.iter().rev().copied().collect();
+    let right = slot(
+        ui,
+        right_rect,
+        egui::Layout::right_to_left(egui::Align::Center),
+        &right_widgets,
+    );
+
+    let left_edge = if bar.left.is_empty() {
+        bar_rect.left()
+    } else {
+        left.right() + spacing
+    };
+    let right_edge = if bar.right.is_empty() {
+        bar_rect.right()
+    } else {
+        right.left() - spacing
+    };
+    if bar.center.is_empty() || right_edge <= left_edge {
+        return;
+    }
+    let row_height = bar_rect.height() / bar.center.len() as f32;
+    for (row, widget) in bar.center.iter().enumerate() {
+        let top = bar_rect.top() + row_height * row as f32;
+        let rect = egui::Rect::from_x_y_ranges(left_edge..=right_edge, top..=top + row_height);
+        slot(
+            ui,
+            rect,
+            egui::Layout::centered_and_justified(egui::Direction::LeftToRight),
+            std::slice::from_ref(widget),
+        );
+    }
+}
+
+/// One of the bar's widgets. Returns whether it showed anything.
+fn show_bar_widget(
+    ui: &mut egui::Ui,
+    state: &mut AppState,
+    widget: BarWidget,
+    have_logo: bool,
+    bar_height: f32,
+) -> bool {
+    let label_color = state.config.ui.label_color.color32();
+    match widget {
+        BarWidget::Input => show_input_field(ui, state),
+        BarWidget::NowPlaying => {
+            let mut text = format!(
+                "{} {}",
+                state.config.ui.now_playing_label, state.playback.current_file_name
+            );
+            // Point out when the ring has moved on without us
+            if let Some(lot) = &state.ring_lot {
+                let current = state.playback.current();
+                if current.map_or(true, |video| &video.hip_number != lot) {
+                    text.push_str(&format!("   (in ring: {})", lot));
+                }
+            }
+            if let Some(hip) = state.arbiter.queued_hip() {
+                text.push_str(&format!("   (queued: {})", hip));
+            }
+            ui.label(egui::RichText::new(text).color(label_color));
+        }
+        BarWidget::Clock => return show_clock(ui, state),
+        BarWidget::Ticker => return show_ticker(ui, state),
+        BarWidget::Logo => {
+            if have_logo {
+                // Calculate logo size to fit within the bar height
+                let max_height = bar_height * 0.7; // Use 70% of bar height
+                let logo_size = egui::Vec2::new(200.0, max_height);
+
+                // Include the SVG bytes at compile time
+                ui.add(
+                    egui::Image::from_bytes(
+                        "bytes://logo.svg",
+                        include_bytes!("../../../assets/logo/logo.svg"),
+                    )
+                    .fit_to_exact_size(logo_size)
+                    .maintain_aspect_ratio(true),
+                );
+            } else {
+                // Fallback to text if no logo
+                ui.label(
+                    egui::RichText::new(&state.config.ui.company_label)
+                        .color(label_color)
+                        .strong(),
+                );
+            }
+        }
+        BarWidget::Stats => {
+            let Some(stats) = state.sale_stats() else {
+                return false;
+            };
+            ui.label(
+                egui::RichText::new(sale_summary(&stats))
+                    .small()
+                    .color(label_color),
+            );
+        }
+        BarWidget::Meter => {
+            show_audio_meter(ui, state);
+            return state.playback.has_audio().is_some();
+        }
+    }
+    true
+}
+
+/// The hip number field and its label, outlined in red while rejected.
+fn show_input_field(ui: &mut egui::Ui, state: &mut AppState) {
+    ui.vertical(|ui| {
+        ui.label(
+            egui::RichText::new(&state.config.ui.input_label)
+                .color(state.config.ui.label_color.color32()),
+        );
+        let mut input_text = state.input.buffer.clone();
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut input_text)
+                .desired_width(state.config.ui.input_field_width)
+                .font(egui::TextStyle::Body.resolve(ui.style()))
+                .text_color(if state.input.is_rejected() {
+                    egui::Color32::RED
+                } else {
+                    state.config.ui.input_text_color.color32()
+                })
+                .frame(false),
+        );
+        state
+            .input
+            .set_text(&input_text, state.config.ui.input_max_length);
+
+        let stroke_color = if state.input.is_rejected() {
+            egui::Color32::RED
+        } else {
+            state.config.ui.input_stroke_color.color32()
+        };
+        ui.painter().rect_stroke(
+            response.rect.expand(state.config.ui.stroke_width / 2.0),
+            0.0,
+            egui::Stroke::new(state.config.ui.stroke_width, stroke_color),
+        );
+    });
+}
+
+/// `[bar] ticker_text` scrolling in from the right across `ticker_width`.
+/// Returns whether there is one.
+fn show_ticker(ui: &mut egui::Ui, state: &AppState) -> bool {
+    let bar = &state.config.bar;
+    if bar.ticker_text.is_empty() {
+        return false;
+    }
+    let galley = ui.painter().layout_no_wrap(
+        bar.ticker_text.clone(),
+        egui::TextStyle::Body.resolve(ui.style()),
+        state.config.ui.label_color.color32(),
+    );
+    let size = egui::vec2(bar.ticker_width, galley.size().y);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let offset = if bar.ticker_speed > 0.0 {
+        // From just past the right edge until gone past the left
+        let travel = rect.width() + galley.size().x;
+        (ui.input(|i| i.time) as f32 * bar.ticker_speed).rem_euclid(travel)
+    } else {
+        rect.width()
+    };
+    ui.painter().with_clip_rect(rect).galley(
+        egui::pos2(rect.right() - offset, rect.top()),
+        galley,
+        egui::Color32::PLACEHOLDER,
+    );
+    true
+}
+
+/// The sale so far, e.g. `12 lots · 88 left · 1:05:09`.
+fn sale_summary(stats: &analytics::SaleStats) -> String {
+    let mut text = format!("{} lots · {} left", stats.lots_played, stats.remaining);
+    if let Some(elapsed) = stats.elapsed {
+        let seconds = elapsed.as_secs();
+        text.push_str(&format!(
+            " · {}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        ));
+    }
+    text
+}
+
+/// The time of day and the lot countdown in the right corner of the bar, as
+/// set in `[clock]`. Returns whether either is shown.
+fn show_clock(ui: &mut egui::Ui, state: &AppState) -> bool {
+    let settings = &state.config.clock;
+    let label_color = state.config.ui.label_color.color32();
+    let mut shown = false;
+    if settings.show_clock {
+        let now = chrono::Local::now();
+        ui.label(
+            egui::RichText::new(clock::time_of_day(&now, &settings.format))
+                .monospace()
+                .color(label_color),
+        );
+        shown = true;
+    }
+    if let Some(left) = state.countdown.left() {
+        if shown {
+            ui.add_space(state.config.ui.ui_spacing);
+        }
+        let color = if left <= settings.warning_seconds.get() {
+            egui::Color32::RED
+        } else {
+            label_color
+        };
+        ui.label(
+            egui::RichText::new(clock::format_left(left))
+                .monospace()
+                .strong()
+                .color(color),
+        );
+        shown = true;
+    }
+    shown
+}
+
+/// The hip number being typed, in large type over the video, fading out
+/// after the last key or Enter.
+fn show_entry_overlay(ui: &egui::Ui, state: &AppState, video_rect: egui::Rect) {
+    let overlay = &state.config.entry_overlay;
+    if !overlay.enabled {
+        return;
+    }
+    let hold = overlay.hold_seconds.get();
+    let Some((text, opacity)) = state.input.echo(hold, overlay.fade_seconds.get()) else {
+        return;
+    };
+    let color = if state.input.is_rejected() {
+        egui::Color32::RED
+    } else {
+        state.config.ui.input_text_color.color32()
+    };
+    let painter = ui.painter_at(video_rect);
+    let galley = painter.layout_no_wrap(
+        text.to_string(),
+        egui::FontId::monospace(overlay.font_size),
+        color.gamma_multiply(opacity),
+    );
+    let center = video_rect.min
+        + egui::vec2(
+            video_rect.width() * overlay.x.get(),
+            video_rect.height() * overlay.y.get(),
+        );
+    let rect = egui::Rect::from_center_size(center, galley.size());
+    painter.rect_filled(
+        rect.expand(overlay.font_size * 0.15),
+        overlay.font_size * 0.1,
+        egui::Color32::from_black_alpha((160.0 * opacity) as u8),
+    );
+    painter.galley(rect.min, galley, color);
+}
+
+/// Over the video: a spinner while the next video opens, or why it couldn't.
+/// A poster of the video opening is left undimmed.
+fn show_player_state(ui: &mut egui::Ui, state: &AppState, poster: bool) {
+    let (loading, text) = match state.playback.state() {
+        // Still playing from what was read before the dropout
+        PlayerState::Playing if state.media_offline => {
+            show_banner(ui, ui.max_rect(), "Media source offline".to_string());
+            return;
+        }
+        _ if state.media_offline => (true, "Media source offline, reconnecting".to_string()),
+        PlayerState::Loading => (
+            true,
+            format!("Loading {}", state.playback.current_file_name),
+        ),
+        PlayerState::Error(e) => (false, e),
+        PlayerState::Idle | PlayerState::Playing => return,
+    };
+    let rect = ui.max_rect();
+    if !poster {
+        ui.painter()
+            .rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+    }
+    ui.allocate_new_ui(egui::UiBuilder::new().max_rect(rect), |ui| {
+        ui.vertical_centered(|ui| {
+            ui.add_space(rect.height() / 2.0 - state.config.ui.placeholder_font_size);
+            if loading {
+                ui.add(egui::Spinner::new().size(state.config.ui.placeholder_font_size));
+            }
+            ui.label(
+                egui::RichText::new(text)
+                    .size(state.config.ui.placeholder_font_size / 2.0)
+                    .color(egui::Color32::WHITE),
+            );
+        });
+    });
+}
+
+/// A line of yellow `text` across the top of `rect`.
+fn show_banner(ui: &egui::Ui, rect: egui::Rect, text: String) {
+    let banner = egui::Rect::from_min_size(rect.min, egui::vec2(rect.width(), 28.0));
+    ui.painter()
+        .rect_filled(banner, 0.0, egui::Color32::from_black_alpha(160));
+    ui.painter().text(
+        banner.center(),
+        egui::Align2::CENTER_CENTER,
+        text,
+        egui::FontId::proportional(16.0),
+        egui::Color32::from_rgb(255, 200, 0),
+    );
+}
+
+/// The review's progress and keys, or its tally once every hip is done.
+fn show_review(ctx: &egui::Context, review: &Review) {
+    let (ok, needs_fix, unmarked) = review.counts();
+    let Some(item) = review.current() else {
+        egui::Window::new("Review finished")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} OK, {} need a fix, {} not marked.",
+                    ok, needs_fix, unmarked
+                ));
+                if let Some(report) = &review.report {
+                    ui.label(format!("Report saved to {}", report.display()));
+                }
+                ui.label("Press ← to go back.");
+            });
+        return;
+    };
+    egui::Window::new("Review")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-16.0, 16.0))
+        .show(ctx, |ui| {
+            ui.label(
+                egui::RichText::new(format!(
+                    "Hip {} ({} of {})",
+                    item.hip,
+                    review.position + 1,
+                    review.items.len()
+                ))
+                .strong(),
+            );
+            ui.label(&item.file);
+            if let Some(verdict) = item.verdict {
+                ui.label(format!("Marked {}", verdict));
+            }
+            ui.label(format!("{} OK, {} need a fix so far", ok, needs_fix));
+            ui.separator();
+            ui.label("O / Enter: OK    F: needs fix    R: replay");
+            ui.label("←: previous    →: skip");
+        });
+}
+
+const METER_WIDTH: f32 = 60.0;
+
+/// Whether the video playing has sound, at a glance: a level meter of the
+/// video's sound over the output's, or what's missing.
+fn show_audio_meter(ui: &mut egui::Ui, state: &AppState) {
+    let label_color = state.config.ui.label_color.color32();
+    match state.playback.has_audio() {
+        None => {}
+        Some(false) => {
+            ui.label(egui::RichText::new("No audio track").color(label_color));
+        }
+        Some(true) => {
+            // The hip's trim, when it has one
+            let gain = state
+                .playback
+                .hip_override(state.playback.current_index)
+                .and_then(|hip| hip.gain);
+            if let Some(gain) = gain.filter(|gain| *gain != 1.0) {
+                let db = if gain > 0.0 {
+                    20.0 * gain.log10()
+                } else {
+                    -99.0
+                };
+                ui.label(egui::RichText::new(format!("{:+.0} dB", db)).color(label_color));
+            }
+            let level = mixer::level();
+            let (rect, response) =
+                ui.allocate_exact_size(egui::vec2(METER_WIDTH, 14.0), egui::Sense::hover());
+            for (row, value) in [level.program, level.output].into_iter().enumerate() {
+                let row = egui::Rect::from_min_size(
+                    rect.min + egui::vec2(0.0, row as f32 * 8.0),
+                    egui::vec2(METER_WIDTH, 6.0),
+                );
+                let lit = egui::Rect::from_min_size(
+                    row.min,
+                    egui::vec2(meter_fraction(value) * METER_WIDTH, 6.0),
+                );
+                ui.painter()
+                    .rect_filled(row, 0.0, egui::Color32::from_gray(40));
+                ui.painter().rect_filled(lit, 0.0, meter_color(value));
+            }
+            response.on_hover_text("Top: the video's sound. Bottom: the output.");
+            // Sound going in but none coming out
+            if level.program > 0.01 && level.output < 0.001 {
+                ui.label(egui::RichText::new("Output muted").color(egui::Color32::RED));
+            }
+            if state.silence.warning() {
+                ui.label(egui::RichText::new("No sound").color(egui::Color32::YELLOW))
+                    .on_hover_text("The video has an audio track but isn't making a sound");
+            }
+        }
+    }
+}
+
+/// How much of the meter a peak level lights, on a -60 to 0 dB scale.
+fn meter_fraction(level: f32) -> f32 {
+    if level <= 0.0 {
+        return 0.0;
+    }
+    ((20.0 * level.log10() + 60.0) / 60.0).clamp(0.0, 1.0)
+}
+
+fn meter_color(level: f32) -> egui::Color32 {
+    if level >= 0.99 {
+        egui::Color32::RED
+    } else if level >= 0.5 {
+        egui::Color32::YELLOW
+    } else {
+        egui::Color32::GREEN
+    }
+}
+
+/// Thumbnails loaded as textures per frame, so opening the grid doesn't
+/// stall the video.
+const THUMBNAILS_PER_FRAME: usize = 8;
+
+/// Thumbnail textures by file, `None` for one that can't be loaded. The
+/// ones shown longest ago are dropped at the textures ceiling.
+type ThumbnailTextures = Lru<'static, PathBuf, Option<egui::TextureHandle>>;
+
+/// Load the thumbnail at `path` as a texture, keeping it in `cache`; a
+/// thumbnail that can't be loaded is kept as `None`, so it isn't tried again
+/// every frame.
+fn load_thumbnail(
+    ctx: &egui::Context,
+    path: &Path,
+    cache: &mut ThumbnailTextures,
+) -> Option<egui::TextureHandle> {
+    let rgba = match image::open(path) {
+        Ok(img) => img.to_rgba8(),
+        Err(e) => {
+            warn!("Failed to load thumbnail {}: {}", path.display(), e);
+            cache.insert(path.to_path_buf(), None, 0);
+            return None;
+        }
+    };
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    let color_image = egui::ColorImage::from_rgba_unmultiplied(size, rgba.as_raw());
+    let bytes = texture_bytes(&color_image);
+    // Made room for before it's uploaded
+    cache.make_room(bytes);
+    let texture = ctx.load_texture(
+        format!("thumbnail {}", path.display()),
+        color_image,
+        Default::default(),
+    );
+    cache.insert(path.to_path_buf(), Some(texture.clone()), bytes);
+    Some(texture)
+}
+
+fn texture_bytes(image: &egui::ColorImage) -> u64 {
+    (image.pixels.len() * std::mem::size_of::<egui::Color32>()) as u64
+}
+
+/// `img`, scaled down to take at most `room` bytes as a texture.
+fn fit_texture_room(img: image::DynamicImage, room: u64, path: &Path) -> image::DynamicImage {
+    let bytes = img.width() as u64 * img.height() as u64 * 4;
+    if bytes <= room {
+        return img;
+    }
+    let scale = (room as f64 / bytes as f64).sqrt();
+    let width = ((img.width() as f64 * scale) as u32).max(1);
+    let height = ((img.height() as f64 * scale) as u32).max(1);
+    warn!(
+        "Splash image {} is {}x{}, more than the texture ceiling leaves room for; showing it at {}x{}",
+        path.display(),
+        img.width(),
+        img.height(),
+        width,
+        height
+    );
+    img.resize(width, height, image::imageops::FilterType::Triangle)
+}
+
+/// The keys of this frame the player acts on.
+fn frame_input(ctx: &egui::Context) -> FrameInput {
+    ctx.input(|i| FrameInput {
+        text: i
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                egui::Event::Text(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect(),
+        enter: i.key_pressed(egui::Key::Enter),
+        force: i.modifiers.shift,
+        forward: i.key_pressed(egui::Key::ArrowUp) || i.key_pressed(egui::Key::ArrowRight),
+        backward: i.key_pressed(egui::Key::ArrowDown) || i.key_pressed(egui::Key::ArrowLeft),
+        undo: i.key_pressed(egui::Key::Backspace),
+        toggle_browser: i.key_pressed(egui::Key::F2),
+        toggle_perf_hud: i.key_pressed(egui::Key::F3),
+        start_countdown: i.key_pressed(egui::Key::F4),
+        test_pattern: i.key_pressed(egui::Key::F5),
+        next_ring: i.key_pressed(egui::Key::F6),
+        trim: i.num_presses(egui::Key::Plus) as i32 + i.num_presses(egui::Key::Equals) as i32
+            - i.num_presses(egui::Key::Minus) as i32,
+    })
+}
+
+fn load_logo() -> Option<String> {
+    let logo_path = config::logo_path();
+
+    if logo_path.exists() {
+        info!("Logo loaded from {}", logo_path.display());
+        // Convert path to file:// URI for egui_extras
+        Some(format!("file://{}", logo_path.display()))
+    } else {
+        warn!("Logo not found at {}", logo_path.display());
+        None
+    }
+}
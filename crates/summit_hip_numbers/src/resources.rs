@@ -0,0 +1,336 @@
+//! Memory the player holds on to, by kind, against the ceilings in
+//! `[resources]`, so a kiosk running for days stays within the RAM of a 4 GB
+//! PC. Like [`metrics`](crate::metrics) the counts are atomics, kept by the
+//! decoder threads and the UI as they take and free memory, in one
+//! [`Ledger`] for the process.
+//!
+//! What happens at a ceiling is up to the holder: audio decoding waits for
+//! playback to catch up, cached thumbnails are dropped, used longest ago
+//! first, and a splash image is scaled down to fit. Memory held past a
+//! ceiling anyway is logged once each time it goes over.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use summit_hip_core::config::ResourcesConfig;
+
+const MB: u64 = 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// Video frame and splash image textures.
+    Textures,
+    /// Decoded audio waiting for the output device.
+    AudioBuffer,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 2] = [Kind::Textures, Kind::AudioBuffer];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Textures => "textures",
+            Kind::AudioBuffer => "audio_buffer",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The memory held of each kind, and the ceilings. The player keeps one for
+/// the whole process; the functions below read and set it.
+pub struct Ledger {
+    used: [AtomicU64; 2],
+    /// Ceilings in bytes, 0 for none.
+    limits: [AtomicU64; 2],
+    over: [AtomicBool; 2],
+}
+
+static LEDGER: Ledger = Ledger::new();
+
+impl Ledger {
+    pub const fn new() -> Self {
+        Self {
+            used: [AtomicU64::new(0), AtomicU64::new(0)],
+            limits: [AtomicU64::new(0), AtomicU64::new(0)],
+            over: [AtomicBool::new(false), AtomicBool::new(false)],
+        }
+    }
+
+    pub fn set_limits(&self, config: &ResourcesConfig) {
+        self.limits[Kind::Textures.index()].store(config.texture_mb * MB, Ordering::Relaxed);
+        self.limits[Kind::AudioBuffer.index()]
+            .store(config.audio_buffer_mb * MB, Ordering::Relaxed);
+    }
+
+    /// Bytes of `kind` held now.
+    pub fn used(&self, kind: Kind) -> u64 {
+        self.used[kind.index()].load(Ordering::Relaxed)
+    }
+
+    pub fn limit(&self, kind: Kind) -> Option<u64> {
+        match self.limits[kind.index()].load(Ordering::Relaxed) {
+            0 => None,
+            bytes => Some(bytes),
+        }
+    }
+
+    /// Bytes of `kind` that can still be taken before its ceiling.
+    pub fn room(&self, kind: Kind) -> u64 {
+        self.limit(kind)
+            .map_or(u64::MAX, |limit| limit.saturating_sub(self.used(kind)))
+    }
+
+    /// Whether `bytes` more of `kind` would stay within its ceiling.
+    pub fn fits(&self, kind: Kind, bytes: u64) -> bool {
+        self.limit(kind)
+            .map_or(true, |limit| self.used(kind).saturating_add(bytes) <= limit)
+    }
+
+    /// Memory of `kind` counted here.
+    pub fn usage(&self, kind: Kind) -> Usage<'_> {
+        Usage {
+            ledger: self,
+            kind,
+            bytes: 0,
+        }
+    }
+
+    /// An owner of `kind` went from holding `from` bytes to `to`.
+    fn change(&self, kind: Kind, from: u64, to: u64) {
+        let used = &self.used[kind.index()];
+        let now = if to >= from {
+            used.fetch_add(to - from, Ordering::Relaxed) + (to - from)
+        } else {
+            used.fetch_sub(from - to, Ordering::Relaxed) - (from - to)
+        };
+
+        let over = self.limit(kind).is_some_and(|limit| now > limit);
+        if self.over[kind.index()].swap(over, Ordering::Relaxed) != over {
+            let limit = self.limit(kind).unwrap_or(0);
+            if over {
+                log::warn!(
+                    "{} use of {} MB is over its {} MB ceiling",
+                    kind.name(),
+                    now / MB,
+                    limit / MB
+                );
+            } else {
+                log::info!("{} use is back under its ceiling", kind.name());
+            }
+        }
+    }
+}
+
+impl Default for Ledger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn set_limits(config: &ResourcesConfig) {
+    LEDGER.set_limits(config);
+}
+
+/// Bytes of `kind` held now.
+pub fn used(kind: Kind) -> u64 {
+    LEDGER.used(kind)
+}
+
+/// Bytes of `kind` that can still be taken before its ceiling.
+pub fn room(kind: Kind) -> u64 {
+    LEDGER.room(kind)
+}
+
+/// Memory of one kind held by one owner, counted until it is dropped.
+pub struct Usage<'a> {
+    ledger: &'a Ledger,
+    kind: Kind,
+    bytes: u64,
+}
+
+impl Usage<'static> {
+    pub fn new(kind: Kind) -> Self {
+        LEDGER.usage(kind)
+    }
+}
+
+impl Usage<'_> {
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// The owner now holds `bytes`.
+    pub fn set(&mut self, bytes: u64) {
+        self.ledger.change(self.kind, self.bytes, bytes);
+        self.bytes = bytes;
+    }
+}
+
+impl Drop for Usage<'_> {
+    fn drop(&mut self) {
+        self.set(0);
+    }
+}
+
+/// Things of one kind kept to be used again, like the thumbnail textures.
+/// When more won't fit under the kind's ceiling, the ones used longest ago
+/// are dropped to make room.
+pub struct Lru<'a, K, V> {
+    entries: HashMap<K, Cached<V>>,
+    /// Counts the uses, to tell which entry was used longest ago.
+    clock: u64,
+    usage: Usage<'a>,
+}
+
+struct Cached<V> {
+    value: V,
+    bytes: u64,
+    used: u64,
+}
+
+impl<K: Eq + Hash + Clone, V> Lru<'static, K, V> {
+    pub fn new(kind: Kind) -> Self {
+        Self::in_ledger(&LEDGER, kind)
+    }
+}
+
+impl<'a, K: Eq + Hash + Clone, V> Lru<'a, K, V> {
+    pub fn in_ledger(ledger: &'a Ledger, kind: Kind) -> Self {
+        Self {
+            entries: HashMap::new(),
+            clock: 0,
+            usage: ledger.usage(kind),
+        }
+    }
+
+    /// The entry for `key`, now the one used last.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.clock += 1;
+        let cached = self.entries.get_mut(key)?;
+        cached.used = self.clock;
+        Some(&cached.value)
+    }
+
+    /// Keep `value`, taking `bytes`, after dropping what it takes to make
+    /// room for it.
+    pub fn insert(&mut self, key: K, value: V, bytes: u64) {
+        if let Some(old) = self.entries.remove(&key) {
+            self.usage.set(self.usage.bytes() - old.bytes);
+        }
+        self.make_room(bytes);
+        self.clock += 1;
+        self.usage.set(self.usage.bytes() + bytes);
+        self.entries.insert(
+            key,
+            Cached {
+                value,
+                bytes,
+                used: self.clock,
+            },
+        );
+    }
+
+    /// Drop the entries used longest ago until `bytes` more fit under the
+    /// ceiling, or there are none left; 0 to get back under it after
+    /// something else grew.
+    pub fn make_room(&mut self, bytes: u64) {
+        let kind = self.usage.kind;
+        while !self.usage.ledger.fits(kind, bytes) {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, cached)| cached.used)
+                .map(|(key, _)| key.clone())
+            else {
+                return;
+            };
+            if let Some(cached) = self.entries.remove(&oldest) {
+                self.usage.set(self.usage.bytes() - cached.bytes);
+            }
+            log::debug!("Dropped a cached {} entry to make room", kind.name());
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.usage.set(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger(texture_mb: u64) -> Ledger {
+        let ledger = Ledger::new();
+        ledger.set_limits(&ResourcesConfig {
+            texture_mb,
+            audio_buffer_mb: 0,
+        });
+        ledger
+    }
+
+    #[test]
+    fn test_usage() {
+        let ledger = ledger(1);
+        let mut frame = ledger.usage(Kind::Textures);
+        frame.set(MB / 2);
+        let mut splash = ledger.usage(Kind::Textures);
+        splash.set(MB / 4);
+        assert_eq!(ledger.used(Kind::Textures), 3 * MB / 4);
+        assert_eq!(ledger.room(Kind::Textures), MB / 4);
+
+        frame.set(MB);
+        assert!(ledger.over[Kind::Textures.index()].load(Ordering::Relaxed));
+        assert_eq!(ledger.room(Kind::Textures), 0);
+        drop(splash);
+        frame.set(MB / 2);
+        assert!(!ledger.over[Kind::Textures.index()].load(Ordering::Relaxed));
+        drop(frame);
+        assert_eq!(ledger.used(Kind::Textures), 0);
+
+        // No ceiling
+        assert_eq!(ledger.limit(Kind::AudioBuffer), None);
+        assert_eq!(ledger.room(Kind::AudioBuffer), u64::MAX);
+    }
+
+    #[test]
+    fn test_lru_drops_used_longest_ago() {
+        let ledger = ledger(1);
+        let mut cache = Lru::in_ledger(&ledger, Kind::Textures);
+        cache.insert("001", 1, MB / 4);
+        cache.insert("002", 2, MB / 4);
+        cache.insert("003", 3, MB / 4);
+        assert_eq!(ledger.used(Kind::Textures), 3 * MB / 4);
+
+        // 002 was used longest ago once 001 is looked at again
+        assert_eq!(cache.get(&"001"), Some(&1));
+        cache.insert("004", 4, MB / 2);
+        assert_eq!(cache.get(&"002"), None);
+        assert_eq!(cache.get(&"003"), Some(&3));
+        assert_eq!(cache.get(&"001"), Some(&1));
+        assert_eq!(cache.get(&"004"), Some(&4));
+        assert_eq!(ledger.used(Kind::Textures), MB);
+
+        // Something else held grows: 003 and then 001 give way
+        let mut frame = ledger.usage(Kind::Textures);
+        frame.set(MB / 2);
+        cache.make_room(0);
+        assert_eq!(cache.get(&"003"), None);
+        assert_eq!(cache.get(&"001"), None);
+        assert_eq!(cache.get(&"004"), Some(&4));
+        assert_eq!(ledger.used(Kind::Textures), MB);
+
+        // Bigger than the ceiling: everything else goes, and it's kept anyway
+        cache.insert("005", 5, 2 * MB);
+        assert_eq!(cache.get(&"004"), None);
+        assert_eq!(cache.get(&"005"), Some(&5));
+
+        cache.clear();
+        assert_eq!(cache.get(&"005"), None);
+        assert_eq!(ledger.used(Kind::Textures), MB / 2);
+    }
+}
@@ -0,0 +1,83 @@
+//! RS-232 listener for the [`line_protocol`](crate::line_protocol), built with
+//! `--features serial` and enabled with `[serial] enabled = true`.
+
+use crate::line_protocol;
+use std::io::{self, Read};
+use std::thread;
+use std::time::Duration;
+use summit_hip_core::config::SerialConfig;
+use summit_hip_core::control::Remote;
+
+/// How long to wait before reopening a port that failed or was unplugged.
+const REOPEN_DELAY: Duration = Duration::from_secs(5);
+
+/// Start listening in a background thread. The port is reopened whenever it
+/// fails, so a USB serial adapter can be unplugged and plugged back in.
+pub fn start(config: &SerialConfig, remote: Remote) -> Result<(), String> {
+    let config = config.clone();
+    thread::Builder::new()
+        .name("serial-control".to_string())
+        .spawn(move || loop {
+            if let Err(e) = serve_port(&config, &remote) {
+                log::warn!("Serial control on {}: {}", config.port, e);
+            }
+            thread::sleep(REOPEN_DELAY);
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn serve_port(config: &SerialConfig, remote: &Remote) -> io::Result<()> {
+    let port = serialport::new(&config.port, config.baud)
+        .timeout(Duration::from_secs(1))
+        .open()?;
+    log::info!(
+        "Serial control listening on {} at {} baud",
+        config.port,
+        config.baud
+    );
+    let writer = port.try_clone()?;
+    line_protocol::serve(RetryOnTimeout(port), writer, remote)
+}
+
+/// Serial reads time out when the line is idle; keep waiting instead of
+/// treating that as the end of input.
+struct RetryOnTimeout<R>(R);
+
+impl<R: Read> Read for RetryOnTimeout<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.0.read(buf) {
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Times out once before every chunk of data.
+    struct Idle<'a>(&'a [u8], bool);
+
+    impl Read for Idle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.1 = !self.1;
+            if self.1 {
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+            self.0.read(buf)
+        }
+    }
+
+    #[test]
+    fn test_idle_line_is_not_end_of_input() {
+        let mut text = String::new();
+        RetryOnTimeout(Idle(b"PING\r", false))
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "PING\r");
+    }
+}
@@ -0,0 +1,86 @@
+//! What the player does when it can't start: say what failed and how to fix
+//! it, in a small window when it was going to open one, so an operator at a
+//! kiosk sees more than a window that never appears.
+
+use eframe::egui;
+use std::fmt;
+
+/// Why the player couldn't start, and what to do about it.
+#[derive(Debug, PartialEq)]
+pub struct StartupError {
+    pub problem: String,
+    pub fix: String,
+}
+
+impl fmt::Display for StartupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\nTo fix it: {}", self.problem, self.fix)
+    }
+}
+
+impl StartupError {
+    pub fn log_file(path: &str, e: &std::io::Error) -> Self {
+        Self {
+            problem: format!("Can't open the log file {}: {}", path, e),
+            fix: "set [logging] file in config.toml to a file in a folder this account can \
+                  write to, or install the player in a writable folder"
+                .to_string(),
+        }
+    }
+}
+
+/// Report `error` on stderr and, with `windowed`, in an error window, then
+/// exit with status 1.
+pub fn exit(error: StartupError, windowed: bool) -> ! {
+    eprintln!("Error: {}", error);
+    if windowed {
+        let options = eframe::NativeOptions {
+            viewport: egui::ViewportBuilder::default().with_inner_size([520.0, 240.0]),
+            ..Default::default()
+        };
+        let shown = eframe::run_native(
+            "Summit Hip Numbers",
+            options,
+            Box::new(|_cc| Ok(Box::new(ErrorWindow { error }))),
+        );
+        if let Err(e) = shown {
+            eprintln!("Can't open the error window either: {}", e);
+        }
+    }
+    std::process::exit(1);
+}
+
+struct ErrorWindow {
+    error: StartupError,
+}
+
+impl eframe::App for ErrorWindow {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Summit Hip Numbers couldn't start");
+            ui.add_space(8.0);
+            ui.label(&self.error.problem);
+            ui.add_space(8.0);
+            ui.label(egui::RichText::new("To fix it").strong());
+            ui.label(&self.error.fix);
+            ui.add_space(12.0);
+            if ui.button("Quit").clicked() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_file_error() {
+        let e = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        let error = StartupError::log_file("/opt/summit/shn.log", &e);
+        let text = error.to_string();
+        assert!(text.starts_with("Can't open the log file /opt/summit/shn.log: "));
+        assert!(text.contains("\nTo fix it: set [logging] file in config.toml"));
+    }
+}
@@ -0,0 +1,191 @@
+//! `--supervise`: a thin parent process that relaunches the player when it
+//! crashes, so unattended kiosks recover without staff.
+//!
+//! The player it launches saves the video on screen and the position in it
+//! to a resume file about once a second (named in `SUMMIT_RESUME_FILE`), and
+//! picks up from there after a restart. A clean exit ends supervision.
+//! Unsupervised, the same state goes to `session.json` next to the log file
+//! and the player offers to resume from it after a power cut.
+//! Restarts back off from 1 second up to a minute while the player keeps
+//! crashing soon after starting.
+
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Tells the supervised player where to save and find its resume state.
+pub const RESUME_FILE_ENV: &str = "SUMMIT_RESUME_FILE";
+
+const MIN_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(60);
+/// A player that ran this long is considered to have recovered.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// What was playing when the resume file was last written.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ResumeState {
+    pub path: String,
+    pub position_seconds: f64,
+    /// The video waiting to play after a splash, which resumes instead.
+    #[serde(default)]
+    pub queued: Option<String>,
+    /// Videos played, which the splash interval counts.
+    #[serde(default)]
+    pub videos_played: usize,
+}
+
+/// Where an unsupervised player saves its session: next to `log_file`.
+pub fn session_file(log_file: &Path) -> PathBuf {
+    log_file
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join("session.json")
+}
+
+impl ResumeState {
+    pub fn load(file: &Path) -> Option<Self> {
+        let text = fs::read_to_string(file).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    /// Write through a temporary file, so a crash mid-write never leaves a
+    /// half-written resume file behind.
+    pub fn save(&self, file: &Path) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        let tmp = file.with_extension("tmp");
+        fs::write(&tmp, json)
+            .and_then(|()| fs::rename(&tmp, file))
+            .map_err(|e| format!("{}: {}", file.display(), e))
+    }
+}
+
+/// Delay before each restart, doubling while the player keeps crashing.
+struct Backoff {
+    delay: Duration,
+}
+
+impl Backoff {
+    fn new() -> Self {
+        Self { delay: MIN_DELAY }
+    }
+
+    fn after_crash(&mut self, ran_for: Duration) -> Duration {
+        if ran_for >= STABLE_AFTER {
+            self.delay = MIN_DELAY;
+        }
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(MAX_DELAY);
+        delay
+    }
+}
+
+/// The player's own arguments, without `--supervise`.
+fn child_args(args: impl Iterator<Item = OsString>) -> Vec<OsString> {
+    args.skip(1).filter(|arg| arg != "--supervise").collect()
+}
+
+/// Run the player until it exits cleanly, relaunching it after crashes.
+/// Returns the exit code for the supervisor.
+pub fn run() -> i32 {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            log::error!("Can't find the player executable: {}", e);
+            return 1;
+        }
+    };
+    let args = child_args(std::env::args_os());
+    let resume_file: PathBuf =
+        std::env::temp_dir().join(format!("summit_hip_numbers-{}.resume", std::process::id()));
+    // Only restarts resume; the first launch starts fresh
+    let _ = fs::remove_file(&resume_file);
+
+    let mut backoff = Backoff::new();
+    let mut restarts = 0u32;
+    let code = loop {
+        let started = Instant::now();
+        let status = Command::new(&exe)
+            .args(&args)
+            .env(RESUME_FILE_ENV, &resume_file)
+            .status();
+        let status = match status {
+            Ok(status) => status,
+            Err(e) => {
+                log::error!("Failed to launch {}: {}", exe.display(), e);
+                break 1;
+            }
+        };
+        if status.success() {
+            log::info!("Player exited normally after {} restart(s)", restarts);
+            break 0;
+        }
+
+        let delay = backoff.after_crash(started.elapsed());
+        restarts += 1;
+        log::error!(
+            "Player exited with {} after {:.0}s; restart {} in {:.0}s",
+            status,
+            started.elapsed().as_secs_f64(),
+            restarts,
+            delay.as_secs_f64()
+        );
+        thread::sleep(delay);
+    };
+    let _ = fs::remove_file(&resume_file);
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let mut backoff = Backoff::new();
+        let quick = Duration::from_secs(2);
+        let delays: Vec<u64> = (0..8)
+            .map(|_| backoff.after_crash(quick).as_secs())
+            .collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(backoff.after_crash(STABLE_AFTER), MIN_DELAY);
+    }
+
+    #[test]
+    fn test_child_args() {
+        let args = [
+            "summit_hip_numbers",
+            "--supervise",
+            "--config-path",
+            "a.toml",
+        ]
+        .map(OsString::from);
+        assert_eq!(
+            child_args(args.into_iter()),
+            ["--config-path", "a.toml"].map(OsString::from)
+        );
+    }
+
+    #[test]
+    fn test_resume_state_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("player.resume");
+        assert_eq!(ResumeState::load(&file), None);
+        let state = ResumeState {
+            path: "videos/042.mp4".to_string(),
+            position_seconds: 12.5,
+            queued: Some("videos/043.mp4".to_string()),
+            videos_played: 7,
+        };
+        state.save(&file).unwrap();
+        assert_eq!(ResumeState::load(&file), Some(state));
+
+        // Written before the queue and count were saved
+        fs::write(&file, r#"{"path":"videos/042.mp4","position_seconds":3.0}"#).unwrap();
+        let state = ResumeState::load(&file).unwrap();
+        assert_eq!((state.queued, state.videos_played), (None, 0));
+    }
+}
@@ -0,0 +1,219 @@
+//! Keep several kiosks on the same lot. The `[sync]` leader broadcasts its
+//! hip, pause and blackout state over UDP whenever it changes and once a
+//! second; followers in the same `group` switch to match.
+//!
+//! The state is sent rather than the commands, so a follower that misses a
+//! packet or starts late catches up with the next one.
+
+use serde::{Deserialize, Serialize};
+use std::net::UdpSocket;
+use std::thread;
+use std::time::{Duration, Instant};
+use summit_hip_core::config::{SyncConfig, SyncMode};
+use summit_hip_core::control::{Command, Remote, Status};
+
+/// How often the leader repeats its state when nothing changes.
+const REPEAT_INTERVAL: Duration = Duration::from_secs(1);
+/// How often the leader checks its own state for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct SyncState {
+    group: String,
+    hip: Option<String>,
+    paused: bool,
+    blackout: bool,
+}
+
+impl SyncState {
+    fn new(group: &str, status: &Status) -> Self {
+        Self {
+            group: group.to_string(),
+            hip: status.hip.clone(),
+            paused: status.paused,
+            blackout: status.blackout,
+        }
+    }
+}
+
+pub fn start(config: &SyncConfig, remote: Remote) -> Result<(), String> {
+    let config = config.clone();
+    let spawned = match config.mode {
+        SyncMode::Off => return Ok(()),
+        SyncMode::Leader => {
+            let socket = UdpSocket::bind("0.0.0.0:0")
+                .and_then(|socket| socket.set_broadcast(true).map(|()| socket))
+                .map_err(|e| e.to_string())?;
+            thread::Builder::new()
+                .name("sync-leader".to_string())
+                .spawn(move || lead(&socket, &config, &remote))
+        }
+        SyncMode::Follower => {
+            let socket =
+                UdpSocket::bind(&config.bind).map_err(|e| format!("{}: {}", config.bind, e))?;
+            thread::Builder::new()
+                .name("sync-follower".to_string())
+                .spawn(move || follow(&socket, &config, &remote))
+        }
+    };
+    spawned.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn lead(socket: &UdpSocket, config: &SyncConfig, remote: &Remote) {
+    let mut last_sent: Option<(SyncState, Instant)> = None;
+    loop {
+        let state = SyncState::new(&config.group, &remote.status());
+        let due = last_sent.as_ref().map_or(true, |(sent, at)| {
+            *sent != state || at.elapsed() >= REPEAT_INTERVAL
+        });
+        if due {
+            let packet = serde_json::to_vec(&state).unwrap_or_default();
+            if let Err(e) = socket.send_to(&packet, &config.address) {
+                log::warn!("Sync broadcast to {} failed: {}", config.address, e);
+            }
+            last_sent = Some((state, Instant::now()));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn follow(socket: &UdpSocket, config: &SyncConfig, remote: &Remote) {
+    let mut buf = [0u8; 1024];
+    let mut missing_hip: Option<String> = None;
+    loop {
+        let (len, from) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e) => {
+                log::warn!("Sync receive failed: {}", e);
+                continue;
+            }
+        };
+        let state: SyncState = match serde_json::from_slice(&buf[..len]) {
+            Ok(state) => state,
+            Err(e) => {
+                log::debug!("Ignoring sync packet from {}: {}", from, e);
+                continue;
+            }
+        };
+        if state.group != config.group {
+            continue;
+        }
+        let commands = catch_up(&state, &remote.status());
+        for command in commands {
+            if let Command::PlayHip(hip) = &command {
+                // Don't fill the log once a second about the same missing video
+                match remote.check_hip(hip) {
+                    Err(e) => {
+                        if missing_hip.as_ref() != Some(hip) {
+                            log::warn!("Can't follow {}: {}", from, e);
+                            missing_hip = Some(hip.clone());
+                        }
+                        continue;
+                    }
+                    Ok(()) => missing_hip = None,
+                }
+            }
+            if let Err(e) = remote.send(command) {
+                log::warn!("Sync update failed: {}", e);
+            }
+        }
+    }
+}
+
+/// Commands that bring a follower in `status` in line with the leader.
+fn catch_up(leader: &SyncState, status: &Status) -> Vec<Command> {
+    let mut commands = Vec::new();
+    if let Some(hip) = &leader.hip {
+        if status.hip.as_ref() != Some(hip) {
+            commands.push(Command::PlayHip(hip.clone()));
+        }
+    }
+    if leader.paused != status.paused {
+        commands.push(if leader.paused {
+            Command::Pause
+        } else {
+            Command::Resume
+        });
+    }
+    if leader.blackout != status.blackout {
+        commands.push(Command::Blackout(leader.blackout));
+    }
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use summit_hip_core::control::Endpoint;
+
+    #[test]
+    fn test_catch_up() {
+        let leader = SyncState {
+            group: "ring1".to_string(),
+            hip: Some("042".to_string()),
+            paused: true,
+            blackout: false,
+        };
+        let mut status = Status {
+            hip: Some("041".to_string()),
+            blackout: true,
+            ..Status::default()
+        };
+        assert_eq!(
+            catch_up(&leader, &status),
+            vec![
+                Command::PlayHip("042".to_string()),
+                Command::Pause,
+                Command::Blackout(false)
+            ]
+        );
+
+        status.hip = Some("042".to_string());
+        status.paused = true;
+        status.blackout = false;
+        assert!(catch_up(&leader, &status).is_empty());
+    }
+
+    #[test]
+    fn test_follower_applies_leader_state() {
+        let endpoint = Endpoint::default();
+        endpoint.publish(Status {
+            hips: vec!["042".to_string()],
+            ..Status::default()
+        });
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        let config = SyncConfig {
+            group: "ring1".to_string(),
+            ..SyncConfig::default()
+        };
+        let remote = endpoint.remote();
+        thread::spawn(move || follow(&socket, &config, &remote));
+
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        for group in ["ring2", "ring1"] {
+            let state = SyncState {
+                group: group.to_string(),
+                hip: Some("042".to_string()),
+                paused: false,
+                blackout: false,
+            };
+            sender
+                .send_to(&serde_json::to_vec(&state).unwrap(), addr)
+                .unwrap();
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let command = loop {
+            if let Some(command) = endpoint.try_recv() {
+                break command;
+            }
+            assert!(Instant::now() < deadline, "follower did not react");
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(command, Command::PlayHip("042".to_string()));
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(endpoint.try_recv(), None);
+    }
+}
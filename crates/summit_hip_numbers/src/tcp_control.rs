@@ -0,0 +1,75 @@
+//! TCP listener for the [`line_protocol`](crate::line_protocol), enabled with
+//! `[tcp] enabled = true`.
+
+use crate::line_protocol;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use summit_hip_core::config::TcpConfig;
+use summit_hip_core::control::Remote;
+
+/// Start accepting connections in a background thread. Each client gets a
+/// thread of its own; control processors usually keep one connection open.
+pub fn start(config: &TcpConfig, remote: Remote) -> Result<(), String> {
+    let listener =
+        TcpListener::bind(&config.bind).map_err(|e| format!("{}: {}", config.bind, e))?;
+    thread::Builder::new()
+        .name("tcp-control".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => serve_client(stream, remote.clone()),
+                    Err(e) => log::warn!("TCP control connection failed: {}", e),
+                }
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn serve_client(stream: TcpStream, remote: Remote) {
+    let peer = stream
+        .peer_addr()
+        .map(|addr| addr.to_string())
+        .unwrap_or_default();
+    log::info!("TCP control client connected: {}", peer);
+    let spawned = thread::Builder::new()
+        .name("tcp-control-client".to_string())
+        .spawn(move || {
+            let result = stream
+                .try_clone()
+                .and_then(|writer| line_protocol::serve(&stream, writer, &remote));
+            match result {
+                Ok(()) => log::info!("TCP control client disconnected: {}", peer),
+                Err(e) => log::warn!("TCP control client {}: {}", peer, e),
+            }
+        });
+    if let Err(e) = spawned {
+        log::warn!("Failed to start TCP control client thread: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use summit_hip_core::control::{Command, Endpoint};
+
+    #[test]
+    fn test_tcp_round_trip() {
+        let endpoint = Endpoint::default();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let remote = endpoint.remote();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            serve_client(stream, remote);
+        });
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client.write_all(b"PAUSE\r").unwrap();
+        let mut reply = String::new();
+        BufReader::new(&client).read_line(&mut reply).unwrap();
+        assert_eq!(reply, "OK\r\n");
+        assert_eq!(endpoint.try_recv(), Some(Command::Pause));
+    }
+}
@@ -0,0 +1,201 @@
+//! Test patterns for lining up projectors and checking the audio before a
+//! sale, shown with F5 or a `test_pattern` command: SMPTE color bars or an
+//! alignment grid, drawn at the window's pixel size with the `[output]`
+//! adjustments the video gets, and a 1 kHz tone through the mixer.
+
+use std::f32::consts::TAU;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::decode_thread::{self, Outcome};
+use crate::mixer::{self, Role, Source};
+use eframe::egui;
+use egui::Color32;
+use summit_hip_core::control::TestPattern;
+
+/// The tone's pitch, in Hz.
+const TONE_HZ: f32 = 1000.0;
+
+/// The tone's level, -20 dBFS, the usual alignment level.
+const TONE_LEVEL: f32 = 0.1;
+
+/// Lines of the grid across the picture; it's square, so fewer go down it.
+const GRID_COLUMNS: usize = 16;
+
+/// `pattern` at `size` pixels.
+pub fn image(pattern: TestPattern, size: [usize; 2]) -> egui::ColorImage {
+    let size = [size[0].max(1), size[1].max(1)];
+    match pattern {
+        TestPattern::Bars => bars(size),
+        TestPattern::Grid => grid(size),
+    }
+}
+
+/// SMPTE color bars: the seven 75% bars, the reversed strip under them, and
+/// -I, white, +Q and the PLUGE along the bottom.
+fn bars([width, height]: [usize; 2]) -> egui::ColorImage {
+    let grey = |level| Color32::from_gray(level);
+    let black = grey(19);
+    let top = [
+        grey(191),
+        Color32::from_rgb(191, 191, 0),
+        Color32::from_rgb(0, 191, 191),
+        Color32::from_rgb(0, 191, 0),
+        Color32::from_rgb(191, 0, 191),
+        Color32::from_rgb(191, 0, 0),
+        Color32::from_rgb(0, 0, 191),
+    ];
+    let strip = [
+        Color32::from_rgb(0, 0, 191),
+        black,
+        Color32::from_rgb(191, 0, 191),
+        black,
+        Color32::from_rgb(0, 191, 191),
+        black,
+        grey(191),
+    ];
+    // In twelfths of a bar: -I, white, +Q and black take a bar and a quarter
+    // each, the PLUGE's three steps a third each, and black the last bar
+    let bottom = [
+        (15, Color32::from_rgb(0, 33, 76)),
+        (30, Color32::WHITE),
+        (45, Color32::from_rgb(50, 0, 106)),
+        (60, black),
+        (64, grey(9)),
+        (68, black),
+        (72, grey(29)),
+        (84, black),
+    ];
+    let row = |color: &dyn Fn(usize) -> Color32| (0..width).map(color).collect::<Vec<_>>();
+    let top = row(&|x| top[x * 7 / width]);
+    let strip = row(&|x| strip[x * 7 / width]);
+    let bottom = row(&|x| {
+        let at = x * 84 / width;
+        bottom
+            .iter()
+            .find(|(end, _)| at < *end)
+            .map_or(black, |(_, c)| *c)
+    });
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        pixels.extend_from_slice(match y * 12 / height {
+            0..=7 => &top,
+            8 => &strip,
+            _ => &bottom,
+        });
+    }
+    egui::ColorImage {
+        size: [width, height],
+        pixels,
+    }
+}
+
+/// White one-pixel lines on black, [`GRID_COLUMNS`] across with the picture's
+/// edges among them, the center crossed and a circle touching top and bottom.
+fn grid([width, height]: [usize; 2]) -> egui::ColorImage {
+    let cell = width as f32 / GRID_COLUMNS as f32;
+    // Where a new cell starts, and the far edge
+    let on_line = |at: usize, length: usize| {
+        let cell_of = |at: usize| (at as f32 / cell).floor();
+        at == 0 || at == length - 1 || cell_of(at) != cell_of(at - 1)
+    };
+    let (cx, cy) = (width / 2, height / 2);
+    let radius = height as f32 / 2.0 - 1.0;
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let dx = x as f32 - cx as f32;
+            let dy = y as f32 - cy as f32;
+            let center = (x.abs_diff(cx) <= 1 && y.abs_diff(cy) < height / 8)
+                || (y.abs_diff(cy) <= 1 && x.abs_diff(cx) < height / 8);
+            let circle = ((dx * dx + dy * dy).sqrt() - radius).abs() < 1.0;
+            let lit = on_line(x, width) || on_line(y, height) || center || circle;
+            pixels.push(if lit { Color32::WHITE } else { Color32::BLACK });
+        }
+    }
+    egui::ColorImage {
+        size: [width, height],
+        pixels,
+    }
+}
+
+/// The test tone, on every channel of the output; it stops when dropped.
+pub struct Tone {
+    source: Arc<Source>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Tone {
+    /// Start the tone, `None` without an audio output.
+    pub fn start() -> Option<Self> {
+        let mixer = mixer::output()?;
+        let channels = mixer.channels();
+        let rate = mixer.samples_per_second() / channels;
+        let source =
+            Arc::new(Source::new(Role::Program).with_ahead(mixer.samples_per_second() / 5));
+        mixer.add(source.clone());
+
+        let outcome = Outcome::new();
+        let stop = outcome.eos.clone();
+        let tone = Self {
+            source: source.clone(),
+            stop: stop.clone(),
+        };
+        let started = decode_thread::spawn("test tone", outcome, move || {
+            let step = TAU * TONE_HZ / rate as f32;
+            let mut phase = 0.0f32;
+            loop {
+                let mut samples = Vec::with_capacity(rate / 10 * channels);
+                for _ in 0..rate / 10 {
+                    let sample = phase.sin() * TONE_LEVEL;
+                    samples.extend(std::iter::repeat(sample).take(channels));
+                    phase = (phase + step) % TAU;
+                }
+                if !source.push(samples, &stop) {
+                    return Ok(());
+                }
+            }
+        });
+        match started {
+            Ok(_) => Some(tone),
+            Err(e) => {
+                log::error!("Failed to start the test tone: {}", e);
+                None
+            }
+        }
+    }
+}
+
+impl Drop for Tone {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        self.source.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_images() {
+        let bars = image(TestPattern::Bars, [84, 12]);
+        assert_eq!(bars.size, [84, 12]);
+        let at = |image: &egui::ColorImage, x: usize, y: usize| image.pixels[y * 84 + x];
+        assert_eq!(at(&bars, 0, 0), Color32::from_gray(191));
+        assert_eq!(at(&bars, 83, 0), Color32::from_rgb(0, 0, 191));
+        assert_eq!(at(&bars, 0, 8), Color32::from_rgb(0, 0, 191));
+        assert_eq!(at(&bars, 20, 11), Color32::WHITE);
+        assert_eq!(at(&bars, 65, 11), Color32::from_gray(19));
+
+        let grid = image(TestPattern::Grid, [84, 12]);
+        // The edges, and the first line in
+        assert_eq!(at(&grid, 0, 5), Color32::WHITE);
+        assert_eq!(at(&grid, 83, 5), Color32::WHITE);
+        assert_eq!(at(&grid, 6, 5), Color32::WHITE);
+        assert_eq!(at(&grid, 3, 3), Color32::BLACK);
+        assert_eq!(image(TestPattern::Grid, [0, 0]).size, [1, 1]);
+    }
+}
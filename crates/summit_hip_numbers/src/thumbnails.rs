@@ -0,0 +1,264 @@
+//! A small picture of each hip for the browser (F2), and the poster shown
+//! while its video loads: a frame from the start of its first video, decoded
+//! in the background and cached as a PNG in `thumbnails` next to the log
+//! file, so the next start only decodes videos that are new or changed.
+
+use anyhow::{anyhow, Result};
+use ffmpeg_next as ffmpeg;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::decode_thread::{self, Outcome};
+use crate::video_player::frame_time;
+use summit_hip_core::config::Config;
+use summit_hip_core::file_scanner::{is_stream_url, VideoFile};
+use summit_hip_core::playback::rgba_image;
+
+/// Width of a thumbnail, in pixels.
+pub const WIDTH: u32 = 240;
+
+/// Where in the video the thumbnail is taken, past the usual fade in.
+const POSTER_AT: Duration = Duration::from_secs(1);
+
+/// Where the thumbnails are cached.
+pub fn dir(config: &Config) -> PathBuf {
+    Path::new(&config.logging.file)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join("thumbnails")
+}
+
+/// The cached thumbnail of `video`, named after its file.
+fn cache_path(dir: &Path, video: &VideoFile) -> PathBuf {
+    dir.join(format!("{}.png", video.name))
+}
+
+/// Whether `thumbnail` was made since `video` last changed.
+fn is_fresh(thumbnail: &Path, video: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    match (modified(thumbnail), modified(video)) {
+        (Some(made), Some(changed)) => made >= changed,
+        _ => false,
+    }
+}
+
+/// The frame of `path` shown at `at`, or its last for a shorter video,
+/// scaled to `width` pixels wide.
+pub fn decode_frame(path: &str, at: Duration, width: u32) -> Result<image::RgbaImage> {
+    ffmpeg::init().map_err(|e| anyhow!("Failed to initialize FFmpeg: {}", e))?;
+    let mut ictx = ffmpeg::format::input(path)?;
+    let stream = ictx
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or_else(|| anyhow!("no video stream"))?;
+    let index = stream.index();
+    let time_base = stream.time_base();
+    let mut decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?
+        .decoder()
+        .video()?;
+    let height = (decoder.height() as u64 * width as u64 / decoder.width().max(1) as u64).max(1);
+    let mut scaler = ffmpeg::software::scaling::context::Context::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        ffmpeg::format::Pixel::RGBA,
+        width,
+        height as u32,
+        ffmpeg::software::scaling::flag::Flags::BILINEAR,
+    )?;
+
+    let mut decoded = ffmpeg::util::frame::video::Video::empty();
+    let mut scaled = ffmpeg::util::frame::video::Video::empty();
+    let mut have_frame = false;
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != index {
+            continue;
+        }
+        decoder.send_packet(&packet)?;
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            scaler.run(&decoded, &mut scaled)?;
+            have_frame = true;
+            if frame_time(decoded.timestamp(), time_base).map_or(true, |shown| shown >= at) {
+                return to_image(&scaled);
+            }
+        }
+    }
+    decoder.send_eof().ok();
+    while decoder.receive_frame(&mut decoded).is_ok() {
+        scaler.run(&decoded, &mut scaled)?;
+        have_frame = true;
+    }
+    if !have_frame {
+        return Err(anyhow!("no frames decoded"));
+    }
+    to_image(&scaled)
+}
+
+fn to_image(frame: &ffmpeg::util::frame::video::Video) -> Result<image::RgbaImage> {
+    let (width, height) = (frame.width(), frame.height());
+    let color = rgba_image(
+        [width as usize, height as usize],
+        frame.stride(0),
+        frame.data(0),
+    );
+    let pixels = color.pixels.iter().flat_map(|p| p.to_array()).collect();
+    image::RgbaImage::from_raw(width, height, pixels).ok_or_else(|| anyhow!("bad frame size"))
+}
+
+/// The thumbnails of a playlist, made on a background thread.
+pub struct Thumbnails {
+    dir: PathBuf,
+    /// The videos they are for.
+    paths: Vec<String>,
+    /// Each hip's thumbnail as it is made, `None` for a hip that has none.
+    made: Arc<Mutex<BTreeMap<String, Option<PathBuf>>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Thumbnails {
+    /// Start making the thumbnails of `videos` in `dir`, from the first
+    /// video of each hip.
+    pub fn start(videos: &[VideoFile], dir: PathBuf) -> Self {
+        let mut firsts: Vec<VideoFile> = Vec::new();
+        for video in videos {
+            if firsts
+                .last()
+                .map_or(true, |first| first.hip_number != video.hip_number)
+            {
+                firsts.push(video.clone());
+            }
+        }
+        let thumbnails = Self {
+            dir: dir.clone(),
+            paths: videos.iter().map(|video| video.path.clone()).collect(),
+            made: Arc::default(),
+            stop: Arc::default(),
+        };
+        if firsts.is_empty() {
+            return thumbnails;
+        }
+
+        let made = thumbnails.made.clone();
+        let stop = thumbnails.stop.clone();
+        let started = decode_thread::spawn("thumbnails", Outcome::new(), move || {
+            fs::create_dir_all(&dir).map_err(|e| anyhow!("{}: {}", dir.display(), e))?;
+            let mut count = 0;
+            for video in firsts {
+                if stop.load(Ordering::Relaxed) {
+                    return Ok(());
+                }
+                let thumbnail = make(&dir, &video);
+                count += thumbnail.is_some() as usize;
+                made.lock().unwrap().insert(video.hip_number, thumbnail);
+            }
+            log::info!("{} thumbnails ready in {}", count, dir.display());
+            Ok(())
+        });
+        if let Err(e) = started {
+            log::error!("Failed to start making thumbnails: {}", e);
+        }
+        thumbnails
+    }
+
+    /// Whether these are the thumbnails of `videos`.
+    pub fn is_for(&self, videos: &[VideoFile]) -> bool {
+        self.paths.iter().eq(videos.iter().map(|video| &video.path))
+    }
+
+    /// The thumbnail of `hip`: `None` while it's being made, `Some(None)` if
+    /// it has none.
+    pub fn get(&self, hip: &str) -> Option<Option<PathBuf>> {
+        self.made.lock().unwrap().get(hip).cloned()
+    }
+
+    /// The thumbnail to show while `video` loads, once made. Only the first
+    /// video of a hip has one.
+    pub fn poster(&self, video: &VideoFile) -> Option<PathBuf> {
+        let made = self.get(&video.hip_number)??;
+        (made == cache_path(&self.dir, video)).then_some(made)
+    }
+}
+
+impl Drop for Thumbnails {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The cached thumbnail of `video`, made now unless it's up to date.
+fn make(dir: &Path, video: &VideoFile) -> Option<PathBuf> {
+    if is_stream_url(&video.path) {
+        return None;
+    }
+    let thumbnail = cache_path(dir, video);
+    if is_fresh(&thumbnail, Path::new(&video.path)) {
+        return Some(thumbnail);
+    }
+    let saved = decode_frame(&video.path, POSTER_AT, WIDTH)
+        .and_then(|image| image.save(&thumbnail).map_err(anyhow::Error::from));
+    match saved {
+        Ok(()) => Some(thumbnail),
+        Err(e) => {
+            log::warn!("No thumbnail for {}: {}", video.name, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_thumbnails() {
+        let dir = tempfile::tempdir().unwrap();
+        let video = VideoFile {
+            path: dir.path().join("001.mp4").to_string_lossy().to_string(),
+            name: "001.mp4".to_string(),
+            hip_number: "001".to_string(),
+        };
+        let thumbnail = cache_path(dir.path(), &video);
+        assert_eq!(thumbnail, dir.path().join("001.mp4.png"));
+        assert!(!is_fresh(&thumbnail, Path::new(&video.path)));
+
+        fs::write(&video.path, b"").unwrap();
+        image::RgbaImage::new(2, 1).save(&thumbnail).unwrap();
+        assert!(is_fresh(&thumbnail, Path::new(&video.path)));
+        assert_eq!(make(dir.path(), &video), Some(thumbnail.clone()));
+
+        // Videos that aren't there anymore have none
+        fs::remove_file(&video.path).unwrap();
+        assert!(!is_fresh(&thumbnail, Path::new(&video.path)));
+    }
+
+    #[test]
+    fn test_poster() {
+        let video = |name: &str| VideoFile {
+            path: format!("/videos/{}", name),
+            name: name.to_string(),
+            hip_number: name[..3].to_string(),
+        };
+        let dir = PathBuf::from("/logs/thumbnails");
+        let thumbnails = Thumbnails {
+            dir: dir.clone(),
+            paths: Vec::new(),
+            made: Arc::new(Mutex::new(BTreeMap::from([
+                ("001".to_string(), Some(dir.join("001 a.mp4.png"))),
+                ("002".to_string(), None),
+            ]))),
+            stop: Arc::default(),
+        };
+        assert_eq!(
+            thumbnails.poster(&video("001 a.mp4")),
+            Some(dir.join("001 a.mp4.png"))
+        );
+        // Not the picture of another of the hip's videos
+        assert_eq!(thumbnails.poster(&video("001 b.mp4")), None);
+        assert_eq!(thumbnails.poster(&video("002.mp4")), None);
+        assert_eq!(thumbnails.poster(&video("003.mp4")), None);
+    }
+}
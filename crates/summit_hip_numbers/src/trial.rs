@@ -0,0 +1,189 @@
+//! The trial for installs without a license: [`TRIAL_DAYS`] days without
+//! limits, counted from the first start, then the demo limits.
+//!
+//! The start date is kept in `trial.dat` in both the per-user config folder
+//! and next to the executable, so deleting one copy doesn't restart the
+//! trial. Each copy carries a keyed hash over its dates and the machine ID;
+//! an edited copy ends the trial, and the latest date the player has seen
+//! is kept so turning the clock back doesn't add days.
+
+use chrono::NaiveDate;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use summit_hip_core::config;
+
+pub const TRIAL_DAYS: u32 = 14;
+
+pub const TRIAL_FILE_NAME: &str = "trial.dat";
+
+/// Mixed into the hash so the file can't be rewritten by someone who only
+/// reads this source. Release builds set their own; CI passes an empty one
+/// on forks.
+const SECRET: &str = match option_env!("SHN_TRIAL_SECRET") {
+    Some(secret) if !secret.is_empty() => secret,
+    _ => "summit_hip_numbers trial",
+};
+
+#[derive(Clone, Debug, PartialEq)]
+struct State {
+    started: NaiveDate,
+    last_seen: NaiveDate,
+    machine: String,
+}
+
+impl State {
+    fn hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        for part in [
+            SECRET,
+            &self.started.to_string(),
+            &self.last_seen.to_string(),
+            &self.machine,
+        ] {
+            hasher.update(part.as_bytes());
+            hasher.update(b"\n");
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    fn to_text(&self) -> String {
+        format!(
+            "started={}\nlast_seen={}\nmachine={}\nhash={}\n",
+            self.started,
+            self.last_seen,
+            self.machine,
+            self.hash()
+        )
+    }
+
+    fn parse(text: &str) -> Option<State> {
+        let field = |name: &str| {
+            text.lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+                .map(str::trim)
+        };
+        let date = |name: &str| NaiveDate::parse_from_str(field(name)?, "%Y-%m-%d").ok();
+        let state = State {
+            started: date("started")?,
+            last_seen: date("last_seen")?,
+            machine: field("machine")?.to_string(),
+        };
+        (field("hash")? == state.hash()).then_some(state)
+    }
+}
+
+/// Where the trial state is kept.
+pub fn paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(user) = config::user_config_path() {
+        paths.push(user.with_file_name(TRIAL_FILE_NAME));
+    }
+    paths.push(config::exe_dir().join(TRIAL_FILE_NAME));
+    paths
+}
+
+/// Days left in the trial on `today`, starting it if this is the first run,
+/// or why it is over.
+pub fn check(paths: &[PathBuf], today: NaiveDate, machine: &str) -> Result<u32, String> {
+    let mut state: Option<State> = None;
+    for path in paths.iter().filter(|p| p.is_file()) {
+        let found = fs::read_to_string(path)
+            .ok()
+            .and_then(|text| State::parse(&text))
+            .filter(|found| found.machine == machine)
+            .ok_or_else(|| format!("the trial record {} was modified", path.display()))?;
+        state = Some(match state {
+            None => found,
+            Some(state) => State {
+                started: state.started.min(found.started),
+                last_seen: state.last_seen.max(found.last_seen),
+                machine: state.machine,
+            },
+        });
+    }
+    let mut state = state.unwrap_or_else(|| State {
+        started: today,
+        last_seen: today,
+        machine: machine.to_string(),
+    });
+    state.last_seen = state.last_seen.max(today);
+    for path in paths {
+        save(&state, path);
+    }
+
+    let used = (state.last_seen - state.started).num_days().max(0) as u32;
+    if used >= TRIAL_DAYS {
+        let ended = state.started + chrono::Duration::days(TRIAL_DAYS as i64);
+        return Err(format!("the {}-day trial ended on {}", TRIAL_DAYS, ended));
+    }
+    Ok(TRIAL_DAYS - used)
+}
+
+/// Best effort: a read-only location just keeps the other copy.
+fn save(state: &State, path: &Path) {
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = fs::write(path, state.to_text());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const MACHINE: &str = "7F3A-09C2-D415-88E0";
+
+    fn day(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_countdown() {
+        let temp_dir = TempDir::new().unwrap();
+        let paths = [
+            temp_dir.path().join("user").join(TRIAL_FILE_NAME),
+            temp_dir.path().join(TRIAL_FILE_NAME),
+        ];
+        assert_eq!(check(&paths, day("2026-03-01"), MACHINE), Ok(TRIAL_DAYS));
+        assert!(paths.iter().all(|p| p.is_file()));
+        assert_eq!(
+            check(&paths, day("2026-03-05"), MACHINE),
+            Ok(TRIAL_DAYS - 4)
+        );
+
+        // Turning the clock back doesn't give days back
+        assert_eq!(
+            check(&paths, day("2026-03-02"), MACHINE),
+            Ok(TRIAL_DAYS - 4)
+        );
+
+        // Nor does deleting one copy
+        fs::remove_file(&paths[1]).unwrap();
+        assert_eq!(
+            check(&paths, day("2026-03-06"), MACHINE),
+            Ok(TRIAL_DAYS - 5)
+        );
+
+        assert_eq!(
+            check(&paths, day("2026-03-15"), MACHINE),
+            Err("the 14-day trial ended on 2026-03-15".to_string())
+        );
+    }
+
+    #[test]
+    fn test_modified_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let paths = [temp_dir.path().join(TRIAL_FILE_NAME)];
+        check(&paths, day("2026-03-01"), MACHINE).unwrap();
+
+        let text = fs::read_to_string(&paths[0]).unwrap();
+        fs::write(&paths[0], text.replace("2026-03-01", "2026-04-01")).unwrap();
+        let error = check(&paths, day("2026-03-02"), MACHINE).unwrap_err();
+        assert!(error.contains("was modified"), "{}", error);
+
+        fs::write(&paths[0], text).unwrap();
+        assert!(check(&paths, day("2026-03-02"), "0000-0000-0000-0000").is_err());
+    }
+}
@@ -1,30 +1,284 @@
 use anyhow::{anyhow, Result};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::Stream;
-use eframe::epaint::ColorImage;
 use ffmpeg_next as ffmpeg;
 use std::path::Path;
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    mpsc::{channel, Receiver, Sender},
-    Arc, Mutex,
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex, OnceLock,
 };
 use std::thread;
 use std::time::{Duration, Instant};
-use tokio::sync::watch;
+
+use crate::decode_thread::{self, Outcome};
+use crate::metrics;
+use crate::mixer::{self, Role, Source};
+use summit_hip_core::file_scanner::is_stream_url;
+use summit_hip_core::playback::{rgba_image, FrameSender, PlayerBackend};
+
+/// How long FFmpeg waits on an unreachable stream before giving up, in
+/// microseconds.
+const STREAM_TIMEOUT_US: &str = "10000000";
+
+/// Open a file or network stream. RTSP goes over TCP, which cameras and
+/// firewalls handle more reliably than UDP.
+fn open_input(path: &str) -> Result<ffmpeg::format::context::Input> {
+    if !is_stream_url(path) {
+        return Ok(ffmpeg::format::input(path)?);
+    }
+    let mut options = ffmpeg::Dictionary::new();
+    if path.starts_with("rtsp://") {
+        options.set("rtsp_transport", "tcp");
+    }
+    options.set("timeout", STREAM_TIMEOUT_US);
+    Ok(ffmpeg::format::input_with_dictionary(path, options)?)
+}
+
+/// Jump to the keyframe at or before `at`; nothing to do at the start.
+fn seek_to(ictx: &mut ffmpeg::format::context::Input, at: Duration) {
+    if at.is_zero() {
+        return;
+    }
+    let ts = at.as_micros() as i64;
+    if let Err(e) = ictx.seek(ts, ..ts) {
+        log::warn!("Failed to seek to {:.1}s: {}", at.as_secs_f64(), e);
+    }
+}
+
+/// Presentation time of a decoded frame, if it has one.
+pub fn frame_time(timestamp: Option<i64>, time_base: ffmpeg::Rational) -> Option<Duration> {
+    let ts = timestamp.filter(|ts| *ts >= 0)?;
+    if time_base.denominator() == 0 {
+        return None;
+    }
+    let seconds = ts as f64 * time_base.numerator() as f64 / time_base.denominator() as f64;
+    Some(Duration::from_secs_f64(seconds))
+}
+
+/// Decode the best audio stream of `path` from `start_at` into `source`,
+/// until its end or until `stop` is set, resampled to `rate` and
+/// `channels`, the output device's. The background music plays files
+/// through this too.
+pub fn decode_audio(
+    path: &str,
+    source: &Source,
+    rate: u32,
+    channels: u16,
+    start_at: Duration,
+    stop: &AtomicBool,
+) -> Result<()> {
+    let mut ictx = open_input(path)?;
+    seek_to(&mut ictx, start_at);
+    let audio_stream = ictx
+        .streams()
+        .best(ffmpeg::media::Type::Audio)
+        .ok_or_else(|| anyhow!("No audio stream found"))?;
+    let audio_stream_index = audio_stream.index();
+    let time_base = audio_stream.time_base();
+
+    let context_decoder =
+        ffmpeg::codec::context::Context::from_parameters(audio_stream.parameters())?;
+    let mut decoder = context_decoder.decoder().audio()?;
+    let mut resampler = Resampler::new(rate, channels);
+
+    for (stream, packet) in ictx.packets() {
+        if stop.load(Ordering::SeqCst) {
+            log::info!("Audio playback stopped by user");
+            return Ok(());
+        }
+
+        if stream.index() == audio_stream_index {
+            decoder.send_packet(&packet)?;
+
+            let mut decoded = ffmpeg::util::frame::audio::Audio::empty();
+            while decoder.receive_frame(&mut decoded).is_ok() {
+                if frame_time(decoded.timestamp(), time_base).is_some_and(|at| at < start_at) {
+                    continue;
+                }
+                if !source.push(resampler.run(&mut decoded)?, stop) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    decoder.send_eof().ok();
+    let mut decoded = ffmpeg::util::frame::audio::Audio::empty();
+    while decoder.receive_frame(&mut decoded).is_ok() {
+        if !source.push(resampler.run(&mut decoded)?, stop) {
+            return Ok(());
+        }
+    }
+    loop {
+        let samples = resampler.flush()?;
+        if samples.is_empty() || !source.push(samples, stop) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Samples on each channel the resampler may hold back between frames, on
+/// top of what a frame converts to.
+const RESAMPLER_SLACK: usize = 256;
+
+/// Converts decoded audio to the output device's format, interleaved `f32`
+/// at its rate and channel count; otherwise a 44.1 kHz or mono file plays
+/// at the wrong speed on a 48 kHz stereo device.
+struct Resampler {
+    rate: u32,
+    layout: ffmpeg::ChannelLayout,
+    /// Made for the first frame, and again if a frame comes in another
+    /// format.
+    context: Option<ffmpeg::software::resampling::Context>,
+}
+
+impl Resampler {
+    fn new(rate: u32, channels: u16) -> Self {
+        Self {
+            rate,
+            layout: ffmpeg::ChannelLayout::default(channels.max(1) as i32),
+            context: None,
+        }
+    }
+
+    /// A frame in the device's format with room for `samples` on each
+    /// channel, and what the resampler held back.
+    fn output(&self, samples: usize) -> ffmpeg::util::frame::audio::Audio {
+        ffmpeg::util::frame::audio::Audio::new(
+            ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed),
+            samples + RESAMPLER_SLACK,
+            self.layout,
+        )
+    }
+
+    fn run(&mut self, frame: &mut ffmpeg::util::frame::audio::Audio) -> Result<Vec<f32>> {
+        // Some files don't say which channels they have
+        if frame.channel_layout().is_empty() {
+            frame.set_channel_layout(ffmpeg::ChannelLayout::default(frame.channels() as i32));
+        }
+        let input = ffmpeg::software::resampling::context::Definition {
+            format: frame.format(),
+            channel_layout: frame.channel_layout(),
+            rate: frame.rate(),
+        };
+        let mut output = self.output(output_samples(frame.samples(), input.rate, self.rate));
+        let context = match &mut self.context {
+            Some(context) if *context.input() == input => context,
+            context => context.insert(ffmpeg::software::resampling::Context::get(
+                input.format,
+                input.channel_layout,
+                input.rate,
+                ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed),
+                self.layout,
+                self.rate,
+            )?),
+        };
+        context.run(frame, &mut output)?;
+        convert_audio_frame(&output)
+    }
+
+    /// What the resampler still holds, once the decoder is done; empty when
+    /// there's nothing left.
+    fn flush(&mut self) -> Result<Vec<f32>> {
+        let mut output = self.output(0);
+        let Some(context) = &mut self.context else {
+            return Ok(Vec::new());
+        };
+        context.flush(&mut output)?;
+        convert_audio_frame(&output)
+    }
+}
+
+/// Samples on each channel `samples` at `from` Hz make at `to` Hz.
+fn output_samples(samples: usize, from: u32, to: u32) -> usize {
+    if from == 0 {
+        return samples;
+    }
+    (samples as u64 * to as u64 / from as u64) as usize
+}
+
+fn convert_audio_frame(frame: &ffmpeg::util::frame::audio::Audio) -> Result<Vec<f32>> {
+    let format = frame.format();
+    let channels = frame.channels() as usize;
+    let samples = frame.samples();
+
+    let mut output = Vec::new();
+
+    match format {
+        ffmpeg::format::Sample::F32(sample_type) => {
+            let data = frame.data(0);
+            let float_data = unsafe {
+                std::slice::from_raw_parts(data.as_ptr() as *const f32, samples * channels)
+            };
+
+            if sample_type == ffmpeg::format::sample::Type::Packed {
+                output.extend_from_slice(float_data);
+            } else {
+                for i in 0..samples {
+                    for ch in 0..channels {
+                        let ch_data = frame.data(ch);
+                        let ch_float = unsafe {
+                            std::slice::from_raw_parts(ch_data.as_ptr() as *const f32, samples)
+                        };
+                        output.push(ch_float[i]);
+                    }
+                }
+            }
+        }
+        ffmpeg::format::Sample::I16(sample_type) => {
+            let data = frame.data(0);
+            let i16_data = unsafe {
+                std::slice::from_raw_parts(data.as_ptr() as *const i16, samples * channels)
+            };
+
+            if sample_type == ffmpeg::format::sample::Type::Packed {
+                for &sample in i16_data {
+                    output.push(sample as f32 / 32768.0);
+                }
+            } else {
+                for i in 0..samples {
+                    for ch in 0..channels {
+                        let ch_data = frame.data(ch);
+                        let ch_i16 = unsafe {
+                            std::slice::from_raw_parts(ch_data.as_ptr() as *const i16, samples)
+                        };
+                        output.push(ch_i16[i] as f32 / 32768.0);
+                    }
+                }
+            }
+        }
+        _ => {
+            return Err(anyhow!("Unsupported audio format: {:?}", format));
+        }
+    }
+
+    Ok(output)
+}
 
 pub struct VideoPlayer {
-    eos: Arc<AtomicBool>,
-    error: Arc<Mutex<Option<String>>>,
+    outcome: Outcome,
+    /// The video's sound, in the mixer while it plays.
+    audio: Arc<Source>,
+    paused: Arc<AtomicBool>,
+    /// Where playback starts, for resuming after a restart.
+    start_at: Duration,
+    /// Where playback ends early, for a hip's out point.
+    end_at: Option<Duration>,
+    /// Presentation time of the frame on screen, in milliseconds.
+    position_ms: Arc<AtomicU64>,
     video_path: String,
-    texture_sender: watch::Sender<Option<ColorImage>>,
+    texture_sender: FrameSender,
+    /// Set once the first frame has been sent.
+    started: Arc<AtomicBool>,
+    /// Whether the video has an audio track, once it's open.
+    audio_track: Arc<OnceLock<bool>>,
+    /// Joins the audio thread when it ends; not joined itself, so stopping a
+    /// player never waits on FFmpeg.
     _video_thread: Option<thread::JoinHandle<()>>,
-    _audio_thread: Option<thread::JoinHandle<()>>,
-    _audio_stream: Option<Stream>,
 }
 
 impl VideoPlayer {
-    pub fn new(uri: &str, texture_sender: watch::Sender<Option<ColorImage>>) -> Result<Self> {
+    pub fn new(uri: &str, texture_sender: FrameSender) -> Result<Self> {
         ffmpeg::init().map_err(|e| anyhow!("Failed to initialize FFmpeg: {}", e))?;
 
         let video_path = if uri.starts_with("file://") {
@@ -33,165 +287,153 @@ impl VideoPlayer {
             uri.to_string()
         };
 
-        if !Path::new(&video_path).exists() {
+        if is_stream_url(&video_path) {
+            ffmpeg::format::network::init();
+        } else if !Path::new(&video_path).exists() {
             return Err(anyhow!("Video file not found: {}", video_path));
         }
 
         log::info!("Creating FFmpeg player for: {}", video_path);
 
-        let eos = Arc::new(AtomicBool::new(false));
-        let error = Arc::new(Mutex::new(None));
-
         let player = VideoPlayer {
-            eos,
-            error,
+            outcome: Outcome::new(),
+            audio: Arc::new(Source::new(Role::Program)),
+            paused: Arc::new(AtomicBool::new(false)),
+            start_at: Duration::ZERO,
+            end_at: None,
+            position_ms: Arc::new(AtomicU64::new(0)),
             video_path,
             texture_sender,
+            started: Arc::new(AtomicBool::new(false)),
+            audio_track: Arc::new(OnceLock::new()),
             _video_thread: None,
-            _audio_thread: None,
-            _audio_stream: None,
         };
 
         Ok(player)
     }
 
+    /// Start playing on a thread of its own, which opens the video, starts
+    /// the audio and decodes, so a slow file or stream never holds up the
+    /// window. A video that fails to open, or a thread that fails or panics
+    /// while playing it, shows up in [`get_error`](Self::get_error).
     pub fn play(&mut self) -> Result<()> {
         log::info!("Starting FFmpeg playback");
 
         let video_path = self.video_path.clone();
-        let eos = self.eos.clone();
-        let error = self.error.clone();
         let texture_sender = self.texture_sender.clone();
-
-        let ictx = ffmpeg::format::input(&video_path)?;
-
-        let video_stream = ictx
-            .streams()
-            .best(ffmpeg::media::Type::Video)
-            .ok_or_else(|| anyhow!("No video stream found"))?;
-        let video_stream_index = video_stream.index();
-
-        let audio_stream_opt = ictx.streams().best(ffmpeg::media::Type::Audio);
-        let audio_stream_index = audio_stream_opt.as_ref().map(|s| s.index());
-
-        let (audio_tx, audio_rx): (Sender<Vec<f32>>, Receiver<Vec<f32>>) = channel();
-        let audio_stream = if let Some(_stream) = audio_stream_opt {
-            log::info!("Audio stream found, initializing audio output");
-            match Self::setup_audio_output(audio_rx) {
-                Ok(stream) => Some(stream),
-                Err(e) => {
-                    log::warn!(
-                        "Failed to setup audio output: {}, continuing without audio",
-                        e
-                    );
-                    None
-                }
-            }
-        } else {
-            log::info!("No audio stream found in video");
-            None
-        };
-
-        let video_path_clone = video_path.clone();
-        let eos_clone = eos.clone();
-        let error_clone = error.clone();
-
-        let video_handle = thread::spawn(move || {
-            if let Err(e) = Self::video_playback_loop(
-                &video_path_clone,
-                video_stream_index,
+        let audio = self.audio.clone();
+        let paused = self.paused.clone();
+        let start_at = self.start_at;
+        let end_at = self.end_at;
+        let position_ms = self.position_ms.clone();
+        let started = self.started.clone();
+        let audio_track = self.audio_track.clone();
+        let outcome = self.outcome.clone();
+
+        let video_handle = decode_thread::spawn("video decoder", outcome.clone(), move || {
+            Self::open_and_play(
+                &video_path,
                 texture_sender,
-                eos_clone.clone(),
-                error_clone.clone(),
-            ) {
-                log::error!("Video playback error: {}", e);
-                *error_clone.lock().unwrap() = Some(e.to_string());
-                eos_clone.store(true, Ordering::SeqCst);
-            } else {
-                log::info!("Video playback completed normally");
-                eos_clone.store(true, Ordering::SeqCst);
-            }
-        });
-
-        let audio_handle = if let Some(audio_idx) = audio_stream_index {
-            let video_path_clone = video_path.clone();
-            let eos_clone = eos.clone();
-            let error_clone = error.clone();
-
-            Some(thread::spawn(move || {
-                if let Err(e) = Self::audio_playback_loop(
-                    &video_path_clone,
-                    audio_idx,
-                    audio_tx,
-                    eos_clone.clone(),
-                    error_clone.clone(),
-                ) {
-                    log::error!("Audio playback error: {}", e);
-                }
-            }))
-        } else {
-            None
-        };
+                audio,
+                paused,
+                start_at,
+                end_at,
+                position_ms,
+                started,
+                audio_track,
+                outcome,
+            )
+        })?;
 
         self._video_thread = Some(video_handle);
-        self._audio_thread = audio_handle;
-        self._audio_stream = audio_stream;
-
         Ok(())
     }
 
-    fn setup_audio_output(audio_rx: Receiver<Vec<f32>>) -> Result<Stream> {
-        let host = cpal::default_host();
-        let device = host
-            .default_output_device()
-            .ok_or_else(|| anyhow!("No audio output device found"))?;
-
-        let config = device.default_output_config()?;
-        log::info!("Audio output config: {:?}", config);
-
-        let audio_buffer = Arc::new(Mutex::new(Vec::<f32>::new()));
-        let audio_buffer_clone = audio_buffer.clone();
-
-        thread::spawn(move || {
-            while let Ok(samples) = audio_rx.recv() {
-                let mut buffer = audio_buffer_clone.lock().unwrap();
-                buffer.extend_from_slice(&samples);
-            }
-        });
-
-        let stream = device.build_output_stream(
-            &config.into(),
-            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                let mut buffer = audio_buffer.lock().unwrap();
-                let len = data.len().min(buffer.len());
-                if len > 0 {
-                    data[..len].copy_from_slice(&buffer[..len]);
-                    buffer.drain(..len);
-                    if len < data.len() {
-                        data[len..].fill(0.0);
-                    }
-                } else {
-                    data.fill(0.0);
+    /// The playback thread: open the video, start the audio and decode until
+    /// the end or until stopped.
+    #[allow(clippy::too_many_arguments)]
+    fn open_and_play(
+        video_path: &str,
+        texture_sender: FrameSender,
+        audio: Arc<Source>,
+        paused: Arc<AtomicBool>,
+        start_at: Duration,
+        end_at: Option<Duration>,
+        position_ms: Arc<AtomicU64>,
+        started: Arc<AtomicBool>,
+        audio_track: Arc<OnceLock<bool>>,
+        outcome: Outcome,
+    ) -> Result<()> {
+        let (video_stream_index, has_audio) = {
+            let ictx = open_input(video_path)?;
+            let video = ictx
+                .streams()
+                .best(ffmpeg::media::Type::Video)
+                .ok_or_else(|| anyhow!("No video stream found"))?
+                .index();
+            let audio = ictx.streams().best(ffmpeg::media::Type::Audio).is_some();
+            (video, audio)
+        };
+        audio_track.set(has_audio).ok();
+
+        let mixer = if has_audio { mixer::output() } else { None };
+        let audio_thread = if let Some(mixer) = mixer {
+            mixer.add(audio.clone());
+            let (rate, channels) = (mixer.sample_rate(), mixer.channels() as u16);
+            let video_path = video_path.to_string();
+            let eos = outcome.eos.clone();
+            let audio_thread = decode_thread::spawn("audio decoder", outcome.clone(), move || {
+                // Audio that can't be decoded leaves the video playing silently
+                if let Err(e) = decode_audio(&video_path, &audio, rate, channels, start_at, &eos) {
+                    log::error!("Audio playback error: {}", e);
                 }
-            },
-            |err| eprintln!("Audio stream error: {}", err),
-            None,
-        )?;
+                Ok(())
+            })?;
+            Some(audio_thread)
+        } else {
+            if !has_audio {
+                log::info!("No audio stream found in video");
+            }
+            None
+        };
 
-        stream.play()?;
-        log::info!("Audio stream started");
-        Ok(stream)
+        let played = Self::video_playback_loop(
+            video_path,
+            video_stream_index,
+            texture_sender,
+            paused,
+            start_at,
+            end_at,
+            position_ms,
+            started,
+            outcome.eos.clone(),
+            outcome.error.clone(),
+        );
+        // The end of the video is the end of playback; stop the audio too
+        outcome.eos.store(true, Ordering::SeqCst);
+        if let Some(audio_thread) = audio_thread {
+            decode_thread::join(audio_thread);
+        }
+        played
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn video_playback_loop(
         video_path: &str,
         video_stream_index: usize,
-        texture_sender: watch::Sender<Option<ColorImage>>,
+        texture_sender: FrameSender,
+        paused: Arc<AtomicBool>,
+        start_at: Duration,
+        end_at: Option<Duration>,
+        position_ms: Arc<AtomicU64>,
+        started: Arc<AtomicBool>,
         eos: Arc<AtomicBool>,
         error: Arc<Mutex<Option<String>>>,
     ) -> Result<()> {
-        let mut ictx = ffmpeg::format::input(video_path)?;
+        let mut ictx = open_input(video_path)?;
+        seek_to(&mut ictx, start_at);
         let video_stream = ictx.streams().nth(video_stream_index).unwrap();
+        let time_base = video_stream.time_base();
 
         let context_decoder =
             ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())?;
@@ -214,8 +456,9 @@ impl VideoPlayer {
             Duration::from_millis(33)
         };
 
-        let start_time = Instant::now();
+        let mut start_time = Instant::now();
         let mut frame_count = 0u64;
+        let mut frame_rate = metrics::FrameRateMeter::new();
 
         for (stream, packet) in ictx.packets() {
             if eos.load(Ordering::SeqCst) {
@@ -224,6 +467,7 @@ impl VideoPlayer {
             }
 
             if stream.index() == video_stream_index {
+                let mut decode_start = Instant::now();
                 if let Err(e) = decoder.send_packet(&packet) {
                     *error.lock().unwrap() = Some(format!("Failed to send packet: {}", e));
                     return Err(anyhow!("Failed to send packet: {}", e));
@@ -236,26 +480,48 @@ impl VideoPlayer {
                         return Ok(());
                     }
 
+                    // The seek lands on a keyframe; decode up to the resume point
+                    let shown_at = frame_time(decoded.timestamp(), time_base);
+                    if shown_at.is_some_and(|at| at < start_at) {
+                        continue;
+                    }
+                    // Past the out point the video ends, sound and all
+                    if let (Some(at), Some(end)) = (shown_at, end_at) {
+                        if at >= end {
+                            log::info!("Reached the out point at {:?}", end);
+                            return Ok(());
+                        }
+                    }
+                    if let Some(at) = shown_at {
+                        position_ms.store(at.as_millis() as u64, Ordering::Relaxed);
+                    }
+
                     let mut rgb_frame = ffmpeg::util::frame::video::Video::empty();
                     scaler.run(&decoded, &mut rgb_frame)?;
 
-                    let width = rgb_frame.width() as usize;
-                    let height = rgb_frame.height() as usize;
-                    let data = rgb_frame.data(0);
-
-                    let color_image = ColorImage::from_rgba_unmultiplied([width, height], data);
+                    let color_image = rgba_image(
+                        [rgb_frame.width() as usize, rgb_frame.height() as usize],
+                        rgb_frame.stride(0),
+                        rgb_frame.data(0),
+                    );
+                    let decode_time = decode_start.elapsed();
 
-                    if texture_sender.send(Some(color_image)).is_err() {
+                    if texture_sender.send(color_image).is_err() {
                         log::warn!("Failed to send frame to texture channel");
                         return Ok(());
                     }
+                    started.store(true, Ordering::SeqCst);
 
+                    start_time += Self::wait_while_paused(&paused, &eos);
                     frame_count += 1;
                     let expected_time = start_time + frame_duration * frame_count as u32;
                     let now = Instant::now();
                     if expected_time > now {
                         thread::sleep(expected_time - now);
                     }
+                    metrics::frame_shown(decode_time, now > expected_time + frame_duration);
+                    frame_rate.tick();
+                    decode_start = Instant::now();
                 }
             }
         }
@@ -271,13 +537,16 @@ impl VideoPlayer {
                 let mut rgb_frame = ffmpeg::util::frame::video::Video::empty();
                 scaler.run(&decoded, &mut rgb_frame).ok();
 
-                let width = rgb_frame.width() as usize;
-                let height = rgb_frame.height() as usize;
-                let data = rgb_frame.data(0);
-
-                let color_image = ColorImage::from_rgba_unmultiplied([width, height], data);
-                texture_sender.send(Some(color_image)).ok();
+                let color_image = rgba_image(
+                    [rgb_frame.width() as usize, rgb_frame.height() as usize],
+                    rgb_frame.stride(0),
+                    rgb_frame.data(0),
+                );
+                if texture_sender.send(color_image).is_ok() {
+                    started.store(true, Ordering::SeqCst);
+                }
 
+                start_time += Self::wait_while_paused(&paused, &eos);
                 frame_count += 1;
                 let expected_time = start_time + frame_duration * frame_count as u32;
                 let now = Instant::now();
@@ -290,132 +559,128 @@ impl VideoPlayer {
         Ok(())
     }
 
-    fn audio_playback_loop(
-        video_path: &str,
-        audio_stream_index: usize,
-        audio_tx: Sender<Vec<f32>>,
-        eos: Arc<AtomicBool>,
-        _error: Arc<Mutex<Option<String>>>,
-    ) -> Result<()> {
-        let mut ictx = ffmpeg::format::input(video_path)?;
-        let audio_stream = ictx.streams().nth(audio_stream_index).unwrap();
-
-        let context_decoder =
-            ffmpeg::codec::context::Context::from_parameters(audio_stream.parameters())?;
-        let mut decoder = context_decoder.decoder().audio()?;
-
-        for (stream, packet) in ictx.packets() {
-            if eos.load(Ordering::SeqCst) {
-                log::info!("Audio playback stopped by user");
-                return Ok(());
-            }
-
-            if stream.index() == audio_stream_index {
-                decoder.send_packet(&packet)?;
-
-                let mut decoded = ffmpeg::util::frame::audio::Audio::empty();
-                while decoder.receive_frame(&mut decoded).is_ok() {
-                    if eos.load(Ordering::SeqCst) {
-                        return Ok(());
-                    }
-
-                    let samples = Self::convert_audio_frame(&decoded)?;
-                    if audio_tx.send(samples).is_err() {
-                        return Ok(());
-                    }
-                }
-            }
+    /// Block while paused (or until stopped) and return how long that took, so
+    /// the frame clock can be shifted by the pause.
+    fn wait_while_paused(paused: &AtomicBool, eos: &AtomicBool) -> Duration {
+        let start = Instant::now();
+        while paused.load(Ordering::SeqCst) && !eos.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_millis(20));
         }
+        start.elapsed()
+    }
 
-        decoder.send_eof().ok();
-        let mut decoded = ffmpeg::util::frame::audio::Audio::empty();
-        while decoder.receive_frame(&mut decoded).is_ok() {
-            if eos.load(Ordering::SeqCst) {
-                break;
-            }
-            let samples = Self::convert_audio_frame(&decoded)?;
-            audio_tx.send(samples).ok();
-        }
+    /// Start `at` into the video instead of at the beginning. Only takes
+    /// effect when called before [`play`](Self::play).
+    pub fn set_start(&mut self, at: Duration) {
+        self.start_at = at;
+        self.position_ms
+            .store(at.as_millis() as u64, Ordering::Relaxed);
+    }
 
-        Ok(())
+    /// End the video `at` into it, as if it ended there. Only takes effect
+    /// when called before [`play`](Self::play).
+    pub fn set_end(&mut self, at: Duration) {
+        self.end_at = Some(at);
     }
 
-    fn convert_audio_frame(frame: &ffmpeg::util::frame::audio::Audio) -> Result<Vec<f32>> {
-        let format = frame.format();
-        let channels = frame.channels() as usize;
-        let samples = frame.samples();
-
-        let mut output = Vec::new();
-
-        match format {
-            ffmpeg::format::Sample::F32(sample_type) => {
-                let data = frame.data(0);
-                let float_data = unsafe {
-                    std::slice::from_raw_parts(data.as_ptr() as *const f32, samples * channels)
-                };
-
-                if sample_type == ffmpeg::format::sample::Type::Packed {
-                    output.extend_from_slice(float_data);
-                } else {
-                    for i in 0..samples {
-                        for ch in 0..channels {
-                            let ch_data = frame.data(ch);
-                            let ch_float = unsafe {
-                                std::slice::from_raw_parts(ch_data.as_ptr() as *const f32, samples)
-                            };
-                            output.push(ch_float[i]);
-                        }
-                    }
-                }
-            }
-            ffmpeg::format::Sample::I16(sample_type) => {
-                let data = frame.data(0);
-                let i16_data = unsafe {
-                    std::slice::from_raw_parts(data.as_ptr() as *const i16, samples * channels)
-                };
-
-                if sample_type == ffmpeg::format::sample::Type::Packed {
-                    for &sample in i16_data {
-                        output.push(sample as f32 / 32768.0);
-                    }
-                } else {
-                    for i in 0..samples {
-                        for ch in 0..channels {
-                            let ch_data = frame.data(ch);
-                            let ch_i16 = unsafe {
-                                std::slice::from_raw_parts(ch_data.as_ptr() as *const i16, samples)
-                            };
-                            output.push(ch_i16[i] as f32 / 32768.0);
-                        }
-                    }
-                }
-            }
-            _ => {
-                return Err(anyhow!("Unsupported audio format: {:?}", format));
-            }
-        }
+    /// How far into the video the frame on screen is.
+    pub fn position(&self) -> Duration {
+        Duration::from_millis(self.position_ms.load(Ordering::Relaxed))
+    }
+
+    /// Hold the current frame and silence the audio until resumed.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+        self.audio.set_paused(paused);
+    }
 
-        Ok(output)
+    /// Set the audio volume multiplier (1.0 = unchanged). Negative values are
+    /// treated as 0.0.
+    pub fn set_gain(&self, gain: f32) {
+        self.audio.set_gain(gain);
     }
 
     pub fn stop(&self) -> Result<()> {
         log::info!("Stopping FFmpeg player");
-        self.eos.store(true, Ordering::SeqCst);
+        self.outcome.eos.store(true, Ordering::SeqCst);
+        self.audio.close();
         Ok(())
     }
 
     pub fn is_eos(&self) -> bool {
-        self.eos.load(Ordering::SeqCst)
+        self.outcome.eos.load(Ordering::SeqCst)
+    }
+
+    /// Still opening the video: no frame sent yet, and not finished or failed.
+    pub fn is_loading(&self) -> bool {
+        !self.started.load(Ordering::SeqCst) && !self.is_eos()
     }
 
     pub fn get_error(&self) -> Option<String> {
-        self.error.lock().unwrap().clone()
+        self.outcome.error()
+    }
+}
+
+impl PlayerBackend for VideoPlayer {
+    fn open(uri: &str, frames: FrameSender) -> Result<Self, String> {
+        VideoPlayer::new(uri, frames).map_err(|e| e.to_string())
+    }
+
+    fn play(&mut self) -> Result<(), String> {
+        VideoPlayer::play(self).map_err(|e| e.to_string())
+    }
+
+    fn stop(&self) -> Result<(), String> {
+        VideoPlayer::stop(self).map_err(|e| e.to_string())
+    }
+
+    fn set_start(&mut self, at: Duration) {
+        VideoPlayer::set_start(self, at)
+    }
+
+    fn set_end(&mut self, at: Duration) {
+        VideoPlayer::set_end(self, at)
+    }
+
+    fn set_gain(&self, gain: f32) {
+        VideoPlayer::set_gain(self, gain)
+    }
+
+    fn set_paused(&self, paused: bool) {
+        VideoPlayer::set_paused(self, paused)
+    }
+
+    fn position(&self) -> Duration {
+        VideoPlayer::position(self)
+    }
+
+    fn is_eos(&self) -> bool {
+        VideoPlayer::is_eos(self)
+    }
+
+    fn is_loading(&self) -> bool {
+        VideoPlayer::is_loading(self)
+    }
+
+    fn error(&self) -> Option<String> {
+        self.get_error()
+    }
+
+    fn has_audio(&self) -> Option<bool> {
+        self.audio_track.get().copied()
+    }
+
+    fn probe(path: &str) -> Result<(), String> {
+        crate::probe::codecs(path)
+            .map(|_| ())
+            .map_err(|problem| problem.to_string())
     }
 }
 
 impl Drop for VideoPlayer {
     fn drop(&mut self) {
         log::info!("Dropping VideoPlayer");
-        self.eos.store(true, Ordering::SeqCst);
+        self.outcome.eos.store(true, Ordering::SeqCst);
+        self.audio.close();
     }
 }
@@ -0,0 +1,116 @@
+//! The video frame on screen. Drawing with glow, each frame is handed to egui
+//! like any other image. Drawing with wgpu (`[display] renderer = "wgpu"`),
+//! the frames are written into one wgpu texture, kept while the video's size
+//! stays the same, instead of egui allocating a texture every frame.
+
+use eframe::egui;
+use eframe::egui_wgpu;
+use eframe::wgpu;
+
+/// A wgpu texture egui draws by `id`.
+struct Native {
+    texture: wgpu::Texture,
+    id: egui::TextureId,
+}
+
+pub struct VideoTexture {
+    /// Set when drawing with wgpu.
+    wgpu: Option<egui_wgpu::RenderState>,
+    handle: Option<egui::TextureHandle>,
+    native: Option<Native>,
+}
+
+impl VideoTexture {
+    pub fn new(wgpu: Option<egui_wgpu::RenderState>) -> Self {
+        Self {
+            wgpu,
+            handle: None,
+            native: None,
+        }
+    }
+
+    /// Show `image` from the next paint on.
+    pub fn upload(&mut self, ctx: &egui::Context, image: egui::ColorImage) {
+        match &self.wgpu {
+            Some(render_state) => upload_native(render_state, &mut self.native, &image),
+            None => {
+                self.handle = Some(ctx.load_texture("video_frame", image, Default::default()));
+            }
+        }
+    }
+
+    /// The last frame uploaded, once there is one.
+    pub fn id(&self) -> Option<egui::TextureId> {
+        match &self.native {
+            Some(native) => Some(native.id),
+            None => self.handle.as_ref().map(egui::TextureHandle::id),
+        }
+    }
+}
+
+/// Write `image` into `native`, making it anew when the size has changed.
+fn upload_native(
+    render_state: &egui_wgpu::RenderState,
+    native: &mut Option<Native>,
+    image: &egui::ColorImage,
+) {
+    let size = wgpu::Extent3d {
+        width: image.width() as u32,
+        height: image.height() as u32,
+        depth_or_array_layers: 1,
+    };
+    if size.width == 0 || size.height == 0 {
+        return;
+    }
+    let device = &render_state.device;
+    if native
+        .as_ref()
+        .map_or(true, |native| native.texture.size() != size)
+    {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("video_frame"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // What egui's own textures are, so the frame looks the same
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut renderer = render_state.renderer.write();
+        let id = match native.take() {
+            Some(old) => {
+                renderer.update_egui_texture_from_wgpu_texture(
+                    device,
+                    &view,
+                    wgpu::FilterMode::Linear,
+                    old.id,
+                );
+                old.texture.destroy();
+                old.id
+            }
+            None => renderer.register_native_texture(device, &view, wgpu::FilterMode::Linear),
+        };
+        *native = Some(Native { texture, id });
+    }
+    let Some(native) = native else {
+        return;
+    };
+    render_state.queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture: &native.texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        image.as_raw(),
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * size.width),
+            rows_per_image: Some(size.height),
+        },
+        size,
+    );
+}
@@ -0,0 +1,117 @@
+//! Outgoing webhooks on playback events, built with `--features webhooks` and
+//! enabled with `[webhooks] enabled = true`.
+//!
+//! Each URL is a template; `{event}`, `{hip}`, `{file}`, `{message}` and
+//! `{timestamp}` are replaced (percent-encoded) before the request is sent.
+//! POST requests carry the event as JSON with a `timestamp` added.
+
+use serde_json::Value;
+use std::thread;
+use std::time::Duration;
+use summit_hip_core::config::WebhookConfig;
+use summit_hip_core::control::{Event, Remote};
+
+pub fn start(config: &WebhookConfig, remote: Remote) -> Result<(), String> {
+    let config = config.clone();
+    let events = remote.subscribe();
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(config.timeout_seconds.max(1)))
+        .build();
+    thread::Builder::new()
+        .name("webhooks".to_string())
+        .spawn(move || {
+            for event in events {
+                // Errors carry no hip of their own; report the one on screen.
+                let hip = remote.status().hip.unwrap_or_default();
+                let timestamp = chrono::Local::now().to_rfc3339();
+                let Some(body) = payload(&event, &config, &hip, &timestamp) else {
+                    continue;
+                };
+                for template in &config.urls {
+                    let url = expand(template, &body);
+                    let result = if config.method.eq_ignore_ascii_case("GET") {
+                        agent.get(&url).call()
+                    } else {
+                        agent.post(&url).send_json(&body)
+                    };
+                    match result {
+                        Ok(_) => log::debug!("Webhook {} sent", url),
+                        Err(e) => log::warn!("Webhook {} failed: {}", url, e),
+                    }
+                }
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// The JSON for `event`, or `None` if it isn't one of `[webhooks] events`.
+fn payload(event: &Event, config: &WebhookConfig, hip: &str, timestamp: &str) -> Option<Value> {
+    let mut body = serde_json::to_value(event).ok()?;
+    let name = body["event"].as_str()?;
+    if !config.events.iter().any(|e| e == name) {
+        return None;
+    }
+    let fields = body.as_object_mut()?;
+    fields
+        .entry("hip")
+        .or_insert_with(|| Value::from(hip.to_string()));
+    fields.insert("timestamp".to_string(), Value::from(timestamp.to_string()));
+    Some(body)
+}
+
+/// Fill in a URL template from the fields of `body`; unknown fields become
+/// empty strings.
+fn expand(template: &str, body: &Value) -> String {
+    let mut url = template.to_string();
+    for field in ["event", "hip", "file", "message", "timestamp"] {
+        let value = body[field].as_str().unwrap_or_default();
+        url = url.replace(&format!("{{{}}}", field), &percent_encode(value));
+    }
+    url
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_and_template() {
+        let config = WebhookConfig::default();
+        let started = Event::VideoStarted {
+            hip: "042".to_string(),
+            file: "042 Smith.mp4".to_string(),
+        };
+        let body = payload(&started, &config, "042", "2024-05-01T10:00:00+00:00").unwrap();
+        assert_eq!(body["event"], "video_started");
+        assert_eq!(body["timestamp"], "2024-05-01T10:00:00+00:00");
+        assert_eq!(
+            expand("http://sale/log?lot={hip}&f={file}&at={timestamp}", &body),
+            "http://sale/log?lot=042&f=042%20Smith.mp4&at=2024-05-01T10%3A00%3A00%2B00%3A00"
+        );
+
+        let error = Event::Error {
+            message: "bad file".to_string(),
+        };
+        let body = payload(&error, &config, "007", "t").unwrap();
+        assert_eq!(body["hip"], "007");
+        assert_eq!(expand("/{event}/{file}", &body), "/error/");
+
+        let entered = Event::HipEntered {
+            hip: "042".to_string(),
+        };
+        assert_eq!(payload(&entered, &config, "042", "t"), None);
+    }
+}
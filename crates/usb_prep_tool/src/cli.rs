@@ -0,0 +1,276 @@
+//! Preparing sticks from a script, without the window:
+//!
+//! ```text
+//! summit_usb_prep copy --source dist/windows --drive E: --sync
+//! summit_usb_prep copy --source dist/windows --drive E: --drive F: --drive G:
+//! summit_usb_prep check --source dist/windows
+//! ```
+//!
+//! The same checklist, copy, sync and verification as the GUI; the exit
+//! code says how it went.
+
+use crate::copy::{self, Event, Options};
+use crate::validate::{self, Platform};
+use clap::{Args, Subcommand};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+/// Print progress this often.
+const PRINT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Check a dist folder and copy it onto a drive
+    Copy(CopyArgs),
+    /// Check a dist folder without copying
+    Check(SourceArgs),
+}
+
+#[derive(Args)]
+pub struct SourceArgs {
+    /// The dist folder, e.g. dist/windows
+    #[arg(long, value_name = "DIR")]
+    source: PathBuf,
+
+    /// Platform of the kiosk (windows, macos or linux); guessed from the
+    /// folder name if not given
+    #[arg(long, value_name = "PLATFORM")]
+    platform: Option<Platform>,
+}
+
+#[derive(Args)]
+pub struct CopyArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    /// The drive or mount point to copy onto, e.g. E: or /Volumes/KIOSK;
+    /// repeat to prepare several drives at once
+    #[arg(long, value_name = "DRIVE", required = true)]
+    drive: Vec<String>,
+
+    /// Only copy new and changed files
+    #[arg(long)]
+    sync: bool,
+
+    /// With --sync, compare files by checksum instead of size and date
+    #[arg(long, requires = "sync")]
+    by_hash: bool,
+
+    /// With --sync, delete files an earlier copy put on the drive that are no
+    /// longer in the source
+    #[arg(long, requires = "sync")]
+    delete_removed: bool,
+}
+
+pub fn run(command: Command) -> ExitCode {
+    match command {
+        Command::Check(args) => {
+            if check(&args) {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::from(2)
+            }
+        }
+        Command::Copy(args) => {
+            if !check(&args.source) {
+                return ExitCode::from(2);
+            }
+            copy(&args)
+        }
+    }
+}
+
+/// Print the checklist; whether it all passed.
+fn check(args: &SourceArgs) -> bool {
+    let platform = args
+        .platform
+        .unwrap_or_else(|| Platform::guess(&args.source));
+    println!("Checking {} for {}", args.source.display(), platform.name());
+    let checks = validate::validate(&args.source, platform);
+    for check in &checks {
+        match &check.result {
+            Ok(found) => println!("  ✅ {}: {}", check.label, found),
+            Err(problem) => println!("  ❌ {}: {}", check.label, problem),
+        }
+    }
+    validate::passed(&checks)
+}
+
+/// `E:` means the root of drive E, not its current directory.
+fn drive_root(drive: &str) -> PathBuf {
+    if drive.len() == 2 && drive.ends_with(':') {
+        PathBuf::from(format!("{}\\", drive))
+    } else {
+        PathBuf::from(drive)
+    }
+}
+
+fn copy(args: &CopyArgs) -> ExitCode {
+    let drives: Vec<PathBuf> = args.drive.iter().map(|d| drive_root(d)).collect();
+    if let Some(drive) = drives.iter().find(|d| !d.is_dir()) {
+        eprintln!("❌ {} is not a drive or folder", drive.display());
+        return ExitCode::FAILURE;
+    }
+    let options = Options {
+        sync: args.sync,
+        by_hash: args.by_hash,
+        delete_removed: args.delete_removed,
+    };
+
+    // One thread per drive; each line says which drive it's about
+    let all_ok = std::thread::scope(|scope| {
+        let copies: Vec<_> = drives
+            .iter()
+            .map(|drive| {
+                let label = if drives.len() > 1 {
+                    format!("[{}] ", drive.display())
+                } else {
+                    String::new()
+                };
+                scope.spawn(move || copy_one(&args.source.source, drive, options, label))
+            })
+            .collect();
+        let results: Vec<bool> = copies
+            .into_iter()
+            .map(|copy| copy.join().unwrap_or(false))
+            .collect();
+        results.into_iter().all(|ok| ok)
+    });
+    if all_ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Copy onto one drive, printing progress with `label` in front; whether
+/// it worked.
+fn copy_one(source: &Path, drive: &Path, options: Options, label: String) -> bool {
+    let dest = drive.join("SummitHipNumbers");
+    println!("{}Copying to {}", label, dest.display());
+
+    let mut progress = Progress {
+        label: label.clone(),
+        ..Progress::default()
+    };
+    let result = copy::run(source, &dest, options, &AtomicBool::new(false), |event| {
+        progress.update(event)
+    });
+    match result {
+        Ok(summary) if summary.mismatches.is_empty() => {
+            println!(
+                "{}✅ Copy complete and verified: {} files copied, {} unchanged, {} deleted",
+                label,
+                summary.files - summary.skipped,
+                summary.skipped,
+                summary.deleted
+            );
+            true
+        }
+        Ok(summary) => {
+            eprintln!("{}❌ These files did not verify; use another drive:", label);
+            for path in &summary.mismatches {
+                eprintln!("{}  {}", label, path.display());
+            }
+            false
+        }
+        Err(e) => {
+            eprintln!("{}❌ Error: {:#}", label, e);
+            false
+        }
+    }
+}
+
+/// Turns worker events into a line of progress every second.
+struct Progress {
+    label: String,
+    phase: &'static str,
+    started: Instant,
+    printed: Instant,
+    total_files: usize,
+    total_bytes: u64,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self {
+            label: String::new(),
+            phase: "Copying",
+            started: Instant::now(),
+            printed: Instant::now(),
+            total_files: 0,
+            total_bytes: 0,
+        }
+    }
+}
+
+impl Progress {
+    fn update(&mut self, event: Event) {
+        match event {
+            Event::Counted { files, bytes } => {
+                self.total_files = files;
+                self.total_bytes = bytes;
+                println!(
+                    "{}{} files to copy ({:.1} MB)",
+                    self.label,
+                    files,
+                    bytes as f64 / 1e6
+                );
+            }
+            Event::Verifying => {
+                self.phase = "Verifying";
+                self.started = Instant::now();
+            }
+            Event::Progress {
+                files,
+                bytes,
+                current,
+            } => {
+                if self.printed.elapsed() >= PRINT_INTERVAL {
+                    self.printed = Instant::now();
+                    self.print(files, bytes, &current);
+                }
+            }
+            Event::Finished(_) => {}
+        }
+    }
+
+    fn print(&self, files: usize, bytes: u64, current: &Path) {
+        let secs = self.started.elapsed().as_secs_f64();
+        let rate = if secs > 0.0 { bytes as f64 / secs } else { 0.0 };
+        let eta = if rate > 0.0 {
+            let left = self.total_bytes.saturating_sub(bytes) as f64 / rate;
+            copy::format_duration(Duration::from_secs_f64(left))
+        } else {
+            "--:--".to_string()
+        };
+        let percent = (bytes * 100).checked_div(self.total_bytes).unwrap_or(0);
+        println!(
+            "{}{} {:>3}% {}/{} files, {}, {} left ({})",
+            self.label,
+            self.phase,
+            percent,
+            files,
+            self.total_files,
+            copy::format_rate(rate),
+            eta,
+            current.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drive_root() {
+        assert_eq!(drive_root("E:"), PathBuf::from("E:\\"));
+        assert_eq!(
+            drive_root("/Volumes/KIOSK"),
+            PathBuf::from("/Volumes/KIOSK")
+        );
+    }
+}
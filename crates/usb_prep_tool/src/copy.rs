@@ -0,0 +1,573 @@
+//! Copying the dist folder onto a drive on a worker thread, so the window
+//! stays responsive through multi-gigabyte video sets. The worker reports
+//! over a channel and stops between chunks when cancelled. Once copied,
+//! the files are read back and checked (see [`crate::verify`]).
+//!
+//! In sync mode, files the drive already has are left alone, so updating a
+//! stick prepared earlier only copies what changed.
+
+use crate::verify::{self, Entry};
+use anyhow::{Context, Result};
+use eframe::egui;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Don't wake the UI for every chunk.
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How to copy.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Options {
+    /// Skip files the drive already has.
+    pub sync: bool,
+    /// In sync mode, compare files by checksum instead of size and date;
+    /// slower, but catches copies that went bad on the drive.
+    pub by_hash: bool,
+    /// In sync mode, delete files an earlier copy put on the drive that the
+    /// source no longer has.
+    pub delete_removed: bool,
+}
+
+/// What the worker sends back.
+pub enum Event {
+    Counted {
+        files: usize,
+        bytes: u64,
+    },
+    Progress {
+        files: usize,
+        bytes: u64,
+        current: PathBuf,
+    },
+    /// Copying is done; progress now counts the files read back.
+    Verifying,
+    Finished(Result<Summary>),
+}
+
+/// How a finished copy went.
+#[derive(Debug)]
+pub struct Summary {
+    pub files: usize,
+    /// Files left alone in sync mode.
+    pub skipped: usize,
+    /// Files deleted from the drive in sync mode.
+    pub deleted: usize,
+    /// Files whose copy on the drive differs from the source.
+    pub mismatches: Vec<PathBuf>,
+}
+
+/// A copy running on a worker thread.
+pub struct CopyJob {
+    rx: Receiver<Event>,
+    cancel: Arc<AtomicBool>,
+    started: Instant,
+    pub total_files: usize,
+    pub total_bytes: u64,
+    pub copied_files: usize,
+    pub copied_bytes: u64,
+    pub current: Option<PathBuf>,
+    pub verifying: bool,
+}
+
+impl CopyJob {
+    /// Start copying everything under `source` into `dest`.
+    pub fn start(source: PathBuf, dest: PathBuf, options: Options, ctx: egui::Context) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let worker_cancel = cancel.clone();
+        std::thread::spawn(move || {
+            let result = run(&source, &dest, options, &worker_cancel, |event| {
+                let _ = tx.send(event);
+                ctx.request_repaint();
+            });
+            let _ = tx.send(Event::Finished(result));
+            ctx.request_repaint();
+        });
+        Self {
+            rx,
+            cancel,
+            started: Instant::now(),
+            total_files: 0,
+            total_bytes: 0,
+            copied_files: 0,
+            copied_bytes: 0,
+            current: None,
+            verifying: false,
+        }
+    }
+
+    /// Ask the worker to stop; it finishes with an error once it has.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// Take in what the worker has sent; the outcome once it is done.
+    pub fn poll(&mut self) -> Option<Result<Summary>> {
+        while let Ok(event) = self.rx.try_recv() {
+            match event {
+                Event::Counted { files, bytes } => {
+                    self.total_files = files;
+                    self.total_bytes = bytes;
+                }
+                Event::Progress {
+                    files,
+                    bytes,
+                    current,
+                } => {
+                    self.copied_files = files;
+                    self.copied_bytes = bytes;
+                    self.current = Some(current);
+                }
+                Event::Verifying => {
+                    self.verifying = true;
+                    self.started = Instant::now();
+                    self.copied_files = 0;
+                    self.copied_bytes = 0;
+                }
+                Event::Finished(result) => return Some(result),
+            }
+        }
+        None
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes == 0 {
+            0.0
+        } else {
+            self.copied_bytes as f32 / self.total_bytes as f32
+        }
+    }
+
+    /// Average speed so far, in bytes per second.
+    pub fn rate(&self) -> f64 {
+        let secs = self.started.elapsed().as_secs_f64();
+        if secs > 0.0 {
+            self.copied_bytes as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Time left at the average speed so far.
+    pub fn eta(&self) -> Option<Duration> {
+        let rate = self.rate();
+        (rate > 0.0).then(|| {
+            let left = self.total_bytes.saturating_sub(self.copied_bytes);
+            Duration::from_secs_f64(left as f64 / rate)
+        })
+    }
+}
+
+/// Copy `source` into `dest`, check the copy and write the manifest.
+pub fn run(
+    source: &Path,
+    dest: &Path,
+    options: Options,
+    cancel: &AtomicBool,
+    mut report: impl FnMut(Event),
+) -> Result<Summary> {
+    // What the last copy put there, before this one's manifest replaces it
+    let previous = verify::read_manifest(dest);
+    let (copied, skipped) = copy_tree(source, dest, options, cancel, &mut report)?;
+    let deleted = if options.sync && options.delete_removed {
+        delete_removed(source, dest, previous.keys())?
+    } else {
+        0
+    };
+
+    report(Event::Verifying);
+    let mut checked_bytes = 0;
+    let mut last_report = Instant::now();
+    let mismatches = verify::check(dest, &copied, cancel, |index, bytes, current| {
+        checked_bytes += bytes;
+        if last_report.elapsed() >= REPORT_INTERVAL {
+            last_report = Instant::now();
+            report(Event::Progress {
+                files: index,
+                bytes: checked_bytes,
+                current: current.to_path_buf(),
+            });
+        }
+    })?;
+
+    let skipped_files = skipped.len();
+    let mut entries: Vec<Entry> = copied.into_iter().chain(skipped).collect();
+    let files = entries.len();
+    // Files of earlier copies still on the drive stay listed, so a later
+    // sync can still delete them
+    let listed: HashSet<PathBuf> = entries.iter().map(|e| e.path.clone()).collect();
+    for (manifest_path, sha256) in previous {
+        let path = PathBuf::from(manifest_path);
+        if listed.contains(&path) {
+            continue;
+        }
+        if let Some(metadata) = std::fs::metadata(dest.join(&path))
+            .ok()
+            .filter(|m| m.is_file())
+        {
+            entries.push(Entry {
+                path,
+                len: metadata.len(),
+                sha256,
+            });
+        }
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    verify::write_manifest(dest, &entries)?;
+
+    Ok(Summary {
+        files,
+        skipped: skipped_files,
+        deleted,
+        mismatches,
+    })
+}
+/// Copy the files under `source` into `dest`, keeping the layout; what was
+/// copied and, in sync mode, what was already there, with their hashes.
+pub fn copy_tree(
+    source: &Path,
+    dest: &Path,
+    options: Options,
+    cancel: &AtomicBool,
+    mut report: impl FnMut(Event),
+) -> Result<(Vec<Entry>, Vec<Entry>)> {
+    let manifest = if options.sync {
+        verify::read_manifest(dest)
+    } else {
+        HashMap::new()
+    };
+    let mut skipped = Vec::new();
+    let mut files = Vec::new();
+    for entry in WalkDir::new(source).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let len = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        let rel_path = entry.path().strip_prefix(source)?.to_path_buf();
+        if options.sync {
+            let known = manifest.get(&verify::manifest_path(&rel_path));
+            if let Some(sha256) =
+                unchanged(entry.path(), &dest.join(&rel_path), known, options, cancel)?
+            {
+                skipped.push(Entry {
+                    path: rel_path,
+                    len,
+                    sha256,
+                });
+                continue;
+            }
+        }
+        files.push((rel_path, len));
+    }
+    report(Event::Counted {
+        files: files.len(),
+        bytes: files.iter().map(|(_, len)| len).sum(),
+    });
+
+    std::fs::create_dir_all(dest)
+        .with_context(|| format!("Failed to create {}", dest.display()))?;
+
+    let mut copied = Vec::with_capacity(files.len());
+    let mut copied_bytes = 0;
+    let mut last_report = Instant::now();
+    for (index, (rel_path, len)) in files.into_iter().enumerate() {
+        let dest_path = dest.join(&rel_path);
+        if let Some(parent) = dest_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let sha256 = copy_file(&source.join(&rel_path), &dest_path, cancel, |bytes| {
+            copied_bytes += bytes;
+            if last_report.elapsed() >= REPORT_INTERVAL {
+                last_report = Instant::now();
+                report(Event::Progress {
+                    files: index,
+                    bytes: copied_bytes,
+                    current: rel_path.clone(),
+                });
+            }
+        })
+        .with_context(|| format!("Failed to copy {}", rel_path.display()))?;
+
+        report(Event::Progress {
+            files: index + 1,
+            bytes: copied_bytes,
+            current: rel_path.clone(),
+        });
+        copied.push(Entry {
+            path: rel_path,
+            len,
+            sha256,
+        });
+    }
+    Ok((copied, skipped))
+}
+
+/// In sync mode, the hash of a file the drive already has, or `None` if it
+/// needs copying. By checksum, both copies are read; otherwise the same
+/// size and a copy no older than the source count as unchanged.
+fn unchanged(
+    src: &Path,
+    dest: &Path,
+    known: Option<&String>,
+    options: Options,
+    cancel: &AtomicBool,
+) -> Result<Option<String>> {
+    let (Ok(src_meta), Ok(dest_meta)) = (std::fs::metadata(src), std::fs::metadata(dest)) else {
+        return Ok(None);
+    };
+    if src_meta.len() != dest_meta.len() {
+        return Ok(None);
+    }
+    if options.by_hash {
+        let sha256 = verify::hash_file(src, cancel, |_| {})?;
+        let on_drive = verify::hash_file(dest, cancel, |_| {}).unwrap_or_default();
+        return Ok((on_drive == sha256).then_some(sha256));
+    }
+    match (src_meta.modified(), dest_meta.modified()) {
+        (Ok(src_time), Ok(dest_time)) if dest_time >= src_time => match known {
+            Some(sha256) => Ok(Some(sha256.clone())),
+            None => verify::hash_file(src, cancel, |_| {}).map(Some),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Delete the files an earlier copy put under `dest`, listed in its
+/// manifest, that `source` no longer has, and the folders that leaves empty;
+/// how many files went. Anything else on the drive is the player's, like its
+/// license, trial and logs, and stays.
+fn delete_removed<'a>(
+    source: &Path,
+    dest: &Path,
+    previous: impl IntoIterator<Item = &'a String>,
+) -> Result<usize> {
+    let mut deleted = 0;
+    for manifest_path in previous {
+        let rel_path = PathBuf::from(manifest_path);
+        // Only ever below `dest`, whatever the manifest says
+        if !rel_path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            continue;
+        }
+        let dest_path = dest.join(&rel_path);
+        if source.join(&rel_path).is_file() || !dest_path.is_file() {
+            continue;
+        }
+        std::fs::remove_file(&dest_path)
+            .with_context(|| format!("Failed to delete {}", rel_path.display()))?;
+        deleted += 1;
+        for dir in dest_path.ancestors().skip(1) {
+            // Fails while anything is left inside, which is fine
+            if dir == dest || std::fs::remove_dir(dir).is_err() {
+                break;
+            }
+        }
+    }
+    Ok(deleted)
+}
+
+/// Copy one file in chunks, checking for cancel between them, and return
+/// the SHA-256 of what was read. A cancelled copy removes the partial file.
+fn copy_file(
+    src: &Path,
+    dest: &Path,
+    cancel: &AtomicBool,
+    mut on_chunk: impl FnMut(u64),
+) -> Result<String> {
+    let mut input = File::open(src)?;
+    let mut output = File::create(dest)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; CHUNK_SIZE];
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            drop(output);
+            let _ = std::fs::remove_file(dest);
+            anyhow::bail!("Copy cancelled");
+        }
+        let read = input.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        output.write_all(&buffer[..read])?;
+        hasher.update(&buffer[..read]);
+        on_chunk(read as u64);
+    }
+    output.sync_all()?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// `12.3 MB/s`.
+pub fn format_rate(bytes_per_sec: f64) -> String {
+    format!("{:.1} MB/s", bytes_per_sec / 1_000_000.0)
+}
+
+/// `1:05:09`, or `4:09` under an hour.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_copy_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("dist");
+        std::fs::create_dir_all(source.join("videos")).unwrap();
+        std::fs::write(source.join("config.toml"), "a").unwrap();
+        std::fs::write(source.join("videos").join("001_a.mp4"), vec![7; 3000]).unwrap();
+
+        let dest = temp_dir.path().join("usb").join("SummitHipNumbers");
+        let mut counted = None;
+        let summary = run(
+            &source,
+            &dest,
+            Options::default(),
+            &AtomicBool::new(false),
+            |event| {
+                if let Event::Counted { files, bytes } = event {
+                    counted = Some((files, bytes));
+                }
+            },
+        )
+        .unwrap();
+        assert_eq!(summary.files, 2);
+        assert!(summary.mismatches.is_empty());
+        assert_eq!(counted, Some((2, 3001)));
+        assert_eq!(
+            std::fs::read(dest.join("videos").join("001_a.mp4")).unwrap(),
+            vec![7; 3000]
+        );
+        let manifest = std::fs::read_to_string(dest.join(verify::MANIFEST_FILE_NAME)).unwrap();
+        assert_eq!(manifest.lines().count(), 2);
+        assert!(manifest.contains("  videos/001_a.mp4\n"));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("dist");
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::write(source.join("001_a.mp4"), "video").unwrap();
+
+        let dest = temp_dir.path().join("usb");
+        let error = run(
+            &source,
+            &dest,
+            Options::default(),
+            &AtomicBool::new(true),
+            |_| {},
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", error).contains("Copy cancelled"));
+        assert!(!dest.join("001_a.mp4").exists());
+    }
+
+    #[test]
+    fn test_sync() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("dist");
+        std::fs::create_dir_all(source.join("videos")).unwrap();
+        std::fs::write(source.join("config.toml"), "a").unwrap();
+        std::fs::write(source.join("videos").join("001_a.mp4"), "video").unwrap();
+        std::fs::write(source.join("videos").join("002_b.mp4"), "video").unwrap();
+        let dest = temp_dir.path().join("usb");
+        let no_cancel = AtomicBool::new(false);
+        run(&source, &dest, Options::default(), &no_cancel, |_| {}).unwrap();
+
+        std::fs::write(source.join("config.toml"), "changed").unwrap();
+        std::fs::write(source.join("videos").join("003_c.mp4"), "video").unwrap();
+        std::fs::remove_file(source.join("videos").join("002_b.mp4")).unwrap();
+        std::fs::write(dest.join("videos").join("001_a.mp4"), "VIDEO").unwrap();
+        let sync = Options {
+            sync: true,
+            ..Options::default()
+        };
+        let summary = run(&source, &dest, sync, &no_cancel, |_| {}).unwrap();
+        assert_eq!((summary.files, summary.skipped, summary.deleted), (3, 1, 0));
+        assert!(dest.join("videos").join("002_b.mp4").exists());
+        // Same size and newer, so only a checksum notices
+        assert_eq!(
+            std::fs::read_to_string(dest.join("videos").join("001_a.mp4")).unwrap(),
+            "VIDEO"
+        );
+
+        let sync = Options {
+            sync: true,
+            by_hash: true,
+            delete_removed: true,
+        };
+        let summary = run(&source, &dest, sync, &no_cancel, |_| {}).unwrap();
+        assert_eq!((summary.files, summary.skipped, summary.deleted), (3, 2, 1));
+        assert!(summary.mismatches.is_empty());
+        assert!(!dest.join("videos").join("002_b.mp4").exists());
+        assert_eq!(
+            std::fs::read_to_string(dest.join("videos").join("001_a.mp4")).unwrap(),
+            "video"
+        );
+        let manifest = std::fs::read_to_string(dest.join(verify::MANIFEST_FILE_NAME)).unwrap();
+        assert_eq!(manifest.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_sync_keeps_player_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("dist");
+        std::fs::create_dir_all(source.join("videos")).unwrap();
+        std::fs::write(source.join("videos").join("001_a.mp4"), "video").unwrap();
+        std::fs::write(source.join("videos").join("002_b.mp4"), "video").unwrap();
+        let dest = temp_dir.path().join("usb");
+        let no_cancel = AtomicBool::new(false);
+        run(&source, &dest, Options::default(), &no_cancel, |_| {}).unwrap();
+
+        // What the player writes next to itself while it runs
+        std::fs::write(dest.join("license.toml"), "license").unwrap();
+        std::fs::write(dest.join("trial.dat"), "trial").unwrap();
+        std::fs::create_dir_all(dest.join("logs")).unwrap();
+        std::fs::write(dest.join("logs").join("summit.log"), "log").unwrap();
+
+        std::fs::remove_file(source.join("videos").join("002_b.mp4")).unwrap();
+        let sync = Options {
+            sync: true,
+            by_hash: false,
+            delete_removed: true,
+        };
+        let summary = run(&source, &dest, sync, &no_cancel, |_| {}).unwrap();
+        assert_eq!(summary.deleted, 1);
+        assert!(!dest.join("videos").join("002_b.mp4").exists());
+        assert!(dest.join("license.toml").exists());
+        assert!(dest.join("trial.dat").exists());
+        assert!(dest.join("logs").join("summit.log").exists());
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_rate(12_345_678.0), "12.3 MB/s");
+        assert_eq!(format_duration(Duration::from_secs(249)), "4:09");
+        assert_eq!(format_duration(Duration::from_secs(3909)), "1:05:09");
+    }
+}
@@ -1,21 +1,53 @@
+mod cli;
+mod copy;
+mod rename;
+mod transcode;
+mod validate;
+mod verify;
+
 use anyhow::{Context, Result};
+use clap::Parser;
+use copy::{CopyJob, Summary};
 use eframe::egui;
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::sync::{Arc, Mutex};
-use walkdir::WalkDir;
+use validate::Platform;
+
+/// Without a command, opens the window.
+#[derive(Parser)]
+#[command(
+    name = "summit_usb_prep",
+    about = "Prepares USB drives for Summit kiosks"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<cli::Command>,
+}
+
+/// The copy onto one of the selected drives.
+struct DriveCopy {
+    drive: PathBuf,
+    job: Option<CopyJob>, // None once finished
+    status: String,
+    failed: bool,
+}
 
 #[derive(Default)]
 struct UsbPrepApp {
     drives: Arc<Mutex<HashSet<PathBuf>>>, // Detected USB drives
-    selected_drive: Option<PathBuf>,
+    selected_drives: BTreeSet<PathBuf>,
     source_folder: Option<PathBuf>,      // dist folder
     status: String,                      // "Ready", "Copying...", errors
     watcher: Option<RecommendedWatcher>, // For drive detection
-    is_copying: bool,
-    total_files: usize,
-    copied_files: usize,
+    copies: Vec<DriveCopy>,              // One per drive being prepared
+    options: copy::Options,              // Full copy or sync
+    platform: Platform,                  // Kiosk the dist folder is for
+    checks: Vec<validate::Check>,        // Dist folder checklist
+    wizard: rename::Wizard,              // Hip number assignment
+    normalizer: transcode::Normalizer,   // Video transcode queue
 }
 
 impl UsbPrepApp {
@@ -88,74 +120,156 @@ impl UsbPrepApp {
         self.watcher = Some(watcher);
     }
 
-    fn copy_to_drive(&mut self, ctx: &egui::Context) -> Result<()> {
+    fn copy_to_drives(&mut self, ctx: &egui::Context) -> Result<()> {
+        self.revalidate();
+        if !validate::passed(&self.checks) {
+            anyhow::bail!("The dist folder isn't ready; see the checklist");
+        }
+
         let source = self
             .source_folder
             .as_ref()
             .context("No source folder selected")?;
-        let dest = self.selected_drive.as_ref().context("No drive selected")?;
-
-        // Check if destination is writable
-        if !Self::is_usb_drive(dest)? {
-            anyhow::bail!("Selected drive is not writable or not a valid USB drive");
+        if self.selected_drives.is_empty() {
+            anyhow::bail!("No drive selected");
         }
 
-        self.is_copying = true;
-        self.status = "Preparing to copy...".to_string();
-        self.copied_files = 0;
+        // Check the destinations are writable
+        for drive in &self.selected_drives {
+            if !Self::is_usb_drive(drive)? {
+                anyhow::bail!(
+                    "{} is not writable or not a valid USB drive",
+                    drive.display()
+                );
+            }
+        }
 
-        // Count total files first
-        self.total_files = WalkDir::new(source)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-            .count();
+        self.copies = self
+            .selected_drives
+            .iter()
+            .map(|drive| DriveCopy {
+                drive: drive.clone(),
+                job: Some(CopyJob::start(
+                    source.clone(),
+                    drive.join("SummitHipNumbers"),
+                    self.options,
+                    ctx.clone(),
+                )),
+                status: "Preparing to copy...".to_string(),
+                failed: false,
+            })
+            .collect();
+        self.status = format!("Copying to {} drives...", self.copies.len());
+        Ok(())
+    }
 
-        ctx.request_repaint();
+    fn is_copying(&self) -> bool {
+        self.copies.iter().any(|copy| copy.job.is_some())
+    }
 
-        // Create destination subdirectory
-        let dest_subdir = dest.join("SummitHipNumbers");
-        std::fs::create_dir_all(&dest_subdir)?;
+    fn revalidate(&mut self) {
+        if let Some(source) = &self.source_folder {
+            self.checks = validate::validate(source, self.platform);
+        }
+    }
 
-        // Copy files
-        for entry in WalkDir::new(source) {
-            let entry = entry?;
-            if !entry.file_type().is_file() {
+    /// Pick up progress from the copy workers, and their results once done.
+    fn poll_copies(&mut self) {
+        let mut finished = false;
+        for copy in &mut self.copies {
+            let Some(job) = &mut copy.job else {
                 continue;
+            };
+            match job.poll() {
+                None => {
+                    let action = if job.verifying {
+                        "Verifying"
+                    } else {
+                        "Copying"
+                    };
+                    if let Some(current) = &job.current {
+                        copy.status = format!(
+                            "{}... {}/{} files ({})",
+                            action,
+                            job.copied_files,
+                            job.total_files,
+                            current.display()
+                        );
+                    }
+                }
+                Some(result) => {
+                    let cancelled = job.is_cancelled();
+                    (copy.failed, copy.status) = outcome(result, cancelled, self.options.sync);
+                    copy.job = None;
+                    finished = true;
+                }
             }
-
-            let rel_path = entry.path().strip_prefix(source)?;
-            let dest_path = dest_subdir.join(rel_path);
-
-            // Ensure parent directory exists
-            if let Some(parent) = dest_path.parent() {
-                std::fs::create_dir_all(parent)?;
-            }
-
-            std::fs::copy(entry.path(), &dest_path)?;
-            self.copied_files += 1;
-
-            // Update progress
-            self.status = format!(
-                "Copying... {}/{} files",
-                self.copied_files, self.total_files
-            );
-            ctx.request_repaint();
         }
 
-        self.status = format!(
-            "Copy complete! {} files copied to {}",
-            self.total_files,
-            dest_subdir.display()
-        );
-        self.is_copying = false;
+        if finished && !self.is_copying() {
+            let failed = self.copies.iter().filter(|copy| copy.failed).count();
+            self.status = if failed == 0 {
+                format!("✅ All {} drives are ready", self.copies.len())
+            } else {
+                format!(
+                    "❌ {} of {} drives failed; see above",
+                    failed,
+                    self.copies.len()
+                )
+            };
+        }
+    }
+}
 
-        Ok(())
+/// Whether a copy failed, and the line to show for it.
+fn outcome(result: Result<Summary>, cancelled: bool, sync: bool) -> (bool, String) {
+    match result {
+        Ok(summary) if summary.mismatches.is_empty() => {
+            let synced = if sync {
+                format!(
+                    " ({} unchanged, {} deleted)",
+                    summary.skipped, summary.deleted
+                )
+            } else {
+                String::new()
+            };
+            (
+                false,
+                format!(
+                    "✅ Copy complete and verified! {} files copied{}",
+                    summary.files - summary.skipped,
+                    synced
+                ),
+            )
+        }
+        Ok(summary) => {
+            let files: Vec<String> = summary
+                .mismatches
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect();
+            (
+                true,
+                format!(
+                    "❌ {} of {} files did not verify; use another drive: {}",
+                    files.len(),
+                    summary.files - summary.skipped,
+                    files.join(", ")
+                ),
+            )
+        }
+        Err(_) if cancelled => (true, "⏹ Copy cancelled".to_string()),
+        Err(e) => (true, format!("❌ Error: {:#}", e)),
     }
 }
 
 impl eframe::App for UsbPrepApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_copies();
+        if self.wizard.show(ctx) | self.normalizer.show(ctx) {
+            self.revalidate();
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("🏗️ Summit USB Prep Tool");
             ui.label("Prepare USB drives for Summit kiosk deployment");
@@ -166,14 +280,54 @@ impl eframe::App for UsbPrepApp {
                 ui.label("📁 Source dist folder:");
                 if ui.button("Select Folder").clicked() {
                     if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                        self.platform = Platform::guess(&path);
                         self.source_folder = Some(path);
                         self.status = "Source folder selected".to_string();
+                        self.revalidate();
                     }
                 }
             });
 
-            if let Some(path) = &self.source_folder {
+            if let Some(path) = self.source_folder.clone() {
                 ui.label(format!("Selected: {}", path.display()));
+
+                // Package checklist
+                let platform = self.platform;
+                ui.horizontal(|ui| {
+                    ui.label("🖥 Kiosk platform:");
+                    egui::ComboBox::from_id_salt("platform")
+                        .selected_text(self.platform.name())
+                        .show_ui(ui, |ui| {
+                            for option in Platform::ALL {
+                                ui.selectable_value(&mut self.platform, option, option.name());
+                            }
+                        });
+                    if ui.button("Re-check").clicked() {
+                        self.revalidate();
+                    }
+                    if ui.button("🔢 Assign hip numbers...").clicked() {
+                        self.wizard.open(validate::find_video_dir(&path));
+                    }
+                    if ui.button("🎞 Normalize videos...").clicked() {
+                        self.normalizer.open(&path, ctx);
+                    }
+                });
+                if self.platform != platform {
+                    self.revalidate();
+                }
+                for check in &self.checks {
+                    match &check.result {
+                        Ok(found) => {
+                            ui.label(format!("✅ {}: {}", check.label, found));
+                        }
+                        Err(problem) => {
+                            ui.colored_label(
+                                egui::Color32::LIGHT_RED,
+                                format!("❌ {}: {}", check.label, problem),
+                            );
+                        }
+                    }
+                }
             }
 
             ui.separator();
@@ -185,62 +339,140 @@ impl eframe::App for UsbPrepApp {
                 if drives.is_empty() {
                     ui.label("No USB drives detected. Please insert a USB drive.");
                 } else {
-                    for drive in drives.iter() {
+                    let mut sorted: Vec<&PathBuf> = drives.iter().collect();
+                    sorted.sort();
+                    for drive in sorted {
                         let drive_name = drive
                             .file_name()
                             .map(|n| n.to_string_lossy().to_string())
                             .unwrap_or_else(|| "Unknown".to_string());
 
-                        let selected = self.selected_drive.as_ref() == Some(drive);
+                        let mut selected = self.selected_drives.contains(drive);
                         if ui
-                            .radio(selected, format!("{} ({})", drive_name, drive.display()))
-                            .clicked()
+                            .checkbox(
+                                &mut selected,
+                                format!("{} ({})", drive_name, drive.display()),
+                            )
+                            .changed()
                         {
-                            self.selected_drive = Some(drive.clone());
-                            self.status = format!("Selected drive: {}", drive_name);
+                            if selected {
+                                self.selected_drives.insert(drive.clone());
+                            } else {
+                                self.selected_drives.remove(drive);
+                            }
+                            self.status = format!("{} drives selected", self.selected_drives.len());
                         }
                     }
+                    if ui.button("Select all").clicked() {
+                        self.selected_drives = drives.iter().cloned().collect();
+                        self.status = format!("{} drives selected", self.selected_drives.len());
+                    }
                 }
             }
 
             ui.separator();
 
+            // Sync options
+            let copying = self.is_copying();
+            ui.add_enabled_ui(!copying, |ui| {
+                ui.checkbox(
+                    &mut self.options.sync,
+                    "🔄 Sync: only copy new and changed files",
+                );
+                ui.add_enabled_ui(self.options.sync, |ui| {
+                    ui.indent("sync_options", |ui| {
+                        ui.checkbox(
+                            &mut self.options.by_hash,
+                            "Compare by checksum (slower, reads the whole drive)",
+                        );
+                        ui.checkbox(
+                            &mut self.options.delete_removed,
+                            "Delete copied files that are no longer in the source",
+                        );
+                    });
+                });
+            });
+
             // Copy button
-            let can_copy =
-                !self.is_copying && self.source_folder.is_some() && self.selected_drive.is_some();
+            let can_copy = !copying
+                && self.source_folder.is_some()
+                && validate::passed(&self.checks)
+                && !self.selected_drives.is_empty();
 
-            if ui
-                .add_enabled(can_copy, egui::Button::new("🚀 Copy to Selected Drive"))
-                .clicked()
-            {
-                if let Err(e) = self.copy_to_drive(ctx) {
-                    self.status = format!("❌ Error: {}", e);
-                    self.is_copying = false;
+            ui.horizontal(|ui| {
+                let label = match self.selected_drives.len() {
+                    0 | 1 => "🚀 Copy to Selected Drive".to_string(),
+                    count => format!("🚀 Copy to {} Drives", count),
+                };
+                if ui.add_enabled(can_copy, egui::Button::new(label)).clicked() {
+                    if let Err(e) = self.copy_to_drives(ctx) {
+                        self.status = format!("❌ Error: {}", e);
+                    }
                 }
-            }
+                if copying && self.copies.len() > 1 && ui.button("⏹ Cancel All").clicked() {
+                    for job in self.copies.iter().filter_map(|copy| copy.job.as_ref()) {
+                        job.cancel();
+                    }
+                }
+            });
 
             ui.separator();
 
             // Status
             ui.label(&self.status);
 
-            // Progress bar during copying
-            if self.is_copying && self.total_files > 0 {
-                let progress = self.copied_files as f32 / self.total_files as f32;
-                ui.add(egui::ProgressBar::new(progress).show_percentage());
-            }
+            // Progress, speed and cancel for each drive
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for copy in &self.copies {
+                    ui.separator();
+                    ui.strong(format!("💾 {}", copy.drive.display()));
+                    if copy.failed {
+                        ui.colored_label(egui::Color32::LIGHT_RED, &copy.status);
+                    } else {
+                        ui.label(&copy.status);
+                    }
+                    let Some(job) = &copy.job else {
+                        continue;
+                    };
+                    ui.add(egui::ProgressBar::new(job.fraction()).show_percentage());
+                    ui.horizontal(|ui| {
+                        let eta = job
+                            .eta()
+                            .map(copy::format_duration)
+                            .unwrap_or_else(|| "--:--".to_string());
+                        ui.label(format!("{}, {} left", copy::format_rate(job.rate()), eta));
+                        let cancelling = job.is_cancelled();
+                        if ui
+                            .add_enabled(!cancelling, egui::Button::new("⏹ Cancel"))
+                            .clicked()
+                        {
+                            job.cancel();
+                        }
+                    });
+                }
+            });
         });
     }
 }
 
-fn main() -> eframe::Result<()> {
+fn main() -> ExitCode {
+    if let Some(command) = Cli::parse().command {
+        return cli::run(command);
+    }
+
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_inner_size([400.0, 300.0]),
+        viewport: egui::ViewportBuilder::default().with_inner_size([560.0, 560.0]),
         ..Default::default()
     };
-    eframe::run_native(
+    match eframe::run_native(
         "Summit USB Prep",
         options,
         Box::new(|_cc| Ok(Box::new(UsbPrepApp::new()))),
-    )
+    ) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Failed to open the window: {}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,296 @@
+//! The hip number wizard: videos in the dist folder that the player would
+//! skip because their names don't start with a 3-digit hip number, and a
+//! way to give them one before they reach the kiosk.
+
+use anyhow::{Context, Result};
+use eframe::egui;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use summit_hip_core::file_scanner::{hip_number, VIDEO_EXTENSIONS};
+
+/// A video without a hip number and the one typed for it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    pub file_name: String,
+    pub hip: String,
+}
+
+/// Videos in `video_dir` the player would skip for want of a hip number,
+/// with the number their name suggests, if any.
+pub fn unnumbered(video_dir: &Path) -> Vec<Row> {
+    let Ok(entries) = std::fs::read_dir(video_dir) else {
+        return Vec::new();
+    };
+    let mut rows: Vec<Row> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| is_video(name) && hip_number(name).is_none())
+        .map(|file_name| Row {
+            hip: suggest(&file_name),
+            file_name,
+        })
+        .collect();
+    rows.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    rows
+}
+
+fn is_video(file_name: &str) -> bool {
+    file_name
+        .rsplit_once('.')
+        .is_some_and(|(_, ext)| VIDEO_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// A hip number typed as 1 to 3 digits, zero-padded.
+pub fn parse_hip(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty() && text.len() <= 3 && text.bytes().all(|b| b.is_ascii_digit()))
+        .then(|| format!("{:0>3}", text))
+}
+
+/// The number a name like `12_smith.mp4` already starts with.
+fn suggest(file_name: &str) -> String {
+    let digits: String = file_name
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    parse_hip(&digits).unwrap_or_default()
+}
+
+/// `012_smith.mp4` for `12 smith.MP4` and hip `012`: any number the name
+/// started with is replaced, and the extension lowercased so the player
+/// lists it.
+pub fn target_name(file_name: &str, hip: &str) -> String {
+    let (stem, ext) = file_name.rsplit_once('.').unwrap_or((file_name, ""));
+    let rest = stem
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start_matches(['_', '-', ' ', '.']);
+    let mut name = hip.to_string();
+    if !rest.is_empty() {
+        name.push('_');
+        name.push_str(rest);
+    }
+    name.push('.');
+    name.push_str(&ext.to_ascii_lowercase());
+    name
+}
+
+/// Rename, or copy under the new name, every row that has a hip number;
+/// how many files that was. Nothing is touched unless every new name is
+/// valid and free.
+pub fn apply(video_dir: &Path, rows: &[Row], copy: bool) -> Result<usize> {
+    let mut moves = Vec::new();
+    let mut targets = HashSet::new();
+    for row in rows.iter().filter(|row| !row.hip.trim().is_empty()) {
+        let hip = parse_hip(&row.hip).with_context(|| {
+            format!(
+                "\"{}\" for {} is not a hip number (1 to 3 digits)",
+                row.hip, row.file_name
+            )
+        })?;
+        let target = target_name(&row.file_name, &hip);
+        if !targets.insert(target.clone()) {
+            anyhow::bail!("Two videos would both be named {}", target);
+        }
+        if video_dir.join(&target).exists() {
+            anyhow::bail!("{} already exists", target);
+        }
+        moves.push((video_dir.join(&row.file_name), video_dir.join(target)));
+    }
+    for (from, to) in &moves {
+        let done = if copy {
+            std::fs::copy(from, to).map(|_| ())
+        } else {
+            std::fs::rename(from, to)
+        };
+        done.with_context(|| format!("Failed to write {}", to.display()))?;
+    }
+    Ok(moves.len())
+}
+
+/// The wizard window.
+#[derive(Default)]
+pub struct Wizard {
+    pub open: bool,
+    video_dir: PathBuf,
+    rows: Vec<Row>,
+    start: String,
+    copy: bool,
+    message: Option<Result<String, String>>,
+}
+
+impl Wizard {
+    pub fn open(&mut self, video_dir: PathBuf) {
+        self.rows = unnumbered(&video_dir);
+        self.video_dir = video_dir;
+        self.message = None;
+        self.open = true;
+    }
+
+    /// Whether files were renamed, so the checklist needs running again.
+    pub fn show(&mut self, ctx: &egui::Context) -> bool {
+        if !self.open {
+            return false;
+        }
+        let mut changed = false;
+        let mut open = self.open;
+        egui::Window::new("Assign Hip Numbers")
+            .open(&mut open)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "These videos in {} don't start with a 3-digit hip number, so the \
+                     player would skip them. Type a number for each, or put them in order \
+                     and number them.",
+                    self.video_dir.display()
+                ));
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| self.show_rows(ui));
+                if self.rows.is_empty() {
+                    ui.label("✅ Every video has a hip number.");
+                }
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Number in list order from");
+                    ui.add(egui::TextEdit::singleline(&mut self.start).desired_width(40.0));
+                    if ui.button("Number").clicked() {
+                        self.number_in_order();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.copy, false, "Rename files");
+                    ui.radio_value(&mut self.copy, true, "Copy with new names (keep originals)");
+                });
+                if ui
+                    .add_enabled(!self.rows.is_empty(), egui::Button::new("Apply"))
+                    .clicked()
+                {
+                    self.message = Some(match apply(&self.video_dir, &self.rows, self.copy) {
+                        Ok(count) => {
+                            changed = true;
+                            self.rows = unnumbered(&self.video_dir);
+                            Ok(format!("{} videos now have hip numbers", count))
+                        }
+                        Err(e) => Err(format!("{:#}", e)),
+                    });
+                }
+                match &self.message {
+                    Some(Ok(message)) => {
+                        ui.colored_label(egui::Color32::LIGHT_GREEN, message);
+                    }
+                    Some(Err(message)) => {
+                        ui.colored_label(egui::Color32::LIGHT_RED, message);
+                    }
+                    None => {}
+                }
+            });
+        self.open = open;
+        changed
+    }
+
+    fn show_rows(&mut self, ui: &mut egui::Ui) {
+        let mut swap = None;
+        let last = self.rows.len().saturating_sub(1);
+        egui::Grid::new("hip_rows").striped(true).show(ui, |ui| {
+            for (index, row) in self.rows.iter_mut().enumerate() {
+                ui.add(egui::TextEdit::singleline(&mut row.hip).desired_width(40.0));
+                ui.label(&row.file_name);
+                match parse_hip(&row.hip) {
+                    Some(hip) => ui.label(format!("→ {}", target_name(&row.file_name, &hip))),
+                    None if row.hip.trim().is_empty() => ui.label(""),
+                    None => ui.colored_label(egui::Color32::LIGHT_RED, "not a hip number"),
+                };
+                if ui.add_enabled(index > 0, egui::Button::new("⬆")).clicked() {
+                    swap = Some(index - 1);
+                }
+                if ui
+                    .add_enabled(index < last, egui::Button::new("⬇"))
+                    .clicked()
+                {
+                    swap = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = swap {
+            self.rows.swap(index, index + 1);
+        }
+    }
+
+    fn number_in_order(&mut self) {
+        let Some(start) = parse_hip(&self.start).and_then(|hip| hip.parse::<u32>().ok()) else {
+            self.message = Some(Err(format!("\"{}\" is not a hip number", self.start)));
+            return;
+        };
+        for (offset, row) in self.rows.iter_mut().enumerate() {
+            row.hip = format!("{:03}", start + offset as u32);
+        }
+        self.message = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(parse_hip("7"), Some("007".to_string()));
+        assert_eq!(parse_hip(" 042 "), Some("042".to_string()));
+        assert_eq!(parse_hip("1234"), None);
+        assert_eq!(parse_hip("4a"), None);
+        assert_eq!(target_name("smith.mp4", "007"), "007_smith.mp4");
+        assert_eq!(
+            target_name("12 Smith Ranch.MP4", "012"),
+            "012_Smith Ranch.mp4"
+        );
+        assert_eq!(target_name("1234.mp4", "123"), "123.mp4");
+        assert_eq!(suggest("12_smith.mp4"), "012");
+        assert_eq!(suggest("smith.mp4"), "");
+    }
+
+    #[test]
+    fn test_apply() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        for name in ["001_jones.mp4", "smith.mp4", "12 brown.MP4", "notes.txt"] {
+            std::fs::write(dir.join(name), name).unwrap();
+        }
+        let mut rows = unnumbered(dir);
+        assert_eq!(
+            rows,
+            [
+                Row {
+                    file_name: "12 brown.MP4".to_string(),
+                    hip: "012".to_string()
+                },
+                Row {
+                    file_name: "smith.mp4".to_string(),
+                    hip: String::new()
+                },
+            ]
+        );
+
+        // A bad number or a name that's taken stops everything
+        rows[1].hip = "abc".to_string();
+        assert!(apply(dir, &rows, false).is_err());
+        rows[1].hip = "1".to_string();
+        std::fs::write(dir.join("001_smith.mp4"), "").unwrap();
+        assert!(apply(dir, &rows, false).is_err());
+        assert!(dir.join("12 brown.MP4").exists());
+        std::fs::remove_file(dir.join("001_smith.mp4")).unwrap();
+
+        assert_eq!(apply(dir, &rows, true).unwrap(), 2);
+        assert!(dir.join("smith.mp4").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("001_smith.mp4")).unwrap(),
+            "smith.mp4"
+        );
+        assert!(dir.join("012_brown.mp4").exists());
+    }
+}
@@ -0,0 +1,590 @@
+//! Normalizing videos before they go onto a stick: anything the kiosk may
+//! struggle with (HEVC, 10-bit, over 1080p, or not an MP4) is re-encoded to
+//! H.264 1080p with AAC audio using the ffmpeg and ffprobe bundled next to
+//! this tool, or found on PATH. Problems then show up here rather than on
+//! sale day.
+
+use crate::validate;
+use eframe::egui;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use summit_hip_core::file_scanner::hip_number;
+
+/// Containers worth probing; the player itself only lists `.mp4`.
+const VIDEO_EXTENSIONS: [&str; 6] = ["mp4", "mov", "mkv", "avi", "m4v", "webm"];
+
+/// The kiosk-friendly profile, at most 1920x1080.
+const ENCODE_ARGS: [&str; 16] = [
+    "-map", "0:v:0", "-map", "0:a:0?", "-c:v", "libx264", "-preset", "medium", "-crf", "20",
+    "-pix_fmt", "yuv420p", "-c:a", "aac", "-b:a", "160k",
+];
+const SCALE: &str =
+    "scale='min(1920,iw)':'min(1080,ih)':force_original_aspect_ratio=decrease:force_divisible_by=2";
+
+/// What ffprobe says about a video's first video stream.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Probe {
+    pub codec: String,
+    pub width: u32,
+    pub height: u32,
+    pub pix_fmt: String,
+    pub duration: f64,
+}
+
+impl Probe {
+    /// Parse `ffprobe -of default=noprint_wrappers=1` output.
+    pub fn parse(text: &str) -> Option<Probe> {
+        let mut probe = Probe::default();
+        for (key, value) in text.lines().filter_map(|line| line.split_once('=')) {
+            match key {
+                "codec_name" => probe.codec = value.to_string(),
+                "width" => probe.width = value.parse().ok()?,
+                "height" => probe.height = value.parse().ok()?,
+                "pix_fmt" => probe.pix_fmt = value.to_string(),
+                "duration" => probe.duration = value.parse().unwrap_or(0.0),
+                _ => {}
+            }
+        }
+        (!probe.codec.is_empty()).then_some(probe)
+    }
+
+    /// `hevc 3840x2160 yuv420p10le`.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {}x{} {}",
+            self.codec, self.width, self.height, self.pix_fmt
+        )
+    }
+
+    /// Why `file_name` with this stream should be re-encoded, if it should.
+    pub fn problems(&self, file_name: &str) -> Option<String> {
+        let mut problems = Vec::new();
+        if self.codec != "h264" {
+            problems.push(format!("{} codec", self.codec.to_uppercase()));
+        }
+        if !matches!(self.pix_fmt.as_str(), "yuv420p" | "yuvj420p") {
+            problems.push(if self.pix_fmt.contains("10") {
+                "10-bit".to_string()
+            } else {
+                self.pix_fmt.clone()
+            });
+        }
+        if self.width > 1920 || self.height > 1080 {
+            problems.push(format!("{}x{}", self.width, self.height));
+        }
+        if !file_name.to_ascii_lowercase().ends_with(".mp4") {
+            problems.push("not an MP4".to_string());
+        }
+        (!problems.is_empty()).then(|| problems.join(", "))
+    }
+}
+
+/// `name` next to this executable, or on PATH.
+pub fn find_tool(name: &str) -> Option<PathBuf> {
+    let file_name = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    if let Some(dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+        for candidate in [dir.join(&file_name), dir.join("ffmpeg").join(&file_name)] {
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+    }
+    Command::new(&file_name)
+        .arg("-version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .ok()
+        .filter(|status| status.success())
+        .map(|_| PathBuf::from(file_name))
+}
+
+pub fn probe(ffprobe: &Path, path: &Path) -> Result<Probe, String> {
+    let output = Command::new(ffprobe)
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=codec_name,width,height,pix_fmt:format=duration",
+            "-of",
+            "default=noprint_wrappers=1",
+        ])
+        .arg(path)
+        .output()
+        .map_err(|e| format!("Failed to run ffprobe: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Probe::parse(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| "no video stream".to_string())
+}
+
+/// `012_smith.mp4` for `012_smith.mov`.
+pub fn output_name(file_name: &str) -> String {
+    let stem = file_name
+        .rsplit_once('.')
+        .map_or(file_name, |(stem, _)| stem);
+    format!("{}.mp4", stem)
+}
+
+/// Seconds encoded so far, from an `-progress` line.
+fn parse_progress(line: &str) -> Option<f64> {
+    let micros = line
+        .strip_prefix("out_time_us=")
+        .or_else(|| line.strip_prefix("out_time_ms="))?;
+    Some(micros.trim().parse::<f64>().ok()? / 1_000_000.0)
+}
+
+/// Re-encode `src` into `dest`, reporting the fraction done. Killed when
+/// `cancel` is set.
+pub fn transcode(
+    ffmpeg: &Path,
+    src: &Path,
+    dest: &Path,
+    duration: f64,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(f32),
+) -> Result<(), String> {
+    let mut child = Command::new(ffmpeg)
+        .args(["-y", "-v", "error", "-nostats", "-progress", "pipe:1", "-i"])
+        .arg(src)
+        .args(ENCODE_ARGS)
+        .args(["-vf", SCALE, "-movflags", "+faststart"])
+        .arg(dest)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+    // Read stderr as it comes, so ffmpeg never stalls on a full pipe
+    let stderr = child.stderr.take().map(|stderr| {
+        std::thread::spawn(move || {
+            BufReader::new(stderr)
+                .lines()
+                .map_while(Result::ok)
+                .filter(|line| !line.trim().is_empty())
+                .last()
+        })
+    });
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if cancel.load(Ordering::Relaxed) {
+                let _ = child.kill();
+                let _ = child.wait();
+                let _ = std::fs::remove_file(dest);
+                return Err("Cancelled".to_string());
+            }
+            if let Some(done) = parse_progress(&line).filter(|_| duration > 0.0) {
+                on_progress((done / duration).clamp(0.0, 1.0) as f32);
+            }
+        }
+    }
+    let status = child.wait().map_err(|e| format!("ffmpeg failed: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        let _ = std::fs::remove_file(dest);
+        let last = stderr.and_then(|reader| reader.join().ok().flatten());
+        Err(last.unwrap_or_else(|| "ffmpeg failed".to_string()))
+    }
+}
+
+/// Re-encode a video in place: the new file takes the `.mp4` name, and the
+/// original moves to `originals` (or is deleted without one). Refused when
+/// another video already has that name.
+fn normalize(
+    ffmpeg: &Path,
+    video_dir: &Path,
+    item: &Item,
+    originals: Option<&Path>,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(f32),
+) -> Result<(), String> {
+    let src = video_dir.join(&item.file_name);
+    let output = output_name(&item.file_name);
+    if output != item.file_name && video_dir.join(&output).exists() {
+        return Err(format!("{} is already there", output));
+    }
+    let part = video_dir.join(format!(".partial-{}", output));
+    let duration = item.probe.as_ref().map_or(0.0, |p| p.duration);
+    transcode(ffmpeg, &src, &part, duration, cancel, on_progress)?;
+
+    match originals {
+        Some(dir) => {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            let kept = dir.join(&item.file_name);
+            if std::fs::rename(&src, &kept).is_err() {
+                std::fs::copy(&src, &kept).map_err(|e| e.to_string())?;
+                std::fs::remove_file(&src).map_err(|e| e.to_string())?;
+            }
+        }
+        None => std::fs::remove_file(&src).map_err(|e| e.to_string())?,
+    }
+    std::fs::rename(&part, video_dir.join(output)).map_err(|e| e.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum State {
+    Waiting,
+    Running(f32),
+    Done,
+    Failed(String),
+}
+
+/// A video in the queue.
+#[derive(Clone, Debug)]
+pub struct Item {
+    pub file_name: String,
+    pub probe: Result<Probe, String>,
+    /// Why it should be re-encoded; `None` if it's fine as it is.
+    pub problems: Option<String>,
+    pub selected: bool,
+    pub state: State,
+}
+
+enum Event {
+    Scanned(Vec<Item>),
+    State(usize, State),
+    Finished,
+}
+
+/// Probe every hip-numbered video in `video_dir`.
+fn scan(ffprobe: &Path, video_dir: &Path) -> Vec<Item> {
+    let Ok(entries) = std::fs::read_dir(video_dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| {
+            let numbered = hip_number(&output_name(name)).is_some();
+            let video = name.rsplit_once('.').is_some_and(|(_, ext)| {
+                VIDEO_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())
+            });
+            numbered && video
+        })
+        .collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|file_name| {
+            let probe = probe(ffprobe, &video_dir.join(&file_name));
+            let problems = match &probe {
+                Ok(probe) => probe.problems(&file_name),
+                Err(e) => Some(format!("can't be read: {}", e)),
+            };
+            Item {
+                selected: probe.is_ok() && problems.is_some(),
+                file_name,
+                probe,
+                problems,
+                state: State::Waiting,
+            }
+        })
+        .collect()
+}
+
+/// The transcode queue window.
+#[derive(Default)]
+pub struct Normalizer {
+    pub open: bool,
+    video_dir: PathBuf,
+    originals: PathBuf,
+    keep_originals: bool,
+    items: Vec<Item>,
+    scanning: bool,
+    running: bool,
+    error: Option<String>,
+    rx: Option<Receiver<Event>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Normalizer {
+    /// Open the window for the videos of `dist` and start probing them.
+    pub fn open(&mut self, dist: &Path, ctx: &egui::Context) {
+        self.open = true;
+        if self.running {
+            return;
+        }
+        self.video_dir = validate::find_video_dir(dist);
+        let name = dist.file_name().unwrap_or_default().to_string_lossy();
+        self.originals = dist.with_file_name(format!("{}-originals", name));
+        self.keep_originals = true;
+        self.items.clear();
+        self.error = None;
+
+        let Some(ffprobe) = find_tool("ffprobe") else {
+            self.error = Some(
+                "ffprobe wasn't found; put ffmpeg and ffprobe next to this tool or on PATH"
+                    .to_string(),
+            );
+            return;
+        };
+        let (tx, rx) = mpsc::channel();
+        let video_dir = self.video_dir.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(Event::Scanned(scan(&ffprobe, &video_dir)));
+            ctx.request_repaint();
+        });
+        self.rx = Some(rx);
+        self.scanning = true;
+    }
+
+    /// Whether videos changed, so the checklist needs running again.
+    pub fn show(&mut self, ctx: &egui::Context) -> bool {
+        let changed = self.poll();
+        if !self.open {
+            return changed;
+        }
+        let mut open = self.open;
+        egui::Window::new("Normalize Videos")
+            .open(&mut open)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Videos in HEVC, 10-bit, over 1080p or not in MP4 may stutter or not \
+                     play on the kiosk. Selected videos are re-encoded to H.264 1080p with \
+                     AAC audio.",
+                );
+                ui.separator();
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::LIGHT_RED, error);
+                }
+                if self.scanning {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Checking videos...");
+                    });
+                } else if self.items.is_empty() && self.error.is_none() {
+                    ui.label(format!("No videos found in {}", self.video_dir.display()));
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(320.0)
+                    .show(ui, |ui| self.show_items(ui));
+                ui.separator();
+
+                ui.add_enabled_ui(!self.running, |ui| {
+                    ui.checkbox(
+                        &mut self.keep_originals,
+                        format!("Keep originals in {}", self.originals.display()),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    let any = self.items.iter().any(|item| item.selected);
+                    if ui
+                        .add_enabled(
+                            any && !self.running && !self.scanning,
+                            egui::Button::new("🎞 Transcode selected"),
+                        )
+                        .clicked()
+                    {
+                        self.start(ctx);
+                    }
+                    if self.running && ui.button("⏹ Cancel").clicked() {
+                        self.cancel.store(true, Ordering::Relaxed);
+                    }
+                });
+            });
+        self.open = open;
+        changed
+    }
+
+    fn show_items(&mut self, ui: &mut egui::Ui) {
+        let running = self.running;
+        egui::Grid::new("transcode_items")
+            .striped(true)
+            .show(ui, |ui| {
+                for item in &mut self.items {
+                    ui.add_enabled(
+                        !running && item.probe.is_ok(),
+                        egui::Checkbox::without_text(&mut item.selected),
+                    );
+                    ui.label(&item.file_name);
+                    match &item.probe {
+                        Ok(probe) => ui.label(probe.describe()),
+                        Err(_) => ui.label(""),
+                    };
+                    match &item.problems {
+                        Some(problems) => {
+                            ui.colored_label(egui::Color32::YELLOW, problems);
+                        }
+                        None => {
+                            ui.label("✅ OK");
+                        }
+                    }
+                    match &item.state {
+                        State::Waiting => ui.label(""),
+                        State::Running(fraction) => ui.add(
+                            egui::ProgressBar::new(*fraction)
+                                .show_percentage()
+                                .desired_width(120.0),
+                        ),
+                        State::Done => ui.label("✅ Done"),
+                        State::Failed(e) => ui.colored_label(egui::Color32::LIGHT_RED, e),
+                    };
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn start(&mut self, ctx: &egui::Context) {
+        let Some(ffmpeg) = find_tool("ffmpeg") else {
+            self.error =
+                Some("ffmpeg wasn't found; put ffmpeg next to this tool or on PATH".to_string());
+            return;
+        };
+        let queue: Vec<(usize, Item)> = self
+            .items
+            .iter()
+            .cloned()
+            .enumerate()
+            .filter(|(_, item)| item.selected)
+            .collect();
+        let (tx, rx) = mpsc::channel();
+        self.cancel = Arc::new(AtomicBool::new(false));
+        let cancel = self.cancel.clone();
+        let video_dir = self.video_dir.clone();
+        let originals = self.keep_originals.then(|| self.originals.clone());
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let send = |tx: &Sender<Event>, event| {
+                let _ = tx.send(event);
+                ctx.request_repaint();
+            };
+            for (index, item) in queue {
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+                send(&tx, Event::State(index, State::Running(0.0)));
+                let mut last = 0.0;
+                let result = normalize(
+                    &ffmpeg,
+                    &video_dir,
+                    &item,
+                    originals.as_deref(),
+                    &cancel,
+                    |fraction| {
+                        // Every whole percent is plenty
+                        if fraction - last >= 0.01 {
+                            last = fraction;
+                            send(&tx, Event::State(index, State::Running(fraction)));
+                        }
+                    },
+                );
+                let state = match result {
+                    Ok(()) => State::Done,
+                    Err(e) => State::Failed(e),
+                };
+                send(&tx, Event::State(index, state));
+            }
+            send(&tx, Event::Finished);
+        });
+        self.rx = Some(rx);
+        self.running = true;
+    }
+
+    /// Take in what the worker has sent; whether a transcode finished.
+    fn poll(&mut self) -> bool {
+        let Some(rx) = &self.rx else {
+            return false;
+        };
+        let mut changed = false;
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                Event::Scanned(items) => {
+                    self.items = items;
+                    self.scanning = false;
+                }
+                Event::State(index, state) => {
+                    changed |= state == State::Done;
+                    if let Some(item) = self.items.get_mut(index) {
+                        if state == State::Done {
+                            item.selected = false;
+                        }
+                        item.state = state;
+                    }
+                }
+                Event::Finished => self.running = false,
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_probe() {
+        let probe = Probe::parse(
+            "codec_name=hevc\nwidth=3840\nheight=2160\npix_fmt=yuv420p10le\nduration=12.500000\n",
+        )
+        .unwrap();
+        assert_eq!(probe.describe(), "hevc 3840x2160 yuv420p10le");
+        assert_eq!(probe.duration, 12.5);
+        assert_eq!(
+            probe.problems("001_a.mov").as_deref(),
+            Some("HEVC codec, 10-bit, 3840x2160, not an MP4")
+        );
+
+        let fine = Probe {
+            codec: "h264".to_string(),
+            width: 1920,
+            height: 1080,
+            pix_fmt: "yuv420p".to_string(),
+            duration: 30.0,
+        };
+        assert_eq!(fine.problems("001_a.MP4"), None);
+        assert!(Probe::parse("").is_none());
+    }
+
+    #[test]
+    fn test_names_and_progress() {
+        assert_eq!(output_name("012_smith.mov"), "012_smith.mp4");
+        assert_eq!(output_name("012_smith.mp4"), "012_smith.mp4");
+        assert_eq!(parse_progress("out_time_us=2500000"), Some(2.5));
+        assert_eq!(parse_progress("out_time_ms=1000000"), Some(1.0));
+        assert_eq!(parse_progress("progress=continue"), None);
+    }
+
+    #[test]
+    fn test_normalize_keeps_existing_mp4() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("012_smith.mov"), b"mov").unwrap();
+        std::fs::write(temp_dir.path().join("012_smith.mp4"), b"mp4").unwrap();
+        let item = Item {
+            file_name: "012_smith.mov".to_string(),
+            probe: Err("not probed".to_string()),
+            problems: None,
+            selected: true,
+            state: State::Waiting,
+        };
+        let result = normalize(
+            Path::new("no-ffmpeg"),
+            temp_dir.path(),
+            &item,
+            None,
+            &AtomicBool::new(false),
+            |_| {},
+        );
+        assert_eq!(result, Err("012_smith.mp4 is already there".to_string()));
+        assert!(temp_dir.path().join("012_smith.mov").exists());
+        assert_eq!(
+            std::fs::read(temp_dir.path().join("012_smith.mp4")).unwrap(),
+            b"mp4"
+        );
+    }
+}
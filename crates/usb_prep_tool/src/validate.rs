@@ -0,0 +1,221 @@
+//! Checking a dist folder before it goes onto a stick: the player binary
+//! for the kiosk's platform, a config.toml that parses, the asset folders,
+//! and at least one video the player will pick up.
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use summit_hip_core::file_scanner::hip_number;
+
+const REQUIRED_DIRS: [&str; 3] = ["videos", "splash", "logo"];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Platform {
+    #[default]
+    Windows,
+    MacOs,
+    Linux,
+}
+
+impl Platform {
+    pub const ALL: [Platform; 3] = [Platform::Windows, Platform::MacOs, Platform::Linux];
+
+    /// As `cargo xtask dist --platform` names it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Platform::Windows => "windows",
+            Platform::MacOs => "macos",
+            Platform::Linux => "linux",
+        }
+    }
+
+    pub fn binary_name(self) -> &'static str {
+        match self {
+            Platform::Windows => "summit_hip_numbers.exe",
+            Platform::MacOs | Platform::Linux => "summit_hip_numbers",
+        }
+    }
+
+    /// The platform a `dist/<platform>` folder was built for, else the one
+    /// this tool runs on.
+    pub fn guess(dist: &Path) -> Platform {
+        let name = dist.file_name().map(|n| n.to_string_lossy().to_lowercase());
+        Platform::ALL
+            .into_iter()
+            .find(|p| name.as_deref() == Some(p.name()))
+            .unwrap_or(if cfg!(windows) {
+                Platform::Windows
+            } else if cfg!(target_os = "macos") {
+                Platform::MacOs
+            } else {
+                Platform::Linux
+            })
+    }
+}
+
+impl FromStr for Platform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Platform::ALL
+            .into_iter()
+            .find(|p| p.name() == s.to_ascii_lowercase())
+            .ok_or_else(|| format!("'{}' is not a platform (windows, macos or linux)", s))
+    }
+}
+
+/// One line of the checklist: what was checked, and what was found or what
+/// is wrong.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Check {
+    pub label: &'static str,
+    pub result: Result<String, String>,
+}
+
+pub fn passed(checks: &[Check]) -> bool {
+    checks.iter().all(|check| check.result.is_ok())
+}
+
+pub fn validate(dist: &Path, platform: Platform) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let binary = platform.binary_name();
+    checks.push(Check {
+        label: "Player binary",
+        result: if dist.join(binary).is_file() {
+            Ok(binary.to_string())
+        } else {
+            Err(format!(
+                "{} is missing; build it with `cargo xtask dist --platform {}`",
+                binary,
+                platform.name()
+            ))
+        },
+    });
+
+    let config = std::fs::read_to_string(dist.join("config.toml"))
+        .map_err(|e| format!("config.toml can't be read: {}", e))
+        .and_then(|text| {
+            text.parse::<toml::Table>()
+                .map_err(|e| format!("config.toml doesn't parse: {}", e))
+        });
+    let video_dir = video_dir(dist, config.as_ref().ok());
+    checks.push(Check {
+        label: "config.toml",
+        result: config.map(|_| "parses".to_string()),
+    });
+
+    let missing: Vec<&str> = REQUIRED_DIRS
+        .into_iter()
+        .filter(|dir| !dist.join(dir).is_dir())
+        .collect();
+    checks.push(Check {
+        label: "Folders",
+        result: if missing.is_empty() {
+            Ok(REQUIRED_DIRS.join(", "))
+        } else {
+            Err(format!("missing {}", missing.join(", ")))
+        },
+    });
+
+    checks.push(Check {
+        label: "Videos",
+        result: check_videos(&video_dir),
+    });
+    checks
+}
+
+/// Where the player will look for videos in `dist`.
+pub fn find_video_dir(dist: &Path) -> PathBuf {
+    let config = std::fs::read_to_string(dist.join("config.toml"))
+        .ok()
+        .and_then(|text| text.parse::<toml::Table>().ok());
+    video_dir(dist, config.as_ref())
+}
+
+/// `[video] directory` from the config, relative to the dist folder.
+fn video_dir(dist: &Path, config: Option<&toml::Table>) -> PathBuf {
+    let configured = config
+        .and_then(|c| c.get("video")?.get("directory")?.as_str())
+        .unwrap_or("./videos");
+    dist.join(configured)
+}
+
+fn check_videos(video_dir: &Path) -> Result<String, String> {
+    let entries = std::fs::read_dir(video_dir)
+        .map_err(|e| format!("{} can't be read: {}", video_dir.display(), e))?;
+    let mut hips = Vec::new();
+    let mut unnumbered = 0;
+    for entry in entries.flatten() {
+        if !entry.path().is_file() {
+            continue;
+        }
+        match hip_number(&entry.file_name().to_string_lossy()) {
+            Some(hip) => hips.push(hip.to_string()),
+            None => unnumbered += 1,
+        }
+    }
+    hips.sort();
+    let (Some(first), Some(last)) = (hips.first(), hips.last()) else {
+        return Err(format!(
+            "no videos named with a 3-digit hip number (like 001_name.mp4) in {}",
+            video_dir.display()
+        ));
+    };
+    let mut found = format!("{} videos, hips {}-{}", hips.len(), first, last);
+    if unnumbered > 0 {
+        found.push_str(&format!(
+            "; {} without a hip number will be skipped",
+            unnumbered
+        ));
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_validate() {
+        let temp_dir = TempDir::new().unwrap();
+        let dist = temp_dir.path().join("windows");
+        std::fs::create_dir_all(dist.join("videos")).unwrap();
+        std::fs::write(dist.join("config.toml"), "[video\n").unwrap();
+        assert_eq!(Platform::guess(&dist), Platform::Windows);
+        assert_eq!("MacOS".parse(), Ok(Platform::MacOs));
+        assert!("amiga".parse::<Platform>().is_err());
+
+        let checks = validate(&dist, Platform::Windows);
+        assert!(!passed(&checks));
+        let failed: Vec<&str> = checks
+            .iter()
+            .filter(|c| c.result.is_err())
+            .map(|c| c.label)
+            .collect();
+        assert_eq!(
+            failed,
+            ["Player binary", "config.toml", "Folders", "Videos"]
+        );
+
+        std::fs::write(dist.join("summit_hip_numbers.exe"), "").unwrap();
+        std::fs::write(
+            dist.join("config.toml"),
+            "[video]\ndirectory = \"./media\"\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(dist.join("splash")).unwrap();
+        std::fs::create_dir_all(dist.join("logo")).unwrap();
+        std::fs::create_dir_all(dist.join("media")).unwrap();
+        std::fs::write(dist.join("media").join("007_b.mp4"), "").unwrap();
+        std::fs::write(dist.join("media").join("003_a.mp4"), "").unwrap();
+        std::fs::write(dist.join("media").join("notes.txt"), "").unwrap();
+        let checks = validate(&dist, Platform::Windows);
+        assert!(passed(&checks), "{:?}", checks);
+        assert_eq!(
+            checks[3].result,
+            Ok("2 videos, hips 003-007; 1 without a hip number will be skipped".to_string())
+        );
+        assert!(!passed(&validate(&dist, Platform::Linux)));
+    }
+}
@@ -0,0 +1,229 @@
+//! Checking a finished copy: every file on the drive is read back and its
+//! SHA-256 compared with the source's, taken while copying. Cheap USB
+//! sticks do corrupt files silently. The hashes are written to the drive
+//! as a `sha256sum`-style manifest, so the stick can be checked again later.
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const MANIFEST_FILE_NAME: &str = "SHA256SUMS";
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// A copied file, relative to the copy's root.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub len: u64,
+    pub sha256: String,
+}
+
+/// Hash a file, checking for cancel between chunks.
+pub fn hash_file(
+    path: &Path,
+    cancel: &AtomicBool,
+    mut on_chunk: impl FnMut(u64),
+) -> Result<String> {
+    let mut input = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; CHUNK_SIZE];
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            anyhow::bail!("Verification cancelled");
+        }
+        let read = input.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        on_chunk(read as u64);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Read back each entry under `dest`; the ones that are missing or differ.
+pub fn check(
+    dest: &Path,
+    entries: &[Entry],
+    cancel: &AtomicBool,
+    mut on_chunk: impl FnMut(usize, u64, &Path),
+) -> Result<Vec<PathBuf>> {
+    let mut mismatches = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let hash = match hash_file(&dest.join(&entry.path), cancel, |bytes| {
+            on_chunk(index, bytes, &entry.path)
+        }) {
+            Ok(hash) => hash,
+            Err(_) if cancel.load(Ordering::Relaxed) => anyhow::bail!("Verification cancelled"),
+            Err(_) => String::new(),
+        };
+        if hash != entry.sha256 {
+            mismatches.push(entry.path.clone());
+        }
+    }
+    Ok(mismatches)
+}
+
+/// A path as the manifest writes it, with forward slashes.
+pub fn manifest_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// One `<hash>  <path>` line per file. As `sha256sum` does, a path with a
+/// line break in it is escaped, and its line starts with `\`.
+pub fn manifest_text(entries: &[Entry]) -> String {
+    entries
+        .iter()
+        .map(|entry| {
+            let path = manifest_path(&entry.path);
+            if path.contains(['\n', '\r']) {
+                let escaped = path.replace('\n', "\\n").replace('\r', "\\r");
+                format!("\\{}  {}\n", entry.sha256, escaped)
+            } else {
+                format!("{}  {}\n", entry.sha256, path)
+            }
+        })
+        .collect()
+}
+
+/// The hashes in manifest `text`, by manifest path. Lines that aren't
+/// `<hash>  <path>` are skipped.
+pub fn parse_manifest(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            let (hash, path) = line.split_once("  ")?;
+            match hash.strip_prefix('\\') {
+                Some(hash) => Some((unescape(path)?, hash.to_string())),
+                None => Some((path.to_string(), hash.to_string())),
+            }
+        })
+        .collect()
+}
+
+/// Undo the `sha256sum` escapes; `None` for an escape it doesn't write.
+fn unescape(path: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            '\\' => '\\',
+            _ => return None,
+        });
+    }
+    Some(unescaped)
+}
+
+/// The hashes in the manifest under `dest`, by manifest path; empty if
+/// there is none.
+pub fn read_manifest(dest: &Path) -> HashMap<String, String> {
+    parse_manifest(&std::fs::read_to_string(dest.join(MANIFEST_FILE_NAME)).unwrap_or_default())
+}
+
+pub fn write_manifest(dest: &Path, entries: &[Entry]) -> Result<()> {
+    let path = dest.join(MANIFEST_FILE_NAME);
+    std::fs::write(&path, manifest_text(entries))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use tempfile::TempDir;
+
+    fn entry(path: &str, contents: &str) -> Entry {
+        Entry {
+            path: PathBuf::from(path),
+            len: contents.len() as u64,
+            sha256: hex::encode(Sha256::digest(contents.as_bytes())),
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let temp_dir = TempDir::new().unwrap();
+        let dest = temp_dir.path();
+        std::fs::create_dir_all(dest.join("videos")).unwrap();
+        std::fs::write(dest.join("config.toml"), "a").unwrap();
+        std::fs::write(dest.join("videos").join("001_a.mp4"), "corrupt").unwrap();
+
+        let entries = [
+            entry("config.toml", "a"),
+            entry("videos/001_a.mp4", "video"),
+            entry("videos/002_b.mp4", "video"),
+        ];
+        let mismatches = check(dest, &entries, &AtomicBool::new(false), |_, _, _| {}).unwrap();
+        assert_eq!(
+            mismatches,
+            vec![
+                PathBuf::from("videos/001_a.mp4"),
+                PathBuf::from("videos/002_b.mp4")
+            ]
+        );
+        assert!(check(dest, &entries, &AtomicBool::new(true), |_, _, _| {}).is_err());
+    }
+
+    #[test]
+    fn test_manifest() {
+        let entries = [entry("videos/001_a.mp4", "")];
+        assert_eq!(
+            manifest_text(&entries),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  videos/001_a.mp4\n"
+        );
+
+        let temp_dir = TempDir::new().unwrap();
+        write_manifest(temp_dir.path(), &entries).unwrap();
+        let manifest = read_manifest(temp_dir.path());
+        assert_eq!(manifest.get("videos/001_a.mp4"), Some(&entries[0].sha256));
+    }
+
+    #[test]
+    fn test_manifest_escapes_line_breaks() {
+        let entries = [entry("videos/001\nlot.mp4", "")];
+        let text = manifest_text(&entries);
+        assert!(text.starts_with('\\'));
+        assert!(text.ends_with("  videos/001\\nlot.mp4\n"));
+        assert_eq!(
+            parse_manifest(&text).get("videos/001\nlot.mp4"),
+            Some(&entries[0].sha256)
+        );
+        // An escape sha256sum doesn't write is skipped, not guessed at
+        assert!(parse_manifest("\\abc  videos/001\\tlot.mp4\n").is_empty());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_manifest_never_panics(text in any::<String>()) {
+            parse_manifest(&text);
+        }
+
+        #[test]
+        fn prop_manifest_round_trips(
+            files in prop::collection::btree_map(any::<String>(), "[0-9a-f]{64}", 0..8)
+        ) {
+            let entries: Vec<Entry> = files
+                .iter()
+                .map(|(path, sha256)| Entry {
+                    path: PathBuf::from(path),
+                    len: 0,
+                    sha256: sha256.clone(),
+                })
+                .collect();
+            let manifest = parse_manifest(&manifest_text(&entries));
+            for entry in &entries {
+                prop_assert_eq!(manifest.get(&manifest_path(&entry.path)), Some(&entry.sha256));
+            }
+        }
+    }
+}
@@ -82,7 +82,7 @@ log "${YELLOW}[3/8] Building application ($VARIANT)...${NC}"
 # Set build command based on variant
 if [ "$VARIANT" = "demo" ]; then
     APP_NAME="Summit HIP Numbers Demo"
-    FEATURES="--features demo"
+    FEATURES=""
     log "Building demo version..."
 else
     APP_NAME="Summit HIP Numbers"
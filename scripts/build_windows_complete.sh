@@ -104,8 +104,8 @@ log "Created: $DIST_DIR"
 # Build application
 log "${YELLOW}=== Building application ===${NC}"
 if [ "$VARIANT" = "demo" ]; then
-    log "Building demo version with restricted features..."
-    cargo build --release --package summit_hip_numbers --features demo 2>&1 | tee -a "$BUILD_LOG"
+    log "Building demo package (unlicensed copies run with demo limits)..."
+    cargo build --release --package summit_hip_numbers 2>&1 | tee -a "$BUILD_LOG"
     BINARY_NAME="summit_hip_numbers_demo.exe"
     SOURCE_BINARY="target/release/summit_hip_numbers.exe"
 else
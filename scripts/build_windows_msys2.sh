@@ -30,7 +30,7 @@ mkdir -p "$BUILD_LOG_DIR"
 
 # Set build flags based on variant
 if [ "$VARIANT" = "demo" ]; then
-    CARGO_FLAGS="--features demo"
+    CARGO_FLAGS=""
     EXE_OUTPUT_NAME="summit_hip_numbers_demo.exe"
 else
     CARGO_FLAGS=""
@@ -79,8 +79,8 @@ log "${GREEN}✓ Created $DIST_DIR${NC}"
 log ""
 log "${YELLOW}=== Building application ($VARIANT) ===${NC}"
 if [ "$VARIANT" = "demo" ]; then
-    log "Building with demo features..."
-    cargo build --release --features demo --verbose 2>&1 | tee -a "$LOG_FILE"
+    log "Building demo package (unlicensed copies run with demo limits)..."
+    cargo build --release --verbose 2>&1 | tee -a "$LOG_FILE"
 else
     log "Building full version..."
     cargo build --release --verbose 2>&1 | tee -a "$LOG_FILE"
@@ -151,8 +151,5 @@ echo
 echo -e "${BLUE}To run the application:${NC}"
 echo "  cargo run --release --package summit_hip_numbers"
 echo
-echo -e "${BLUE}To run with demo features:${NC}"
-echo "  cargo run --release --package summit_hip_numbers --features demo"
-echo
 echo -e "${YELLOW}Note: The application uses FFmpeg for video playback.${NC}"
 echo -e "${YELLOW}Make sure you have video files in the configured videos directory.${NC}"
\ No newline at end of file
@@ -58,7 +58,6 @@ fi
 
 # 7. Build check (all platforms)
 run_check "Build (default)" "cargo build --release"
-run_check "Build (demo)" "cargo build --release --features demo"
 
 # 8. Documentation check
 run_check "Documentation" "cargo doc --no-deps --all-features"
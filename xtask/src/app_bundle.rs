@@ -0,0 +1,244 @@
+//! The macOS `.app` bundle and its DMG, built from `dist/macos` with
+//! `dist --installer`. A bare binary in a zip gets quarantined and
+//! translocated by Gatekeeper; a signed bundle on a disk image does not.
+//!
+//! ```text
+//! Summit Hip Numbers.app/Contents/
+//! ├── Info.plist
+//! ├── MacOS/summit_hip_numbers
+//! ├── Frameworks/*.dylib       install names @executable_path/../Frameworks
+//! └── Resources/               AppIcon.icns, config.toml, videos, splash, logo
+//! ```
+
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const APP_NAME: &str = "Summit Hip Numbers";
+const BUNDLE_ID: &str = "com.summitps.hipnumbers";
+const BINARY_NAME: &str = "summit_hip_numbers";
+
+/// Build the bundle and `summit_hip_numbers-<version>-macos.dmg` next to
+/// the archive. The bundle is signed with `SHN_CODESIGN_IDENTITY` (a
+/// Developer ID, with the hardened runtime for notarization) if set,
+/// otherwise ad hoc so rewritten dylibs still load on Apple silicon.
+pub fn macos(root: &Path, platform_dist: &Path, version: &str) -> Result<PathBuf> {
+    println!("  [4/4] Creating macOS app bundle and DMG...");
+    let dist_dir = platform_dist.parent().unwrap();
+    let staging = dist_dir.join("dmg");
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    let app = staging.join(format!("{}.app", APP_NAME));
+    let contents = app.join("Contents");
+    let macos = contents.join("MacOS");
+    let frameworks = contents.join("Frameworks");
+    let resources = contents.join("Resources");
+    for dir in [&macos, &frameworks, &resources] {
+        fs::create_dir_all(dir)?;
+    }
+
+    let binary = macos.join(BINARY_NAME);
+    fs::copy(platform_dist.join(BINARY_NAME), &binary).context("Failed to copy the binary")?;
+    let mut dylibs = Vec::new();
+    for entry in fs::read_dir(platform_dist)? {
+        let path = entry?.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("dylib") {
+            let dest = frameworks.join(path.file_name().unwrap());
+            fs::copy(&path, &dest)?;
+            dylibs.push(dest);
+        }
+    }
+    for name in ["config.toml", "videos", "splash", "logo"] {
+        let src = platform_dist.join(name);
+        if src.is_dir() {
+            crate::copy_dir_recursive(&src, &resources.join(name))?;
+        } else if src.is_file() {
+            fs::copy(&src, resources.join(name))?;
+        }
+    }
+    relink(&binary, &dylibs)?;
+    for dylib in &dylibs {
+        relink(dylib, &dylibs)?;
+    }
+    println!(
+        "  ✓ Bundled {} dylibs into Contents/Frameworks",
+        dylibs.len()
+    );
+
+    let has_icon = match make_icon(root, &resources.join("AppIcon.icns")) {
+        Ok(()) => true,
+        Err(e) => {
+            println!("  ⚠ No app icon: {:#}", e);
+            false
+        }
+    };
+    fs::write(contents.join("Info.plist"), info_plist(version, has_icon))?;
+
+    sign(&app, &dylibs)?;
+    crate::run_tool(
+        Command::new("ln")
+            .args(["-s", "/Applications"])
+            .arg(staging.join("Applications")),
+    )?;
+
+    let dmg = dist_dir.join(format!("summit_hip_numbers-{}-macos.dmg", version));
+    let status = Command::new("hdiutil")
+        .args(["create", "-volname", APP_NAME, "-ov", "-format", "UDZO"])
+        .arg("-srcfolder")
+        .arg(&staging)
+        .arg(&dmg)
+        .status()
+        .context("Failed to run hdiutil")?;
+    if !status.success() {
+        bail!("hdiutil failed to create {}", dmg.display());
+    }
+    fs::remove_dir_all(&staging)?;
+
+    println!("  ✓ DMG: {}", dmg.display());
+    Ok(dmg)
+}
+
+/// Point `file`'s own install name (for a dylib) and its references to the
+/// bundled dylibs at `Contents/Frameworks`. `fix_macos_dylib_paths` has
+/// already made them `@executable_path/<name>`.
+fn relink(file: &Path, dylibs: &[PathBuf]) -> Result<()> {
+    let mut cmd = Command::new("install_name_tool");
+    let file_name = file.file_name().unwrap().to_string_lossy();
+    if file.extension().and_then(|s| s.to_str()) == Some("dylib") {
+        cmd.arg("-id")
+            .arg(format!("@executable_path/../Frameworks/{}", file_name));
+    }
+    for dylib in dylibs {
+        let name = dylib.file_name().unwrap().to_string_lossy();
+        cmd.arg("-change")
+            .arg(format!("@executable_path/{}", name))
+            .arg(format!("@executable_path/../Frameworks/{}", name));
+    }
+    let output = cmd
+        .arg(file)
+        .output()
+        .context("Failed to run install_name_tool")?;
+    if !output.status.success() {
+        bail!(
+            "install_name_tool failed for {}: {}",
+            file_name,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(())
+}
+
+/// `AppIcon.icns` from `build-assets/macos-x64/AppIcon.icns` if there is
+/// one, otherwise rendered from the logo with Quick Look and `iconutil`.
+fn make_icon(root: &Path, icns: &Path) -> Result<()> {
+    let prebuilt = root
+        .join("build-assets")
+        .join("macos-x64")
+        .join("AppIcon.icns");
+    if prebuilt.is_file() {
+        fs::copy(&prebuilt, icns)?;
+        return Ok(());
+    }
+
+    let logo = root.join("assets").join("logo").join("logo.svg");
+    let work = icns.with_extension("work");
+    fs::create_dir_all(&work)?;
+    crate::run_tool(
+        Command::new("qlmanage")
+            .args(["-t", "-s", "1024", "-o"])
+            .arg(&work)
+            .arg(&logo),
+    )?;
+    let png = work.join("logo.svg.png");
+    let iconset = work.join("AppIcon.iconset");
+    fs::create_dir_all(&iconset)?;
+    for size in [16, 32, 128, 256, 512] {
+        for (scale, suffix) in [(1, ""), (2, "@2x")] {
+            let pixels = (size * scale).to_string();
+            crate::run_tool(
+                Command::new("sips")
+                    .args(["-z", &pixels, &pixels])
+                    .arg(&png)
+                    .arg("--out")
+                    .arg(iconset.join(format!("icon_{}x{}{}.png", size, size, suffix))),
+            )?;
+        }
+    }
+    crate::run_tool(
+        Command::new("iconutil")
+            .args(["-c", "icns", "-o"])
+            .arg(icns)
+            .arg(&iconset),
+    )?;
+    fs::remove_dir_all(&work)?;
+    Ok(())
+}
+
+fn info_plist(version: &str, has_icon: bool) -> String {
+    let icon = if has_icon {
+        "    <key>CFBundleIconFile</key>\n    <string>AppIcon</string>\n"
+    } else {
+        ""
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>CFBundleName</key>
+    <string>{name}</string>
+    <key>CFBundleDisplayName</key>
+    <string>{name}</string>
+    <key>CFBundleIdentifier</key>
+    <string>{id}</string>
+    <key>CFBundleExecutable</key>
+    <string>{binary}</string>
+    <key>CFBundlePackageType</key>
+    <string>APPL</string>
+    <key>CFBundleShortVersionString</key>
+    <string>{version}</string>
+    <key>CFBundleVersion</key>
+    <string>{version}</string>
+{icon}    <key>LSMinimumSystemVersion</key>
+    <string>10.13</string>
+    <key>NSHighResolutionCapable</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        name = APP_NAME,
+        id = BUNDLE_ID,
+        binary = BINARY_NAME,
+        version = version,
+        icon = icon,
+    )
+}
+
+/// Sign the dylibs, then the bundle around them. install_name_tool has
+/// invalidated any signature they had.
+fn sign(app: &Path, dylibs: &[PathBuf]) -> Result<()> {
+    let identity = std::env::var("SHN_CODESIGN_IDENTITY")
+        .ok()
+        .filter(|identity| !identity.trim().is_empty());
+    match &identity {
+        Some(identity) => println!("  ✓ Signing as {}", identity),
+        None => println!("  ⚠ SHN_CODESIGN_IDENTITY is not set; signing ad hoc"),
+    }
+    for file in dylibs.iter().map(PathBuf::as_path).chain([app]) {
+        let mut cmd = Command::new("codesign");
+        cmd.args(["--force", "--sign"]);
+        match &identity {
+            Some(identity) => {
+                cmd.arg(identity)
+                    .args(["--options", "runtime", "--timestamp"]);
+            }
+            None => {
+                cmd.arg("-");
+            }
+        }
+        crate::run_tool(cmd.arg(file))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,313 @@
+//! What goes out with every archive and installer `dist` builds, so venues
+//! can check a download: a `<file>.sha256` in `sha256sum` format, a
+//! CycloneDX SBOM per platform, and, when a key is configured, detached
+//! GPG (`<file>.asc`) and minisign (`<file>.minisig`) signatures.
+//! `xtask verify` checks them all.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const PLAYER_PACKAGE: &str = "summit_hip_numbers";
+
+/// Checksum and sign each artifact, writing `<file>.sha256`, and `.asc` if
+/// `SHN_GPG_KEY` names a key, and `.minisig` if `SHN_MINISIGN_KEY` is the
+/// path of an unencrypted minisign secret key.
+pub fn checksum_and_sign(artifacts: &[PathBuf]) -> Result<()> {
+    let gpg_key = env_var("SHN_GPG_KEY");
+    let minisign_key = env_var("SHN_MINISIGN_KEY");
+    if gpg_key.is_none() && minisign_key.is_none() {
+        println!("  ⚠ SHN_GPG_KEY and SHN_MINISIGN_KEY are not set; artifacts will not be signed");
+    }
+    for artifact in artifacts {
+        let name = file_name(artifact);
+        let line = format!("{}  {}\n", sha256(artifact)?, name);
+        fs::write(sidecar(artifact, "sha256"), line)?;
+
+        if let Some(key) = &gpg_key {
+            crate::run_tool(
+                Command::new("gpg")
+                    .args(["--batch", "--yes", "--armor", "--detach-sign"])
+                    .args(["--local-user", key, "--output"])
+                    .arg(sidecar(artifact, "asc"))
+                    .arg(artifact),
+            )?;
+        }
+        if let Some(key) = &minisign_key {
+            crate::run_tool(
+                Command::new("minisign")
+                    .args(["-S", "-s", key, "-m"])
+                    .arg(artifact),
+            )?;
+        }
+        println!("  ✓ Checksummed {}", name);
+    }
+    Ok(())
+}
+
+/// Write a CycloneDX SBOM for the player built for `target`: every crate it
+/// links, from `cargo metadata`, and the libraries bundled in
+/// `platform_dist` with their hashes.
+pub fn write_sbom(
+    root: &Path,
+    target: &str,
+    version: &str,
+    platform_dist: &Path,
+    out: &Path,
+) -> Result<()> {
+    let output = Command::new("cargo")
+        .current_dir(root)
+        .args([
+            "metadata",
+            "--format-version",
+            "1",
+            "--filter-platform",
+            target,
+        ])
+        .output()
+        .context("Failed to run cargo metadata")?;
+    if !output.status.success() {
+        bail!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let metadata: Value =
+        serde_json::from_slice(&output.stdout).context("cargo metadata printed invalid JSON")?;
+
+    let mut components: Vec<Value> = linked_crates(&metadata)?
+        .iter()
+        .map(|package| {
+            let name = package["name"].as_str().unwrap_or_default();
+            let version = package["version"].as_str().unwrap_or_default();
+            let purl = format!("pkg:cargo/{}@{}", name, version);
+            let mut component = json!({
+                "type": "library",
+                "bom-ref": purl,
+                "name": name,
+                "version": version,
+                "purl": purl,
+            });
+            if let Some(license) = package["license"].as_str() {
+                component["licenses"] = json!([{ "expression": license }]);
+            }
+            component
+        })
+        .collect();
+    for library in bundled_libraries(platform_dist)? {
+        let name = file_name(&library);
+        components.push(json!({
+            "type": "library",
+            "bom-ref": format!("file:{}", name),
+            "name": name,
+            "description": "Bundled native library",
+            "hashes": [{ "alg": "SHA-256", "content": sha256(&library)? }],
+        }));
+    }
+
+    let sbom = json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "component": {
+                "type": "application",
+                "bom-ref": format!("pkg:cargo/{}@{}", PLAYER_PACKAGE, version),
+                "name": PLAYER_PACKAGE,
+                "version": version,
+            },
+        },
+        "components": components,
+    });
+    fs::write(out, serde_json::to_string_pretty(&sbom)?)?;
+    println!(
+        "  ✓ SBOM: {} ({} components)",
+        out.display(),
+        components.len()
+    );
+    Ok(())
+}
+
+/// Packages the player depends on at run time (not dev or build
+/// dependencies), by name and version.
+fn linked_crates(metadata: &Value) -> Result<Vec<&Value>> {
+    let packages: BTreeMap<&str, &Value> = metadata["packages"]
+        .as_array()
+        .context("cargo metadata has no packages")?
+        .iter()
+        .filter_map(|package| Some((package["id"].as_str()?, package)))
+        .collect();
+    let nodes: BTreeMap<&str, &Value> = metadata["resolve"]["nodes"]
+        .as_array()
+        .context("cargo metadata has no dependency graph")?
+        .iter()
+        .filter_map(|node| Some((node["id"].as_str()?, node)))
+        .collect();
+    let player = packages
+        .iter()
+        .find(|(_, package)| package["name"] == PLAYER_PACKAGE)
+        .map(|(id, _)| *id)
+        .context("The player is not in cargo metadata")?;
+
+    let mut seen = BTreeMap::new();
+    let mut queue = VecDeque::from([player]);
+    while let Some(id) = queue.pop_front() {
+        let Some(node) = nodes.get(id) else { continue };
+        for dep in node["deps"].as_array().into_iter().flatten() {
+            let normal = dep["dep_kinds"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .any(|kind| kind["kind"].is_null());
+            let Some(dep_id) = dep["pkg"].as_str() else {
+                continue;
+            };
+            if normal && seen.insert(dep_id, packages[dep_id]).is_none() {
+                queue.push_back(dep_id);
+            }
+        }
+    }
+    let mut crates: Vec<&Value> = seen.into_values().collect();
+    crates.sort_by_key(|package| {
+        (
+            package["name"].as_str().unwrap_or_default(),
+            package["version"].as_str().unwrap_or_default(),
+        )
+    });
+    Ok(crates)
+}
+
+/// The `.dll`, `.dylib` and `.so` files next to the binary.
+fn bundled_libraries(platform_dist: &Path) -> Result<Vec<PathBuf>> {
+    let mut libraries: Vec<PathBuf> = fs::read_dir(platform_dist)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            let name = file_name(path);
+            name.ends_with(".dll") || name.ends_with(".dylib") || name.contains(".so")
+        })
+        .collect();
+    libraries.sort();
+    Ok(libraries)
+}
+
+/// Check the artifacts in `files`, or every one with a `.sha256` in
+/// `dist_dir`: the checksum, and any signature next to it. GPG signatures
+/// are checked against the keyring; minisign ones against the public key
+/// in `SHN_MINISIGN_PUBLIC_KEY`.
+pub fn verify(dist_dir: &Path, files: &[PathBuf]) -> Result<()> {
+    let files = if files.is_empty() {
+        let mut found: Vec<PathBuf> = fs::read_dir(dist_dir)
+            .with_context(|| format!("Failed to read {}", dist_dir.display()))?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("sha256"))
+            .map(|path| path.with_extension(""))
+            .collect();
+        found.sort();
+        found
+    } else {
+        files.to_vec()
+    };
+    if files.is_empty() {
+        bail!("Nothing to verify in {}", dist_dir.display());
+    }
+
+    let minisign_public_key = env_var("SHN_MINISIGN_PUBLIC_KEY");
+    let mut failed = 0;
+    for file in &files {
+        println!("{}", file.display());
+        for (check, result) in verify_one(file, minisign_public_key.as_deref()) {
+            match result {
+                Ok(()) => println!("  ✓ {}", check),
+                Err(e) => {
+                    println!("  ✗ {}: {:#}", check, e);
+                    failed += 1;
+                }
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("{} checks failed", failed);
+    }
+    println!("\n✓ All {} artifacts verified", files.len());
+    Ok(())
+}
+
+fn verify_one(file: &Path, minisign_public_key: Option<&str>) -> Vec<(&'static str, Result<()>)> {
+    let mut checks = vec![("SHA-256", verify_checksum(file))];
+    let asc = sidecar(file, "asc");
+    if asc.exists() {
+        checks.push((
+            "GPG signature",
+            crate::run_tool(Command::new("gpg").arg("--verify").arg(&asc).arg(file)),
+        ));
+    }
+    if sidecar(file, "minisig").exists() {
+        let result = match minisign_public_key {
+            Some(key) => crate::run_tool(
+                Command::new("minisign")
+                    .args(["-V", "-P", key, "-m"])
+                    .arg(file),
+            ),
+            None => Err(anyhow::anyhow!("set SHN_MINISIGN_PUBLIC_KEY to check it")),
+        };
+        checks.push(("minisign signature", result));
+    }
+    checks
+}
+
+fn verify_checksum(file: &Path) -> Result<()> {
+    let path = sidecar(file, "sha256");
+    let text =
+        fs::read_to_string(&path).with_context(|| format!("{} is missing", path.display()))?;
+    let expected = text
+        .split_whitespace()
+        .next()
+        .context("the .sha256 file is empty")?;
+    let actual = sha256(file)?;
+    if !actual.eq_ignore_ascii_case(expected) {
+        bail!("expected {}, got {}", expected, actual);
+    }
+    Ok(())
+}
+
+pub fn sha256(path: &Path) -> Result<String> {
+    let mut input =
+        fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 1024 * 1024];
+    loop {
+        let read = input.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// `windows.zip.sha256` for `windows.zip`.
+fn sidecar(artifact: &Path, extension: &str) -> PathBuf {
+    let mut name = artifact.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
@@ -0,0 +1,152 @@
+//! Third-party archives `dist` needs, FFmpeg's above all. They are kept in
+//! a cache directory shared by every checkout (`SHN_FFMPEG_CACHE`, else
+//! `.ffmpeg` in the repo), resumed from a `.part` file when a download is
+//! cut off, and checked against the SHA-256 pinned for them in
+//! `xtask/downloads.sha256` before anything is extracted. An archive with no
+//! pin is refused, unless `dist --pin` asks for new archives to be pinned.
+
+use anyhow::{bail, Context, Result};
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+const PINS: &str = "downloads.sha256";
+
+/// Whether an archive with no pin is pinned, rather than refused.
+static PIN_NEW: AtomicBool = AtomicBool::new(false);
+
+/// Pin archives that have no pin yet, for `dist --pin`.
+pub fn pin_new_archives() {
+    PIN_NEW.store(true, Ordering::Relaxed);
+}
+
+/// Where downloads and the FFmpeg trees extracted from them live.
+pub fn cache_dir(root: &Path) -> PathBuf {
+    match std::env::var_os("SHN_FFMPEG_CACHE") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => root.join(".ffmpeg"),
+    }
+}
+
+/// The archive at `url`, from the cache if it has it, verified against its
+/// pin. An archive that was never pinned is refused, or with `--pin` pinned
+/// now, and the new line in `xtask/downloads.sha256` should be committed.
+pub fn fetch(root: &Path, url: &str) -> Result<PathBuf> {
+    let name = url.rsplit('/').next().unwrap_or(url);
+    let downloads = cache_dir(root).join("downloads");
+    fs::create_dir_all(&downloads)?;
+    let archive = downloads.join(name);
+
+    if archive.is_file() {
+        println!("    Using cached {}", archive.display());
+    } else {
+        println!("    Downloading from: {}", url);
+        download(url, &archive)?;
+    }
+    check_pin(root, name, &archive, PIN_NEW.load(Ordering::Relaxed))?;
+    Ok(archive)
+}
+
+/// Download into `<dest>.part`, carrying on from its end if it is there,
+/// and rename it to `dest` once complete.
+fn download(url: &str, dest: &Path) -> Result<()> {
+    let mut partial = dest.as_os_str().to_owned();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+    let offset = fs::metadata(&partial).map(|meta| meta.len()).unwrap_or(0);
+
+    let client = reqwest::blocking::Client::builder()
+        .connect_timeout(Duration::from_secs(30))
+        .timeout(None)
+        .build()?;
+    let mut request = client.get(url);
+    if offset > 0 {
+        println!("    Resuming after {} MB", offset / (1024 * 1024));
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    let mut response = request.send().context("Failed to download")?;
+
+    let append = match response.status() {
+        StatusCode::PARTIAL_CONTENT => true,
+        // The .part already holds the whole file
+        StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+            fs::rename(&partial, dest)?;
+            return Ok(());
+        }
+        status if status.is_success() => false,
+        status => bail!("Failed to download {}: HTTP {}", url, status),
+    };
+    let mut options = OpenOptions::new();
+    if append {
+        options.append(true);
+    } else {
+        options.write(true).create(true).truncate(true);
+    }
+    let mut file = options
+        .open(&partial)
+        .with_context(|| format!("Failed to open {}", partial.display()))?;
+    response
+        .copy_to(&mut file)
+        .context("Download interrupted; run again to resume it")?;
+    file.flush()?;
+    drop(file);
+    fs::rename(&partial, dest)?;
+    Ok(())
+}
+
+/// Compare the archive's SHA-256 with the pin for `name`, deleting it on a
+/// mismatch so the next run downloads it afresh. With no pin, `pin_new`
+/// pins it; otherwise it's refused.
+fn check_pin(root: &Path, name: &str, archive: &Path, pin_new: bool) -> Result<()> {
+    let pins_path = root.join("xtask").join(PINS);
+    let pins = fs::read_to_string(&pins_path).unwrap_or_default();
+    let pinned = pins
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some((fields.next()?, fields.next()?))
+        })
+        .find(|(_, file)| *file == name)
+        .map(|(hash, _)| hash.to_string());
+    let actual = crate::artifacts::sha256(archive)?;
+
+    match pinned {
+        Some(expected) if expected.eq_ignore_ascii_case(&actual) => {
+            println!("    ✓ SHA-256 matches the pin");
+        }
+        Some(expected) => {
+            fs::remove_file(archive)?;
+            bail!(
+                "{} has SHA-256 {}, but xtask/{} pins {}; deleted it",
+                name,
+                actual,
+                PINS,
+                expected
+            );
+        }
+        None if !pin_new => bail!(
+            "{} has no SHA-256 pinned in xtask/{}; check that {} is the archive \
+             you expect, then run `cargo xtask dist --pin` and commit the line it adds",
+            name,
+            PINS,
+            archive.display()
+        ),
+        None => {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&pins_path)?;
+            writeln!(file, "{}  {}", actual, name)?;
+            println!(
+                "    ⚠ {} was not pinned; pinned SHA-256 {} in xtask/{}, commit it",
+                name, actual, PINS
+            );
+        }
+    }
+    Ok(())
+}
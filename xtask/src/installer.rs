@@ -0,0 +1,55 @@
+//! Installers built from a platform's dist folder with `dist --installer`,
+//! alongside the archive.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where Inno Setup installs its compiler when it isn't on PATH.
+const ISCC_DEFAULT: &str = r"C:\Program Files (x86)\Inno Setup 6\ISCC.exe";
+
+/// Build the Windows installer from `platform_dist` with Inno Setup and
+/// `scripts/installer.iss`: the player, its DLLs, the config and assets, a
+/// Start Menu entry and an optional start-at-login task.
+///
+/// When `SHN_SIGNTOOL` is set to a sign command with `$f` for the file, e.g.
+/// `signtool sign /f kiosk.pfx /fd sha256 /tr http://timestamp.digicert.com /td sha256 $f`,
+/// the player, the installer and its uninstaller are signed with it.
+pub fn windows(root: &Path, platform_dist: &Path, version: &str) -> Result<PathBuf> {
+    println!("  [4/4] Creating Windows installer...");
+    let iscc = find_iscc()
+        .context("Inno Setup's ISCC was not found; install Inno Setup 6 or put ISCC on PATH")?;
+    let output_dir = platform_dist.parent().unwrap();
+    let output_name = format!("summit_hip_numbers-{}-windows-setup", version);
+
+    let mut cmd = Command::new(&iscc);
+    cmd.arg(format!("/DSourceDir={}", platform_dist.display()))
+        .arg(format!("/DMyAppVersion={}", version))
+        .arg(format!("/O{}", output_dir.display()))
+        .arg(format!("/F{}", output_name));
+    match std::env::var("SHN_SIGNTOOL") {
+        Ok(sign) if !sign.trim().is_empty() => {
+            println!("  ✓ Signing with SHN_SIGNTOOL");
+            cmd.arg("/DSign").arg(format!("/Sshn={}", sign));
+        }
+        _ => println!("  ⚠ SHN_SIGNTOOL is not set; the installer will not be signed"),
+    }
+    let status = cmd
+        .arg(root.join("scripts").join("installer.iss"))
+        .status()
+        .with_context(|| format!("Failed to run {}", iscc.display()))?;
+    if !status.success() {
+        bail!("Inno Setup failed to build the installer");
+    }
+
+    let installer = output_dir.join(format!("{}.exe", output_name));
+    println!("  ✓ Installer: {}", installer.display());
+    Ok(installer)
+}
+
+fn find_iscc() -> Option<PathBuf> {
+    which::which("iscc")
+        .or_else(|_| which::which("ISCC"))
+        .ok()
+        .or_else(|| Some(PathBuf::from(ISCC_DEFAULT)).filter(|path| path.is_file()))
+}
@@ -0,0 +1,201 @@
+//! The Linux packages built from `dist/linux` with `dist --installer`: a
+//! `.deb` for kiosks that are installed once, and an AppImage that runs
+//! from anywhere, USB sticks included. Both carry a `.desktop` entry and
+//! the logo as icon; the `.deb` also ships a systemd user unit that starts
+//! the player at login once enabled.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const PACKAGE: &str = "summit-hip-numbers";
+const BINARY_NAME: &str = "summit_hip_numbers";
+
+/// Build both packages next to the archive.
+pub fn linux(root: &Path, platform_dist: &Path, version: &str) -> Result<Vec<PathBuf>> {
+    println!("  [4/4] Creating .deb and AppImage...");
+    let packages = vec![
+        deb(root, platform_dist, version)?,
+        appimage(root, platform_dist, version)?,
+    ];
+    for package in &packages {
+        println!("  ✓ Package: {}", package.display());
+    }
+    Ok(packages)
+}
+
+/// `/opt/summit-hip-numbers` holds the dist folder as is, with a launcher
+/// in `/usr/bin`. The config there is read-only, so the player saves
+/// settings to `~/.config/SummitHipNumbers`.
+fn deb(root: &Path, platform_dist: &Path, version: &str) -> Result<PathBuf> {
+    let dist_dir = platform_dist.parent().unwrap();
+    let staging = dist_dir.join("deb");
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    let install_dir = format!("/opt/{}", PACKAGE);
+    crate::copy_dir_recursive(platform_dist, &staging.join(&install_dir[1..]))?;
+
+    let bin = staging.join("usr").join("bin");
+    fs::create_dir_all(&bin)?;
+    write_executable(&bin.join(PACKAGE), &launcher(&install_dir))?;
+    write_shared_files(root, &staging.join("usr").join("share"))?;
+    let units = staging.join("usr").join("lib").join("systemd").join("user");
+    fs::create_dir_all(&units)?;
+    fs::write(units.join(format!("{}.service", PACKAGE)), service_unit())?;
+
+    let debian = staging.join("DEBIAN");
+    fs::create_dir_all(&debian)?;
+    fs::write(debian.join("control"), control(version))?;
+
+    let deb = dist_dir.join(format!("{}_{}_amd64.deb", PACKAGE, version));
+    crate::run_tool(
+        Command::new("dpkg-deb")
+            .args(["--build", "--root-owner-group"])
+            .arg(&staging)
+            .arg(&deb),
+    )?;
+    fs::remove_dir_all(&staging)?;
+    Ok(deb)
+}
+
+/// The dist folder in `usr/lib/summit-hip-numbers` of an AppDir, started by
+/// `AppRun`. Built with `appimagetool`, extracted rather than mounted so it
+/// runs without FUSE in CI.
+fn appimage(root: &Path, platform_dist: &Path, version: &str) -> Result<PathBuf> {
+    let dist_dir = platform_dist.parent().unwrap();
+    let app_dir = dist_dir.join("AppDir");
+    if app_dir.exists() {
+        fs::remove_dir_all(&app_dir)?;
+    }
+    let lib_dir = format!("usr/lib/{}", PACKAGE);
+    crate::copy_dir_recursive(platform_dist, &app_dir.join(&lib_dir))?;
+    write_executable(&app_dir.join("AppRun"), &app_run(&lib_dir))?;
+    write_shared_files(root, &app_dir.join("usr").join("share"))?;
+    fs::write(
+        app_dir.join(format!("{}.desktop", PACKAGE)),
+        desktop_entry(),
+    )?;
+    fs::copy(logo(root), app_dir.join(format!("{}.svg", PACKAGE)))?;
+
+    let appimage = dist_dir.join(format!("{}-{}-x86_64.AppImage", BINARY_NAME, version));
+    let appimagetool = which::which("appimagetool")
+        .context("appimagetool was not found; download it from https://github.com/AppImage/appimagetool/releases and put it on PATH")?;
+    crate::run_tool(
+        Command::new(appimagetool)
+            .env("ARCH", "x86_64")
+            .env("APPIMAGE_EXTRACT_AND_RUN", "1")
+            .arg(&app_dir)
+            .arg(&appimage),
+    )?;
+    fs::remove_dir_all(&app_dir)?;
+    Ok(appimage)
+}
+
+/// The `.desktop` entry and icon under `share`.
+fn write_shared_files(root: &Path, share: &Path) -> Result<()> {
+    let applications = share.join("applications");
+    let icons = share
+        .join("icons")
+        .join("hicolor")
+        .join("scalable")
+        .join("apps");
+    fs::create_dir_all(&applications)?;
+    fs::create_dir_all(&icons)?;
+    fs::write(
+        applications.join(format!("{}.desktop", PACKAGE)),
+        desktop_entry(),
+    )?;
+    fs::copy(logo(root), icons.join(format!("{}.svg", PACKAGE)))
+        .context("Failed to copy the logo as icon")?;
+    Ok(())
+}
+
+fn logo(root: &Path) -> PathBuf {
+    root.join("assets").join("logo").join("logo.svg")
+}
+
+/// The bundled FFmpeg libraries sit next to the binary.
+fn launcher(install_dir: &str) -> String {
+    format!(
+        "#!/bin/sh\n\
+         export LD_LIBRARY_PATH=\"{dir}${{LD_LIBRARY_PATH:+:$LD_LIBRARY_PATH}}\"\n\
+         exec \"{dir}/{binary}\" \"$@\"\n",
+        dir = install_dir,
+        binary = BINARY_NAME,
+    )
+}
+
+fn app_run(lib_dir: &str) -> String {
+    format!(
+        "#!/bin/sh\n\
+         HERE=\"$(dirname \"$(readlink -f \"$0\")\")\"\n\
+         export LD_LIBRARY_PATH=\"$HERE/{dir}${{LD_LIBRARY_PATH:+:$LD_LIBRARY_PATH}}\"\n\
+         exec \"$HERE/{dir}/{binary}\" \"$@\"\n",
+        dir = lib_dir,
+        binary = BINARY_NAME,
+    )
+}
+
+fn desktop_entry() -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=Summit Hip Numbers\n\
+         Comment=Hip number video kiosk\n\
+         Exec={package}\n\
+         Icon={package}\n\
+         Terminal=false\n\
+         Categories=AudioVideo;Video;Player;\n",
+        package = PACKAGE,
+    )
+}
+
+/// Started with the graphical session once a user runs
+/// `systemctl --user enable --now summit-hip-numbers`.
+fn service_unit() -> String {
+    format!(
+        "[Unit]\n\
+         Description=Summit Hip Numbers kiosk player\n\
+         PartOf=graphical-session.target\n\
+         After=graphical-session.target\n\
+         \n\
+         [Service]\n\
+         ExecStart=/usr/bin/{package}\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy=graphical-session.target\n",
+        package = PACKAGE,
+    )
+}
+
+fn control(version: &str) -> String {
+    format!(
+        "Package: {package}\n\
+         Version: {version}\n\
+         Architecture: amd64\n\
+         Maintainer: Summit Professional Services\n\
+         Depends: libasound2 | libasound2t64\n\
+         Section: video\n\
+         Priority: optional\n\
+         Homepage: https://github.com/millerjes37/summit_hip_numbers\n\
+         Description: Hip number video kiosk player\n \
+         Plays videos passively and switches to the one for a hip number\n \
+         typed on a keypad.\n",
+        package = PACKAGE,
+        version = version,
+    )
+}
+
+fn write_executable(path: &Path, contents: &str) -> Result<()> {
+    fs::write(path, contents)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
@@ -6,6 +6,15 @@ use std::process::Command;
 use std::{env, fs};
 use walkdir::WalkDir;
 
+mod app_bundle;
+mod artifacts;
+mod download;
+mod installer;
+mod linux_package;
+mod release;
+mod smoke;
+mod test_assets;
+
 #[derive(Parser)]
 #[command(name = "xtask")]
 #[command(about = "Build automation for Summit HIP Numbers")]
@@ -18,13 +27,64 @@ struct Cli {
 enum Commands {
     /// Build distribution packages for all platforms
     Dist {
-        /// Specific platform to build (linux, macos, windows, or all)
+        /// Specific platform to build (linux, macos, windows, linux-arm64,
+        /// linux-armv7, windows-arm64, or all for the first three)
         #[arg(long, default_value = "all")]
         platform: String,
 
-        /// Variant to build (full, demo, or all)
-        #[arg(long, default_value = "all")]
+        /// Also build the platform's installer (Windows: Inno Setup;
+        /// macOS: .app bundle in a DMG; Linux: .deb and AppImage)
+        #[arg(long)]
+        installer: bool,
+
+        /// Pin the SHA-256 of any downloaded archive that has no pin yet in
+        /// xtask/downloads.sha256, instead of refusing it
+        #[arg(long)]
+        pin: bool,
+    },
+    /// Build the player and run it from a dist-style folder, so the
+    /// exe-relative config and asset lookup is exercised
+    Run {
+        /// `demo` to run with the demo limits, as a licensed build does
+        /// without a license; `full` for an unlimited development build
+        #[arg(long, default_value = "full")]
         variant: String,
+
+        /// Arguments passed on to the player, after `--`
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Synthesize numbered test videos into tests/assets and assets/videos
+    GenAssets {
+        /// How many videos, numbered from 001
+        #[arg(long, default_value_t = 20)]
+        count: u32,
+
+        /// Length of each video in seconds
+        #[arg(long, default_value_t = 5)]
+        seconds: u32,
+    },
+    /// Unpack a built archive and run the player's --self-check in it
+    Smoke {
+        /// Platform whose archive to test (default: this one)
+        #[arg(long)]
+        platform: Option<String>,
+    },
+    /// Check the checksums and signatures of built artifacts
+    Verify {
+        /// Artifacts to check; every one with a .sha256 in dist/ if none
+        files: Vec<PathBuf>,
+    },
+    /// Bump versions, build everything, update CHANGELOG.md, tag, and
+    /// publish a GitHub release (needs GITHUB_TOKEN)
+    Release {
+        /// The version to release, as vX.Y.Z
+        #[arg(long)]
+        tag: String,
+
+        /// Only print the version and changelog
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
@@ -32,13 +92,32 @@ fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Dist { platform, variant } => build_dist(&platform, &variant)?,
+        Commands::Dist {
+            platform,
+            installer,
+            pin,
+        } => {
+            if pin {
+                download::pin_new_archives();
+            }
+            build_dist(&platform, installer)?
+        }
+        Commands::Run { variant, args } => run_staged(&variant, &args)?,
+        Commands::GenAssets { count, seconds } => {
+            test_assets::generate(&project_root(), count, seconds)?
+        }
+        Commands::Smoke { platform } => {
+            let platform = platform.unwrap_or_else(|| host_platform().to_string());
+            smoke::run(&project_root(), &platform)?
+        }
+        Commands::Verify { files } => artifacts::verify(&project_root().join("dist"), &files)?,
+        Commands::Release { tag, dry_run } => release::run(&project_root(), &tag, dry_run)?,
     }
 
     Ok(())
 }
 
-fn build_dist(platform: &str, variant: &str) -> Result<()> {
+fn build_dist(platform: &str, installer: bool) -> Result<()> {
     let root = project_root();
     let dist_dir = root.join("dist");
 
@@ -49,18 +128,15 @@ fn build_dist(platform: &str, variant: &str) -> Result<()> {
         vec![platform]
     };
 
-    // Determine which variants to build
-    let variants = if variant == "all" {
-        vec!["full", "demo"]
-    } else {
-        vec![variant]
-    };
+    if env::var("SHN_LICENSE_PUBLIC_KEY").map_or(true, |key| key.trim().is_empty()) {
+        println!(
+            "Warning: SHN_LICENSE_PUBLIC_KEY is not set; the binaries will not need a license"
+        );
+    }
 
     for platform in platforms {
-        for variant in variants.iter() {
-            println!("\n=== Building {} - {} ===", platform, variant);
-            build_platform(&root, &dist_dir, platform, variant)?;
-        }
+        println!("\n=== Building {} ===", platform);
+        build_platform(&root, &dist_dir, platform, installer)?;
     }
 
     println!("\n✓ All distributions built successfully!");
@@ -69,24 +145,100 @@ fn build_dist(platform: &str, variant: &str) -> Result<()> {
     Ok(())
 }
 
+/// Build a debug player, stage it with the assets in `dist/run` and run it
+/// from there. The folder is outside `target/` on purpose: the player takes
+/// assets from the repo when its path contains `target`.
+fn run_staged(variant: &str, args: &[String]) -> Result<()> {
+    let root = project_root();
+    let stage = root.join("dist").join("run");
+
+    println!("=== Building ({}) ===", variant);
+    let mut build_cmd = Command::new("cargo");
+    build_cmd
+        .current_dir(&root)
+        .env("CARGO_TARGET_DIR", root.join("target"))
+        .args(["build", "--package", "summit_hip_numbers"]);
+    let demo = match variant {
+        "full" => false,
+        "demo" => true,
+        _ => bail!("Unknown variant: {} (full or demo)", variant),
+    };
+    let status = build_cmd.status().context("Failed to run cargo build")?;
+    if !status.success() {
+        bail!("Build failed");
+    }
+
+    println!("\n=== Staging {} ===", stage.display());
+    if stage.exists() {
+        fs::remove_dir_all(&stage)
+            .with_context(|| format!("Failed to clear {}", stage.display()))?;
+    }
+    fs::create_dir_all(&stage)?;
+    let binary_name = format!("summit_hip_numbers{}", env::consts::EXE_SUFFIX);
+    let binary = stage.join(&binary_name);
+    fs::copy(
+        root.join("target").join("debug").join(&binary_name),
+        &binary,
+    )
+    .context("Failed to copy the player binary")?;
+    copy_assets(&root, &stage)?;
+    if cfg!(windows) {
+        bundle_windows_dlls(&ensure_ffmpeg(&root, host_platform())?, &stage)?;
+    }
+
+    println!("\n=== Running ===");
+    let mut player = Command::new(&binary);
+    if demo {
+        // The demo limits whatever license or trial the machine has
+        player.arg("--demo");
+    }
+    let status = player
+        .current_dir(&stage)
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to start {}", binary.display()))?;
+    if !status.success() {
+        bail!("The player exited with {}", status);
+    }
+    Ok(())
+}
+
 fn ensure_ffmpeg(root: &Path, platform: &str) -> Result<PathBuf> {
-    let ffmpeg_dir = root.join(".ffmpeg").join(format!("{}-x64", platform));
+    let ffmpeg_dir = ffmpeg_dir(root, platform);
 
     if ffmpeg_dir.exists() {
         println!("  ✓ FFmpeg already downloaded for {}", platform);
         return Ok(ffmpeg_dir);
     }
 
-    println!("  ⬇ Downloading portable FFmpeg for {}...", platform);
-
     match platform {
-        "windows" => download_ffmpeg_windows(&ffmpeg_dir)?,
+        "windows" => {
+            println!("  ⬇ Downloading portable FFmpeg for {}...", platform);
+            download_ffmpeg_windows(root, &ffmpeg_dir)?
+        }
+        "windows-arm64" => {
+            // There is no versioned ARM64 shared build to download and pin;
+            // vcpkg's has the same bin/lib/include layout
+            let dir = env::var_os("FFMPEG_DIR")
+                .map(PathBuf::from)
+                .filter(|dir| dir.join("bin").is_dir())
+                .with_context(|| {
+                    format!(
+                        "No FFmpeg for windows-arm64: run `vcpkg install ffmpeg:arm64-windows` \
+                         and set FFMPEG_DIR to its installed\\arm64-windows folder, or \
+                         unpack an ARM64 shared build into {}",
+                        ffmpeg_dir.display()
+                    )
+                })?;
+            println!("  ✓ Using FFmpeg from FFMPEG_DIR: {}", dir.display());
+            return Ok(dir);
+        }
         "macos" => {
             // macOS uses Homebrew-provided FFmpeg libraries
             println!("  ℹ macOS will use Homebrew FFmpeg libraries");
             return Ok(ffmpeg_dir); // Return dummy path, we'll set env vars later
         }
-        "linux" => {
+        p if platform_os(p) == "linux" => {
             // Linux uses vendored FFmpeg (compiled from source)
             println!("  ℹ Linux will use vendored FFmpeg (compiled from source)");
             return Ok(ffmpeg_dir); // Return dummy path
@@ -98,24 +250,21 @@ fn ensure_ffmpeg(root: &Path, platform: &str) -> Result<PathBuf> {
     Ok(ffmpeg_dir)
 }
 
-fn download_ffmpeg_windows(ffmpeg_dir: &Path) -> Result<()> {
+fn download_ffmpeg_windows(root: &Path, ffmpeg_dir: &Path) -> Result<()> {
     // Download FFmpeg full build with headers from gyan.dev
     let url = "https://github.com/GyanD/codexffmpeg/releases/download/7.1/ffmpeg-7.1-full_build-shared.zip";
-
-    println!("    Downloading from: {}", url);
-    let response = reqwest::blocking::get(url).context("Failed to download FFmpeg")?;
-
-    if !response.status().is_success() {
-        bail!("Failed to download FFmpeg: HTTP {}", response.status());
-    }
-
-    let bytes = response.bytes().context("Failed to read response")?;
+    let archive_path = download::fetch(root, url)?;
 
     println!("    Extracting FFmpeg archive...");
-    let cursor = std::io::Cursor::new(bytes);
-    let mut archive = zip::ZipArchive::new(cursor)?;
+    let mut archive = zip::ZipArchive::new(fs::File::open(&archive_path)?)?;
 
-    fs::create_dir_all(ffmpeg_dir)?;
+    // Extract beside the final directory and rename once complete, so an
+    // interrupted extraction is not taken for a cached one
+    let staging = ffmpeg_dir.with_extension("partial");
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
 
     // Extract files we need (bin/ and lib/)
     for i in 0..archive.len() {
@@ -149,7 +298,7 @@ fn download_ffmpeg_windows(ffmpeg_dir: &Path) -> Result<()> {
             continue;
         }
 
-        let outpath = ffmpeg_dir.join(&relative_path);
+        let outpath = staging.join(&relative_path);
 
         if file.name().ends_with('/') {
             fs::create_dir_all(&outpath)?;
@@ -161,6 +310,7 @@ fn download_ffmpeg_windows(ffmpeg_dir: &Path) -> Result<()> {
             std::io::copy(&mut file, &mut outfile)?;
         }
     }
+    fs::rename(&staging, ffmpeg_dir)?;
 
     Ok(())
 }
@@ -298,26 +448,13 @@ fn setup_macos_ffmpeg_env(build_cmd: &mut Command, platform: &str) -> Result<()>
 }
 
 #[allow(dead_code)]
-fn download_ffmpeg_macos(ffmpeg_dir: &Path) -> Result<()> {
+fn download_ffmpeg_macos(root: &Path, ffmpeg_dir: &Path) -> Result<()> {
     // Download static FFmpeg build from evermeet.cx (universal binary)
     let url = "https://evermeet.cx/ffmpeg/ffmpeg-7.1.7z";
-
-    println!("    Downloading from: {}", url);
-    let response = reqwest::blocking::get(url).context("Failed to download FFmpeg")?;
-
-    if !response.status().is_success() {
-        bail!("Failed to download FFmpeg: HTTP {}", response.status());
-    }
-
-    let bytes = response.bytes().context("Failed to read response")?;
+    let temp_file = download::fetch(root, url)?;
 
     println!("    Extracting FFmpeg archive...");
-
-    // Use 7z to extract (since it's a 7z archive)
-    // First write to a temp file
-    let temp_file = ffmpeg_dir.with_extension("7z");
     fs::create_dir_all(ffmpeg_dir.parent().unwrap())?;
-    fs::write(&temp_file, &bytes).context("Failed to write temp file")?;
 
     // Extract using 7z command
     let status = Command::new("7z")
@@ -347,9 +484,6 @@ fn download_ffmpeg_macos(ffmpeg_dir: &Path) -> Result<()> {
         }
     }
 
-    // Clean up temp file
-    let _ = fs::remove_file(&temp_file);
-
     // Find the extracted ffmpeg binary and move it to the expected location
     let extracted_dir = ffmpeg_dir;
     if extracted_dir.exists() {
@@ -409,7 +543,7 @@ fn ensure_cross_installed() -> Result<()> {
     Ok(())
 }
 
-fn build_platform(root: &Path, dist_dir: &Path, platform: &str, variant: &str) -> Result<()> {
+fn build_platform(root: &Path, dist_dir: &Path, platform: &str, installer: bool) -> Result<()> {
     // Detect current platform
     let current_os = env::consts::OS;
 
@@ -427,10 +561,6 @@ fn build_platform(root: &Path, dist_dir: &Path, platform: &str, variant: &str) -
         .arg("--package")
         .arg("summit_hip_numbers");
 
-    if variant == "demo" {
-        build_cmd.arg("--features").arg("demo");
-    }
-
     // Check if Docker is available for cross-compilation
     let docker_available = Command::new("docker")
         .arg("--version")
@@ -443,16 +573,27 @@ fn build_platform(root: &Path, dist_dir: &Path, platform: &str, variant: &str) -
     // For Windows, try to use downloaded FFmpeg if Docker not available
     // Only macOS requires native builds (can't cross-compile to macOS)
     let (target, use_cross) = match platform {
-        "linux" => {
-            if current_os == "linux" {
+        p if platform_os(p) == "linux" => {
+            let linux = linux_target(p)?;
+            if current_os == "linux" && env::consts::ARCH == linux.arch {
                 // Native Linux build on Linux runner
-                ("x86_64-unknown-linux-gnu", false)
+                (linux.triple, false)
+            } else if current_os == "linux"
+                && which::which(format!("{}-gcc", linux.multiarch)).is_ok()
+            {
+                // GNU cross toolchain and the target's multiarch libraries
+                ensure_rust_target(linux.triple)?;
+                setup_linux_cross_env(&mut build_cmd, linux);
+                (linux.triple, false)
             } else if !docker_available {
-                println!("  ⚠ Skipping Linux build (requires Docker for cross-compilation)");
+                println!(
+                    "  ⚠ Skipping {} build (requires Docker for cross-compilation)",
+                    p
+                );
                 return Ok(());
             } else {
                 // Cross-compilation from non-Linux runner
-                ("x86_64-unknown-linux-gnu", true)
+                (linux.triple, true)
             }
         }
         "windows" => {
@@ -467,6 +608,15 @@ fn build_platform(root: &Path, dist_dir: &Path, platform: &str, variant: &str) -
                 return Ok(());
             }
         }
+        "windows-arm64" => {
+            // MSVC only: cross has no ARM64 Windows image
+            if current_os != "windows" {
+                println!("  ⚠ Skipping Windows ARM64 build (requires Windows runner)");
+                return Ok(());
+            }
+            ensure_rust_target("aarch64-pc-windows-msvc")?;
+            ("aarch64-pc-windows-msvc", false)
+        }
         "macos" => {
             if current_os != "macos" {
                 println!("  ⚠ Skipping macOS build (requires macOS runner)");
@@ -482,17 +632,7 @@ fn build_platform(root: &Path, dist_dir: &Path, platform: &str, variant: &str) -
                     return Ok(());
                 }
             };
-
-            // Ensure target is installed
-            println!("  Ensuring Rust target {} is installed...", target);
-            let status = Command::new("rustup")
-                .args(["target", "add", target])
-                .status()
-                .context("Failed to run rustup")?;
-
-            if !status.success() {
-                bail!("Failed to install Rust target: {}", target);
-            }
+            ensure_rust_target(target)?;
 
             (target, false)
         }
@@ -503,7 +643,7 @@ fn build_platform(root: &Path, dist_dir: &Path, platform: &str, variant: &str) -
 
     // Set FFmpeg environment variables
     // For Windows cross-compilation, use system FFmpeg libraries installed in Docker container
-    if platform == "windows" && ffmpeg_dir.exists() && !use_cross {
+    if platform_os(platform) == "windows" && ffmpeg_dir.exists() && !use_cross {
         let bin_dir = ffmpeg_dir.join("bin");
         let lib_dir = ffmpeg_dir.join("lib");
         let include_dir = ffmpeg_dir.join("include");
@@ -546,22 +686,18 @@ fn build_platform(root: &Path, dist_dir: &Path, platform: &str, variant: &str) -
     };
 
     if !status.success() {
-        return Err(anyhow::anyhow!(
-            "Build failed for {} - {}",
-            platform,
-            variant
-        ));
+        return Err(anyhow::anyhow!("Build failed for {}", platform));
     }
 
     // Create distribution directory
     println!("  [2/4] Creating distribution directory...");
-    let dist_name = format!("{}-{}", platform, variant);
+    let dist_name = platform.to_string();
     let platform_dist = dist_dir.join(&dist_name);
     fs::create_dir_all(&platform_dist)?;
 
     // Copy binary
     println!("  [3/4] Copying binary and assets...");
-    let binary_name = if platform == "windows" {
+    let binary_name = if platform_os(platform) == "windows" {
         "summit_hip_numbers.exe"
     } else {
         "summit_hip_numbers"
@@ -601,19 +737,32 @@ fn build_platform(root: &Path, dist_dir: &Path, platform: &str, variant: &str) -
     copy_assets(root, &platform_dist)?;
 
     // Bundle platform-specific FFmpeg libraries
-    if platform == "windows" {
-        bundle_windows_dlls(root, &platform_dist)?;
-    } else if platform == "macos" {
-        bundle_macos_dylibs(&platform_dist)?;
-    } else if platform == "linux" {
-        bundle_linux_libs(&platform_dist)?;
+    match platform_os(platform) {
+        "windows" => bundle_windows_dlls(&ffmpeg_dir, &platform_dist)?,
+        "macos" => bundle_macos_dylibs(&platform_dist)?,
+        _ => bundle_linux_libs(&platform_dist, linux_target(platform)?)?,
     }
 
     // Create archive
     println!("  [4/4] Creating archive...");
-    create_archive(&dist_name, &platform_dist, platform)?;
+    let version = player_version(root)?;
+    let mut artifacts = vec![create_archive(&dist_name, &platform_dist, platform)?];
+
+    if installer {
+        match platform {
+            "windows" => artifacts.push(installer::windows(root, &platform_dist, &version)?),
+            "macos" => artifacts.push(app_bundle::macos(root, &platform_dist, &version)?),
+            "linux" => artifacts.extend(linux_package::linux(root, &platform_dist, &version)?),
+            _ => println!("  ⚠ No installer for {} yet", platform),
+        }
+    }
+
+    let sbom = dist_dir.join(format!("{}.cdx.json", dist_name));
+    artifacts::write_sbom(root, target, &version, &platform_dist, &sbom)?;
+    artifacts.push(sbom);
+    artifacts::checksum_and_sign(&artifacts)?;
 
-    println!("  ✓ {} - {} complete", platform, variant);
+    println!("  ✓ {} complete", platform);
 
     Ok(())
 }
@@ -827,10 +976,10 @@ fn fix_macos_dylib_paths(dist_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-fn bundle_windows_dlls(root: &Path, dist_dir: &Path) -> Result<()> {
+fn bundle_windows_dlls(ffmpeg_dir: &Path, dist_dir: &Path) -> Result<()> {
     println!("  [3.5/4] Bundling Windows DLLs...");
 
-    let ffmpeg_bin = root.join(".ffmpeg/windows-x64/bin");
+    let ffmpeg_bin = ffmpeg_dir.join("bin");
 
     if !ffmpeg_bin.exists() {
         println!("  ⚠ FFmpeg bin directory not found, skipping DLL bundling");
@@ -868,7 +1017,7 @@ fn bundle_windows_dlls(root: &Path, dist_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-fn bundle_linux_libs(dist_dir: &Path) -> Result<()> {
+fn bundle_linux_libs(dist_dir: &Path, linux: &LinuxTarget) -> Result<()> {
     println!("  [3.5/4] Bundling Linux FFmpeg libraries...");
 
     let mut copied = 0;
@@ -883,16 +1032,18 @@ fn bundle_linux_libs(dist_dir: &Path) -> Result<()> {
         "libswresample.so*",
     ];
 
+    // Only the target's multiarch directory on an ARM build, since `find`
+    // would also turn up the host's libraries under /usr/lib
+    let multiarch_dir = format!("/usr/lib/{}", linux.multiarch);
+    let mut lib_dirs = vec![multiarch_dir.as_str()];
+    if linux.arch == "x86_64" {
+        lib_dirs.insert(0, "/usr/lib");
+    }
+
     for pattern in &ffmpeg_libs {
         let output = Command::new("find")
-            .args([
-                "/usr/lib",
-                "/usr/lib/x86_64-linux-gnu",
-                "-name",
-                pattern,
-                "-type",
-                "f",
-            ])
+            .args(&lib_dirs)
+            .args(["-name", pattern, "-type", "f"])
             .output()
             .with_context(|| format!("Failed to find {} libraries", pattern))?;
 
@@ -929,15 +1080,16 @@ fn bundle_linux_libs(dist_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-fn create_archive(name: &str, source: &Path, platform: &str) -> Result<()> {
+fn create_archive(name: &str, source: &Path, platform: &str) -> Result<PathBuf> {
     let parent = source.parent().unwrap();
 
-    match platform {
+    match platform_os(platform) {
         "windows" => {
             // Create ZIP for Windows using zip crate
             let archive_name = format!("{}.zip", name);
             let zip_path = parent.join(&archive_name);
             create_zip(source, &zip_path)?;
+            Ok(zip_path)
         }
         _ => {
             // Create tar.gz for Unix
@@ -952,10 +1104,9 @@ fn create_archive(name: &str, source: &Path, platform: &str) -> Result<()> {
             if !status.success() {
                 return Err(anyhow::anyhow!("tar.gz creation failed"));
             }
+            Ok(parent.join(archive_name))
         }
     }
-
-    Ok(())
 }
 
 fn create_zip(source_dir: &Path, output_path: &Path) -> Result<()> {
@@ -993,6 +1144,149 @@ fn create_zip(source_dir: &Path, output_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// The `dist` platform this machine builds natively.
+fn host_platform() -> &'static str {
+    match (env::consts::OS, env::consts::ARCH) {
+        ("windows", "aarch64") => "windows-arm64",
+        ("windows", _) => "windows",
+        ("macos", _) => "macos",
+        (_, "aarch64") => "linux-arm64",
+        (_, "arm") => "linux-armv7",
+        _ => "linux",
+    }
+}
+
+/// `linux`, `macos` or `windows`: what a `dist` platform runs on.
+fn platform_os(platform: &str) -> &str {
+    platform.split('-').next().unwrap_or(platform)
+}
+
+/// A Linux `dist` platform and the Rust target it is built for.
+struct LinuxTarget {
+    platform: &'static str,
+    triple: &'static str,
+    /// Debian multiarch triplet, also the GNU cross toolchain's prefix
+    multiarch: &'static str,
+    /// `env::consts::ARCH` of a machine that builds it natively
+    arch: &'static str,
+}
+
+/// `linux-arm64` is for 64-bit ARM boxes, Raspberry Pi 3, 4 and 5 on
+/// 64-bit Raspberry Pi OS included; `linux-armv7` for 32-bit Raspberry Pi
+/// OS.
+const LINUX_TARGETS: [LinuxTarget; 3] = [
+    LinuxTarget {
+        platform: "linux",
+        triple: "x86_64-unknown-linux-gnu",
+        multiarch: "x86_64-linux-gnu",
+        arch: "x86_64",
+    },
+    LinuxTarget {
+        platform: "linux-arm64",
+        triple: "aarch64-unknown-linux-gnu",
+        multiarch: "aarch64-linux-gnu",
+        arch: "aarch64",
+    },
+    LinuxTarget {
+        platform: "linux-armv7",
+        triple: "armv7-unknown-linux-gnueabihf",
+        multiarch: "arm-linux-gnueabihf",
+        arch: "arm",
+    },
+];
+
+fn linux_target(platform: &str) -> Result<&'static LinuxTarget> {
+    LINUX_TARGETS
+        .iter()
+        .find(|linux| linux.platform == platform)
+        .with_context(|| format!("Unsupported platform: {}", platform))
+}
+
+/// Point the linker, cc, pkg-config and bindgen at the GNU cross toolchain
+/// (`gcc-aarch64-linux-gnu`) and the target's multiarch packages
+/// (`libavcodec-dev:arm64` and the rest).
+fn setup_linux_cross_env(build_cmd: &mut Command, linux: &LinuxTarget) {
+    let gcc = format!("{}-gcc", linux.multiarch);
+    let triple_var = linux.triple.replace('-', "_");
+    build_cmd
+        .env(
+            format!("CARGO_TARGET_{}_LINKER", triple_var.to_uppercase()),
+            &gcc,
+        )
+        .env(format!("CC_{}", triple_var), &gcc)
+        .env("PKG_CONFIG_ALLOW_CROSS", "1")
+        .env("PKG_CONFIG_SYSROOT_DIR", "/")
+        .env(
+            "PKG_CONFIG_LIBDIR",
+            format!(
+                "/usr/lib/{}/pkgconfig:/usr/share/pkgconfig",
+                linux.multiarch
+            ),
+        )
+        .env(
+            "BINDGEN_EXTRA_CLANG_ARGS",
+            format!("--target={}", linux.multiarch),
+        );
+}
+
+/// Where FFmpeg for a `dist` platform is unpacked in the download cache.
+fn ffmpeg_dir(root: &Path, platform: &str) -> PathBuf {
+    let name = if platform.contains('-') {
+        platform.to_string()
+    } else {
+        format!("{}-x64", platform)
+    };
+    download::cache_dir(root).join(name)
+}
+
+fn ensure_rust_target(target: &str) -> Result<()> {
+    println!("  Ensuring Rust target {} is installed...", target);
+    let status = Command::new("rustup")
+        .args(["target", "add", target])
+        .status()
+        .context("Failed to run rustup")?;
+
+    if !status.success() {
+        bail!("Failed to install Rust target: {}", target);
+    }
+    Ok(())
+}
+
+/// Run a packaging tool, failing with what it printed to stderr.
+fn run_tool(cmd: &mut Command) -> Result<()> {
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    let output = cmd
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// The `version` of the player crate, for installer and package names.
+fn player_version(root: &Path) -> Result<String> {
+    let manifest = root
+        .join("crates")
+        .join("summit_hip_numbers")
+        .join("Cargo.toml");
+    let text = fs::read_to_string(&manifest)
+        .with_context(|| format!("Failed to read {}", manifest.display()))?;
+    text.lines()
+        .find_map(|line| {
+            let value = line
+                .strip_prefix("version")?
+                .trim_start()
+                .strip_prefix('=')?;
+            Some(value.trim().trim_matches('"').to_string())
+        })
+        .context("No version in the player's Cargo.toml")
+}
+
 fn project_root() -> PathBuf {
     Path::new(&env!("CARGO_MANIFEST_DIR"))
         .ancestors()
@@ -0,0 +1,446 @@
+//! `release --tag vX.Y.Z`: bump every workspace crate to the version, build
+//! all platforms with installers, prepend the git history since the last
+//! tag to `CHANGELOG.md`, then commit, tag, push and publish a GitHub
+//! release with every artifact attached.
+
+use anyhow::{bail, Context, Result};
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, USER_AGENT};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const API: &str = "https://api.github.com";
+
+/// What `dist --platform all` builds, each of which a release must have.
+const PLATFORMS: [&str; 3] = ["linux", "macos", "windows"];
+
+/// Release `tag`. With `dry_run`, only print the version and changelog.
+pub fn run(root: &Path, tag: &str, dry_run: bool) -> Result<()> {
+    let version = parse_tag(tag)?;
+    if !git(root, &["status", "--porcelain"])?.is_empty() {
+        bail!("The working tree has uncommitted changes; commit or stash them first");
+    }
+    if git(root, &["tag", "--list", tag])? == tag {
+        bail!("Tag {} already exists", tag);
+    }
+    let previous = git(root, &["describe", "--tags", "--abbrev=0", "--match", "v*"]).ok();
+    let notes = release_notes(root, previous.as_deref())?;
+    let section = format!(
+        "## {} - {}\n\n{}",
+        tag,
+        chrono::Local::now().format("%Y-%m-%d"),
+        notes
+    );
+
+    println!("=== Release {} ===", tag);
+    match &previous {
+        Some(previous) => println!("Changes since {}:\n", previous),
+        None => println!("Changes:\n"),
+    }
+    println!("{}", notes);
+    if dry_run {
+        println!("Dry run; nothing changed");
+        return Ok(());
+    }
+    let token = github_token()?;
+    let repo = github_repo(root)?;
+
+    println!("=== Bumping versions to {} ===", version);
+    for manifest in workspace_manifests(root)? {
+        set_version(&manifest, version)?;
+        println!("  ✓ {}", manifest.strip_prefix(root)?.display());
+    }
+    prepend_changelog(&root.join("CHANGELOG.md"), &section)?;
+
+    let dist_dir = root.join("dist");
+    if dist_dir.exists() {
+        fs::remove_dir_all(&dist_dir)?;
+    }
+    crate::build_dist("all", true)?;
+    let assets = release_assets(&dist_dir)?;
+    let missing = missing_platforms(&assets);
+    if !missing.is_empty() {
+        // Before the tag is pushed, which would stop a rerun
+        bail!(
+            "No archive was built for {}; release from a machine that builds every \
+             platform (a Mac with Docker). The version bump and CHANGELOG.md are \
+             left uncommitted",
+            missing.join(", ")
+        );
+    }
+
+    println!("\n=== Tagging {} ===", tag);
+    git(root, &["add", "--all"])?;
+    git(root, &["commit", "--message", &format!("Release {}", tag)])?;
+    git(root, &["tag", "--annotate", tag, "--message", &section])?;
+    git(root, &["push", "origin", "HEAD", tag])?;
+    println!("  ✓ Pushed {}", tag);
+
+    println!("\n=== Publishing to {} ===", repo);
+    publish(&token, &repo, tag, version, &notes, &assets)?;
+    println!("\n✓ Released {}", tag);
+    Ok(())
+}
+
+/// `1.2.3` for `v1.2.3`; a pre-release suffix such as `-rc.1` is kept.
+fn parse_tag(tag: &str) -> Result<&str> {
+    let version = tag
+        .strip_prefix('v')
+        .with_context(|| format!("The tag must look like vX.Y.Z, not {}", tag))?;
+    let core = version.split('-').next().unwrap_or(version);
+    let parts: Vec<&str> = core.split('.').collect();
+    if parts.len() != 3
+        || parts
+            .iter()
+            .any(|part| part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()))
+    {
+        bail!("The tag must look like vX.Y.Z, not {}", tag);
+    }
+    Ok(version)
+}
+
+/// One line per commit since `previous`, oldest last, as a Markdown list.
+fn release_notes(root: &Path, previous: Option<&str>) -> Result<String> {
+    let range = previous.map(|previous| format!("{}..HEAD", previous));
+    let mut args = vec!["log", "--no-merges", "--format=- %s (%h)"];
+    if let Some(range) = &range {
+        args.push(range);
+    }
+    let log = git(root, &args)?;
+    if log.is_empty() {
+        bail!("No commits since {}", previous.unwrap_or("the start"));
+    }
+    Ok(format!("{}\n", log))
+}
+
+fn workspace_manifests(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut manifests = vec![root.join("xtask").join("Cargo.toml")];
+    for entry in fs::read_dir(root.join("crates"))? {
+        let manifest = entry?.path().join("Cargo.toml");
+        if manifest.is_file() {
+            manifests.push(manifest);
+        }
+    }
+    manifests.sort();
+    Ok(manifests)
+}
+
+/// Rewrite the `version` of `[package]`, and of `[package.metadata.bundle]`
+/// where cargo-bundle reads it, leaving the rest of the file as it is.
+fn set_version(manifest: &Path, version: &str) -> Result<()> {
+    let text = fs::read_to_string(manifest)
+        .with_context(|| format!("Failed to read {}", manifest.display()))?;
+    let out = with_version(&text, version)
+        .with_context(|| format!("No package version in {}", manifest.display()))?;
+    fs::write(manifest, out)?;
+    Ok(())
+}
+
+/// `text`, a manifest, with its package version set; `None` if it has none.
+fn with_version(text: &str, version: &str) -> Option<String> {
+    let mut section = "";
+    let mut changed = false;
+    let mut out = String::with_capacity(text.len());
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            section = trimmed;
+        }
+        let is_version = trimmed
+            .strip_prefix("version")
+            .is_some_and(|rest| rest.trim_start().starts_with('='));
+        if is_version && matches!(section, "[package]" | "[package.metadata.bundle]") {
+            out.push_str(&format!("version = \"{}\"", version));
+            changed = true;
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    changed.then_some(out)
+}
+
+fn prepend_changelog(path: &Path, section: &str) -> Result<()> {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    fs::write(path, with_section(&existing, section))?;
+    println!("  ✓ CHANGELOG.md");
+    Ok(())
+}
+
+/// The changelog `existing` with `section` at the top, under its heading.
+fn with_section(existing: &str, section: &str) -> String {
+    let rest = existing
+        .strip_prefix("# Changelog")
+        .unwrap_or(existing)
+        .trim_start();
+    let mut text = format!("# Changelog\n\n{}", section);
+    if !rest.is_empty() {
+        text.push('\n');
+        text.push_str(rest);
+    }
+    text
+}
+
+/// The files `dist` leaves at the top of `dist/`: archives, installers and
+/// packages, and their checksums, signatures and SBOMs.
+fn release_assets(dist_dir: &Path) -> Result<Vec<PathBuf>> {
+    const SUFFIXES: [&str; 10] = [
+        ".zip",
+        ".tar.gz",
+        ".dmg",
+        "-setup.exe",
+        ".deb",
+        ".AppImage",
+        ".sha256",
+        ".asc",
+        ".minisig",
+        ".cdx.json",
+    ];
+    let mut assets: Vec<PathBuf> = fs::read_dir(dist_dir)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+        })
+        .collect();
+    assets.sort();
+    Ok(assets)
+}
+
+/// The [`PLATFORMS`] with no archive among `assets`; `dist` skips the ones
+/// the machine can't build.
+fn missing_platforms(assets: &[PathBuf]) -> Vec<&'static str> {
+    PLATFORMS
+        .into_iter()
+        .filter(|platform| {
+            !assets.iter().any(|asset| {
+                let name = asset.file_name().unwrap_or_default().to_string_lossy();
+                [".zip", ".tar.gz"]
+                    .iter()
+                    .any(|suffix| name == format!("{}{}", platform, suffix))
+            })
+        })
+        .collect()
+}
+
+/// Create the release as a draft, upload the assets, then publish it, so
+/// a failed upload never leaves a half-filled public release.
+fn publish(
+    token: &str,
+    repo: &str,
+    tag: &str,
+    version: &str,
+    notes: &str,
+    assets: &[PathBuf],
+) -> Result<()> {
+    let client = Client::builder().timeout(None).build()?;
+    let release = send(
+        github(
+            token,
+            client.post(format!("{}/repos/{}/releases", API, repo)),
+        )
+        .body(
+            json!({
+                "tag_name": tag,
+                "name": format!("Summit Hip Numbers {}", version),
+                "body": notes,
+                "draft": true,
+                "prerelease": version.contains('-'),
+            })
+            .to_string(),
+        ),
+    )
+    .context("Failed to create the release")?;
+    let id = release["id"]
+        .as_u64()
+        .context("GitHub returned no release id")?;
+    let upload_url = release["upload_url"]
+        .as_str()
+        .context("GitHub returned no upload URL")?;
+    let upload_url = upload_url.split('{').next().unwrap_or(upload_url);
+
+    for asset in assets {
+        let name = asset.file_name().unwrap().to_string_lossy();
+        send(
+            github(token, client.post(upload_url))
+                .query(&[("name", name.as_ref())])
+                .header(CONTENT_TYPE, "application/octet-stream")
+                .body(fs::File::open(asset)?),
+        )
+        .with_context(|| format!("Failed to upload {}", name))?;
+        println!("  ✓ Uploaded {}", name);
+    }
+
+    let release = send(
+        github(
+            token,
+            client.patch(format!("{}/repos/{}/releases/{}", API, repo, id)),
+        )
+        .body(json!({ "draft": false }).to_string()),
+    )
+    .context("Failed to publish the release")?;
+    if let Some(url) = release["html_url"].as_str() {
+        println!("  ✓ {}", url);
+    }
+    Ok(())
+}
+
+fn github(token: &str, request: RequestBuilder) -> RequestBuilder {
+    request
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .header(ACCEPT, "application/vnd.github+json")
+        .header(USER_AGENT, "summit_hip_numbers-xtask")
+        .header("X-GitHub-Api-Version", "2022-11-28")
+}
+
+fn send(request: RequestBuilder) -> Result<Value> {
+    let response = request.send()?;
+    let status = response.status();
+    let body = response.bytes()?;
+    if !status.is_success() {
+        bail!("HTTP {}: {}", status, String::from_utf8_lossy(&body));
+    }
+    Ok(serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn github_token() -> Result<String> {
+    ["GITHUB_TOKEN", "GH_TOKEN"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|token| !token.is_empty()))
+        .context("Set GITHUB_TOKEN to a token allowed to push and create releases")
+}
+
+/// `owner/name`, from `GITHUB_REPOSITORY` as in Actions, else the `origin`
+/// remote.
+fn github_repo(root: &Path) -> Result<String> {
+    if let Ok(repo) = std::env::var("GITHUB_REPOSITORY") {
+        return Ok(repo);
+    }
+    let url = git(root, &["remote", "get-url", "origin"])?;
+    repo_from_url(&url).with_context(|| format!("origin ({}) is not a GitHub repository", url))
+}
+
+/// `owner/name` from an HTTPS or SSH GitHub remote URL.
+fn repo_from_url(url: &str) -> Option<String> {
+    let (_, path) = url.split_once("github.com")?;
+    let path = path
+        .trim_start_matches([':', '/'])
+        .trim_end_matches('/')
+        .trim_end_matches(".git");
+    let (owner, name) = path.split_once('/')?;
+    (!owner.is_empty() && !name.is_empty() && !name.contains('/')).then(|| path.to_string())
+}
+
+/// Run git in `root`, returning its trimmed output.
+fn git(root: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(root)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tag() {
+        assert_eq!(parse_tag("v1.2.3").unwrap(), "1.2.3");
+        assert_eq!(parse_tag("v1.2.3-rc.1").unwrap(), "1.2.3-rc.1");
+        for tag in ["1.2.3", "v1.2", "v1.2.3.4", "v1..3", "v1.2.x", "v"] {
+            assert!(parse_tag(tag).is_err(), "{}", tag);
+        }
+    }
+
+    #[test]
+    fn test_with_version() {
+        let manifest = "[package]\n\
+                        name = \"summit_hip_numbers\"\n\
+                        version = \"0.1.0\"\n\
+                        \n\
+                        [dependencies]\n\
+                        serde = { version = \"1.0\" }\n\
+                        \n\
+                        [package.metadata.bundle]\n\
+                        version   =  \"0.1.0\"\n\
+                        \n\
+                        [dependencies.log]\n\
+                        version = \"0.4\"\n";
+        assert_eq!(
+            with_version(manifest, "1.2.3").unwrap(),
+            manifest
+                .replace("version = \"0.1.0\"", "version = \"1.2.3\"")
+                .replace("version   =  \"0.1.0\"", "version = \"1.2.3\"")
+        );
+        // Dependency versions alone aren't the package's
+        assert_eq!(
+            with_version("[dependencies]\nversion = \"1\"\nversioned = 2\n", "1.2.3"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_with_section() {
+        let section = "## v1.1.0 - 2026-10-16\n\n- Fix (abc1234)\n";
+        assert_eq!(
+            with_section("", section),
+            format!("# Changelog\n\n{}", section)
+        );
+        assert_eq!(
+            with_section(
+                "# Changelog\n\n## v1.0.0 - 2026-01-01\n\n- First\n",
+                section
+            ),
+            format!(
+                "# Changelog\n\n{}\n## v1.0.0 - 2026-01-01\n\n- First\n",
+                section
+            )
+        );
+        // A changelog without the heading gets one
+        assert_eq!(
+            with_section("## v1.0.0\n", section),
+            format!("# Changelog\n\n{}\n## v1.0.0\n", section)
+        );
+    }
+
+    #[test]
+    fn test_repo_from_url() {
+        for url in [
+            "https://github.com/millerjes37/summit_hip_numbers.git",
+            "https://github.com/millerjes37/summit_hip_numbers",
+            "git@github.com:millerjes37/summit_hip_numbers.git",
+            "ssh://git@github.com/millerjes37/summit_hip_numbers/",
+        ] {
+            assert_eq!(
+                repo_from_url(url).as_deref(),
+                Some("millerjes37/summit_hip_numbers"),
+                "{}",
+                url
+            );
+        }
+        assert_eq!(repo_from_url("https://gitlab.com/a/b.git"), None);
+        assert_eq!(repo_from_url("https://github.com/millerjes37"), None);
+    }
+
+    #[test]
+    fn test_missing_platforms() {
+        let assets: Vec<PathBuf> = ["linux.tar.gz", "linux.tar.gz.sha256", "windows.zip"]
+            .iter()
+            .map(|name| Path::new("dist").join(name))
+            .collect();
+        assert_eq!(missing_platforms(&assets), vec!["macos"]);
+        assert_eq!(missing_platforms(&[]), PLATFORMS.to_vec());
+    }
+}
@@ -0,0 +1,87 @@
+//! `smoke`: unpack a platform's freshly built archive, add the synthetic
+//! test videos from `gen-assets`, and run the player's `--self-check` in it.
+//! A DLL or dylib left out of the bundle fails here rather than at a venue.
+
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+pub fn run(root: &Path, platform: &str) -> Result<()> {
+    let dist_dir = root.join("dist");
+    let archive = ["zip", "tar.gz"]
+        .iter()
+        .map(|ext| dist_dir.join(format!("{}.{}", platform, ext)))
+        .find(|path| path.is_file())
+        .with_context(|| {
+            format!(
+                "No {} archive in {}; build it with `cargo xtask dist --platform {}`",
+                platform,
+                dist_dir.display(),
+                platform
+            )
+        })?;
+
+    let out = dist_dir.join("smoke");
+    if out.exists() {
+        fs::remove_dir_all(&out)?;
+    }
+    fs::create_dir_all(&out)?;
+    println!("Unpacking {}", archive.display());
+    unpack(&archive, &out)?;
+    let dir = out.join(platform);
+
+    let test_assets = root.join("tests").join("assets");
+    let mut added = 0;
+    for entry in fs::read_dir(&test_assets)? {
+        let path = entry?.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("mp4") {
+            fs::copy(&path, dir.join("videos").join(path.file_name().unwrap()))?;
+            added += 1;
+        }
+    }
+    if added == 0 {
+        println!("  ⚠ No test videos in tests/assets; run `cargo xtask gen-assets` first");
+    } else {
+        println!("  ✓ Added {} test videos", added);
+    }
+
+    let binary = dir.join(if crate::platform_os(platform) == "windows" {
+        "summit_hip_numbers.exe"
+    } else {
+        "summit_hip_numbers"
+    });
+    let mut cmd = Command::new(&binary);
+    cmd.arg("--self-check")
+        .arg("--config-path")
+        .arg(dir.join("config.toml"))
+        .current_dir(&dir);
+    if crate::platform_os(platform) == "linux" {
+        // As the .deb and AppImage launchers do
+        cmd.env("LD_LIBRARY_PATH", &dir);
+    }
+    println!("Running {} --self-check", binary.display());
+    let status = cmd
+        .status()
+        .with_context(|| format!("Failed to start {}", binary.display()))?;
+    if !status.success() {
+        bail!(
+            "Smoke test failed: the {} build can't play its videos",
+            platform
+        );
+    }
+    fs::remove_dir_all(&out)?;
+    println!("\n✓ {} smoke test passed", platform);
+    Ok(())
+}
+
+fn unpack(archive: &Path, out: &Path) -> Result<()> {
+    let file = fs::File::open(archive)?;
+    if archive.extension().and_then(|s| s.to_str()) == Some("zip") {
+        zip::ZipArchive::new(file)?.extract(out)?;
+    } else {
+        tar::Archive::new(GzDecoder::new(file)).unpack(out)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! `gen-assets`: short synthetic videos, each a solid colour with its hip
+//! number burned in and a tone whose pitch rises with the number, so
+//! playback and switching can be checked by eye and ear. Named
+//! `NNN_test.mp4`; running it again replaces them.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const COLORS: [&str; 8] = [
+    "darkred",
+    "darkgreen",
+    "navy",
+    "darkorange",
+    "purple",
+    "teal",
+    "saddlebrown",
+    "dimgray",
+];
+
+/// Write `count` videos of `seconds` each into `tests/assets` and
+/// `assets/videos`.
+pub fn generate(root: &Path, count: u32, seconds: u32) -> Result<()> {
+    if !(1..=999).contains(&count) {
+        anyhow::bail!("--count must be 1 to 999 (hip numbers have 3 digits)");
+    }
+    let ffmpeg = find_ffmpeg(root)?;
+    println!("Using {}", ffmpeg.display());
+
+    let dirs = [
+        root.join("tests").join("assets"),
+        root.join("assets").join("videos"),
+    ];
+    for dir in &dirs {
+        fs::create_dir_all(dir)?;
+    }
+    for hip in 1..=count {
+        let name = format!("{:03}_test.mp4", hip);
+        let first = dirs[0].join(&name);
+        crate::run_tool(&mut synthesize(&ffmpeg, hip, seconds, &first))?;
+        for dir in &dirs[1..] {
+            fs::copy(&first, dir.join(&name))?;
+        }
+        println!("  ✓ {}", name);
+    }
+    println!(
+        "\n✓ {} videos in {}",
+        count,
+        dirs.iter()
+            .map(|dir| dir.display().to_string())
+            .collect::<Vec<_>>()
+            .join(" and ")
+    );
+    Ok(())
+}
+
+fn synthesize(ffmpeg: &Path, hip: u32, seconds: u32, out: &Path) -> Command {
+    let color = COLORS[(hip as usize - 1) % COLORS.len()];
+    let video = format!(
+        "color=c={}:size=1280x720:rate=30:duration={}",
+        color, seconds
+    );
+    let tone = format!(
+        "sine=frequency={}:duration={}",
+        220 + 20 * (hip % 50),
+        seconds
+    );
+    let label = format!(
+        "drawtext=text='HIP {:03}':fontsize=200:fontcolor=white:\
+         x=(w-text_w)/2:y=(h-text_h)/2",
+        hip
+    );
+    let mut cmd = Command::new(ffmpeg);
+    cmd.args(["-y", "-loglevel", "error", "-f", "lavfi", "-i"])
+        .arg(video)
+        .args(["-f", "lavfi", "-i"])
+        .arg(tone)
+        .args(["-vf", &label])
+        .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+        .args(["-c:a", "aac", "-shortest"])
+        .arg(out);
+    cmd
+}
+
+/// The ffmpeg `dist` bundles for this platform, else one on PATH. On
+/// Windows it is downloaded if need be.
+fn find_ffmpeg(root: &Path) -> Result<PathBuf> {
+    let platform = crate::host_platform();
+    let ffmpeg_dir = if crate::platform_os(platform) == "windows" {
+        crate::ensure_ffmpeg(root, platform)?
+    } else {
+        crate::ffmpeg_dir(root, platform)
+    };
+    let bundled = ffmpeg_dir
+        .join("bin")
+        .join(format!("ffmpeg{}", std::env::consts::EXE_SUFFIX));
+    if bundled.is_file() {
+        return Ok(bundled);
+    }
+    which::which("ffmpeg").context("ffmpeg was not found; install it or put it on PATH")
+}